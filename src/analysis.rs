@@ -0,0 +1,904 @@
+/*
+ * analysis.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::{self, Display, Formatter};
+use petgraph::{
+	algo::{is_cyclic_directed, toposort},
+	Direction::{Incoming, Outgoing},
+	graph::{DiGraph, NodeIndex},
+	visit::{Dfs, Reversed, Walker}
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
+use rand::{Rng, rngs::StdRng, SeedableRng};
+
+////////////////////////////////////////////////////////////////////////////////
+//                             Position ranges.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The range of output positions that a section may legally occupy, given the
+/// ordering constraints. Both ends are **zero-based** and inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PositionRange
+{
+	/// The earliest legal position, i.e., the number of ancestors.
+	pub(crate) earliest: usize,
+
+	/// The latest legal position, i.e., the number of sections less the number
+	/// of descendants (and then adjusted to zero-based).
+	pub(crate) latest: usize
+}
+
+/// Compute the [feasible&#32;positions](PositionRange) of every vertex of the
+/// specified constraint graph, in vertex order. Answer `None` if the graph
+/// contains a cycle, because then no position is feasible for anybody.
+pub(crate) fn feasible_positions(
+	graph: &DiGraph<usize, (), usize>
+) -> Option<Vec<PositionRange>>
+{
+	if is_cyclic_directed(graph)
+	{
+		return None
+	}
+	let count = graph.node_count();
+	Some(
+		graph.node_indices()
+			.map(|index| PositionRange {
+				earliest: ancestor_count(graph, index),
+				latest: count - 1 - descendant_count(graph, index)
+			})
+			.collect()
+	)
+}
+
+/// How much room the ordering constraints leave the sections to move, as
+/// summarized from their [feasible&#32;positions](PositionRange).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Freedom
+{
+	/// The mean width of the feasible ranges, as a fraction of the widest
+	/// possible range, from `0.0` (fully determined) to `1.0` (unconstrained).
+	pub(crate) mean: f32,
+
+	/// The number of sections that can occupy only one position.
+	pub(crate) pinned: usize,
+
+	/// The number of sections that can occupy any position.
+	pub(crate) unconstrained: usize
+}
+
+/// Summarize the [freedom](Freedom) of the sections whose
+/// [feasible&#32;positions](PositionRange) are specified.
+pub(crate) fn freedom(ranges: &[PositionRange]) -> Freedom
+{
+	let widest = ranges.len().saturating_sub(1);
+	let slack = |range: &PositionRange| range.latest - range.earliest;
+	let mean = if widest == 0 { 0.0 }
+		else
+		{
+			ranges.iter().map(slack).sum::<usize>() as f32
+				/ (ranges.len() * widest) as f32
+		};
+	Freedom {
+		mean,
+		pinned: ranges.iter().filter(|r| slack(r) == 0).count(),
+		unconstrained: ranges.iter().filter(|r| slack(r) == widest).count()
+	}
+}
+
+/// Answer the number of vertices from which the specified vertex is reachable,
+/// excluding the vertex itself.
+fn ancestor_count(
+	graph: &DiGraph<usize, (), usize>,
+	index: NodeIndex<usize>
+) -> usize
+{
+	let reversed = Reversed(graph);
+	Dfs::new(reversed, index).iter(reversed).count() - 1
+}
+
+/// Answer the number of vertices reachable from the specified vertex,
+/// excluding the vertex itself.
+fn descendant_count(
+	graph: &DiGraph<usize, (), usize>,
+	index: NodeIndex<usize>
+) -> usize
+{
+	Dfs::new(graph, index).iter(graph).count() - 1
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                             Ordering counts.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The number of valid orderings, i.e., linear extensions, of a constraint
+/// graph. Small graphs are counted exactly, but large graphs are only
+/// estimated, because exact counting is intractable in general.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum OrderingCount
+{
+	/// The exact number of valid orderings.
+	Exact(u64),
+
+	/// An estimate of the number of valid orderings, as a base-10 logarithm
+	/// (because the count can vastly exceed the range of any primitive type).
+	Estimated(f64)
+}
+
+impl OrderingCount
+{
+	/// Answer the base-10 logarithm of the count. Answer negative infinity if
+	/// there are no valid orderings.
+	pub(crate) fn log10(&self) -> f64
+	{
+		match self
+		{
+			Self::Exact(count) => (*count as f64).log10(),
+			Self::Estimated(log10) => *log10
+		}
+	}
+
+	/// Answer `true` if there are no valid orderings, i.e., because of a
+	/// paradox.
+	pub(crate) fn is_zero(&self) -> bool
+	{
+		matches!(self, Self::Exact(0))
+	}
+}
+
+impl Display for OrderingCount
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Exact(count) => write!(f, "{}", with_separators(*count)),
+			Self::Estimated(log10) if *log10 < 9.0 =>
+			{
+				// Round to two significant figures, because the estimate isn't
+				// any better than that.
+				let magnitude = 10f64.powi(log10.floor() as i32 - 1);
+				let rounded = (10f64.powf(*log10) / magnitude).round() * magnitude;
+				write!(f, "about {}", with_separators(rounded as u64))
+			},
+			Self::Estimated(log10) =>
+			{
+				let exponent = log10.floor();
+				let mantissa = 10f64.powf(log10 - exponent);
+				write!(f, "about {:.1} × 10^{}", mantissa, exponent)
+			}
+		}
+	}
+}
+
+/// Count (or estimate) the [valid&#32;orderings](OrderingCount) of the
+/// specified constraint graph. A cyclic graph has no valid orderings.
+pub(crate) fn count_orderings(graph: &DiGraph<usize, (), usize>)
+	-> OrderingCount
+{
+	if graph.node_count() <= EXACT_COUNT_LIMIT
+	{
+		OrderingCount::Exact(count_orderings_exactly(graph))
+	}
+	else
+	{
+		estimate_orderings(graph)
+	}
+}
+
+/// Count the valid orderings of the specified constraint graph exactly, using
+/// dynamic programming over the subsets of placed vertices. The graph must not
+/// have more than [`EXACT_COUNT_LIMIT`] vertices.
+fn count_orderings_exactly(graph: &DiGraph<usize, (), usize>) -> u64
+{
+	ways_to_place(graph)[(1 << graph.node_count()) - 1]
+}
+
+/// Answer, for each subset of the vertices of the specified constraint graph,
+/// encoded as a bit set, the number of ways to order the vertices of that
+/// subset, such that every vertex follows its predecessors. A subset that
+/// omits the predecessor of one of its members has no such orderings. The
+/// graph must not have more than [`EXACT_COUNT_LIMIT`] vertices.
+fn ways_to_place(graph: &DiGraph<usize, (), usize>) -> Vec<u64>
+{
+	let count = graph.node_count();
+	// Encode the predecessors of each vertex as a bit set.
+	let predecessors = graph.node_indices()
+		.map(|index|
+			graph.neighbors_directed(index, Incoming)
+				.fold(0usize, |set, p| set | (1 << p.index()))
+		)
+		.collect::<Vec<_>>();
+	let mut ways = vec![0u64; 1 << count];
+	ways[0] = 1;
+	for placed in 0 .. ways.len()
+	{
+		if ways[placed] == 0
+		{
+			continue
+		}
+		for (vertex, required) in predecessors.iter().enumerate()
+		{
+			let bit = 1 << vertex;
+			if placed & bit == 0 && required & placed == *required
+			{
+				ways[placed | bit] += ways[placed];
+			}
+		}
+	}
+	ways
+}
+
+/// Estimate the valid orderings of the specified constraint graph, using
+/// Knuth's sequential estimator: pick a random root at each step, and take the
+/// product of the number of roots available at each step. The mean of this
+/// product over many trials is an unbiased estimate of the true count. The
+/// random number generator is seeded deterministically, so that the estimate
+/// is stable across frames.
+fn estimate_orderings(graph: &DiGraph<usize, (), usize>) -> OrderingCount
+{
+	let mut rng = StdRng::seed_from_u64(ESTIMATE_SEED);
+	let indegrees = graph.node_indices()
+		.map(|index| graph.neighbors_directed(index, Incoming).count())
+		.collect::<Vec<_>>();
+	let samples = (0 .. ESTIMATE_SAMPLES)
+		.map(|_| {
+			let mut indegrees = indegrees.clone();
+			let mut roots = graph.node_indices()
+				.filter(|index| indegrees[index.index()] == 0)
+				.collect::<Vec<_>>();
+			let mut log10 = 0.0;
+			for _ in 0 .. graph.node_count()
+			{
+				if roots.is_empty()
+				{
+					// The remaining vertices form a cycle, so this path
+					// contributes nothing to the count.
+					return f64::NEG_INFINITY
+				}
+				log10 += (roots.len() as f64).log10();
+				let root = roots.swap_remove(rng.gen_range(0 .. roots.len()));
+				for successor in graph.neighbors_directed(root, Outgoing)
+				{
+					indegrees[successor.index()] -= 1;
+					if indegrees[successor.index()] == 0
+					{
+						roots.push(successor);
+					}
+				}
+			}
+			log10
+		})
+		.collect::<Vec<_>>();
+	// Average the samples in the logarithmic domain, to avoid overflow.
+	let max = samples.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+	if max == f64::NEG_INFINITY
+	{
+		return OrderingCount::Exact(0)
+	}
+	let sum = samples.iter().map(|s| 10f64.powf(s - max)).sum::<f64>();
+	OrderingCount::Estimated(max + sum.log10() - (samples.len() as f64).log10())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                             Position heatmaps.                             //
+////////////////////////////////////////////////////////////////////////////////
+
+/// How often each section landed in each output position over many sampled
+/// shuffles, which reveals how the constraints actually shape the space of
+/// possible structures.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PositionHeatmap
+{
+	/// The number of times each section landed in each position, indexed first
+	/// by **zero-based** section and then by **zero-based** position.
+	pub(crate) counts: Vec<Vec<u32>>,
+
+	/// The number of sampled shuffles.
+	pub(crate) samples: u32,
+
+	/// The number of attempted shuffles that found no ordering.
+	pub(crate) failures: u32
+}
+
+impl PositionHeatmap
+{
+	/// Create an empty heatmap for the specified number of sections.
+	pub(crate) fn new(count: usize) -> Self
+	{
+		Self { counts: vec![vec![0; count]; count], samples: 0, failures: 0 }
+	}
+
+	/// Add the tallies of the specified heatmap, which must have the same
+	/// number of sections, to those of the receiver.
+	pub(crate) fn merge(&mut self, other: &Self)
+	{
+		for (mine, theirs) in self.counts.iter_mut().zip(&other.counts)
+		{
+			for (mine, theirs) in mine.iter_mut().zip(theirs)
+			{
+				*mine += *theirs;
+			}
+		}
+		self.samples += other.samples;
+		self.failures += other.failures;
+	}
+
+	/// Answer the fraction of the sampled shuffles in which the specified
+	/// section landed in the specified position, both **zero-based**.
+	pub(crate) fn frequency(&self, section: usize, position: usize) -> f32
+	{
+		if self.samples == 0
+		{
+			return 0.0
+		}
+		self.counts[section][position] as f32 / self.samples as f32
+	}
+}
+
+/// Attempt the specified number of shuffles of the specified number of
+/// sections, each drawn by `draw`, and tally the position of every section
+/// in a [heatmap](PositionHeatmap). `draw` answers an ordering as
+/// **zero-based** section indices, which may omit sections, or `None` if it
+/// found no ordering, which the heatmap counts as a
+/// [failure](PositionHeatmap::failures).
+pub(crate) fn sample_positions(
+	count: usize,
+	samples: usize,
+	draw: &mut impl FnMut(&mut StdRng) -> Option<Vec<usize>>,
+	rng: &mut StdRng
+) -> PositionHeatmap
+{
+	let mut heatmap = PositionHeatmap::new(count);
+	for _ in 0 .. samples
+	{
+		match draw(rng)
+		{
+			Some(order) =>
+			{
+				for (position, index) in order.into_iter().enumerate()
+				{
+					if let Some(slot) = heatmap.counts.get_mut(index)
+						.and_then(|counts| counts.get_mut(position))
+					{
+						*slot += 1;
+					}
+				}
+				heatmap.samples += 1;
+			},
+			None => heatmap.failures += 1
+		}
+	}
+	heatmap
+}
+
+/// A [heatmap](PositionHeatmap) under construction. On a native build, the
+/// samples are taken on a background thread, which stops once the job is
+/// dropped; on the web, which lacks threads, a batch of samples is taken
+/// whenever the job is [polled](Self::poll).
+pub(crate) struct HeatmapJob
+{
+	/// The heatmap so far.
+	pub(crate) heatmap: PositionHeatmap,
+
+	/// The total number of samples to take.
+	total: usize,
+
+	/// The receiver of batches of samples from the background thread.
+	#[cfg(not(target_arch = "wasm32"))]
+	receiver: mpsc::Receiver<PositionHeatmap>,
+
+	/// The number of samples that are uniform, if the samples are drawn by a
+	/// [sampler](UniformSampler) that can [run&#32;out](MAXIMUM_MIXING_BUDGET).
+	pub(crate) uniform_samples: Option<usize>,
+
+	/// The source of the sampled orderings.
+	#[cfg(target_arch = "wasm32")]
+	draw: Box<dyn FnMut(&mut StdRng) -> Option<Vec<usize>>>,
+
+	/// The random number generator.
+	#[cfg(target_arch = "wasm32")]
+	rng: StdRng
+}
+
+impl HeatmapJob
+{
+	/// Start taking the specified number of samples of the orderings of the
+	/// specified number of sections, as [drawn](sample_positions) by `draw`,
+	/// using the specified random number generator. Only the specified number
+	/// of [uniform&#32;samples](Self::uniform_samples), if any, are uniform.
+	pub(crate) fn start(
+		count: usize,
+		total: usize,
+		uniform_samples: Option<usize>,
+		draw: impl FnMut(&mut StdRng) -> Option<Vec<usize>>
+			+ Send
+			+ 'static,
+		rng: StdRng
+	) -> Self
+	{
+		let heatmap = PositionHeatmap::new(count);
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			let (sender, receiver) = mpsc::channel();
+			std::thread::spawn(move || {
+				let mut draw = draw;
+				let mut rng = rng;
+				let mut remaining = total;
+				while remaining != 0
+				{
+					let batch = remaining.min(HEATMAP_BATCH_SIZE);
+					let sample =
+						sample_positions(count, batch, &mut draw, &mut rng);
+					if sender.send(sample).is_err()
+					{
+						// The job was dropped, so nobody wants the rest.
+						break
+					}
+					remaining -= batch;
+				}
+			});
+			Self { heatmap, total, uniform_samples, receiver }
+		}
+		#[cfg(target_arch = "wasm32")]
+		{
+			Self {
+				heatmap,
+				total,
+				uniform_samples,
+				draw: Box::new(draw),
+				rng
+			}
+		}
+	}
+
+	/// Incorporate any samples taken since the last poll. Answer `true` if
+	/// every sample has been taken.
+	pub(crate) fn poll(&mut self) -> bool
+	{
+		#[cfg(not(target_arch = "wasm32"))]
+		while let Ok(batch) = self.receiver.try_recv()
+		{
+			self.heatmap.merge(&batch);
+		}
+		#[cfg(target_arch = "wasm32")]
+		if !self.is_complete()
+		{
+			let batch = (self.total - self.attempts()).min(HEATMAP_BATCH_SIZE);
+			let sample = sample_positions(
+				self.heatmap.counts.len(),
+				batch,
+				&mut self.draw,
+				&mut self.rng
+			);
+			self.heatmap.merge(&sample);
+		}
+		self.is_complete()
+	}
+
+	/// Answer `true` if every sample has been taken.
+	pub(crate) fn is_complete(&self) -> bool
+	{
+		self.attempts() >= self.total
+	}
+
+	/// Answer the fraction of the samples taken so far.
+	pub(crate) fn progress(&self) -> f32
+	{
+		self.attempts() as f32 / self.total.max(1) as f32
+	}
+
+	/// Answer the number of samples taken so far that may not be uniform,
+	/// because the [sampler](UniformSampler) exhausted its
+	/// [budget](MAXIMUM_MIXING_BUDGET) before drawing them.
+	pub(crate) fn skewed_samples(&self) -> usize
+	{
+		self.uniform_samples
+			.map_or(0, |uniform| self.attempts().saturating_sub(uniform))
+	}
+
+	/// Answer the number of samples attempted so far, whether or not they
+	/// found an ordering.
+	fn attempts(&self) -> usize
+	{
+		(self.heatmap.samples + self.heatmap.failures) as usize
+	}
+}
+
+/// Render the specified number with commas between groups of three digits.
+fn with_separators(n: u64) -> String
+{
+	let digits = n.to_string();
+	let mut result = String::new();
+	for (index, digit) in digits.chars().enumerate()
+	{
+		if index > 0 && (digits.len() - index) % 3 == 0
+		{
+			result.push(',');
+		}
+		result.push(digit);
+	}
+	result
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                            Implied constraints.                            //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the specified constraint graph orders the vertex `earlier`
+/// before the vertex `later` by way of at least one intervening vertex, such
+/// that any edge directly from `earlier` to `later` is redundant. The vertices
+/// are given by their **zero-based** indices. The graph must not contain a
+/// cycle.
+pub(crate) fn implied_indirectly(
+	graph: &DiGraph<usize, (), usize>,
+	earlier: usize,
+	later: usize
+) -> bool
+{
+	let mut seen = vec![false; graph.node_count()];
+	let mut pending = graph.neighbors(NodeIndex::new(earlier))
+		.filter(|node| node.index() != later)
+		.collect::<Vec<_>>();
+	while let Some(node) = pending.pop()
+	{
+		if node.index() == later
+		{
+			return true
+		}
+		if !std::mem::replace(&mut seen[node.index()], true)
+		{
+			pending.extend(graph.neighbors(node));
+		}
+	}
+	false
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                             Uniform sampling.                              //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A source of random orderings of the vertices of a constraint graph, such
+/// that every vertex follows its predecessors, and such that every such
+/// ordering is equally likely. The sampler prepares once and then samples
+/// many times, e.g., for the retries of a single shuffle.
+///
+/// Small graphs are sampled exactly, by [counting](ways_to_place) the
+/// orderings of every subset. Larger graphs are sampled by
+/// [mixing](mix), which is uniform only in the limit, but far less biased
+/// than peeling off random roots. The first sample mixes for
+/// <code>O(n<sup>3</sup> log n)</code> steps, up to
+/// [`MAXIMUM_MIXING_STEPS`]; each later sample continues the same chain for a
+/// fraction of that, and the whole sampler stops mixing in earnest once it has
+/// spent [`MAXIMUM_MIXING_BUDGET`] steps, so that large manuscripts still
+/// shuffle promptly.
+pub(crate) struct UniformSampler
+{
+	/// The constraint graph, which must not contain a cycle.
+	graph: DiGraph<usize, (), usize>,
+
+	/// The [ways](ways_to_place) to order each subset of the vertices, if the
+	/// graph is small enough to sample exactly.
+	ways: Option<Vec<u64>>,
+
+	/// The current state of the Markov chain, i.e., the most recent ordering,
+	/// if the graph is sampled by mixing.
+	chain: Vec<NodeIndex<usize>>,
+
+	/// The number of steps to mix before the next sample.
+	steps: usize,
+
+	/// The number of steps to mix before each sample after the first.
+	resample: usize,
+
+	/// The number of steps that remain in the budget.
+	budget: usize
+}
+
+impl UniformSampler
+{
+	/// Prepare to sample the orderings of the specified constraint graph,
+	/// which must not contain a cycle.
+	pub(crate) fn new(graph: DiGraph<usize, (), usize>) -> Self
+	{
+		let count = graph.node_count();
+		if count <= EXACT_COUNT_LIMIT
+		{
+			return Self {
+				ways: Some(ways_to_place(&graph)),
+				graph,
+				chain: vec![],
+				steps: 0,
+				resample: 0,
+				budget: 0
+			}
+		}
+		let log = usize::BITS - count.leading_zeros();
+		let steps = count.saturating_pow(3)
+			.saturating_mul(log as usize)
+			.min(MAXIMUM_MIXING_STEPS);
+		Self {
+			ways: None,
+			chain: toposort(&graph, None)
+				.expect("Constraint graph must not contain a cycle"),
+			graph,
+			steps,
+			resample: (steps / RESAMPLING_DIVISOR).max(count),
+			budget: MAXIMUM_MIXING_BUDGET
+		}
+	}
+
+	/// Draw an ordering at random. Answer the ordering as **zero-based**
+	/// section indices.
+	pub(crate) fn sample(&mut self, rng: &mut impl Rng) -> Vec<usize>
+	{
+		let order = match &self.ways
+		{
+			Some(ways) => uniform_order_exactly(&self.graph, ways, rng),
+			None =>
+			{
+				let count = self.chain.len();
+				let steps = self.steps.min(self.budget).max(count);
+				self.budget = self.budget.saturating_sub(steps);
+				mix(&self.graph, &mut self.chain, steps, rng);
+				// Later samples start from a well-mixed state, so they need
+				// far fewer steps.
+				self.steps = self.resample;
+				self.chain.clone()
+			}
+		};
+		order.into_iter()
+			.map(|index| self.graph[index] - 1)
+			.collect()
+	}
+
+	/// Answer how many more samples the sampler can draw before it exhausts
+	/// its [budget](MAXIMUM_MIXING_BUDGET), after which the samples favor
+	/// orderings near their predecessors, or `None` if the sampler samples
+	/// exactly and never runs out.
+	pub(crate) fn uniform_samples(&self) -> Option<usize>
+	{
+		if self.ways.is_some()
+		{
+			return None
+		}
+		if self.steps > self.budget
+		{
+			return Some(0)
+		}
+		Some(1 + (self.budget - self.steps) / self.resample)
+	}
+}
+
+/// Sample an ordering of the vertices of the specified constraint graph
+/// uniformly, by choosing the vertices from last to first: each vertex that
+/// may come last among those that remain is chosen in proportion to the
+/// number of [ways](ways_to_place) to order the others. The graph must not
+/// have more than [`EXACT_COUNT_LIMIT`] vertices.
+fn uniform_order_exactly(
+	graph: &DiGraph<usize, (), usize>,
+	ways: &[u64],
+	rng: &mut impl Rng
+) -> Vec<NodeIndex<usize>>
+{
+	let mut remaining = (1usize << graph.node_count()) - 1;
+	let mut order = vec![];
+	while remaining != 0
+	{
+		// Only a vertex without remaining successors leaves behind a set of
+		// vertices that can be ordered, so the weights of the others are
+		// zero.
+		let mut choice = rng.gen_range(0 .. ways[remaining]);
+		let vertex = (0 .. graph.node_count())
+			.filter(|vertex| remaining & (1 << vertex) != 0)
+			.find(|vertex| {
+				let weight = ways[remaining & !(1 << vertex)];
+				if choice < weight { true }
+				else
+				{
+					choice -= weight;
+					false
+				}
+			})
+			.unwrap();
+		remaining &= !(1 << vertex);
+		order.push(NodeIndex::new(vertex));
+	}
+	order.reverse();
+	order
+}
+
+/// Advance the specified valid ordering of the vertices of the specified
+/// constraint graph by the specified number of steps of the Markov chain of
+/// Karzanov and Khachiyan: choose an adjacent pair of vertices at random and,
+/// half of the time, exchange them, unless that would place a vertex before
+/// its predecessor. Adjacent vertices can only be ordered by an edge directly
+/// between them, so each step is cheap. Bubley and Dyer showed that the chain
+/// mixes in <code>O(n<sup>3</sup> log n)</code> steps.
+fn mix(
+	graph: &DiGraph<usize, (), usize>,
+	order: &mut [NodeIndex<usize>],
+	steps: usize,
+	rng: &mut impl Rng
+)
+{
+	if order.len() < 2
+	{
+		return
+	}
+	for _ in 0 .. steps
+	{
+		let position = rng.gen_range(0 .. order.len() - 1);
+		if rng.gen::<bool>()
+			&& graph.find_edge(order[position], order[position + 1]).is_none()
+		{
+			order.swap(position, position + 1);
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The largest graph, in vertices, whose orderings are counted exactly.
+/// Regardless of the constraints, the dynamic program requires
+/// <code>2<sup>n</sup></code> space.
+const EXACT_COUNT_LIMIT: usize = 16;
+
+/// The maximum number of steps that a [sampler](UniformSampler) mixes before
+/// its first sample.
+const MAXIMUM_MIXING_STEPS: usize = 8_000_000;
+
+/// The factor by which a [sampler](UniformSampler) reduces the steps that it
+/// mixes before each sample after the first.
+const RESAMPLING_DIVISOR: usize = 16;
+
+/// The maximum number of steps that a [sampler](UniformSampler) mixes in
+/// total, beyond which each sample takes only one step per vertex, which
+/// bounds the time to shuffle a large manuscript.
+const MAXIMUM_MIXING_BUDGET: usize = 16_000_000;
+
+/// The number of random trials to average when estimating the number of
+/// orderings.
+const ESTIMATE_SAMPLES: usize = 256;
+
+/// The seed for estimating the number of orderings.
+const ESTIMATE_SEED: u64 = 0x5707_5401;
+
+/// The number of samples per batch of a [heatmap&#32;job](HeatmapJob), which
+/// bounds the work done per frame on the web.
+const HEATMAP_BATCH_SIZE: usize = 250;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use std::collections::HashMap;
+	use crate::ordering::tests::graph;
+	use super::*;
+
+	/// Answer `true` if the specified ordering, given as **zero-based**
+	/// section indices, contains every section of the specified graph
+	/// exactly once and puts every section before its successors.
+	fn is_valid(order: &[usize], graph: &DiGraph<usize, (), usize>) -> bool
+	{
+		let mut sorted = order.to_vec();
+		sorted.sort_unstable();
+		sorted == (0 .. graph.node_count()).collect::<Vec<_>>()
+			&& graph.raw_edges().iter().all(|edge| {
+				let position = |index: NodeIndex<usize>| order.iter()
+					.position(|section| *section == index.index());
+				position(edge.source()) < position(edge.target())
+			})
+	}
+
+	#[test]
+	fn counts_small_graphs_exactly()
+	{
+		assert_eq!(count_orderings(&graph(4, &[])), OrderingCount::Exact(24));
+		assert_eq!(
+			count_orderings(&graph(4, &[(0, 1), (2, 3)])),
+			OrderingCount::Exact(6)
+		);
+		assert_eq!(
+			count_orderings(&graph(3, &[(0, 1), (1, 2)])),
+			OrderingCount::Exact(1)
+		);
+	}
+
+	#[test]
+	fn samples_small_graphs_uniformly()
+	{
+		// Of the 24 orderings of four sections, exactly 4 put the first
+		// before the second and the second before the third.
+		let graph = graph(4, &[(0, 1), (1, 2)]);
+		let mut sampler = UniformSampler::new(graph.clone());
+		let mut rng = StdRng::seed_from_u64(1);
+		let mut tallies = HashMap::<Vec<usize>, usize>::new();
+		for _ in 0 .. 8_000
+		{
+			let order = sampler.sample(&mut rng);
+			assert!(is_valid(&order, &graph), "{:?}", order);
+			*tallies.entry(order).or_default() += 1;
+		}
+		assert_eq!(tallies.len(), 4);
+		for (order, tally) in tallies
+		{
+			assert!(
+				(1_700 ..= 2_300).contains(&tally),
+				"{:?}: {}",
+				order,
+				tally
+			);
+		}
+	}
+
+	#[test]
+	fn samples_large_graphs_validly()
+	{
+		// Chain every other section, which leaves plenty of freedom.
+		let count = EXACT_COUNT_LIMIT + 8;
+		let edges = (0 .. count - 2)
+			.step_by(2)
+			.map(|index| (index, index + 2))
+			.collect::<Vec<_>>();
+		let graph = graph(count, &edges);
+		let mut sampler = UniformSampler::new(graph.clone());
+		let mut rng = StdRng::seed_from_u64(2);
+		let first = sampler.sample(&mut rng);
+		assert!(is_valid(&first, &graph), "{:?}", first);
+		let second = sampler.sample(&mut rng);
+		assert!(is_valid(&second, &graph), "{:?}", second);
+		assert_ne!(first, second);
+	}
+
+	#[test]
+	fn counts_the_samples_that_remain_uniform()
+	{
+		assert_eq!(UniformSampler::new(graph(4, &[])).uniform_samples(), None);
+		let mut sampler =
+			UniformSampler::new(graph(EXACT_COUNT_LIMIT + 8, &[]));
+		// Leave room for the first sample and one more.
+		sampler.budget = sampler.steps + sampler.resample;
+		let mut rng = StdRng::seed_from_u64(3);
+		assert_eq!(sampler.uniform_samples(), Some(2));
+		sampler.sample(&mut rng);
+		assert_eq!(sampler.uniform_samples(), Some(1));
+		sampler.sample(&mut rng);
+		assert_eq!(sampler.uniform_samples(), Some(0));
+		sampler.sample(&mut rng);
+		assert_eq!(sampler.uniform_samples(), Some(0));
+	}
+}
@@ -29,26 +29,177 @@
  * POSSIBILITY OF SUCH DAMAGE.
  */
 
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::iter::Peekable;
+use std::path::Path;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::str::Chars;
+use std::time::Duration;
 use eframe::{App, CreationContext, Frame};
+#[cfg(not(target_arch = "wasm32"))]
+use eframe::Storage;
 use eframe::emath::Align;
 use egui::
 {
+	Align2,
 	Button,
-	CentralPanel, Checkbox, Context,
-	hex_color,
-	Layout,
-	Response, RichText,
-	ScrollArea, SidePanel,
+	CentralPanel, Checkbox, Color32, Context,
+	DragValue,
+	Event,
+	FontId,
+	Id,
+	Key, KeyboardShortcut,
+	LayerId, Layout,
+	Modifiers,
+	Order,
+	Painter,
+	Rect, Response, RichText,
+	ScrollArea, Sense, Shape, SidePanel, Stroke,
 	TextEdit,
-	Ui
+	TextStyle, TopBottomPanel,
+	Ui,
+	vec2, Vec2,
+	Window
 };
+use egui::epaint::QuadraticBezierShape;
 use egui::scroll_area::ScrollAreaOutput;
-#[cfg(target_arch = "wasm32")]
-use egui::TopBottomPanel;
-use petgraph::{algo::all_simple_paths, graph::{DiGraph, NodeIndex}};
-use rand::{thread_rng, seq::SliceRandom};
+use egui::{CollapsingHeader, ComboBox, Grid};
+use petgraph::{
+	algo::{has_path_connecting, toposort},
+	graph::{DiGraph, NodeIndex}
+};
+use rand::{Rng, rngs::StdRng, SeedableRng, seq::SliceRandom};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::Level;
+use crate::analysis::{
+	count_orderings,
+	feasible_positions,
+	freedom,
+	Freedom,
+	HeatmapJob,
+	implied_indirectly,
+	OrderingCount,
+	PositionRange,
+	UniformSampler
+};
+#[cfg(target_arch = "wasm32")]
+use crate::archive::Archive;
+use crate::chapter::{
+	Chapter,
+	chapter_edges,
+	chapter_names,
+	chapter_policy,
+	ChapterPolicy
+};
+use crate::chronology::{suggest_constraints, Suggestion};
+use crate::config::Config;
+use crate::diagnostics::{clear_events, events_as_text, recent_events};
+use crate::diagram::{Diagram, DiagramNode, layout_diagram, NODE_RADIUS};
+use crate::docx::manuscript_to_docx;
+use crate::epub::{self, chapters_to_epub};
+use crate::export::{
+	Block,
+	blocks_to_markdown,
+	diagram_to_mermaid,
+	diagram_to_png,
+	diagram_to_svg,
+	edge_geometry,
+	iso_timestamp,
+	rows_to_csv,
+	unix_time
+};
+use crate::files::{
+	CSV,
+	DOCX,
+	EPUB,
+	FileKind,
+	HTML,
+	JSON,
+	MARKDOWN,
+	ODT,
+	PDF,
+	PNG,
+	read_dropped_file,
+	save_file,
+	SHUFFLE,
+	SVG,
+	TEXT
+};
+#[cfg(target_arch = "wasm32")]
+use crate::files::ZIP;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::files::{
+	choose_folder,
+	open_in_default_application,
+	open_text_file,
+	open_text_files,
+	read_folder,
+	RTF
+};
+use crate::html::{chapters_to_html, html_to_text, is_html};
+use crate::identity::{
+	fresh_section_ids,
+	match_sections,
+	match_sections_by_content,
+	section_id
+};
+use crate::matter::{
+	BEGIN_MARKER,
+	detect_matter,
+	END_MARKER,
+	Matter,
+	split_boilerplate,
+	with_boilerplate
+};
+use crate::odt::manuscript_to_odt;
+use crate::ordering::{
+	extreme_completion,
+	neighboring_order,
+	random_alternation,
+	random_block_order,
+	random_order,
+	random_pinned_order,
+	random_rotation,
+	sorted_order,
+	with_omissions_early
+};
+use crate::pdf::{blocks_to_pdf, manuscript_to_pdf, PageSize};
+use crate::script::{parse_script, Statement};
+use crate::sharing::{
+	decode_blob,
+	decode_project_file,
+	encode_blob,
+	encode_project_file
+};
+#[cfg(target_arch = "wasm32")]
+use crate::sharing::{
+	blob_from_fragment,
+	blob_link,
+	can_share,
+	forget_fragment,
+	share_text
+};
+#[cfg(all(test, not(target_arch = "wasm32")))]
+use crate::sharing::{blob_from_fragment, blob_link};
+use crate::spelling::{Dictionary, highlight_misspellings, misspellings, Speller};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::spelling::{load_dictionary, preferred_dictionary, system_dictionaries};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::storage::DirectoryStorage;
+use crate::theme::{palette, Palette};
+use crate::transitions::{
+	analyze_transitions,
+	chapter_breaks,
+	first_line,
+	last_line,
+	LOPSIDED_PENALTY,
+	lopsided_adjacencies,
+	total_score,
+	Transition
+};
 
 ////////////////////////////////////////////////////////////////////////////////
 //                             Application model.                             //
@@ -62,6 +213,11 @@ pub struct StoryShufflerApp
 	/// The original manuscript, prior to any mutation.
 	original_manuscript: String,
 
+	/// The name of the file from which the
+	/// [manuscript](Self::original_manuscript) was opened, if any, e.g., for
+	/// suggesting the names of saved files.
+	manuscript_file_name: Option<String>,
+
 	/// Whether the [section&#32;delimiter](Self::delimiter_pattern) should be
 	/// construed as a [regular&#32;expression](Regex).
 	delimiter_pattern_is_regex: bool,
@@ -73,30 +229,432 @@ pub struct StoryShufflerApp
 	/// an invalid [regular&#32;expression](Regex).
 	delimiter_regex_error: Option<String>,
 
+	/// How to split the manuscript into sections, i.e., at occurrences of the
+	/// [section&#32;delimiter](Self::delimiter_pattern) or at headings.
+	split_mode: SplitMode,
+
+	/// The deepest level of Markdown heading at which to
+	/// [split](SplitMode::Headings) the manuscript, e.g., `2` splits at `#`
+	/// and `##` headings, but not at `###` headings.
+	heading_level: usize,
+
+	/// Whether the manuscript is an anthology of distinct stories, separated by
+	/// the [story&#32;delimiter](Self::story_delimiter), whose sections must
+	/// stay together and in order, such that only the stories are shuffled.
+	anthology: bool,
+
+	/// The story delimiter of an [anthology](Self::anthology), as plain text.
+	story_delimiter: String,
+
 	/// The sections of the manuscript, in their original lexical order.
 	/// Whitespace is trimmed from the ends of each section.
 	original_sections: Vec<String>,
 
+	/// The text of each match of the
+	/// [section&#32;delimiter](Self::delimiter_pattern), in order, trimmed, if
+	/// it's a [regular&#32;expression](Self::delimiter_pattern_is_regex), so
+	/// that the [assembled&#32;manuscript](Self::assembled_manuscript) can
+	/// reuse the writer's own section breaks.
+	delimiter_matches: Vec<String>,
+
+	/// The **zero-based** index of the story of each section of an
+	/// [anthology](Self::anthology), in
+	/// [section&#32;order](Self::original_sections). Every section belongs to
+	/// the first story if the manuscript isn't an anthology.
+	section_stories: Vec<usize>,
+
 	/// The [constraints][Constraints] of each section, in
 	/// [section&#32;order](Self::original_sections).
 	constraints: Vec<Constraints>,
 
+	/// The [tags](Constraints::tags) whose sections must appear in strict
+	/// rotation when shuffling, as a comma-separated list, e.g., `Ana, Ben`
+	/// means that the sections tagged `Ana` and `Ben` must alternate,
+	/// beginning with `Ana`. Untagged sections may fall anywhere. Ignored for
+	/// [anthologies](Self::anthology).
+	rotation: String,
+
+	/// How strictly the sections of the [rotation](Self::rotation) tags
+	/// alternate.
+	rotation_mode: RotationMode,
+
+	/// The [constraints](TagConstraint) over every section with a particular
+	/// [tag](Constraints::tags).
+	tag_constraints: Vec<TagConstraint>,
+
+	/// The [chapters](Chapter) whose [policies](ChapterPolicy) the writer
+	/// chose. A chapter comprises the sections that name it as their
+	/// [chapter](Constraints::chapter); chapters that aren't listed here
+	/// follow the default policy. Ignored for
+	/// [anthologies](Self::anthology).
+	chapters: Vec<Chapter>,
+
+	/// The [layers](Layer) of constraints atop the
+	/// [constraints](Self::constraints), each of which the writer may toggle
+	/// before shuffling.
+	layers: Vec<Layer>,
+
+	/// The [scenarios](Scenario), i.e., the named sets of constraints over the
+	/// sections. There is always at least one.
+	scenarios: Vec<Scenario>,
+
+	/// The named [presets](ConstraintPreset) of constraints, which outlive
+	/// any particular manuscript.
+	presets: Vec<ConstraintPreset>,
+
+	/// The **zero-based** index of the active [scenario](Self::scenarios),
+	/// whose state is the [constraints](Self::constraints),
+	/// [rotation](Self::rotation) and its [mode](Self::rotation_mode),
+	/// [tag&#32;constraints](Self::tag_constraints),
+	/// [chapters](Self::chapters), [layers](Self::layers),
+	/// [current&#32;shuffle](Self::shuffled_section_indices), and
+	/// [shuffle&#32;history](Self::shuffle_history).
+	active_scenario: usize,
+
+	/// The stable [identifiers](section_id) of each section, in
+	/// [section&#32;order](Self::original_sections). Unlike section numbers,
+	/// identifiers survive edits elsewhere in the manuscript.
+	section_ids: Vec<String>,
+
+	/// Whether to display the [identifier](Self::section_ids) of each section
+	/// beside its number.
+	show_section_ids: bool,
+
 	/// The lazy [regular&#32;expression](Regex) for validating comma-separated
 	/// section numbers.
 	#[serde(skip)]
 	sections_regex: Option<Regex>,
 
-	/// The shuffled sections, as indices into the
+	/// The complete ordering of the shuffled sections, as indices into the
 	/// [original&#32;sections](Self::original_sections) of the _most recently
-	/// shuffled manuscript_. Note that this _does not_ have to be the current
-	/// manuscript, so this should only be used to index the list at the time of
-	/// shuffling.
+	/// shuffled manuscript_, including the [omitted](Constraints::omit)
+	/// sections. Note that this _does not_ have to be the current manuscript,
+	/// so this should only be used to index the list at the time of shuffling.
 	shuffled_section_indices: Option<Vec<usize>>,
 
+	/// The shuffled sections as [presented](Self::present_order), i.e., the
+	/// [complete&#32;ordering](Self::shuffled_section_indices) less the
+	/// [omitted](Constraints::omit) sections, with the
+	/// [repeated](Constraints::repeats) sections repeated, as indices into the
+	/// [original&#32;sections](Self::original_sections) of the _most recently
+	/// shuffled manuscript_.
+	presented_section_indices: Option<Vec<usize>>,
+
 	/// The lazy shuffled sections, as copies of the
 	/// [original&#32;sections](Self::original_sections), maintained in lockstep
-	/// with [shuffled_section_indices](Self::shuffled_section_indices).
-	shuffled_sections: Option<Vec<String>>
+	/// with [presented_section_indices](Self::presented_section_indices).
+	shuffled_sections: Option<Vec<String>>,
+
+	/// The recent orderings of the
+	/// [original&#32;sections](Self::original_sections) within the active
+	/// [scenario](Self::scenarios), as **zero-based** section indices, from
+	/// oldest to newest. Like the
+	/// [current&#32;shuffle](Self::shuffled_section_indices), an ordering may
+	/// predate the current manuscript.
+	shuffle_history: Vec<Vec<usize>>,
+
+	/// The [record](ShuffleRecord) of every random reordering, across every
+	/// [scenario](Self::scenarios), from oldest to newest, for
+	/// [export](Self::export_shuffle_log). The oldest records are forgotten
+	/// once the log grows too long.
+	shuffle_log: Vec<ShuffleRecord>,
+
+	/// The lazy [analyses](Transition) of the transitions between adjacent
+	/// [shuffled&#32;sections](Self::shuffled_sections).
+	#[serde(skip)]
+	transitions: Option<Vec<Transition>>,
+
+	/// How to present the [shuffled&#32;sections](Self::shuffled_sections).
+	output_view: OutputView,
+
+	/// The section attribute by which to [sort](Self::sort) the sections.
+	sort_key: SortKey,
+
+	/// Whether [sorting](Self::sort) should shuffle sections that tie on the
+	/// [sort&#32;key](Self::sort_key), rather than keep them in their original
+	/// order, e.g., to keep days in order but scramble the scenes within each
+	/// day.
+	shuffle_ties: bool,
+
+	/// The number of sections to select for an [excerpt](Self::excerpt).
+	excerpt_length: usize,
+
+	/// The number of distinct shuffles to
+	/// [generate](Self::generate_variants) as variants.
+	variant_count: usize,
+
+	/// The number of random orderings to produce per shuffle, of which only
+	/// the one with the best-scoring [transitions](Transition) is kept.
+	shuffle_attempts: usize,
+
+	/// Whether to discourage orderings that place the two longest sections,
+	/// or the two shortest sections, side by side, when choosing the best of
+	/// several [attempts](Self::shuffle_attempts).
+	balance_lengths: bool,
+
+	/// Whether to draw each random ordering [uniformly](UniformSampler) from
+	/// all of the orderings that honor the constraint graph, rather than by
+	/// peeling off random roots, which favors some orderings over others.
+	uniform_sampling: bool,
+
+	/// The source of randomness for shuffling, which the
+	/// [configuration](Config) may seed.
+	#[serde(skip)]
+	rng: StdRng,
+
+	/// Whether to group the shuffled sections of a
+	/// [new&#32;manuscript](Self::assembled_manuscript) into chapters, each
+	/// introduced by a generated heading. Never applies to an
+	/// [anthology](Self::anthology).
+	chapterize: bool,
+
+	/// The target length of each chapter, in words.
+	chapter_length: usize,
+
+	/// The text that separates the shuffled sections of a
+	/// [new&#32;manuscript](Self::assembled_manuscript), if it should override
+	/// the one derived from the section delimiter.
+	output_separator: Option<String>,
+
+	/// The size of the pages of exported PDF documents.
+	page_size: PageSize,
+
+	/// The status colors.
+	palette: Palette,
+
+	/// Whether the colors window is open.
+	#[serde(skip)]
+	colors_window_open: bool,
+
+	/// Whether the diagnostics panel is open.
+	diagnostics_open: bool,
+
+	/// Whether the position heatmap window is open.
+	#[serde(skip)]
+	heatmap_window_open: bool,
+
+	/// The sampling of shuffles for the position heatmap, if it has been
+	/// started.
+	#[serde(skip)]
+	heatmap: Option<HeatmapJob>,
+
+	/// Whether the scenario comparison window is open.
+	#[serde(skip)]
+	comparison_window_open: bool,
+
+	/// The most recent [comparison](ScenarioComparison) of the
+	/// [scenarios](Self::scenarios), one per scenario, if any.
+	#[serde(skip)]
+	comparison: Option<Vec<ScenarioComparison>>,
+
+	/// The indices of the two [scenarios](Self::scenarios) whose sample
+	/// shuffles the comparison [window](Self::comparison_window_open) sets
+	/// side by side.
+	#[serde(skip)]
+	compared_pair: (usize, usize),
+
+	/// The number of [valid&#32;orderings](OrderingCount) under the
+	/// [constraint&#32;graph](Self::constraint_graph), together with the
+	/// [shape](OrderingCountKey) of the graph from which it was counted, so
+	/// that it's recounted only when the graph changes.
+	#[serde(skip)]
+	ordering_count: Option<(OrderingCountKey, OrderingCount)>,
+
+	/// The outcome of the most recent file operation, if any.
+	#[serde(skip)]
+	status: Option<StatusMessage>,
+
+	/// The outcome of the most recent
+	/// [import&#32;of&#32;constraints](Self::import_constraints), if any, for
+	/// presentation beside the constraints.
+	#[serde(skip)]
+	constraints_report: Option<ConstraintsReport>,
+
+	/// Whether the writer is reviewing someone else's [project](Project), in
+	/// which case the manuscript and constraints are read-only, though
+	/// shuffling is still permitted.
+	review_mode: bool,
+
+	/// Whether the window for pasting a [project](Project) is open.
+	#[serde(skip)]
+	paste_project_window_open: bool,
+
+	/// The workspace for pasting a [project](Project) from the clipboard.
+	#[serde(skip)]
+	pasted_project: String,
+
+	/// The error to present if the [pasted&#32;project](Self::pasted_project)
+	/// couldn't be decoded.
+	#[serde(skip)]
+	pasted_project_error: Option<String>,
+
+	/// Whether the window for importing a manuscript from the clipboard is
+	/// open.
+	#[serde(skip)]
+	import_window_open: bool,
+
+	/// The workspace for importing a manuscript from the clipboard.
+	#[serde(skip)]
+	pasted_manuscript: String,
+
+	/// Whether the window for [generating](Self::generate_variants) variants
+	/// is open.
+	#[serde(skip)]
+	variants_window_open: bool,
+
+	/// Whether the window that draws the [constraints](Self::constraints) as
+	/// a [diagram](Diagram) is open.
+	#[serde(skip)]
+	graph_window_open: bool,
+
+	/// The **zero-based** index of the section whose constraints are
+	/// highlighted in the [graph&#32;window](Self::graph_window_open), if any.
+	#[serde(skip)]
+	selected_graph_node: Option<usize>,
+
+	/// Whether the window for editing the [constraints](Self::constraints) as
+	/// a [script](Self::script) is open.
+	#[serde(skip)]
+	script_window_open: bool,
+
+	/// The workspace for editing the [constraints](Self::constraints) as a
+	/// constraint script, which [`parse_script`] reads.
+	#[serde(skip)]
+	script: String,
+
+	/// Whether the window for saving and reapplying
+	/// [presets](Self::presets) is open.
+	#[serde(skip)]
+	presets_window_open: bool,
+
+	/// The name under which to save the current constraints as a
+	/// [preset](Self::presets).
+	#[serde(skip)]
+	preset_name: String,
+
+	/// The ordering constraints [suggested](suggest_constraints) by the
+	/// temporal cues of the sections, which await the writer's acceptance or
+	/// dismissal.
+	#[serde(skip)]
+	suggestions: Vec<Suggestion>,
+
+	/// Whether the window for reviewing the [suggestions](Self::suggestions)
+	/// is open.
+	#[serde(skip)]
+	suggestions_window_open: bool,
+
+	/// The [suggestions](Self::suggestions) that the writer dismissed, as
+	/// pairs of [section&#32;identifiers](Self::section_ids), earlier first,
+	/// so that they aren't suggested again, even if the sections move.
+	dismissed_suggestions: Vec<(String, String)>,
+
+	/// Whether the writer asked to [clear](Self::clear_constraints) every
+	/// section's constraints, which awaits confirmation.
+	#[serde(skip)]
+	clear_constraints_pending: bool,
+
+	/// The earlier states of the [constraints](Self::constraints), oldest
+	/// first, to which [undo](Self::undo_constraint_edit) returns.
+	#[serde(skip)]
+	constraint_undo: Vec<Vec<Constraints>>,
+
+	/// The states of the [constraints](Self::constraints) that were undone,
+	/// most recently undone last, to which
+	/// [redo](Self::redo_constraint_edit) returns.
+	#[serde(skip)]
+	constraint_redo: Vec<Vec<Constraints>>,
+
+	/// The [constraints](Self::constraints) as of the most recently
+	/// [recorded](Self::track_constraint_edits) edit, and the
+	/// [scenario](Self::active_scenario) to which they belong.
+	#[serde(skip)]
+	constraint_baseline: (Vec<Constraints>, usize),
+
+	/// The syntax errors of the [script](Self::script), or the problems that
+	/// prevented its application.
+	#[serde(skip)]
+	script_errors: Vec<String>,
+
+	/// Whether the profiler window is open. Profiling scopes are only active
+	/// while it is.
+	#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+	#[serde(skip)]
+	profiler_open: bool,
+
+	/// How often to persist the application state automatically, in seconds.
+	autosave_interval: u64,
+
+	/// Whether the writer has asked to persist the application state right
+	/// away.
+	#[serde(skip)]
+	save_requested: bool,
+
+	/// The [time](egui::InputState::time) of the current frame, in seconds
+	/// since the application started.
+	#[serde(skip)]
+	now: f64,
+
+	/// The [time](Self::now) at which the application state was last
+	/// persisted, if ever during this session.
+	#[serde(skip)]
+	last_saved: Option<f64>,
+
+	/// Whether to check the spelling of the
+	/// [manuscript](Self::original_manuscript).
+	spellcheck: bool,
+
+	/// The name of the selected system [dictionary](Dictionary), if any.
+	/// Always `None` on the web, where no system dictionaries are available.
+	spelling_dictionary: Option<String>,
+
+	/// The writer's personal dictionary, as a word list with one word per
+	/// line.
+	personal_dictionary: String,
+
+	/// Whether the spelling window is open.
+	#[serde(skip)]
+	spelling_window_open: bool,
+
+	/// The lazy [speller](Speller), built from the
+	/// [selected&#32;dictionary](Self::spelling_dictionary) and the
+	/// [personal&#32;dictionary](Self::personal_dictionary).
+	#[serde(skip)]
+	speller: Option<Speller>,
+
+	/// The lazy [system&#32;dictionaries](system_dictionaries), as pairs of
+	/// names and paths.
+	#[cfg(not(target_arch = "wasm32"))]
+	#[serde(skip)]
+	system_dictionaries: Option<Vec<(String, PathBuf)>>,
+
+	/// Whether privacy mode is enabled, i.e., whether the text of the
+	/// manuscript is withheld from persistence. Everything keyed to its
+	/// sections is still persisted, and restored when the writer supplies the
+	/// manuscript again.
+	private: bool,
+
+	/// The constraints of a manuscript whose text was withheld from
+	/// persistence by [privacy&#32;mode](Self::private), if the writer hasn't
+	/// supplied it again yet.
+	withheld: Option<WithheldManuscript>,
+
+	/// Whether the application state is neither loaded nor saved during this
+	/// session, e.g., for a demonstration on a shared machine.
+	#[serde(skip)]
+	ephemeral: bool,
+
+	/// The [storage](DirectoryStorage) in the configured directory, if any,
+	/// which supplants the framework's own storage.
+	#[cfg(not(target_arch = "wasm32"))]
+	#[serde(skip)]
+	custom_storage: Option<DirectoryStorage>,
+
+	/// The address of the page that hosts the application, for
+	/// [links](Self::copy_project_link) to projects. Only available on the
+	/// web.
+	#[cfg(target_arch = "wasm32")]
+	#[serde(skip)]
+	page_url: String
 }
 
 impl Default for StoryShufflerApp
@@ -105,14 +663,100 @@ impl Default for StoryShufflerApp
 	{
 		Self {
 			original_manuscript: Default::default(),
+			manuscript_file_name: None,
 			delimiter_pattern_is_regex: false,
 			delimiter_pattern: DEFAULT_DELIMITER_PATTERN.to_string(),
 			delimiter_regex_error: None,
+			split_mode: SplitMode::Delimiter,
+			heading_level: MAXIMUM_HEADING_LEVEL,
+			anthology: false,
+			story_delimiter: DEFAULT_STORY_DELIMITER.to_string(),
 			original_sections: vec![],
+			delimiter_matches: vec![],
+			section_stories: vec![],
 			constraints: vec![],
+			rotation: String::new(),
+			rotation_mode: RotationMode::Strict,
+			tag_constraints: vec![],
+			layers: vec![],
+			chapters: vec![],
+			scenarios: vec![Scenario::named(DEFAULT_SCENARIO_NAME)],
+			presets: vec![],
+			active_scenario: 0,
+			section_ids: vec![],
+			show_section_ids: false,
 			sections_regex: Some(Regex::new(SECTIONS_LIST_PATTERN).unwrap()),
 			shuffled_section_indices: None,
-			shuffled_sections: None
+			presented_section_indices: None,
+			shuffled_sections: None,
+			shuffle_history: vec![],
+			shuffle_log: vec![],
+			transitions: None,
+			output_view: OutputView::Sections,
+			sort_key: SortKey::Label,
+			shuffle_ties: false,
+			excerpt_length: DEFAULT_EXCERPT_LENGTH,
+			variant_count: DEFAULT_VARIANT_COUNT,
+			shuffle_attempts: 1,
+			balance_lengths: false,
+			uniform_sampling: false,
+			rng: StdRng::from_entropy(),
+			chapterize: false,
+			chapter_length: DEFAULT_CHAPTER_LENGTH,
+			output_separator: None,
+			page_size: PageSize::Letter,
+			palette: Palette::STANDARD,
+			colors_window_open: false,
+			diagnostics_open: false,
+			heatmap_window_open: false,
+			heatmap: None,
+			comparison_window_open: false,
+			comparison: None,
+			compared_pair: (0, 1),
+			ordering_count: None,
+			status: None,
+			constraints_report: None,
+			review_mode: false,
+			paste_project_window_open: false,
+			pasted_project: String::new(),
+			pasted_project_error: None,
+			import_window_open: false,
+			pasted_manuscript: String::new(),
+			variants_window_open: false,
+			graph_window_open: false,
+			selected_graph_node: None,
+			script_window_open: false,
+			presets_window_open: false,
+			preset_name: String::new(),
+			suggestions: vec![],
+			suggestions_window_open: false,
+			dismissed_suggestions: vec![],
+			clear_constraints_pending: false,
+			constraint_undo: vec![],
+			constraint_redo: vec![],
+			constraint_baseline: (vec![], 0),
+			script: String::new(),
+			script_errors: vec![],
+			#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+			profiler_open: false,
+			autosave_interval: DEFAULT_AUTOSAVE_INTERVAL,
+			save_requested: false,
+			now: 0.0,
+			last_saved: None,
+			spellcheck: false,
+			spelling_dictionary: None,
+			personal_dictionary: String::new(),
+			spelling_window_open: false,
+			speller: None,
+			#[cfg(not(target_arch = "wasm32"))]
+			system_dictionaries: None,
+			private: false,
+			withheld: None,
+			ephemeral: false,
+			#[cfg(not(target_arch = "wasm32"))]
+			custom_storage: None,
+			#[cfg(target_arch = "wasm32")]
+			page_url: String::new()
 		}
 	}
 }
@@ -124,633 +768,10720 @@ impl StoryShufflerApp
 	/// look-and-feel of [`egui`] as appropriate. Load any custom fonts.
 	pub fn new(cc: &CreationContext<'_>) -> Self
 	{
-		if let Some(storage) = cc.storage
+		Self::with_config(cc, Default::default())
+	}
+
+	/// Create the application state as by [`new`](Self::new), but according
+	/// to the specified [configuration](Config). Its defaults only apply to
+	/// fresh application state, so that they never clobber the writer's
+	/// persisted choices, but its theme, seed, and storage directory always
+	/// apply.
+	pub fn with_config(cc: &CreationContext<'_>, config: Config) -> Self
+	{
+		#[cfg(not(target_arch = "wasm32"))]
+		let ephemeral = config.ephemeral;
+		#[cfg(target_arch = "wasm32")]
+		let ephemeral = config.ephemeral
+			|| cc.integration_info.web_info.location.query_map
+				.contains_key(NO_PERSIST_PARAMETER);
+		#[cfg(not(target_arch = "wasm32"))]
+		let custom_storage = config.storage_directory.as_deref()
+			.filter(|_| !ephemeral)
+			.and_then(|directory| match DirectoryStorage::open(directory)
+			{
+				Ok(storage) => Some(storage),
+				Err(e) =>
+				{
+					tracing::warn!("Ignoring the storage directory: {}", e);
+					None
+				}
+			});
+		// Prefer the custom storage, but fall back on the framework's, e.g.,
+		// so that the previous state migrates into a new storage directory.
+		#[cfg(not(target_arch = "wasm32"))]
+		let stored = custom_storage.as_ref().and_then(|storage|
+			eframe::get_value::<Self>(storage, eframe::APP_KEY)
+		);
+		#[cfg(target_arch = "wasm32")]
+		let stored = None;
+		let stored = stored.or_else(|| cc.storage
+			.filter(|_| !ephemeral)
+			.and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+		);
+		let mut app = match stored
 		{
-			// Attempt to load the previous application state, falling back on
-			// fresh application state if retrieval fails.
-			Self
+			// Restore the previous application state.
+			Some(app) => Self
 			{
 				sections_regex: Some(
 					Regex::new(SECTIONS_LIST_PATTERN).unwrap()
 				),
-				..eframe::get_value(
-					storage,
-					eframe::APP_KEY
-				).unwrap_or_default()
+				..app
+			},
+			// Create fresh application state, using the configured defaults.
+			None => Self
+			{
+				delimiter_pattern: config.delimiter
+					.unwrap_or_else(|| DEFAULT_DELIMITER_PATTERN.to_string()),
+				delimiter_pattern_is_regex: config.delimiter_is_regex,
+				output_separator: config.output_separator,
+				..Default::default()
 			}
+		};
+		app.ephemeral = ephemeral;
+		if app.withheld.is_some() && app.original_sections.is_empty()
+		{
+			app.status = Some(StatusMessage {
+				text: "Privacy mode didn't save your manuscript. Paste or open \
+					it again to restore its constraints.".to_string(),
+				is_error: false
+			});
 		}
-		else
+		if let Some(seed) = config.seed
+		{
+			app.rng = StdRng::seed_from_u64(seed);
+		}
+		if let Some(theme) = config.theme
+		{
+			cc.egui_ctx.set_visuals(theme.visuals());
+		}
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			app.custom_storage = custom_storage;
+		}
+		if app.section_ids.len() != app.original_sections.len()
+		{
+			// The state predates section identifiers, so mint them now.
+			app.section_ids = fresh_section_ids(&app.original_sections);
+		}
+		if app.section_stories.len() != app.original_sections.len()
+		{
+			// The state predates anthologies, so every section belongs to the
+			// first story.
+			app.section_stories = vec![0; app.original_sections.len()];
+		}
+		if app.scenarios.is_empty()
+		{
+			app.scenarios.push(Scenario::named(DEFAULT_SCENARIO_NAME));
+		}
+		app.active_scenario = app.active_scenario.min(app.scenarios.len() - 1);
+		#[cfg(target_arch = "wasm32")]
+		if cc.integration_info.web_info.location.query_map
+			.contains_key(REVIEW_PARAMETER)
 		{
-			// Storage is not available, so create fresh application state.
-			Default::default()
+			app.review_mode = true;
 		}
+		#[cfg(target_arch = "wasm32")]
+		{
+			let location = &cc.integration_info.web_info.location;
+			app.page_url = location.url.clone();
+			if let Some(blob) = blob_from_fragment(&location.hash)
+			{
+				app.adopt_project_link(&blob);
+			}
+		}
+		if app.spellcheck
+		{
+			// The speller is not persistent, so rebuild it now.
+			app.ensure_speller();
+		}
+		app
 	}
 
 	/// Recompute the manuscript's sections. This might be a consequence of:
 	/// * Changing the [intent](Self::delimiter_pattern_is_regex) of the
 	///   pattern.
 	/// * Changing the [pattern](Self::delimiter_pattern).
+	/// * Changing the [split&#32;mode](Self::split_mode).
 	/// * Changing the [manuscript](Self::original_manuscript).
 	pub(crate) fn update_sections(&mut self)
 	{
-		if self.delimiter_pattern.is_empty()
-		{
-			self.delimiter_regex_error = None;
-			self.original_sections = vec![];
-			self.constraints = vec![];
-		}
-		else if self.delimiter_pattern_is_regex
+		profile_function!();
+		self.delimiter_regex_error = None;
+		let regex = if self.delimiter_pattern_is_regex
+			&& !self.delimiter_pattern.is_empty()
+			&& self.split_mode == SplitMode::Delimiter
 		{
 			match Regex::new(&self.delimiter_pattern)
 			{
-				Ok(regex) =>
-				{
-					self.delimiter_regex_error = None;
-					self.original_sections =
-						regex.split(&self.original_manuscript)
-							.map(|section| section.trim().to_string())
-							.collect();
-					self.constraints = vec![
-						Constraints::default();
-						self.original_sections.len()
-					];
-				},
+				Ok(regex) => Some(regex),
 				Err(e) =>
 				{
 					self.delimiter_regex_error = Some(e.to_string());
-					self.original_sections = vec![];
-					self.constraints = vec![];
+					self.adopt_sections(vec![], vec![]);
+					return
 				}
 			}
 		}
-		else
+		else { None };
+		let mut sections = vec![];
+		let mut stories = vec![];
+		self.capture_delimiter_matches(regex.as_ref());
+		if self.split_mode != SplitMode::Delimiter
+			|| !self.delimiter_pattern.is_empty()
 		{
-			self.delimiter_regex_error = None;
-			self.original_sections =
-				self.original_manuscript.split(&self.delimiter_pattern)
-					.map(|section| section.trim().to_string())
-					.collect();
-			self.constraints = vec![
-				Constraints::default();
-				self.original_sections.len()
-			];
+			for (story, text) in self.story_texts().iter().enumerate()
+			{
+				let before = sections.len();
+				match (self.split_mode, &regex)
+				{
+					(SplitMode::Headings, _) => sections.extend(
+						split_at_headings(text, self.heading_level)
+							.map(|section| section.trim().to_string())
+					),
+					(SplitMode::Scenes, _) => sections.extend(
+						split_at_scenes(text)
+							.map(|section| section.trim().to_string())
+					),
+					(SplitMode::Delimiter, Some(regex)) => sections.extend(
+						regex.split(text)
+							.map(|section| section.trim().to_string())
+					),
+					(_, None) => sections.extend(
+						text.split(self.delimiter_pattern.as_str())
+							.map(|section| section.trim().to_string())
+					)
+				}
+				stories.resize(stories.len() + sections.len() - before, story);
+			}
 		}
+		self.adopt_sections(sections, stories);
 	}
-}
-
-////////////////////////////////////////////////////////////////////////////////
-//                                Constraints.                                //
-////////////////////////////////////////////////////////////////////////////////
-
-/// The constraints placed upon a
-/// [manuscript](StoryShufflerApp::original_manuscript)
-/// [section](StoryShufflerApp::original_sections). Pseudorandom permutation of
-/// the section order must honor the constraints of each section.
-#[derive(Clone, Serialize, Deserialize)]
-struct Constraints
-{
-	/// Whether the associated
-	/// [manuscript](StoryShufflerApp::original_manuscript)
-	/// [section](StoryShufflerApp::original_sections) is locked in place.
-	fixed: bool,
-
-	/// The sections which must occur _strictly after_ the associated
-	/// [section](StoryShufflerApp::original_sections), e.g., for reasons of
-	/// narrative causality, denoted by their **one-based** indices.
-	before: Vec<usize>,
-
-	/// The workspace for in-process edits of [`before`](Self::before).
-	text_buffer: String,
-
-	/// The [text&#32;buffer](Self::text_buffer) is _prima facie_ valid, i.e.,
-	/// it satisfies its lexical requirements if not its semantic ones. Defaults
-	/// to `true`, because an empty buffer is well-formed (and even semantically
-	/// valid).
-	text_buffer_is_valid: bool,
-
-	/// The message to present if a paradox is discovered, i.e., because the
-	/// ordering constraints lead to a cycle.
-	paradox_error: Option<String>
-}
 
-impl Default for Constraints
-{
-	fn default() -> Self
+	/// Answer the texts of the stories of an [anthology](Self::anthology), in
+	/// order, or else the body of the whole
+	/// [manuscript](Self::original_manuscript), i.e., without its
+	/// [boilerplate](split_boilerplate). An anthology is split into its
+	/// stories before each story is split into its sections.
+	fn story_texts(&self) -> Vec<&str>
 	{
-		Self
+		let (_, body, _) = split_boilerplate(&self.original_manuscript);
+		if self.anthology && !self.story_delimiter.is_empty()
 		{
-			fixed: false,
-			before: vec![],
-			text_buffer: String::new(),
-			text_buffer_is_valid: true,
-			paradox_error: None
+			body.split(self.story_delimiter.as_str()).collect()
+		}
+		else
+		{
+			vec![body]
 		}
 	}
-}
 
-/// Create a directed graph that represents the specified
-/// [constraints](Constraints), such that each vertex encodes an index into the
-/// supplied slice and each edge represents the predecessor being lexically
-/// [prior](Constraints::before) to the successor.
-fn compute_graph(constraints: &[Constraints]) -> DiGraph<usize, (), usize>
-{
-	let mut graph = DiGraph::default();
-	let count = constraints.len();
-	if count == 0
-	{
-		// There are no constraints, so save some time and ceremony.
-		return graph
-	}
-	// For simplicity, build the nodes up front.
-	for (index, _) in constraints.iter().enumerate()
+	/// Capture the [matches](Self::delimiter_matches) of the specified
+	/// [section&#32;delimiter](Self::delimiter_pattern), if it's a regular
+	/// expression.
+	fn capture_delimiter_matches(&mut self, regex: Option<&Regex>)
 	{
-		// Adjust the index to one-based.
-		graph.add_node(index + 1);
+		self.delimiter_matches = match regex
+		{
+			Some(regex) => self.story_texts().iter()
+				.flat_map(|text| regex.find_iter(text))
+				.map(|m| m.as_str().trim().to_string())
+				.collect(),
+			None => vec![]
+		};
 	}
-	// Now create all of the edges.
-	for (index, c) in constraints.iter().enumerate()
+
+	/// Replace the [sections](Self::original_sections) with the specified
+	/// ones. Each new section that [matches](match_sections) an old section
+	/// inherits its [identifier](Self::section_ids) and
+	/// [constraints](Self::constraints), with any section numbers rewritten to
+	/// follow their sections to their new positions. Any other section gets a
+	/// fresh identifier and no constraints. `stories` supplies the
+	/// [story](Self::section_stories) of each new section.
+	fn adopt_sections(&mut self, sections: Vec<String>, stories: Vec<usize>)
 	{
-		if index == 0 && c.fixed
+		let matches = match self.withheld.take()
 		{
-			// Handle a fixed beginning specially.
-			for (successor, _) in constraints.iter().enumerate().skip(1)
+			// The manuscript returns after privacy mode withheld it, so
+			// recognize its sections by their hashes, but never by position,
+			// lest a different manuscript inherit the constraints.
+			Some(withheld)
+				if self.original_sections.is_empty() && !sections.is_empty() =>
 			{
-				graph.update_edge(
-					NodeIndex::new(index),
-					NodeIndex::new(successor),
-					()
+				self.section_ids = withheld.section_ids;
+				self.constraints = withheld.constraints;
+				match_sections_by_content(
+					&withheld.hashes,
+					&fresh_section_ids(&sections)
+				)
+			},
+			withheld =>
+			{
+				self.withheld = withheld;
+				match_sections(&self.original_sections, &sections)
+			}
+		};
+		let old_count = self.constraints.len();
+		self.constraints = remap_constraints(&self.constraints, &matches);
+		self.tag_constraints =
+			remap_tag_constraints(&self.tag_constraints, old_count, &matches);
+		// The constraints of the other scenarios follow the sections too.
+		for scenario in &mut self.scenarios
+		{
+			if !scenario.constraints.is_empty()
+			{
+				scenario.tag_constraints = remap_tag_constraints(
+					&scenario.tag_constraints,
+					scenario.constraints.len(),
+					&matches
 				);
+				scenario.constraints =
+					remap_constraints(&scenario.constraints, &matches);
 			}
 		}
-		if index == count - 1 && constraints.last().unwrap().fixed
+		// So does the edit history, unless it belongs to other sections.
+		if self.constraint_baseline.0.len() == self.original_sections.len()
 		{
-			// Handle a fixed ending specially.
-			for (predecessor, _) in constraints
-				.iter().enumerate().take(count - 1)
+			for constraints in self.constraint_undo.iter_mut()
+				.chain(&mut self.constraint_redo)
+				.chain(Some(&mut self.constraint_baseline.0))
 			{
-				graph.update_edge(
-					NodeIndex::new(predecessor),
-					NodeIndex::new(index),
-					()
-				);
+				*constraints = remap_constraints(constraints, &matches);
 			}
 		}
-		for successor in &c.before
+		else
 		{
-			// Adjust the target index to zero-based (because it is
-			// one-based).
-			graph.update_edge(
-				NodeIndex::new(index),
-				NodeIndex::new(*successor - 1),
-				()
-			);
+			self.forget_constraint_edits();
+		}
+		// The suggestions follow the sections too, unless their sections
+		// vanished.
+		let mut moved = vec![None; self.original_sections.len()];
+		for (new, old) in matches.iter().enumerate()
+		{
+			if let Some(old) = old.filter(|old| *old < moved.len())
+			{
+				moved[old] = Some(new);
+			}
+		}
+		self.suggestions.retain_mut(|s| {
+			match (moved.get(s.earlier), moved.get(s.later))
+			{
+				(Some(Some(earlier)), Some(Some(later))) =>
+				{
+					s.earlier = *earlier;
+					s.later = *later;
+					true
+				},
+				_ => false
+			}
+		});
+		// Keep the inherited identifiers, then mint fresh ones for the rest.
+		let mut ids = matches.iter()
+			.map(|old| old.and_then(|old| self.section_ids.get(old).cloned()))
+			.collect::<Vec<_>>();
+		let mut taken = ids.iter().flatten().cloned().collect::<HashSet<_>>();
+		for (id, section) in ids.iter_mut().zip(&sections)
+		{
+			if id.is_none()
+			{
+				let fresh = section_id(section, &taken);
+				taken.insert(fresh.clone());
+				*id = Some(fresh);
+			}
 		}
+		self.section_ids = ids.into_iter().flatten().collect();
+		self.original_sections = sections;
+		self.section_stories = stories;
 	}
-	graph
 }
 
-/// Find any cycles from the [constraint](Constraints) specified by `index`.
-/// If nonempty, the answered [`Vec`] begins and ends with `index`; if empty,
-/// then no cycles were found.
-fn find_cycle(
-	graph: &DiGraph<usize, (), usize>,
-	index: NodeIndex<usize>
-) -> Vec<Vec<NodeIndex<usize>>>
-{
-	// Contrary to what the documentation says, this does not reliably return
-	// shortest paths, so patch up the answer before answering.
-	all_simple_paths(
-		graph,
-		index,
-		index,
-		0,
-		None
-	).collect()
+/// Split the specified Markdown text immediately before each heading whose
+/// level doesn't exceed `level`, such that each heading begins its own piece.
+/// Only ATX headings, like `## Scene`, are recognized, and never within fenced
+/// code blocks. Any text before the first heading is its own piece, unless
+/// it's blank.
+fn split_at_headings(text: &str, level: usize) -> impl Iterator<Item = &str>
+{
+	let mut fence: Option<&str> = None;
+	split_before_lines(text, move |line| {
+		let trimmed = line.trim_start();
+		match fence
+		{
+			Some(marker) if trimmed.starts_with(marker) => fence = None,
+			Some(_) => {},
+			None if trimmed.starts_with("```") => fence = Some("```"),
+			None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+			None =>
+			{
+				let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+				let rest = &trimmed[hashes ..];
+				return (1 ..= level).contains(&hashes)
+					&& rest.chars().next().map_or(true, char::is_whitespace)
+			}
+		}
+		false
+	})
 }
 
-////////////////////////////////////////////////////////////////////////////////
-//                                 Frame UI.                                  //
-////////////////////////////////////////////////////////////////////////////////
+/// Split the specified [Fountain](https://fountain.io) screenplay immediately
+/// before each scene heading, such that each slugline begins its own piece. A
+/// scene heading follows a blank line and begins with `INT`, `EXT`, `EST`,
+/// `INT./EXT`, `INT/EXT`, or `I/E`, in any case, followed by a period or a
+/// space; a line that begins with a single period is a forced scene heading.
+/// Any text before the first scene heading, e.g., the title page, is its own
+/// piece, unless it's blank.
+fn split_at_scenes(text: &str) -> impl Iterator<Item = &str>
+{
+	let mut after_blank = true;
+	split_before_lines(text, move |line| {
+		let trimmed = line.trim();
+		let heading = after_blank && is_scene_heading(trimmed);
+		after_blank = trimmed.is_empty();
+		heading
+	})
+}
 
-impl App for StoryShufflerApp
+/// Answer `true` if the specified line, without surrounding whitespace, is a
+/// [Fountain](https://fountain.io) scene heading.
+fn is_scene_heading(line: &str) -> bool
 {
-	/// Update the UI and handle any pending user interaction. May be called
-	/// many times per second, so handle any slow activity asynchronously.
-	fn update(&mut self, ctx: &Context, _frame: &mut Frame)
+	let mut chars = line.chars();
+	if let Some('.') = chars.next()
 	{
-		#[cfg(target_arch = "wasm32")]
-		self.present_banner(ctx);
-		self.present_configuration_sidebar(ctx);
-		self.present_output_sidebar(ctx);
-		// Note that the manuscript panel must be presented last, because the
-		// main component is a CentralPanel.
-		self.present_manuscript_panel(ctx);
+		// A forced scene heading, but not an ellipsis.
+		return chars.next().map_or(false, char::is_alphanumeric)
 	}
+	let upper = line.to_uppercase();
+	SCENE_HEADING_PREFIXES.iter().any(|prefix| {
+		upper.strip_prefix(prefix).map_or(false, |rest|
+			rest.starts_with(['.', ' '])
+		)
+	})
+}
 
-	/// Called by the framework to save state before shutdown.
-	fn save(&mut self, storage: &mut dyn eframe::Storage)
+/// Split the specified text immediately before each line, except the first,
+/// that satisfies `is_boundary`, which sees every line in order, including
+/// line terminators. Any text before the first boundary is its own piece,
+/// unless it's blank.
+fn split_before_lines(
+	text: &str,
+	mut is_boundary: impl FnMut(&str) -> bool
+) -> impl Iterator<Item = &str>
+{
+	let mut starts = vec![0];
+	let mut offset = 0;
+	for line in text.split_inclusive('\n')
 	{
-		eframe::set_value(storage, eframe::APP_KEY, self);
+		if is_boundary(line) && offset > 0
+		{
+			starts.push(offset);
+		}
+		offset += line.len();
 	}
+	starts.push(text.len());
+	let pieces = starts.windows(2)
+		.map(|bounds| &text[bounds[0] .. bounds[1]])
+		.collect::<Vec<_>>();
+	// Drop a blank preamble, but never the whole manuscript.
+	let skip = (pieces.len() > 1 && pieces[0].trim().is_empty()) as usize;
+	pieces.into_iter().skip(skip)
 }
 
-////////////////////////////////////////////////////////////////////////////////
-//                                 Banner UI.                                 //
-////////////////////////////////////////////////////////////////////////////////
+/// Carry the specified constraints of the old sections over to the new
+/// sections, given the index of the matching old section, if any, for each new
+/// section. Unmatched sections are unconstrained, and references to sections
+/// that vanished are dropped.
+fn remap_constraints(
+	constraints: &[Constraints],
+	matches: &[Option<usize>]
+) -> Vec<Constraints>
+{
+	let renumbering = renumbering(constraints.len(), matches);
+	matches.iter()
+		.map(|old| match old.and_then(|old| constraints.get(old))
+		{
+			Some(c) =>
+			{
+				let renumber = |list: &[usize]| list.iter()
+					.filter_map(|n| n.checked_sub(1)
+						.and_then(|n| renumbering.get(n).copied().flatten())
+					)
+					.collect::<Vec<_>>();
+				let before = renumber(&c.before);
+				let after = renumber(&c.after);
+				let near = renumber(&c.near);
+				let apart = renumber(&c.apart);
+				let not_adjacent = renumber(&c.not_adjacent);
+				let prefer_before = renumber(&c.prefer_before);
+				let offset_from = c.offset_from
+					.and_then(|n| renumber(&[n]).first().copied());
+				Constraints {
+					fixed: c.fixed,
+					before_all: c.before_all,
+					after_all: c.after_all,
+					pin: c.pin,
+					parity: c.parity,
+					repeats: c.repeats,
+					repeat_gap: c.repeat_gap,
+					text_buffer: section_list_text(&before),
+					before,
+					after_text_buffer: section_list_text(&after),
+					after,
+					near_text_buffer: section_list_text(&near),
+					near,
+					near_distance: c.near_distance,
+					apart_text_buffer: section_list_text(&apart),
+					apart,
+					apart_gap: c.apart_gap,
+					offset_from,
+					offset: c.offset,
+					not_adjacent_text_buffer: section_list_text(&not_adjacent),
+					not_adjacent,
+					prefer_before_text_buffer:
+						section_list_text(&prefer_before),
+					prefer_before,
+					preference_weight: c.preference_weight,
+					place_preference: c.place_preference,
+					place_weight: c.place_weight,
+					label: c.label.clone(),
+					date: c.date.clone(),
+					tags: c.tags.clone(),
+					must_include: c.must_include,
+					omit: c.omit,
+					group: c.group.clone(),
+					chapter: c.chapter.clone(),
+					matter: c.matter,
+					..Default::default()
+				}
+			},
+			None => Constraints::default()
+		})
+		.collect()
+}
 
-#[cfg(target_arch = "wasm32")]
-impl StoryShufflerApp
+/// Carry the specified [tag&#32;constraints](TagConstraint) of the specified
+/// number of old sections over to the new sections, given the index of the
+/// matching old section, if any, for each new section. Section numbers are
+/// rewritten to follow their sections, and constraints whose sections vanished
+/// are dropped.
+fn remap_tag_constraints(
+	tag_constraints: &[TagConstraint],
+	old_count: usize,
+	matches: &[Option<usize>]
+) -> Vec<TagConstraint>
 {
-	/// Display a simple banner (in lieu of a title bar on a native build).
-	fn present_banner(&mut self, ctx: &Context)
+	let renumbering = renumbering(old_count, matches);
+	let renumber = |n: usize| n.checked_sub(1)
+		.and_then(|n| renumbering.get(n).copied().flatten());
+	tag_constraints.iter()
+		.filter_map(|t| {
+			let kind = match t.kind
+			{
+				TagConstraintKind::After(n) =>
+					TagConstraintKind::After(renumber(n)?),
+				TagConstraintKind::Before(n) =>
+					TagConstraintKind::Before(renumber(n)?),
+				TagConstraintKind::NeverAdjacent =>
+					TagConstraintKind::NeverAdjacent
+			};
+			Some(TagConstraint { tag: t.tag.clone(), kind })
+		})
+		.collect()
+}
+
+/// Answer the new **one-based** number of each of the specified number of old
+/// sections, if it survives, given the index of the matching old section, if
+/// any, for each new section.
+fn renumbering(
+	old_count: usize,
+	matches: &[Option<usize>]
+) -> Vec<Option<usize>>
+{
+	let mut renumbering = vec![None; old_count];
+	for (new, old) in matches.iter().enumerate()
 	{
-		TopBottomPanel::top("banner").show(ctx, |ui| {
-			ui.centered_and_justified(|ui| {
-				ui.set_height(50.0);
-				ui.heading(
-					RichText::new("Story Shuffler")
-						.strong()
-						.size(24.0)
-				);
-			});
-        });
+		if let Some(slot) = old.and_then(|old| renumbering.get_mut(old))
+		{
+			*slot = Some(new + 1);
+		}
 	}
+	renumbering
 }
 
-////////////////////////////////////////////////////////////////////////////////
-//                         Configuration sidebar UI.                          //
-////////////////////////////////////////////////////////////////////////////////
+/// How to [split](StoryShufflerApp::update_sections) the manuscript into
+/// sections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum SplitMode
+{
+	/// Split at occurrences of the
+	/// [section&#32;delimiter](StoryShufflerApp::delimiter_pattern).
+	#[default]
+	Delimiter,
 
-impl StoryShufflerApp
+	/// Split at [Markdown&#32;headings](split_at_headings), up to the
+	/// [heading&#32;level](StoryShufflerApp::heading_level).
+	Headings,
+
+	/// Split at the [scene&#32;headings](split_at_scenes) of a Fountain
+	/// screenplay.
+	Scenes
+}
+
+impl SplitMode
 {
-	/// Display the [sidebar][SidePanel] and handle any interactions associated
-	/// therewith.
-	fn present_configuration_sidebar(&mut self, ctx: &Context)
+	/// Every split mode, in presentation order.
+	const ALL: [SplitMode; 3] =
+		[SplitMode::Delimiter, SplitMode::Headings, SplitMode::Scenes];
+
+	/// Answer the human-readable name of the split mode.
+	fn name(self) -> &'static str
 	{
-		SidePanel::left("configuration_panel").show(ctx, |ui| {
-			heading(ui, "Parsing").on_hover_ui(|ui| {
-				ui.horizontal_wrapped(|ui| {
-					ui.spacing_mut().item_spacing.x = 0.0;
-					ui.label(
-						"Here you can specify how your manuscript will be \
-						split into sections. Those sections will appear in the "
-					);
-					ui.label(RichText::new("Constraints").strong());
-					ui.label(
-						" section below whenever you edit these options "
-					);
-					ui.label(RichText::new("or").italics());
-					ui.label(" your manuscript.");
-				});
-			});
-			ui.spacing_mut().item_spacing.y = 3.0;
-			ui.horizontal(|ui| {
-				if ui.add(
-					Checkbox::without_text(&mut self.delimiter_pattern_is_regex)
-				).clicked()
-				{
-					// The user toggled the intention for the pattern (between
-					// plain and regex), so update the pattern accordingly.
-					self.update_sections();
-				}
-				ui.hyperlink_to(
-					"Use regex",
-					"https://docs.rs/regex/latest/regex/#syntax"
-				);
-			}).response.on_hover_text(
-				"Treat the section break as a regular expression rather \
-				than just plain text. Click the hyperlink for the official \
-				syntax reference."
-			);
-			ui.horizontal(|ui| {
-				ui.label("Section delimiter: ");
-				if ui.text_edit_singleline(
-					&mut self.delimiter_pattern
-				).changed()
-				{
-					// The user changed the pattern, which might mandate a new
-					// regex, so update the pattern accordingly.
-					self.update_sections();
-				}
-			}).response.on_hover_text(
-				"Set this to the section break pattern. Your manuscript will \
-				be broken into sections at occurrences of this pattern, and \
-				whitespace will be trimmed from  the beginning and end of each \
-				section."
-			);
-			ui.separator();
-			self.present_regex_error(ui);
-			self.present_constraints(ui);
-			// Retain additional space, to preserve repositioning of the sash.
-			ui.allocate_space(ui.available_size());
-		});
+		match self
+		{
+			SplitMode::Delimiter => "Section delimiter",
+			SplitMode::Headings => "Markdown headings",
+			SplitMode::Scenes => "Fountain scene headings"
+		}
 	}
+}
 
-	/// Display the specified [regular&#32;expression][Regex] compilation error
-	/// on the [UI](Ui).
-	fn present_regex_error(&self, ui: &mut Ui)
+/// How strictly the sections of the [rotation](StoryShufflerApp::rotation)
+/// tags alternate when [shuffled](StoryShufflerApp::shuffle).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum RotationMode
+{
+	/// The tags take turns in strict rotation, beginning with the first tag.
+	#[default]
+	Strict,
+
+	/// The tags may appear in any order, but no two sections with the same
+	/// tag may be adjacent.
+	NoRepeats
+}
+
+impl RotationMode
+{
+	/// Every rotation mode, in presentation order.
+	const ALL: [RotationMode; 2] =
+		[RotationMode::Strict, RotationMode::NoRepeats];
+
+	/// Answer the human-readable name of the rotation mode.
+	fn name(self) -> &'static str
 	{
-		if let Some(ref error) = self.delimiter_regex_error
+		match self
 		{
-			// The pattern is a regex and the regex is busted, so present the
-			// problem. Use monospace so that any syntax errors are properly
-			// displayed.
-			ui.label(
-				RichText::new(error.to_string())
-					.monospace()
-					.color(hex_color!("#aa0000"))
-					.strong()
-			);
-			ui.separator();
+			RotationMode::Strict => "In strict rotation",
+			RotationMode::NoRepeats => "Never twice in a row"
 		}
 	}
+}
 
-	/// Display the [original&#32;sections](Self::original_sections) along with
-	/// their [constraints](Self::constraints).
-	fn present_constraints(&mut self, ui: &mut Ui)
+/// How to present the
+/// [shuffled&#32;sections](StoryShufflerApp::shuffled_sections) in the output
+/// sidebar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum OutputView
+{
+	/// Present each section in full, in its new position.
+	Sections,
+
+	/// Present each transition between adjacent sections, i.e., the last line
+	/// of the preceding section and the first line of the following one.
+	Transitions
+}
+
+/// The section attribute by which to [sort](StoryShufflerApp::sort) the
+/// sections, as an alternative to shuffling them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum SortKey
+{
+	/// Sort alphabetically by [label](Constraints::label).
+	Label,
+
+	/// Sort chronologically by [date](Constraints::date).
+	Date,
+
+	/// Sort alphabetically by first [tag](Constraints::tags).
+	Tag,
+
+	/// Sort by length, from shortest to longest.
+	WordCount
+}
+
+impl SortKey
+{
+	/// Every sort key, in presentation order.
+	const ALL: [SortKey; 4] =
+		[SortKey::Label, SortKey::Date, SortKey::Tag, SortKey::WordCount];
+
+	/// Answer the human-readable name of the sort key.
+	fn name(self) -> &'static str
 	{
-		if self.original_sections.len() < 2
+		match self
 		{
-			// There's no reason to present sections if there's only one
-			// section; this might even be confusing for the user.
-			return
+			SortKey::Label => "Label",
+			SortKey::Date => "Date",
+			SortKey::Tag => "Tag",
+			SortKey::WordCount => "Word count"
 		}
-		heading(ui, "Constraints").on_hover_ui(|ui| {
-			ui.horizontal_wrapped(|ui| {
-				ui.spacing_mut().item_spacing.x = 0.0;
-				ui.label(
-					"Here you can specify how movement of your manuscript's \
-					sections can be constrained. When you are satisfied with \
-					the constraints, you can click "
-				);
-				ui.label(RichText::new("🎲 Shuffle").strong());
-				ui.label(
-					" to obtain a permutation of your \
-					manuscript that satisfies your constraints. Any ordering \
-					paradoxes will be reported beneath their respective \
-					constraints."
-				);
-			});
-		});
-		ui.spacing_mut().item_spacing.y = 3.0;
-		scrollable_sections(
-			ui,
-			&(0 .. self.original_sections.len()).collect::<Vec<_>>(),
-			&mut self.original_sections,
-			Some(&mut self.constraints),
-			self.sections_regex.as_ref()
-		);
 	}
+
+	/// Compare the sections at the specified **zero-based** indices by this
+	/// key. Textual attributes are compared [naturally](natural_cmp), and
+	/// sections that lack the attribute sort after those that have it.
+	fn compare(
+		self,
+		sections: &[String],
+		constraints: &[Constraints],
+		a: usize,
+		b: usize
+	) -> Ordering
+	{
+		let text = |index: usize| -> &str {
+			let c = &constraints[index];
+			match self
+			{
+				SortKey::Label => c.label.trim(),
+				SortKey::Date => c.date.trim(),
+				SortKey::Tag => c.tags().next().unwrap_or_default(),
+				SortKey::WordCount => unreachable!()
+			}
+		};
+		match self
+		{
+			SortKey::WordCount => sections[a].split_whitespace().count()
+				.cmp(&sections[b].split_whitespace().count()),
+			_ =>
+			{
+				let (a, b) = (text(a), text(b));
+				a.is_empty().cmp(&b.is_empty())
+					.then_with(|| natural_cmp(a, b))
+			}
+		}
+	}
+}
+
+/// Compare the specified strings naturally, i.e., without regard to case and
+/// such that embedded numbers are compared numerically, e.g., `Day 2` sorts
+/// before `Day 10`. Dates written as `YYYY-MM-DD` sort chronologically.
+fn natural_cmp(a: &str, b: &str) -> Ordering
+{
+	let mut a = a.chars().peekable();
+	let mut b = b.chars().peekable();
+	loop
+	{
+		let ordering = match (a.peek().copied(), b.peek().copied())
+		{
+			(None, None) => return Ordering::Equal,
+			(None, Some(_)) => return Ordering::Less,
+			(Some(_), None) => return Ordering::Greater,
+			(Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() =>
+			{
+				let (x, y) = (leading_number(&mut a), leading_number(&mut b));
+				x.len().cmp(&y.len()).then_with(|| x.cmp(&y))
+			},
+			(Some(x), Some(y)) =>
+			{
+				a.next();
+				b.next();
+				x.to_lowercase().cmp(y.to_lowercase())
+			}
+		};
+		if ordering != Ordering::Equal
+		{
+			return ordering
+		}
+	}
+}
+
+/// Consume the run of decimal digits at the front of the specified characters,
+/// answering it without any leading zeroes, such that longer answers denote
+/// greater numbers.
+fn leading_number(chars: &mut Peekable<Chars<'_>>) -> String
+{
+	let mut digits = String::new();
+	while let Some(digit) = chars.next_if(char::is_ascii_digit)
+	{
+		digits.push(digit);
+	}
+	digits.trim_start_matches('0').to_string()
+}
+
+/// The outcome of a file operation, for presentation in the menu bar.
+#[derive(Clone, Debug)]
+struct StatusMessage
+{
+	/// The message.
+	text: String,
+
+	/// Whether the operation failed.
+	is_error: bool
+}
+
+/// The outcome of an
+/// [import&#32;of&#32;constraints](StoryShufflerApp::import_constraints).
+#[derive(Clone, Debug)]
+struct ConstraintsReport
+{
+	/// The name or path of the imported file.
+	path: String,
+
+	/// Whether the constraints were imported.
+	imported: bool,
+
+	/// The problems that prevented the import, or the mismatches that didn't.
+	problems: Vec<String>
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-//                            Manuscript panel UI.                            //
+//                                 Projects.                                  //
 ////////////////////////////////////////////////////////////////////////////////
 
-impl StoryShufflerApp
+/// The portable part of the [application&#32;state](StoryShufflerApp), i.e.,
+/// the writer's work, without any preferences or transient state. Suitable for
+/// moving work between the web and native builds, or between writers.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Project
 {
-	/// Display the [manuscript&#32;panel][CentralPanel] and handle any
+	/// The [original&#32;manuscript](StoryShufflerApp::original_manuscript).
+	manuscript: String,
+
+	/// Whether the [delimiter](Self::delimiter_pattern) is a
+	/// [regular&#32;expression](Regex).
+	delimiter_pattern_is_regex: bool,
+
+	/// The [section&#32;delimiter](StoryShufflerApp::delimiter_pattern).
+	delimiter_pattern: String,
+
+	/// The [split&#32;mode](StoryShufflerApp::split_mode).
+	split_mode: SplitMode,
+
+	/// The [heading&#32;level](StoryShufflerApp::heading_level).
+	heading_level: usize,
+
+	/// Whether the manuscript is an [anthology](StoryShufflerApp::anthology).
+	anthology: bool,
+
+	/// The [story&#32;delimiter](StoryShufflerApp::story_delimiter).
+	story_delimiter: String,
+
+	/// The [sections](StoryShufflerApp::original_sections).
+	sections: Vec<String>,
+
+	/// The [stories](StoryShufflerApp::section_stories) of the sections.
+	section_stories: Vec<usize>,
+
+	/// The [section&#32;identifiers](StoryShufflerApp::section_ids).
+	section_ids: Vec<String>,
+
+	/// The [constraints](StoryShufflerApp::constraints).
+	constraints: Vec<Constraints>,
+
+	/// The [rotation](StoryShufflerApp::rotation).
+	rotation: String,
+
+	/// The [rotation&#32;mode](StoryShufflerApp::rotation_mode).
+	rotation_mode: RotationMode,
+
+	/// The [tag&#32;constraints](StoryShufflerApp::tag_constraints).
+	tag_constraints: Vec<TagConstraint>,
+
+	/// The [chapters](StoryShufflerApp::chapters).
+	chapters: Vec<Chapter>,
+
+	/// The [layers](StoryShufflerApp::layers).
+	layers: Vec<Layer>,
+
+	/// The
+	/// [current&#32;shuffle](StoryShufflerApp::shuffled_section_indices), if
+	/// any.
+	shuffled_section_indices: Option<Vec<usize>>,
+
+	/// The [scenarios](StoryShufflerApp::scenarios).
+	scenarios: Vec<Scenario>,
+
+	/// The [active&#32;scenario](StoryShufflerApp::active_scenario).
+	active_scenario: usize,
+
+	/// Whether the project should be opened in
+	/// [review&#32;mode](StoryShufflerApp::review_mode).
+	review: bool
+}
+
+impl StoryShufflerApp
+{
+	/// Answer the current [project](Project).
+	fn project(&self) -> Project
+	{
+		Project {
+			manuscript: self.original_manuscript.clone(),
+			delimiter_pattern_is_regex: self.delimiter_pattern_is_regex,
+			delimiter_pattern: self.delimiter_pattern.clone(),
+			split_mode: self.split_mode,
+			heading_level: self.heading_level,
+			anthology: self.anthology,
+			story_delimiter: self.story_delimiter.clone(),
+			sections: self.original_sections.clone(),
+			section_stories: self.section_stories.clone(),
+			section_ids: self.section_ids.clone(),
+			constraints: self.constraints.clone(),
+			rotation: self.rotation.clone(),
+			rotation_mode: self.rotation_mode,
+			tag_constraints: self.tag_constraints.clone(),
+			chapters: self.chapters.clone(),
+			layers: self.layers.clone(),
+			shuffled_section_indices: self.shuffled_section_indices.clone(),
+			scenarios: self.scenarios.clone(),
+			active_scenario: self.active_scenario,
+			review: self.review_mode
+		}
+	}
+
+	/// Replace the writer's work with the specified [project](Project). Answer
+	/// an explanation, without changing anything, if the project is
+	/// inconsistent.
+	fn adopt_project(&mut self, project: Project) -> Result<(), String>
+	{
+		let count = project.sections.len();
+		if project.constraints.len() != count
+			|| !references_are_in_bounds(&project.constraints)
+			|| project.shuffled_section_indices.as_ref().map_or(false,
+				|indices| indices.iter().any(|index| *index >= count)
+			)
+			|| !project.scenarios.iter().all(|scenario|
+				scenario.constraints.is_empty()
+					|| scenario.constraints.len() == count
+					&& references_are_in_bounds(&scenario.constraints)
+			)
+		{
+			return Err("The project is damaged: its constraints refer to \
+				nonexistent sections.".to_string())
+		}
+		self.section_ids = if project.section_ids.len() == count
+		{
+			project.section_ids
+		}
+		else
+		{
+			fresh_section_ids(&project.sections)
+		};
+		self.section_stories = if project.section_stories.len() == count
+		{
+			project.section_stories
+		}
+		else
+		{
+			vec![0; count]
+		};
+		self.original_manuscript = project.manuscript;
+		self.manuscript_file_name = None;
+		self.delimiter_pattern_is_regex = project.delimiter_pattern_is_regex;
+		self.delimiter_pattern = project.delimiter_pattern;
+		self.delimiter_regex_error = None;
+		self.split_mode = project.split_mode;
+		// A project that predates heading levels considers every level.
+		self.heading_level = match project.heading_level
+		{
+			0 => MAXIMUM_HEADING_LEVEL,
+			level => level.min(MAXIMUM_HEADING_LEVEL)
+		};
+		self.anthology = project.anthology;
+		self.story_delimiter = project.story_delimiter;
+		self.original_sections = project.sections;
+		self.constraints = project.constraints;
+		self.rotation = project.rotation;
+		self.rotation_mode = project.rotation_mode;
+		self.tag_constraints = project.tag_constraints;
+		self.chapters = project.chapters;
+		self.layers = project.layers;
+		self.forget_constraint_edits();
+		self.restore_order(project.shuffled_section_indices);
+		self.shuffle_history = vec![];
+		self.scenarios = project.scenarios;
+		if self.scenarios.is_empty()
+		{
+			self.scenarios.push(Scenario::named(DEFAULT_SCENARIO_NAME));
+		}
+		self.active_scenario =
+			project.active_scenario.min(self.scenarios.len() - 1);
+		self.review_mode = project.review;
+		let regex = (self.split_mode == SplitMode::Delimiter
+				&& self.delimiter_pattern_is_regex
+				&& !self.delimiter_pattern.is_empty())
+			.then(|| Regex::new(&self.delimiter_pattern).ok())
+			.flatten();
+		self.capture_delimiter_matches(regex.as_ref());
+		Ok(())
+	}
+}
+
+/// The ordering constraints of a manuscript, without the manuscript itself,
+/// e.g., for applying the same constraints to several manuscripts from the
+/// command line.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ConstraintSet
+{
+	/// The [section&#32;delimiter](StoryShufflerApp::delimiter_pattern), if
+	/// it should override the default.
+	delimiter_pattern: Option<String>,
+
+	/// Whether the [delimiter](Self::delimiter_pattern) is a
+	/// [regular&#32;expression](Regex).
+	delimiter_pattern_is_regex: bool,
+
+	/// The constraints of each section, in section order. Any sections beyond
+	/// the end are unconstrained.
+	sections: Vec<SectionConstraints>
+}
+
+/// The ordering constraints of a single section within a
+/// [constraint&#32;set](ConstraintSet).
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SectionConstraints
+{
+	/// The **one-based** index of the section, which makes each entry
+	/// self-describing when the set is shared between collaborators. `0`
+	/// means that the entry applies to the section at its own position.
+	section: usize,
+
+	/// Whether the section is [locked&#32;in&#32;place](Constraints::fixed).
+	fixed: bool,
+
+	/// Whether the section must [precede](Constraints::before_all) every
+	/// other section.
+	before_all: bool,
+
+	/// Whether the section must [follow](Constraints::after_all) every other
+	/// section.
+	after_all: bool,
+
+	/// The sections which must occur
+	/// [strictly&#32;after](Constraints::before) the section, denoted by
+	/// their **one-based** indices.
+	before: Vec<usize>,
+
+	/// The sections which must occur
+	/// [strictly&#32;before](Constraints::after) the section, denoted by
+	/// their **one-based** indices.
+	after: Vec<usize>,
+
+	/// The sections which must land [near](Constraints::near) the section,
+	/// denoted by their **one-based** indices.
+	near: Vec<usize>,
+
+	/// The [distance](Constraints::near_distance) within which the sections
+	/// [near](Self::near) the section must land. `0` means the default.
+	near_distance: usize,
+
+	/// The sections which must land [apart](Constraints::apart) from the
+	/// section, denoted by their **one-based** indices.
+	apart: Vec<usize>,
+
+	/// The [gap](Constraints::apart_gap) that must separate the section from
+	/// the sections [apart](Self::apart) from it. `0` means the default.
+	apart_gap: usize,
+
+	/// The section that the section must [follow](Constraints::offset_from)
+	/// by an exact offset, denoted by its **one-based** index, if any.
+	offset_from: Option<usize>,
+
+	/// The exact [offset](Constraints::offset) by which the section follows
+	/// the section given by [`offset_from`](Self::offset_from). `0` means the
+	/// default.
+	offset: usize,
+
+	/// The sections which must never be
+	/// [adjacent](Constraints::not_adjacent) to the section, denoted by their
+	/// **one-based** indices.
+	not_adjacent: Vec<usize>,
+
+	/// The sections which should [preferably](Constraints::prefer_before)
+	/// occur after the section, denoted by their **one-based** indices.
+	prefer_before: Vec<usize>,
+
+	/// The [weight](Constraints::preference_weight) of each of the
+	/// [preferences](Self::prefer_before) of the section. `0` means the
+	/// default.
+	preference_weight: f32,
+
+	/// The [place&#32;preference](Constraints::place_preference) of the
+	/// section.
+	place_preference: PlacePreference,
+
+	/// The [weight](Constraints::place_weight) of the
+	/// [place&#32;preference](Self::place_preference) of the section. `0`
+	/// means the default.
+	place_weight: f32,
+
+	/// The name of the [group](Constraints::group) of the section, if any.
+	group: String,
+
+	/// The **one-based** position to which the section is
+	/// [pinned](Constraints::pin), if any.
+	pin: Option<usize>,
+
+	/// The [parity](Constraints::parity) of the positions that the section
+	/// may occupy, if restricted.
+	parity: Option<Parity>,
+
+	/// The number of times that the section [appears](Constraints::repeats)
+	/// in every reordering. `0` means once.
+	repeats: usize,
+
+	/// The [gap](Constraints::repeat_gap) between the appearances of the
+	/// section. `0` means the default.
+	repeat_gap: usize
+}
+
+/// A ready-made arrangement of [constraints](Constraints) for a common
+/// narrative structure, which the writer can
+/// [apply](StoryShufflerApp::apply_template) to any manuscript and then tweak.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ConstraintTemplate
+{
+	/// Fix the first and last sections, e.g., the outer story of a frame
+	/// narrative, and let the sections between them float.
+	FrameStory,
+
+	/// Chain the sections in the story's internal chronology, as given by
+	/// their [dates](Constraints::date), or in manuscript order if no section
+	/// has a date.
+	Chronology,
+
+	/// Deal the sections in turn to the specified number of threads, like
+	/// cards, and keep the sections of each thread in manuscript order, so
+	/// that only the braiding of the threads changes.
+	Braid(usize)
+}
+
+impl ConstraintTemplate
+{
+	/// Every template, in presentation order.
+	const ALL: [ConstraintTemplate; 4] = [
+		ConstraintTemplate::FrameStory,
+		ConstraintTemplate::Chronology,
+		ConstraintTemplate::Braid(2),
+		ConstraintTemplate::Braid(3)
+	];
+
+	/// Answer the human-readable name of the template.
+	fn name(self) -> String
+	{
+		match self
+		{
+			ConstraintTemplate::FrameStory =>
+				"Frame story: fix first & last".to_string(),
+			ConstraintTemplate::Chronology =>
+				"Chronological chain".to_string(),
+			ConstraintTemplate::Braid(threads) =>
+				format!("Braided narrative of {} threads", threads)
+		}
+	}
+
+	/// Answer a description of the template, suitable for a tooltip.
+	fn description(self) -> String
+	{
+		match self
+		{
+			ConstraintTemplate::FrameStory =>
+				"Fix the first and last sections in place, e.g., the outer \
+				story of a frame narrative, and let everything between them \
+				move freely.".to_string(),
+			ConstraintTemplate::Chronology =>
+				"Chain the sections that have dates in the story's \
+				chronology, so that a shuffle can only interleave the \
+				undated sections. Sections with the same date may occur in \
+				either order. If no section has a date, every section is \
+				chained in manuscript order, for you to loosen.".to_string(),
+			ConstraintTemplate::Braid(threads) => format!(
+				"Deal the sections to {} threads in turn, like cards, so that \
+				§1 starts the first thread, §2 starts the second, and so \
+				on. Each thread keeps its own order, but the threads \
+				interleave freely.",
+				threads
+			)
+		}
+	}
+
+	/// Answer the [constraint&#32;set](ConstraintSet) that the template
+	/// prescribes for sections with the specified existing
+	/// [constraints](Constraints), of which only the
+	/// [dates](Constraints::date) matter.
+	fn constraint_set(self, constraints: &[Constraints]) -> ConstraintSet
+	{
+		let count = constraints.len();
+		let mut sections = (1 ..= count)
+			.map(|section| SectionConstraints {
+				section,
+				..Default::default()
+			})
+			.collect::<Vec<_>>();
+		match self
+		{
+			ConstraintTemplate::FrameStory =>
+			{
+				if let Some(first) = sections.first_mut()
+				{
+					first.fixed = true;
+				}
+				if let Some(last) = sections.last_mut()
+				{
+					last.fixed = true;
+				}
+			},
+			ConstraintTemplate::Chronology =>
+			{
+				let date = |index: usize| constraints[index].date.trim();
+				let mut dated = (0 .. count)
+					.filter(|index| !date(*index).is_empty())
+					.collect::<Vec<_>>();
+				dated.sort_by(|a, b| natural_cmp(date(*a), date(*b)));
+				// Gather the sections into runs of the same date. Each section
+				// must precede every section of the next run, which leaves
+				// sections of the same date unordered.
+				let mut runs = Vec::<Vec<usize>>::new();
+				for index in dated
+				{
+					match runs.last_mut()
+					{
+						Some(run) if natural_cmp(date(run[0]), date(index))
+							== Ordering::Equal => run.push(index),
+						_ => runs.push(vec![index])
+					}
+				}
+				if runs.is_empty()
+				{
+					runs = (0 .. count).map(|index| vec![index]).collect();
+				}
+				for pair in runs.windows(2)
+				{
+					for earlier in &pair[0]
+					{
+						sections[*earlier].before.extend(
+							pair[1].iter().map(|later| later + 1)
+						);
+					}
+				}
+			},
+			ConstraintTemplate::Braid(threads) =>
+			{
+				let dealt = count.saturating_sub(threads);
+				for (index, section) in sections.iter_mut().enumerate()
+					.take(dealt)
+				{
+					section.before.push(index + threads + 1);
+				}
+			}
+		}
+		ConstraintSet {
+			delimiter_pattern: None,
+			delimiter_pattern_is_regex: false,
+			sections
+		}
+	}
+}
+
+/// A named [constraint&#32;set](ConstraintSet), along with the constraints
+/// over tags and the rotation, which the writer saved in order to reapply it
+/// later, e.g., to another manuscript with as many sections.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ConstraintPreset
+{
+	/// The name of the preset.
+	name: String,
+
+	/// The constraints of the sections. The delimiter is omitted, because a
+	/// preset isn't tied to any particular manuscript.
+	constraints: ConstraintSet,
+
+	/// The [rotation](StoryShufflerApp::rotation).
+	rotation: String,
+
+	/// The [rotation&#32;mode](StoryShufflerApp::rotation_mode).
+	rotation_mode: RotationMode,
+
+	/// The [tag&#32;constraints](StoryShufflerApp::tag_constraints).
+	tag_constraints: Vec<TagConstraint>
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Persistence.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Everything keyed to the sections of a manuscript whose text was withheld
+/// from persistence by [privacy&#32;mode](StoryShufflerApp::private), which
+/// awaits the return of the manuscript.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct WithheldManuscript
+{
+	/// The [identifiers](fresh_section_ids) derived afresh from the content
+	/// of each section, which recognize the sections when the writer supplies
+	/// the manuscript again.
+	hashes: Vec<String>,
+
+	/// The [section&#32;identifiers](StoryShufflerApp::section_ids).
+	section_ids: Vec<String>,
+
+	/// The [constraints](StoryShufflerApp::constraints).
+	constraints: Vec<Constraints>
+}
+
+/// The text of a manuscript, along with everything keyed to its sections, as
+/// temporarily removed from the [application&#32;state](StoryShufflerApp) by
+/// [`withhold_manuscript`](StoryShufflerApp::withhold_manuscript).
+struct ManuscriptText
+{
+	/// The [original&#32;manuscript](StoryShufflerApp::original_manuscript).
+	manuscript: String,
+
+	/// The [file&#32;name](StoryShufflerApp::manuscript_file_name) of the
+	/// manuscript, if any.
+	file_name: Option<String>,
+
+	/// The [sections](StoryShufflerApp::original_sections).
+	sections: Vec<String>,
+
+	/// The [delimiter&#32;matches](StoryShufflerApp::delimiter_matches).
+	delimiter_matches: Vec<String>,
+
+	/// The [stories](StoryShufflerApp::section_stories) of the sections.
+	section_stories: Vec<usize>,
+
+	/// The [section&#32;identifiers](StoryShufflerApp::section_ids).
+	section_ids: Vec<String>,
+
+	/// The [constraints](StoryShufflerApp::constraints).
+	constraints: Vec<Constraints>,
+
+	/// The
+	/// [current&#32;shuffle](StoryShufflerApp::shuffled_section_indices), if
+	/// any.
+	shuffled_section_indices: Option<Vec<usize>>,
+
+	/// The
+	/// [presented&#32;shuffle](StoryShufflerApp::presented_section_indices), if
+	/// any.
+	presented_section_indices: Option<Vec<usize>>,
+
+	/// The [shuffled&#32;sections](StoryShufflerApp::shuffled_sections), if
+	/// any.
+	shuffled_sections: Option<Vec<String>>,
+
+	/// The [withheld&#32;manuscript](StoryShufflerApp::withheld), if any.
+	withheld: Option<WithheldManuscript>
+}
+
+impl StoryShufflerApp
+{
+	/// Remove the text of the manuscript, along with everything keyed to its
+	/// sections, from the application state, leaving behind only a
+	/// [withheld&#32;manuscript](WithheldManuscript). If the manuscript is
+	/// still missing from a previous session, then leave that session's
+	/// withheld manuscript intact. Answer the removed text, for
+	/// [restoration](Self::restore_manuscript).
+	fn withhold_manuscript(&mut self) -> ManuscriptText
+	{
+		let text = ManuscriptText {
+			manuscript: std::mem::take(&mut self.original_manuscript),
+			file_name: self.manuscript_file_name.take(),
+			sections: std::mem::take(&mut self.original_sections),
+			delimiter_matches: std::mem::take(&mut self.delimiter_matches),
+			section_stories: std::mem::take(&mut self.section_stories),
+			section_ids: std::mem::take(&mut self.section_ids),
+			constraints: std::mem::take(&mut self.constraints),
+			shuffled_section_indices: self.shuffled_section_indices.take(),
+			presented_section_indices: self.presented_section_indices.take(),
+			shuffled_sections: self.shuffled_sections.take(),
+			withheld: self.withheld.clone()
+		};
+		if !text.sections.is_empty()
+		{
+			self.withheld = Some(WithheldManuscript {
+				hashes: fresh_section_ids(&text.sections),
+				section_ids: text.section_ids.clone(),
+				constraints: text.constraints.clone()
+			});
+		}
+		text
+	}
+
+	/// Write the application state to the
+	/// [custom&#32;storage](Self::custom_storage), if any, or else to the
+	/// specified storage.
+	fn persist(&mut self, storage: &mut dyn eframe::Storage)
+	{
+		#[cfg(not(target_arch = "wasm32"))]
+		if let Some(mut custom) = self.custom_storage.take()
+		{
+			eframe::set_value(&mut custom, eframe::APP_KEY, self);
+			custom.flush();
+			tracing::info!(
+				"Saved the application state to {}.",
+				custom.path().display()
+			);
+			self.custom_storage = Some(custom);
+			return
+		}
+		eframe::set_value(storage, eframe::APP_KEY, self);
+		tracing::info!("Saved the application state.");
+	}
+
+	/// Restore the specified text, as removed by
+	/// [`withhold_manuscript`](Self::withhold_manuscript).
+	fn restore_manuscript(&mut self, text: ManuscriptText)
+	{
+		self.original_manuscript = text.manuscript;
+		self.manuscript_file_name = text.file_name;
+		self.original_sections = text.sections;
+		self.delimiter_matches = text.delimiter_matches;
+		self.section_stories = text.section_stories;
+		self.section_ids = text.section_ids;
+		self.constraints = text.constraints;
+		self.shuffled_section_indices = text.shuffled_section_indices;
+		self.presented_section_indices = text.presented_section_indices;
+		self.shuffled_sections = text.shuffled_sections;
+		self.withheld = text.withheld;
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                            Headless shuffling.                             //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Create application state for shuffling the specified manuscript without
+	/// a user interface, splitting it into sections according to the specified
+	/// [constraint&#32;set](ConstraintSet), which it then adopts. `delimiter`
+	/// overrides the delimiter of the constraint set, if specified, which in
+	/// turn overrides that of the [configuration](Config). Answer an
+	/// explanation if the constraints don't fit the manuscript.
+	pub(crate) fn headless(
+		manuscript: String,
+		constraints: &ConstraintSet,
+		delimiter: Option<(String, bool)>,
+		config: &Config
+	) -> Result<Self, String>
+	{
+		let (delimiter_pattern, delimiter_pattern_is_regex) = delimiter
+			.or_else(|| constraints.delimiter_pattern.clone()
+				.map(|pattern| (pattern, constraints.delimiter_pattern_is_regex))
+			)
+			.or_else(|| config.delimiter.clone()
+				.map(|pattern| (pattern, config.delimiter_is_regex))
+			)
+			.unwrap_or_else(|| (DEFAULT_DELIMITER_PATTERN.to_string(), false));
+		let mut app = Self {
+			original_manuscript: manuscript,
+			delimiter_pattern,
+			delimiter_pattern_is_regex,
+			output_separator: config.output_separator.clone(),
+			rng: config.seed
+				.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+			..Default::default()
+		};
+		app.update_sections();
+		if let Some(ref error) = app.delimiter_regex_error
+		{
+			return Err(format!("The section delimiter is malformed: {}", error))
+		}
+		app.apply_constraint_set(constraints)
+			.map_err(|problems| problems.join(" "))?;
+		Ok(app)
+	}
+
+	/// Replace the [constraints](Self::constraints) that a
+	/// [constraint&#32;set](ConstraintSet) can express, i.e., the fixed flags
+	/// and the ordering constraints, with those of the specified constraint
+	/// set. Other attributes, like labels and tags, survive. Answer any
+	/// mismatches that didn't prevent the replacement, e.g., a different
+	/// delimiter. Answer every problem, without changing anything, if the
+	/// constraint set doesn't fit the sections.
+	fn apply_constraint_set(
+		&mut self,
+		set: &ConstraintSet
+	) -> Result<Vec<String>, Vec<String>>
+	{
+		let count = self.original_sections.len();
+		let mut problems = vec![];
+		let mut mismatches = vec![];
+		for (index, section) in set.sections.iter().enumerate()
+		{
+			let number = index + 1;
+			if section.section != 0 && section.section != number
+			{
+				problems.push(format!(
+					"The constraints are out of order: entry {} is for §{}.",
+					number,
+					section.section
+				));
+			}
+			if number > count
+			{
+				if section.fixed
+					|| section.before_all
+					|| section.after_all
+					|| !section.before.is_empty()
+					|| !section.after.is_empty()
+					|| !section.near.is_empty()
+					|| !section.apart.is_empty()
+					|| section.offset_from.is_some()
+					|| !section.not_adjacent.is_empty()
+					|| !section.prefer_before.is_empty()
+				{
+					problems.push(format!(
+						"The constraints mention §{}, but the manuscript only \
+						has {} sections.",
+						number,
+						count
+					));
+				}
+				continue
+			}
+			for n in section.before.iter()
+				.chain(&section.after)
+				.chain(&section.near)
+				.chain(&section.apart)
+				.chain(&section.offset_from)
+				.chain(&section.not_adjacent)
+				.chain(&section.prefer_before)
+				.filter(|n| **n == 0 || **n > count)
+			{
+				problems.push(format!(
+					"The constraints of §{} mention §{}, but the manuscript \
+					only has {} sections.",
+					number,
+					n,
+					count
+				));
+			}
+			if section.pin.map_or(false, |pin| pin == 0 || pin > count)
+			{
+				mismatches.push(format!(
+					"§{} is pinned to a position beyond the end, so it's \
+					unpinned.",
+					number
+				));
+			}
+		}
+		if !problems.is_empty()
+		{
+			return Err(problems)
+		}
+		if let Some(ref pattern) = set.delimiter_pattern
+		{
+			if *pattern != self.delimiter_pattern
+				|| set.delimiter_pattern_is_regex
+					!= self.delimiter_pattern_is_regex
+			{
+				mismatches.push(format!(
+					"The constraints were made for sections delimited by {}, \
+					so they might not fit these sections.",
+					pattern
+				));
+			}
+		}
+		if set.sections.len() < count
+		{
+			mismatches.push(format!(
+				"The constraints cover only {} of {} sections; the rest are \
+				unconstrained.",
+				set.sections.len(),
+				count
+			));
+		}
+		let empty = SectionConstraints::default();
+		for (index, c) in self.constraints.iter_mut().enumerate()
+		{
+			let section = set.sections.get(index).unwrap_or(&empty);
+			c.fixed = section.fixed;
+			c.before_all = section.before_all;
+			c.after_all = section.after_all;
+			c.before = section.before.clone();
+			c.text_buffer = section_list_text(&section.before);
+			c.text_buffer_is_valid = true;
+			c.after = section.after.clone();
+			c.after_text_buffer = section_list_text(&section.after);
+			c.after_text_buffer_is_valid = true;
+			c.near = section.near.clone();
+			c.near_text_buffer = section_list_text(&section.near);
+			c.near_text_buffer_is_valid = true;
+			c.near_distance = match section.near_distance
+			{
+				0 => DEFAULT_NEAR_DISTANCE,
+				distance => distance
+			};
+			c.apart = section.apart.clone();
+			c.apart_text_buffer = section_list_text(&section.apart);
+			c.apart_text_buffer_is_valid = true;
+			c.apart_gap = match section.apart_gap
+			{
+				0 => DEFAULT_APART_GAP,
+				gap => gap
+			};
+			c.offset_from = section.offset_from;
+			c.offset = match section.offset
+			{
+				0 => DEFAULT_OFFSET,
+				offset => offset
+			};
+			c.not_adjacent = section.not_adjacent.clone();
+			c.not_adjacent_text_buffer =
+				section_list_text(&section.not_adjacent);
+			c.not_adjacent_text_buffer_is_valid = true;
+			c.prefer_before = section.prefer_before.clone();
+			c.prefer_before_text_buffer =
+				section_list_text(&section.prefer_before);
+			c.prefer_before_text_buffer_is_valid = true;
+			c.preference_weight = if section.preference_weight > 0.0
+			{
+				section.preference_weight.min(MAXIMUM_PREFERENCE_WEIGHT)
+			}
+			else
+			{
+				DEFAULT_PREFERENCE_WEIGHT
+			};
+			c.place_preference = section.place_preference;
+			c.place_weight = if section.place_weight > 0.0
+			{
+				section.place_weight.min(MAXIMUM_PLACE_WEIGHT)
+			}
+			else
+			{
+				DEFAULT_PLACE_WEIGHT
+			};
+			c.group = section.group.clone();
+			c.pin = section.pin.filter(|pin| (1 ..= count).contains(pin));
+			c.parity = section.parity;
+			c.repeats = section.repeats.clamp(1, MAXIMUM_REPEATS);
+			c.repeat_gap = match section.repeat_gap
+			{
+				0 => DEFAULT_REPEAT_GAP,
+				gap => gap
+			};
+			c.paradox_error = None;
+		}
+		Ok(mismatches)
+	}
+
+	/// Answer the current [constraints](Self::constraints), along with the
+	/// delimiter settings that determine the sections, as a
+	/// [constraint&#32;set](ConstraintSet), without the manuscript itself.
+	fn constraint_set(&self) -> ConstraintSet
+	{
+		ConstraintSet {
+			delimiter_pattern: Some(self.delimiter_pattern.clone()),
+			delimiter_pattern_is_regex: self.delimiter_pattern_is_regex,
+			sections: self.constraints.iter()
+				.enumerate()
+				.map(|(index, c)| SectionConstraints {
+					section: index + 1,
+					fixed: c.fixed,
+					before_all: c.before_all,
+					after_all: c.after_all,
+					before: c.before.clone(),
+					after: c.after.clone(),
+					near: c.near.clone(),
+					near_distance: c.near_distance,
+					apart: c.apart.clone(),
+					apart_gap: c.apart_gap,
+					offset_from: c.offset_from,
+					offset: c.offset,
+					not_adjacent: c.not_adjacent.clone(),
+					prefer_before: c.prefer_before.clone(),
+					preference_weight: c.preference_weight,
+					place_preference: c.place_preference,
+					place_weight: c.place_weight,
+					group: c.group.clone(),
+					pin: c.pin,
+					parity: c.parity,
+					repeats: c.repeats,
+					repeat_gap: c.repeat_gap
+				})
+				.collect()
+		}
+	}
+
+	/// Answer the current [constraints](Self::constraints) as the text of a
+	/// constraint script, one [statement](Statement) per line, which
+	/// [`parse_script`] reads back.
+	fn constraint_script(&self) -> String
+	{
+		let mut statements = vec![];
+		let mut groups = Vec::<(String, Vec<usize>)>::new();
+		for (index, c) in self.constraints.iter().enumerate()
+		{
+			let number = index + 1;
+			match c.pin
+			{
+				_ if c.fixed => statements.push(Statement::Fixed(number)),
+				Some(pin) => statements.push(Statement::Pin(number, pin)),
+				None => {}
+			}
+			if c.before_all
+			{
+				statements.push(Statement::First(number));
+			}
+			if c.after_all
+			{
+				statements.push(Statement::Last(number));
+			}
+			if let Some(parity) = c.parity
+			{
+				statements.push(Statement::Parity(number, parity));
+			}
+			if c.repeats > 1
+			{
+				statements.push(
+					Statement::Repeat(number, c.repeats, c.repeat_gap)
+				);
+			}
+			statements.extend(c.before.iter()
+				.map(|n| Statement::Before(number, *n))
+			);
+			statements.extend(c.after.iter()
+				.map(|n| Statement::After(number, *n))
+			);
+			statements.extend(c.near.iter()
+				.map(|n| Statement::Near(number, *n, c.near_distance))
+			);
+			statements.extend(c.apart.iter()
+				.map(|n| Statement::Apart(number, *n, c.apart_gap))
+			);
+			statements.extend(c.offset_from
+				.map(|n| Statement::Offset(n, number, c.offset))
+			);
+			statements.extend(c.not_adjacent.iter()
+				.map(|n| Statement::NotAdjacent(number, *n))
+			);
+			let weight = (c.preference_weight != DEFAULT_PREFERENCE_WEIGHT)
+				.then_some(c.preference_weight);
+			statements.extend(c.prefer_before.iter()
+				.map(|n| Statement::Prefer(number, *n, weight))
+			);
+			if c.place_preference != PlacePreference::Anywhere
+			{
+				let weight = (c.place_weight != DEFAULT_PLACE_WEIGHT)
+					.then_some(c.place_weight);
+				statements.push(
+					Statement::Place(number, c.place_preference, weight)
+				);
+			}
+			if let Some(group) = c.group()
+			{
+				match groups.iter_mut().find(|(name, _)| name == group)
+				{
+					Some((_, members)) => members.push(number),
+					None => groups.push((group.to_string(), vec![number]))
+				}
+			}
+		}
+		statements.extend(groups.into_iter()
+			.map(|(name, members)| Statement::Group(name, members))
+		);
+		statements.iter().map(|s| format!("{}\n", s)).collect()
+	}
+
+	/// Answer the [constraint&#32;set](ConstraintSet) that the specified
+	/// [statements](Statement) of a constraint script describe, each given
+	/// with its **one-based** line number, or an explanation of every
+	/// statement that contradicts an earlier one. The set covers every section
+	/// mentioned, so that [`apply_constraint_set`](Self::apply_constraint_set)
+	/// can report those beyond the end of the manuscript.
+	fn script_constraint_set(
+		&self,
+		statements: &[(usize, Statement)]
+	) -> Result<ConstraintSet, Vec<String>>
+	{
+		let count = statements.iter()
+			.flat_map(|(_, statement)| statement.sections())
+			.max()
+			.unwrap_or_default()
+			.max(self.constraints.len());
+		let mut sections = (1 ..= count)
+			.map(|section| SectionConstraints {
+				section,
+				..Default::default()
+			})
+			.collect::<Vec<_>>();
+		let mut problems = vec![];
+		for (line, statement) in statements
+		{
+			let mut problem = |text: String|
+				problems.push(format!("Line {}: {}", line, text));
+			match statement
+			{
+				Statement::Before(a, b) => sections[a - 1].before.push(*b),
+				Statement::After(a, b) => sections[a - 1].after.push(*b),
+				Statement::Fixed(a) => sections[a - 1].fixed = true,
+				Statement::First(a) => sections[a - 1].before_all = true,
+				Statement::Last(a) => sections[a - 1].after_all = true,
+				Statement::Pin(a, pin) => match sections[a - 1].pin
+				{
+					Some(other) if other != *pin => problem(format!(
+						"§{} is already pinned to position {}.",
+						a,
+						other
+					)),
+					_ => sections[a - 1].pin = Some(*pin)
+				},
+				Statement::Parity(a, parity) => match sections[a - 1].parity
+				{
+					Some(other) if other != *parity => problem(format!(
+						"§{} must already occupy {} positions.",
+						a,
+						other.name()
+					)),
+					_ => sections[a - 1].parity = Some(*parity)
+				},
+				Statement::Repeat(a, times, gap) =>
+				{
+					let section = &mut sections[a - 1];
+					if section.repeats == 0 || section.repeats == *times
+					{
+						section.repeats = *times;
+						section.repeat_gap = *gap;
+					}
+					else
+					{
+						problem(format!(
+							"§{} must already appear {} times.",
+							a,
+							section.repeats
+						));
+					}
+				},
+				Statement::Group(name, members) =>
+				{
+					for member in members
+					{
+						let section = &mut sections[member - 1];
+						if section.group.is_empty()
+						{
+							section.group = name.clone();
+						}
+						else if section.group != *name
+						{
+							problem(format!(
+								"§{} is already in group {}.",
+								member,
+								section.group
+							));
+						}
+					}
+				},
+				Statement::Near(a, b, distance) =>
+				{
+					let section = &mut sections[a - 1];
+					if section.near.is_empty()
+						|| section.near_distance == *distance
+					{
+						section.near.push(*b);
+						section.near_distance = *distance;
+					}
+					else
+					{
+						problem(format!(
+							"§{} must already be within {} of other \
+							sections; each section has only one distance.",
+							a,
+							places(section.near_distance)
+						));
+					}
+				},
+				Statement::Apart(a, b, gap) =>
+				{
+					let section = &mut sections[a - 1];
+					if section.apart.is_empty() || section.apart_gap == *gap
+					{
+						section.apart.push(*b);
+						section.apart_gap = *gap;
+					}
+					else
+					{
+						problem(format!(
+							"§{} must already be separated from other \
+							sections by at least {}; each section has only \
+							one gap.",
+							a,
+							others(section.apart_gap)
+						));
+					}
+				},
+				Statement::Offset(a, b, offset) =>
+				{
+					let section = &mut sections[b - 1];
+					match section.offset_from
+					{
+						Some(other) if other != *a
+							|| section.offset != *offset =>
+							problem(format!(
+								"§{} must already land exactly {} after §{}.",
+								b,
+								places(section.offset),
+								other
+							)),
+						_ =>
+						{
+							section.offset_from = Some(*a);
+							section.offset = *offset;
+						}
+					}
+				},
+				Statement::NotAdjacent(a, b) =>
+					sections[a - 1].not_adjacent.push(*b),
+				Statement::Prefer(a, b, weight) =>
+				{
+					let section = &mut sections[a - 1];
+					let weight = weight.unwrap_or(DEFAULT_PREFERENCE_WEIGHT);
+					if section.prefer_before.is_empty()
+						|| section.preference_weight == weight
+					{
+						section.prefer_before.push(*b);
+						section.preference_weight = weight;
+					}
+					else
+					{
+						problem(format!(
+							"The preferences of §{} already have weight {}; \
+							each section has only one weight.",
+							a,
+							section.preference_weight
+						));
+					}
+				},
+				Statement::Place(a, place, weight) =>
+				{
+					let section = &mut sections[a - 1];
+					if section.place_preference == PlacePreference::Anywhere
+						|| section.place_preference == *place
+					{
+						section.place_preference = *place;
+						section.place_weight =
+							weight.unwrap_or(DEFAULT_PLACE_WEIGHT);
+					}
+					else
+					{
+						problem(format!(
+							"§{} already prefers to land {}.",
+							a,
+							section.place_preference.keyword()
+						));
+					}
+				}
+			}
+		}
+		if !problems.is_empty()
+		{
+			return Err(problems)
+		}
+		Ok(ConstraintSet {
+			delimiter_pattern: None,
+			delimiter_pattern_is_regex: self.delimiter_pattern_is_regex,
+			sections
+		})
+	}
+
+	/// Shuffle the manuscript without a user interface, as though by
+	/// [`shuffle`](Self::shuffle), under the enabled [layers](Self::layers).
+	/// Answer the shuffled manuscript, or an explanation of any paradoxes.
+	pub(crate) fn shuffle_headless(&mut self) -> Result<String, String>
+	{
+		let shuffled = self.with_layers(|app| {
+			let shuffled = app.mark_cycles().map(|graph| app.shuffle(graph));
+			shuffled.is_some()
+		});
+		if shuffled == Some(false)
+		{
+			let paradoxes = self.constraints.iter()
+				.filter_map(|c| c.paradox_error.as_deref())
+				.collect::<String>();
+			return Err(paradoxes.trim_end().to_string())
+		}
+		if let Some(status) = self.status.take().filter(|s| s.is_error)
+		{
+			return Err(status.text)
+		}
+		self.assembled_manuscript()
+			.ok_or_else(|| "The manuscript couldn't be shuffled.".to_string())
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                              Review packets.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Assemble a review packet for a workshop or an editor to mark up: the
+	/// sections in their original order, a report of the constraints, and the
+	/// current shuffle, if any, annotated with how far each section moved.
+	fn review_packet(&mut self) -> Vec<Block>
+	{
+		let count = self.original_sections.len();
+		let mut blocks = vec![
+			Block::Heading(1, "Review packet".to_string()),
+			Block::Heading(2, "Sections in their original order".to_string())
+		];
+		for (index, section) in self.original_sections.iter().enumerate()
+		{
+			blocks.push(Block::Heading(3, self.section_title(index)));
+			blocks.extend(paragraphs(section));
+		}
+		blocks.push(Block::Heading(2, "Constraints".to_string()));
+		let mut constrained = false;
+		for (index, c) in self.constraints.iter().enumerate()
+		{
+			if c.fixed && index == 0
+			{
+				blocks.push(Block::Bullet(
+					"§1 is fixed at the beginning.".to_string()
+				));
+				constrained = true;
+			}
+			else if c.fixed && index == count - 1
+			{
+				blocks.push(Block::Bullet(
+					format!("§{} is fixed at the end.", count)
+				));
+				constrained = true;
+			}
+			else if c.fixed
+			{
+				blocks.push(Block::Bullet(
+					format!("§{} is fixed in place.", index + 1)
+				));
+				constrained = true;
+			}
+			if c.before_all
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must come before every other section.",
+					index + 1
+				)));
+				constrained = true;
+			}
+			if c.after_all
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must come after every other section.",
+					index + 1
+				)));
+				constrained = true;
+			}
+			if !c.before.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must come before {}.",
+					index + 1,
+					c.before.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", ")
+				)));
+				constrained = true;
+			}
+			if let Some(pin) = c.pin
+			{
+				blocks.push(Block::Bullet(
+					format!("§{} is pinned to position {}.", index + 1, pin)
+				));
+				constrained = true;
+			}
+			if let Some(parity) = c.parity
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} may only occupy {} positions.",
+					index + 1,
+					parity.name()
+				)));
+				constrained = true;
+			}
+			match c.matter
+			{
+				Matter::Body => {},
+				Matter::Front =>
+				{
+					blocks.push(Block::Bullet(format!(
+						"§{} is front matter, anchored at the beginning.",
+						index + 1
+					)));
+					constrained = true;
+				},
+				Matter::Back =>
+				{
+					blocks.push(Block::Bullet(format!(
+						"§{} is back matter, anchored at the end.",
+						index + 1
+					)));
+					constrained = true;
+				}
+			}
+			if c.omit
+			{
+				blocks.push(Block::Bullet(
+					format!("§{} is omitted from the shuffle.", index + 1)
+				));
+				constrained = true;
+			}
+			if let Some(group) = c.group()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} belongs to the group “{}”, which shuffles as a block.",
+					index + 1,
+					group
+				)));
+				constrained = true;
+			}
+			if let Some(chapter) = c.chapter()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} belongs to the chapter “{}”.",
+					index + 1,
+					chapter
+				)));
+				constrained = true;
+			}
+			let tags = c.tags().collect::<Vec<_>>();
+			if !tags.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} is tagged {}.",
+					index + 1,
+					tags.join(", ")
+				)));
+				constrained = true;
+			}
+			if c.repeats > 1
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} appears {} times, separated by at least {}.",
+					index + 1,
+					c.repeats,
+					others(c.repeat_gap)
+				)));
+				constrained = true;
+			}
+			if !c.after.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must come after {}.",
+					index + 1,
+					c.after.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", ")
+				)));
+				constrained = true;
+			}
+			if !c.near.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must be within {} of {}.",
+					index + 1,
+					places(c.near_distance),
+					c.near.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", ")
+				)));
+				constrained = true;
+			}
+			if !c.apart.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must be separated from {} by at least {}.",
+					index + 1,
+					c.apart.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", "),
+					others(c.apart_gap)
+				)));
+				constrained = true;
+			}
+			if let Some(n) = c.offset_from
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must land exactly {} after §{}.",
+					index + 1,
+					places(c.offset),
+					n
+				)));
+				constrained = true;
+			}
+			if !c.not_adjacent.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} must never be adjacent to {}.",
+					index + 1,
+					c.not_adjacent.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", ")
+				)));
+				constrained = true;
+			}
+			if !c.prefer_before.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} should preferably come before {} (weight {}).",
+					index + 1,
+					c.prefer_before.iter()
+						.map(|n| format!("§{}", n))
+						.collect::<Vec<_>>()
+						.join(", "),
+					c.preference_weight
+				)));
+				constrained = true;
+			}
+			if c.place_preference != PlacePreference::Anywhere
+			{
+				blocks.push(Block::Bullet(format!(
+					"§{} should preferably land {} (weight {}).",
+					index + 1,
+					c.place_preference.name().to_lowercase(),
+					c.place_weight
+				)));
+				constrained = true;
+			}
+		}
+		for tag_constraint in &self.tag_constraints
+		{
+			let tag = tag_constraint.tag.trim();
+			blocks.push(Block::Bullet(match tag_constraint.kind
+			{
+				TagConstraintKind::After(n) => format!(
+					"Every section tagged {} must come after §{}.",
+					tag,
+					n
+				),
+				TagConstraintKind::Before(n) => format!(
+					"Every section tagged {} must come before §{}.",
+					tag,
+					n
+				),
+				TagConstraintKind::NeverAdjacent => format!(
+					"No two sections tagged {} may be adjacent.",
+					tag
+				)
+			}));
+			constrained = true;
+		}
+		if self.anthology
+		{
+			blocks.push(Block::Bullet(
+				"The manuscript is an anthology, so its stories shuffle as \
+				blocks, and chapters and rotations don't apply.".to_string()
+			));
+			constrained = true;
+		}
+		else
+		{
+			for name in chapter_names(&self.chapter_memberships())
+			{
+				blocks.push(Block::Bullet(format!(
+					"The chapter “{}”: {}",
+					name,
+					chapter_policy(&self.chapters, name).description()
+				)));
+				constrained = true;
+			}
+		}
+		if self.rotation_in_effect().is_some()
+		{
+			let tags = self.rotation_tags().join(", ");
+			blocks.push(Block::Bullet(match self.rotation_mode
+			{
+				RotationMode::Strict => format!(
+					"The sections tagged {} take turns in strict rotation.",
+					tags
+				),
+				RotationMode::NoRepeats => format!(
+					"No two sections with the same tag among {} may be \
+					adjacent.",
+					tags
+				)
+			}));
+			constrained = true;
+		}
+		for layer in self.layers.iter().filter(|layer| layer.enabled)
+		{
+			let statements = layer.script.lines()
+				.map(str::trim)
+				.filter(|line| !line.is_empty())
+				.collect::<Vec<_>>();
+			if !statements.is_empty()
+			{
+				blocks.push(Block::Bullet(format!(
+					"The layer “{}” adds: {}.",
+					layer.name,
+					statements.join("; ")
+				)));
+				constrained = true;
+			}
+		}
+		if !constrained
+		{
+			blocks.push(Block::Paragraph(
+				"There are no constraints, so every ordering is valid."
+					.to_string()
+			));
+		}
+		else if let Some(orderings) = self.valid_orderings()
+		{
+			blocks.push(Block::Paragraph(
+				if orderings.is_zero()
+				{
+					"These constraints contradict one another, so no ordering \
+					satisfies them.".to_string()
+				}
+				else
+				{
+					format!(
+						"Valid orderings under the ordering constraints: {}. \
+						Pinned positions, spacing, rotations, stories, and \
+						layers can narrow the possibilities further.",
+						orderings
+					)
+				}
+			));
+		}
+		blocks.push(Block::Heading(2, "Proposed order".to_string()));
+		match (&self.presented_section_indices, &self.shuffled_sections)
+		{
+			(Some(indices), Some(shuffled)) =>
+			{
+				for (position, (index, section)) in
+					indices.iter().zip(shuffled).enumerate()
+				{
+					let movement = match position.cmp(index)
+					{
+						Ordering::Equal => "unmoved".to_string(),
+						Ordering::Less =>
+							format!("moved {} earlier", places(index - position)),
+						Ordering::Greater =>
+							format!("moved {} later", places(position - index))
+					};
+					blocks.push(Block::Heading(3, format!(
+						"{}. {}, {}",
+						position + 1,
+						self.section_title(*index),
+						movement
+					)));
+					blocks.extend(paragraphs(section));
+				}
+			},
+			_ => blocks.push(Block::Paragraph(
+				"No reordering has been proposed yet.".to_string()
+			))
+		}
+		blocks
+	}
+
+	/// Answer the title of the specified section for a review packet, which
+	/// includes its [identifier](Self::section_ids) for unambiguous reference.
+	fn section_title(&self, index: usize) -> String
+	{
+		match self.section_ids.get(index)
+		{
+			Some(id) => format!("§{} ({})", index + 1, id),
+			None => format!("§{}", index + 1)
+		}
+	}
+}
+
+/// Split the specified section into paragraph [blocks](Block), at blank
+/// lines.
+fn paragraphs(section: &str) -> Vec<Block>
+{
+	let mut blocks = vec![];
+	let mut paragraph = Vec::new();
+	for line in section.lines().map(str::trim)
+	{
+		if line.is_empty()
+		{
+			if !paragraph.is_empty()
+			{
+				blocks.push(Block::Paragraph(paragraph.join("\n")));
+				paragraph.clear();
+			}
+		}
+		else
+		{
+			paragraph.push(line);
+		}
+	}
+	if !paragraph.is_empty()
+	{
+		blocks.push(Block::Paragraph(paragraph.join("\n")));
+	}
+	blocks
+}
+
+/// Describe the specified number of positions, e.g., "1 place".
+fn places(count: usize) -> String
+{
+	if count == 1 { "1 place".to_string() }
+	else { format!("{} places", count) }
+}
+
+/// Describe the specified number of intervening sections, e.g., "1 other
+/// section".
+fn others(count: usize) -> String
+{
+	if count == 1 { "1 other section".to_string() }
+	else { format!("{} other sections", count) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Constraints.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The constraints placed upon a
+/// [manuscript](StoryShufflerApp::original_manuscript)
+/// [section](StoryShufflerApp::original_sections). Pseudorandom permutation of
+/// the section order must honor the constraints of each section.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Constraints
+{
+	/// Whether the associated
+	/// [manuscript](StoryShufflerApp::original_manuscript)
+	/// [section](StoryShufflerApp::original_sections) is locked in place,
+	/// i.e., [pinned](Self::pin) to its current position.
+	fixed: bool,
+
+	/// Whether the associated [section](StoryShufflerApp::original_sections)
+	/// must precede every other section, save the
+	/// [front&#32;matter](Constraints::matter). This generalizes a
+	/// [fixed](Self::fixed) beginning to any section.
+	before_all: bool,
+
+	/// Whether the associated [section](StoryShufflerApp::original_sections)
+	/// must follow every other section, save the
+	/// [back&#32;matter](Constraints::matter). This generalizes a
+	/// [fixed](Self::fixed) ending to any section.
+	after_all: bool,
+
+	/// The sections which must occur _strictly after_ the associated
+	/// [section](StoryShufflerApp::original_sections), e.g., for reasons of
+	/// narrative causality, denoted by their **one-based** indices.
+	before: Vec<usize>,
+
+	/// The **one-based** position to which the associated
+	/// [section](StoryShufflerApp::original_sections) is pinned, if any, such
+	/// that every [shuffle](StoryShufflerApp::shuffle) puts it there. This
+	/// generalizes [`fixed`](Self::fixed) to any section and any position.
+	pin: Option<usize>,
+
+	/// The [parity](Parity) of the **one-based** positions that the
+	/// associated [section](StoryShufflerApp::original_sections) may occupy,
+	/// if restricted, e.g., so that interleaved storylines keep to alternate
+	/// places.
+	parity: Option<Parity>,
+
+	/// The number of times that the associated
+	/// [section](StoryShufflerApp::original_sections) appears in every
+	/// reordering, e.g., `3` for a recurring refrain. The ordering constraints
+	/// govern its first appearance; the [repeats](StoryShufflerApp::repeat)
+	/// follow it.
+	repeats: usize,
+
+	/// The minimum number of other sections between each two appearances of a
+	/// [repeated](Self::repeats) section.
+	repeat_gap: usize,
+
+	/// The workspace for in-process edits of [`before`](Self::before).
+	text_buffer: String,
+
+	/// The [text&#32;buffer](Self::text_buffer) is _prima facie_ valid, i.e.,
+	/// it satisfies its lexical requirements if not its semantic ones. Defaults
+	/// to `true`, because an empty buffer is well-formed (and even semantically
+	/// valid).
+	text_buffer_is_valid: bool,
+
+	/// The sections which must occur _strictly before_ the associated
+	/// [section](StoryShufflerApp::original_sections), denoted by their
+	/// **one-based** indices. This is the converse of [`before`](Self::before),
+	/// for the writer's convenience.
+	after: Vec<usize>,
+
+	/// The workspace for in-process edits of [`after`](Self::after).
+	after_text_buffer: String,
+
+	/// The [text&#32;buffer](Self::after_text_buffer) for
+	/// [`after`](Self::after) is _prima facie_ valid, as for
+	/// [`text_buffer_is_valid`](Self::text_buffer_is_valid).
+	after_text_buffer_is_valid: bool,
+
+	/// The sections which must land within
+	/// [`near_distance`](Self::near_distance) positions of the associated
+	/// [section](StoryShufflerApp::original_sections), in either direction,
+	/// e.g., to keep a payoff close to its setup, denoted by their
+	/// **one-based** indices.
+	near: Vec<usize>,
+
+	/// The maximum number of positions between the associated
+	/// [section](StoryShufflerApp::original_sections) and each of the
+	/// sections [near](Self::near) it, such that `1` means adjacent.
+	near_distance: usize,
+
+	/// The workspace for in-process edits of [`near`](Self::near).
+	near_text_buffer: String,
+
+	/// The [text&#32;buffer](Self::near_text_buffer) for [`near`](Self::near)
+	/// is _prima facie_ valid, as for
+	/// [`text_buffer_is_valid`](Self::text_buffer_is_valid).
+	near_text_buffer_is_valid: bool,
+
+	/// The sections which must land at least [`apart_gap`](Self::apart_gap)
+	/// other sections away from the associated
+	/// [section](StoryShufflerApp::original_sections), in either direction,
+	/// e.g., to space out flashbacks or repeated motifs, denoted by their
+	/// **one-based** indices. This is the inverse of [`near`](Self::near).
+	apart: Vec<usize>,
+
+	/// The minimum number of other sections between the associated
+	/// [section](StoryShufflerApp::original_sections) and each of the
+	/// sections [apart](Self::apart) from it.
+	apart_gap: usize,
+
+	/// The workspace for in-process edits of [`apart`](Self::apart).
+	apart_text_buffer: String,
+
+	/// The [text&#32;buffer](Self::apart_text_buffer) for
+	/// [`apart`](Self::apart) is _prima facie_ valid, as for
+	/// [`text_buffer_is_valid`](Self::text_buffer_is_valid).
+	apart_text_buffer_is_valid: bool,
+
+	/// The section that the associated
+	/// [section](StoryShufflerApp::original_sections) must follow by exactly
+	/// [`offset`](Self::offset) positions, e.g., to land an answer exactly
+	/// two scenes after its question, denoted by its **one-based** index, if
+	/// any.
+	offset_from: Option<usize>,
+
+	/// The exact number of positions by which the associated
+	/// [section](StoryShufflerApp::original_sections) follows the section
+	/// given by [`offset_from`](Self::offset_from), such that `1` means
+	/// immediately after.
+	offset: usize,
+
+	/// The sections which must never land immediately before or after the
+	/// associated [section](StoryShufflerApp::original_sections), denoted by
+	/// their **one-based** indices. This is a lighter form of
+	/// [`apart`](Self::apart), without a gap to choose.
+	not_adjacent: Vec<usize>,
+
+	/// The workspace for in-process edits of
+	/// [`not_adjacent`](Self::not_adjacent).
+	not_adjacent_text_buffer: String,
+
+	/// The [text&#32;buffer](Self::not_adjacent_text_buffer) for
+	/// [`not_adjacent`](Self::not_adjacent) is _prima facie_ valid, as for
+	/// [`text_buffer_is_valid`](Self::text_buffer_is_valid).
+	not_adjacent_text_buffer_is_valid: bool,
+
+	/// The sections which should preferably occur after the associated
+	/// [section](StoryShufflerApp::original_sections), denoted by their
+	/// **one-based** indices. Unlike [`before`](Self::before), this is a soft
+	/// constraint: when [shuffling](StoryShufflerApp::shuffle) for the best of
+	/// several attempts, orderings that honor it are favored in proportion to
+	/// its [weight](Self::preference_weight), but no ordering is ruled out
+	/// for want of it.
+	prefer_before: Vec<usize>,
+
+	/// The workspace for in-process edits of
+	/// [`prefer_before`](Self::prefer_before).
+	prefer_before_text_buffer: String,
+
+	/// The [text&#32;buffer](Self::prefer_before_text_buffer) for
+	/// [`prefer_before`](Self::prefer_before) is _prima facie_ valid, as for
+	/// [`text_buffer_is_valid`](Self::text_buffer_is_valid).
+	prefer_before_text_buffer_is_valid: bool,
+
+	/// The importance of each of the [preferences](Self::prefer_before) of
+	/// the associated [section](StoryShufflerApp::original_sections), relative
+	/// to those of other sections and to the quality of the
+	/// [transitions](Transition). Each place by which an ordering misses a
+	/// preference costs this much of its score.
+	preference_weight: f32,
+
+	/// Where the associated [section](StoryShufflerApp::original_sections)
+	/// prefers to land, e.g., early for a hook, which biases every
+	/// [shuffle](StoryShufflerApp::shuffle) without ruling out any ordering.
+	place_preference: PlacePreference,
+
+	/// The strength of the [place&#32;preference](Self::place_preference). At
+	/// its preferred place, the associated
+	/// [section](StoryShufflerApp::original_sections) is `1.0` plus this many
+	/// times as likely to come next as an indifferent section, and at the
+	/// opposite extreme, that many times less likely.
+	place_weight: f32,
+
+	/// The message to present if a paradox is discovered, i.e., because the
+	/// ordering constraints lead to a cycle.
+	paradox_error: Option<String>,
+
+	/// A short name for the associated
+	/// [section](StoryShufflerApp::original_sections), e.g., for
+	/// [sorting](SortKey::Label).
+	label: String,
+
+	/// The place of the associated
+	/// [section](StoryShufflerApp::original_sections) in the story's internal
+	/// chronology, e.g., `1999-03-04` or `Day 3`, which need not match its
+	/// place in the manuscript.
+	date: String,
+
+	/// The tags of the associated
+	/// [section](StoryShufflerApp::original_sections), as a comma-separated
+	/// list.
+	tags: String,
+
+	/// Whether every [excerpt](StoryShufflerApp::excerpt) must include the
+	/// associated [section](StoryShufflerApp::original_sections).
+	must_include: bool,
+
+	/// Whether the associated [section](StoryShufflerApp::original_sections)
+	/// is left out of every reordering, e.g., a deleted scene that the writer
+	/// isn't ready to discard. It stays in the manuscript and keeps its
+	/// constraints, and it still shuffles with the others, so that every
+	/// constraint among them holds, even indirectly, but it never appears in
+	/// the [reordered&#32;manuscript](StoryShufflerApp::shuffled_sections).
+	omit: bool,
+
+	/// The name of the group to which the associated
+	/// [section](StoryShufflerApp::original_sections) belongs, if any. The
+	/// members of a group [shuffle](StoryShufflerApp::shuffle) as a block,
+	/// staying together and in their original order.
+	group: String,
+
+	/// The name of the [chapter](Chapter) to which the associated
+	/// [section](StoryShufflerApp::original_sections) belongs, if any. The
+	/// sections of a chapter stay together, and its
+	/// [policy](ChapterPolicy) decides whether they shuffle within it.
+	chapter: String,
+
+	/// The part of the book to which the associated
+	/// [section](StoryShufflerApp::original_sections) belongs. Front and back
+	/// matter are anchored at the beginning and end of the manuscript.
+	matter: Matter
+}
+
+impl Constraints
+{
+	/// Answer the nonempty [tags](Self::tags), in order of appearance.
+	fn tags(&self) -> impl Iterator<Item = &str>
+	{
+		self.tags.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+	}
+
+	/// Answer `true` if the specified tag is among the [tags](Self::tags),
+	/// without regard to case.
+	fn has_tag(&self, tag: &str) -> bool
+	{
+		self.tags().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+	}
+
+	/// Answer the name of the [group](Self::group), if any.
+	fn group(&self) -> Option<&str>
+	{
+		Some(self.group.trim()).filter(|group| !group.is_empty())
+	}
+
+	/// Answer the name of the [chapter](Self::chapter), if any.
+	fn chapter(&self) -> Option<&str>
+	{
+		Some(self.chapter.trim()).filter(|chapter| !chapter.is_empty())
+	}
+
+	/// Answer every section mentioned by the section lists, e.g.,
+	/// [`before`](Self::before), as **one-based** numbers, in order of
+	/// appearance.
+	fn references(&self) -> impl Iterator<Item = usize> + '_
+	{
+		self.before.iter()
+			.chain(&self.after)
+			.chain(&self.near)
+			.chain(&self.apart)
+			.chain(&self.offset_from)
+			.chain(&self.not_adjacent)
+			.chain(&self.prefer_before)
+			.copied()
+	}
+
+	/// Explain each problem with the section lists of the associated section,
+	/// given by its **zero-based** index, in a manuscript of the specified
+	/// number of sections: a mention of a section beyond the end, or of the
+	/// section itself.
+	fn reference_problems(&self, index: usize, count: usize) -> Vec<String>
+	{
+		let mut problems = vec![];
+		for n in self.references()
+		{
+			let problem = if n > count
+			{
+				format!(
+					"§{} doesn't exist, because there are only {} sections.",
+					n,
+					count
+				)
+			}
+			else if n == index + 1
+			{
+				format!("§{} can't refer to itself.", n)
+			}
+			else
+			{
+				continue
+			};
+			if !problems.contains(&problem)
+			{
+				problems.push(problem);
+			}
+		}
+		problems
+	}
+
+	/// Replace the ordering constraints of the associated section, given by
+	/// its **zero-based** index, with those of the specified `source`, e.g.,
+	/// so that each of several interludes can follow the same chapter
+	/// without retyping its lists. Mentions of the associated section itself
+	/// are dropped. Whether the section is [fixed](Self::fixed),
+	/// [pinned](Self::pin), or first or last of all stays its own, as do its
+	/// descriptive attributes.
+	fn copy_constraints_from(&mut self, source: &Constraints, index: usize)
+	{
+		let copy = |list: &[usize]| list.iter()
+			.copied()
+			.filter(|n| *n != index + 1)
+			.collect::<Vec<_>>();
+		self.before = copy(&source.before);
+		self.text_buffer = section_list_text(&self.before);
+		self.text_buffer_is_valid = true;
+		self.after = copy(&source.after);
+		self.after_text_buffer = section_list_text(&self.after);
+		self.after_text_buffer_is_valid = true;
+		self.near = copy(&source.near);
+		self.near_text_buffer = section_list_text(&self.near);
+		self.near_text_buffer_is_valid = true;
+		self.near_distance = source.near_distance;
+		self.apart = copy(&source.apart);
+		self.apart_text_buffer = section_list_text(&self.apart);
+		self.apart_text_buffer_is_valid = true;
+		self.apart_gap = source.apart_gap;
+		self.offset_from = source.offset_from.filter(|n| *n != index + 1);
+		self.offset = source.offset;
+		self.not_adjacent = copy(&source.not_adjacent);
+		self.not_adjacent_text_buffer = section_list_text(&self.not_adjacent);
+		self.not_adjacent_text_buffer_is_valid = true;
+		self.prefer_before = copy(&source.prefer_before);
+		self.prefer_before_text_buffer =
+			section_list_text(&self.prefer_before);
+		self.prefer_before_text_buffer_is_valid = true;
+		self.preference_weight = source.preference_weight;
+		self.parity = source.parity;
+		self.place_preference = source.place_preference;
+		self.place_weight = source.place_weight;
+	}
+}
+
+impl Default for Constraints
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			fixed: false,
+			before_all: false,
+			after_all: false,
+			before: vec![],
+			pin: None,
+			parity: None,
+			repeats: 1,
+			repeat_gap: DEFAULT_REPEAT_GAP,
+			text_buffer: String::new(),
+			text_buffer_is_valid: true,
+			after: vec![],
+			after_text_buffer: String::new(),
+			after_text_buffer_is_valid: true,
+			near: vec![],
+			near_distance: DEFAULT_NEAR_DISTANCE,
+			near_text_buffer: String::new(),
+			near_text_buffer_is_valid: true,
+			apart: vec![],
+			apart_gap: DEFAULT_APART_GAP,
+			apart_text_buffer: String::new(),
+			apart_text_buffer_is_valid: true,
+			offset_from: None,
+			offset: DEFAULT_OFFSET,
+			not_adjacent: vec![],
+			not_adjacent_text_buffer: String::new(),
+			not_adjacent_text_buffer_is_valid: true,
+			prefer_before: vec![],
+			prefer_before_text_buffer: String::new(),
+			prefer_before_text_buffer_is_valid: true,
+			preference_weight: DEFAULT_PREFERENCE_WEIGHT,
+			place_preference: PlacePreference::Anywhere,
+			place_weight: DEFAULT_PLACE_WEIGHT,
+			paradox_error: None,
+			label: String::new(),
+			date: String::new(),
+			tags: String::new(),
+			must_include: false,
+			omit: false,
+			group: String::new(),
+			chapter: String::new(),
+			matter: Matter::Body
+		}
+	}
+}
+
+/// Answer the **one-based** position to which each section is locked, if any,
+/// either because it's [fixed](Constraints::fixed) in place or because it's
+/// [pinned](Constraints::pin). Positions count only the sections that aren't
+/// [omitted](Constraints::omit), since only those appear in a reordering, so
+/// a fixed section keeps its place among them, and an omitted section is
+/// never locked.
+fn locked_positions(constraints: &[Constraints]) -> Vec<Option<usize>>
+{
+	let mut shown = 0;
+	constraints.iter()
+		.map(|c| {
+			if c.omit
+			{
+				return None
+			}
+			shown += 1;
+			if c.fixed { Some(shown) } else { c.pin }
+		})
+		.collect()
+}
+
+/// Where in the output a section prefers to land, as a
+/// [place&#32;preference](Constraints::place_preference).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum PlacePreference
+{
+	/// The section has no preference.
+	#[default]
+	Anywhere,
+
+	/// The section prefers to land near the beginning.
+	Early,
+
+	/// The section prefers to land near the middle.
+	Middle,
+
+	/// The section prefers to land near the end.
+	Late
+}
+
+impl PlacePreference
+{
+	/// Every place preference, in presentation order.
+	const ALL: [PlacePreference; 4] = [
+		PlacePreference::Anywhere,
+		PlacePreference::Early,
+		PlacePreference::Middle,
+		PlacePreference::Late
+	];
+
+	/// Answer the human-readable name of the place preference.
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			PlacePreference::Anywhere => "Anywhere",
+			PlacePreference::Early => "Early",
+			PlacePreference::Middle => "In the middle",
+			PlacePreference::Late => "Late"
+		}
+	}
+
+	/// Answer the place preference denoted by the specified keyword of a
+	/// constraint script, e.g., `early`, if any.
+	pub(crate) fn from_keyword(keyword: &str) -> Option<Self>
+	{
+		PlacePreference::ALL.into_iter()
+			.find(|place| place.keyword() == keyword)
+	}
+
+	/// Answer the keyword that denotes the place preference in a constraint
+	/// script, which [`from_keyword`](Self::from_keyword) reads back.
+	pub(crate) fn keyword(self) -> &'static str
+	{
+		match self
+		{
+			PlacePreference::Anywhere => "anywhere",
+			PlacePreference::Early => "early",
+			PlacePreference::Middle => "middle",
+			PlacePreference::Late => "late"
+		}
+	}
+
+	/// Answer how well the specified progress through an ordering, from `0.0`
+	/// (the first place) to `1.0` (the last place), suits the place
+	/// preference, from `-1.0` (the opposite extreme) to `1.0` (the preferred
+	/// place).
+	pub(crate) fn affinity(self, progress: f32) -> f32
+	{
+		match self
+		{
+			PlacePreference::Anywhere => 0.0,
+			PlacePreference::Early => 1.0 - 2.0 * progress,
+			PlacePreference::Middle => 1.0 - 2.0 * (2.0 * progress - 1.0).abs(),
+			PlacePreference::Late => 2.0 * progress - 1.0
+		}
+	}
+}
+
+/// Answer the [place&#32;preference](Constraints::place_preference) and
+/// [weight](Constraints::place_weight) of each of the sections with the
+/// specified constraints, for [biasing](random_order) the choice of the next
+/// section, or nothing if no section has a preference.
+fn place_preferences(
+	constraints: &[Constraints]
+) -> Vec<(PlacePreference, f32)>
+{
+	if constraints.iter()
+		.all(|c| c.place_preference == PlacePreference::Anywhere)
+	{
+		return vec![]
+	}
+	constraints.iter()
+		.map(|c| (c.place_preference, c.place_weight))
+		.collect()
+}
+
+/// The parity of the **one-based** positions that a section may
+/// [occupy](Constraints::parity).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Parity
+{
+	/// The section may only occupy odd positions, i.e., first, third, etc.
+	Odd,
+
+	/// The section may only occupy even positions, i.e., second, fourth, etc.
+	Even
+}
+
+impl Parity
+{
+	/// Every parity, in presentation order.
+	pub(crate) const ALL: [Parity; 2] = [Parity::Odd, Parity::Even];
+
+	/// Answer the human-readable name of the parity, which is also its keyword
+	/// in a constraint script.
+	pub(crate) fn name(self) -> &'static str
+	{
+		match self
+		{
+			Parity::Odd => "odd",
+			Parity::Even => "even"
+		}
+	}
+
+	/// Answer `true` if the specified **one-based** position has the parity.
+	pub(crate) fn admits(self, position: usize) -> bool
+	{
+		(position % 2 == 1) == (self == Parity::Odd)
+	}
+
+	/// Answer the number of positions of the parity among the specified
+	/// number of positions.
+	fn capacity(self, count: usize) -> usize
+	{
+		match self
+		{
+			Parity::Odd => (count + 1) / 2,
+			Parity::Even => count / 2
+		}
+	}
+}
+
+/// Find the first section that occupies a position of the wrong
+/// [parity](Constraints::parity) within the specified ordering, given as
+/// **zero-based** section indices. `parities` supplies the parity of each
+/// section, if any. Answer the **zero-based** index of the section and its
+/// parity, or `None` if every section has a position of the right parity.
+fn first_misparity(
+	order: &[usize],
+	parities: &[Option<Parity>]
+) -> Option<(usize, Parity)>
+{
+	order.iter()
+		.enumerate()
+		.find_map(|(position, index)|
+			parities.get(*index)
+				.copied()
+				.flatten()
+				.filter(|parity| !parity.admits(position + 1))
+				.map(|parity| (*index, parity))
+		)
+}
+
+/// An ordering constraint, stated in a [before&#32;list](Constraints::before)
+/// or an [after&#32;list](Constraints::after), that the other constraints
+/// already imply, e.g., `1 < 3` given `1 < 2` and `2 < 3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Redundancy
+{
+	/// The **zero-based** index of the section whose list states the
+	/// constraint.
+	section: usize,
+
+	/// The **one-based** number of the other section, as listed.
+	other: usize,
+
+	/// Whether the constraint is stated in the after list rather than the
+	/// before list.
+	after: bool
+}
+
+impl Redundancy
+{
+	/// Answer a description of the redundancy, suitable for the writer.
+	fn describe(self) -> String
+	{
+		let (earlier, later) =
+			if self.after { (self.other, self.section + 1) }
+			else { (self.section + 1, self.other) };
+		format!(
+			"§{} before §{} is already implied by the other constraints.",
+			earlier,
+			later
+		)
+	}
+}
+
+/// Find every [redundant](Redundancy) ordering constraint among the specified
+/// [constraints](Constraints), i.e., every constraint that repeats an earlier
+/// one or that the specified constraint graph also implies by way of other
+/// sections. Removing every redundant constraint at once leaves the
+/// [transitive&#32;reduction](implied_indirectly) of the graph, which permits
+/// exactly the same orderings. The graph must not contain a cycle.
+fn redundant_constraints(
+	graph: &DiGraph<usize, (), usize>,
+	constraints: &[Constraints]
+) -> Vec<Redundancy>
+{
+	let mut stated = HashSet::new();
+	let mut redundancies = vec![];
+	for (index, c) in constraints.iter().enumerate()
+	{
+		let statements = c.before.iter()
+			.map(|n| (false, *n))
+			.chain(c.after.iter().map(|n| (true, *n)));
+		for (after, other) in statements
+		{
+			let Some(o) = other.checked_sub(1) else { continue };
+			let (earlier, later) =
+				if after { (o, index) } else { (index, o) };
+			if !stated.insert((earlier, later))
+				|| implied_indirectly(graph, earlier, later)
+			{
+				redundancies.push(Redundancy { section: index, other, after });
+			}
+		}
+	}
+	redundancies
+}
+
+/// A constraint over every section that bears a particular
+/// [tag](Constraints::tags), rather than over particular sections. Tag
+/// constraints are expanded into [edges](tag_edges) and
+/// [checks](first_tag_stray) afresh at every shuffle, so they cover sections
+/// tagged later.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct TagConstraint
+{
+	/// The tag, which is compared without regard to case.
+	tag: String,
+
+	/// What the constraint requires of the tagged sections.
+	kind: TagConstraintKind
+}
+
+/// What a [tag&#32;constraint](TagConstraint) requires of the tagged
+/// sections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum TagConstraintKind
+{
+	/// Every tagged section must occur after the section with the specified
+	/// **one-based** number.
+	After(usize),
+
+	/// Every tagged section must occur before the section with the specified
+	/// **one-based** number.
+	Before(usize),
+
+	/// No two tagged sections may be adjacent.
+	#[default]
+	NeverAdjacent
+}
+
+impl TagConstraintKind
+{
+	/// Answer the human-readable name of the kind of constraint.
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			TagConstraintKind::After(_) => "all after",
+			TagConstraintKind::Before(_) => "all before",
+			TagConstraintKind::NeverAdjacent => "never adjacent"
+		}
+	}
+
+	/// Answer the **one-based** number of the section to which the kind of
+	/// constraint refers, if any.
+	fn section(self) -> Option<usize>
+	{
+		match self
+		{
+			TagConstraintKind::After(n) | TagConstraintKind::Before(n) =>
+				Some(n),
+			TagConstraintKind::NeverAdjacent => None
+		}
+	}
+}
+
+/// Answer the ordering constraints that the specified
+/// [tag&#32;constraints](TagConstraint) impose on the sections with the
+/// specified [constraints](Constraints), as pairs of **zero-based** section
+/// indices, the first of which must occur earlier. Constraints that refer to
+/// nonexistent sections impose nothing.
+fn tag_edges(
+	tag_constraints: &[TagConstraint],
+	constraints: &[Constraints]
+) -> Vec<(usize, usize)>
+{
+	let mut edges = vec![];
+	for tag_constraint in tag_constraints
+	{
+		let Some(anchor) = tag_constraint.kind.section()
+			.and_then(|n| n.checked_sub(1))
+			.filter(|anchor| *anchor < constraints.len())
+		else { continue };
+		let tagged = constraints.iter()
+			.enumerate()
+			.filter(|(index, c)|
+				*index != anchor && c.has_tag(&tag_constraint.tag)
+			)
+			.map(|(index, _)| index);
+		match tag_constraint.kind
+		{
+			TagConstraintKind::After(_) =>
+				edges.extend(tagged.map(|index| (anchor, index))),
+			_ => edges.extend(tagged.map(|index| (index, anchor)))
+		}
+	}
+	edges
+}
+
+/// Find the first [nonadjacency](TagConstraintKind::NeverAdjacent)
+/// requirement of the specified [tag&#32;constraints](TagConstraint) that the
+/// specified ordering, given as **zero-based** section indices, violates.
+/// Answer an explanation, or `None` if every requirement is met.
+fn first_tag_stray(
+	order: &[usize],
+	tag_constraints: &[TagConstraint],
+	constraints: &[Constraints]
+) -> Option<String>
+{
+	tag_constraints.iter()
+		.filter(|t| t.kind == TagConstraintKind::NeverAdjacent)
+		.find_map(|t| order.windows(2)
+			.find(|pair|
+				constraints[pair[0]].has_tag(&t.tag)
+					&& constraints[pair[1]].has_tag(&t.tag)
+			)
+			.map(|pair| format!(
+				"§{} and §{} are both tagged {}, so they must never be \
+				adjacent.",
+				pair[0] + 1,
+				pair[1] + 1,
+				t.tag.trim()
+			))
+		)
+}
+
+/// Create a directed graph that represents the specified
+/// [constraints](Constraints), such that each vertex encodes an index into the
+/// supplied slice and each edge represents the predecessor being lexically
+/// [prior](Constraints::before) to the successor. Edges also anchor the
+/// [front&#32;and&#32;back&#32;matter](Constraints::matter), and connect the
+/// sections that must [precede](Constraints::before_all) or
+/// [follow](Constraints::after_all) every other section to the rest of the
+/// body.
+fn compute_graph(constraints: &[Constraints]) -> DiGraph<usize, (), usize>
+{
+	let mut graph = DiGraph::default();
+	let count = constraints.len();
+	if count == 0
+	{
+		// There are no constraints, so save some time and ceremony.
+		return graph
+	}
+	// For simplicity, build the nodes up front.
+	for (index, _) in constraints.iter().enumerate()
+	{
+		// Adjust the index to one-based.
+		graph.add_node(index + 1);
+	}
+	// Now create all of the edges.
+	let positions = locked_positions(constraints);
+	for (index, c) in constraints.iter().enumerate()
+	{
+		// A section that must precede or follow every other section, e.g., a
+		// fixed beginning or ending, is connected to each of them. The front
+		// and back matter stay anchored at their own ends.
+		if c.before_all || index == 0 && c.fixed
+		{
+			for (successor, _) in constraints.iter()
+				.enumerate()
+				.filter(|(successor, o)|
+					*successor != index && o.matter != Matter::Front
+				)
+			{
+				graph.update_edge(
+					NodeIndex::new(index),
+					NodeIndex::new(successor),
+					()
+				);
+			}
+		}
+		if c.after_all || index == count - 1 && c.fixed
+		{
+			for (predecessor, _) in constraints.iter()
+				.enumerate()
+				.filter(|(predecessor, o)|
+					*predecessor != index && o.matter != Matter::Back
+				)
+			{
+				graph.update_edge(
+					NodeIndex::new(predecessor),
+					NodeIndex::new(index),
+					()
+				);
+			}
+		}
+		for successor in &c.before
+		{
+			// Adjust the target index to zero-based (because it is
+			// one-based).
+			graph.update_edge(
+				NodeIndex::new(index),
+				NodeIndex::new(*successor - 1),
+				()
+			);
+		}
+		if let Some(position) = positions[index]
+		{
+			// Order the fixed and pinned sections by their positions. The
+			// shuffle puts each in its place.
+			for (other, _) in positions.iter()
+				.enumerate()
+				.filter(|(_, p)| p.map_or(false, |p| p > position))
+			{
+				graph.update_edge(
+					NodeIndex::new(index),
+					NodeIndex::new(other),
+					()
+				);
+			}
+		}
+		for predecessor in c.after.iter().chain(&c.offset_from)
+		{
+			// An "after" constraint is just a "before" constraint seen from
+			// the other end. So is an exact offset, as far as the order is
+			// concerned.
+			graph.update_edge(
+				NodeIndex::new(*predecessor - 1),
+				NodeIndex::new(index),
+				()
+			);
+		}
+	}
+	// Anchor the front matter at the beginning and the back matter at the
+	// end, each in its original order. Chain each kind of matter, and connect
+	// the chains to the body only at their ends, so that the number of paths
+	// through the graph stays small.
+	let of = |matter: Matter| constraints.iter()
+		.enumerate()
+		.filter(move |(_, c)| c.matter == matter)
+		.map(|(index, _)| NodeIndex::new(index))
+		.collect::<Vec<_>>();
+	let front = of(Matter::Front);
+	let body = of(Matter::Body);
+	let back = of(Matter::Back);
+	for chain in [&front, &back]
+	{
+		for pair in chain.windows(2)
+		{
+			graph.update_edge(pair[0], pair[1], ());
+		}
+	}
+	if let Some(last) = front.last()
+	{
+		for index in body.iter().chain(back.first())
+		{
+			graph.update_edge(*last, *index, ());
+		}
+	}
+	if let Some(first) = back.first()
+	{
+		for index in &body
+		{
+			graph.update_edge(*index, *first, ());
+		}
+	}
+	graph
+}
+
+/// Create the directed graph of the whole system of constraints: the
+/// [section&#32;constraints](compute_graph), plus the edges imposed by the
+/// [tag&#32;constraints](tag_edges) and, unless the manuscript is an
+/// anthology, by the [chapters](chapter_edges).
+fn compute_constraint_graph(
+	constraints: &[Constraints],
+	tag_constraints: &[TagConstraint],
+	chapters: &[Chapter],
+	anthology: bool
+) -> DiGraph<usize, (), usize>
+{
+	let mut graph = compute_graph(constraints);
+	for (from, to) in tag_edges(tag_constraints, constraints)
+	{
+		graph.update_edge(NodeIndex::new(from), NodeIndex::new(to), ());
+	}
+	if !anthology
+	{
+		let memberships = constraints.iter()
+			.map(Constraints::chapter)
+			.collect::<Vec<_>>();
+		for (from, to) in chapter_edges(&memberships, chapters)
+		{
+			graph.update_edge(NodeIndex::new(from), NodeIndex::new(to), ());
+		}
+	}
+	graph
+}
+
+/// Lay out a [diagram](Diagram) of the specified [constraints](Constraints),
+/// identifying each node by the [identifier](StoryShufflerApp::section_ids) of
+/// the corresponding section and describing it by the section's opening. Only
+/// the explicit [ordering&#32;constraints](Constraints::before) are drawn as
+/// edges, because a fixed beginning or ending would otherwise contribute an
+/// edge to every other section. Answer `None` if the constraints refer to
+/// nonexistent sections.
+fn diagram(
+	sections: &[String],
+	ids: &[String],
+	constraints: &[Constraints]
+) -> Option<Diagram>
+{
+	if !references_are_in_bounds(constraints)
+	{
+		return None
+	}
+	let graph = compute_graph(constraints);
+	let nodes = sections.iter()
+		.zip(ids)
+		.zip(constraints)
+		.enumerate()
+		.map(|(index, ((section, id), c))| DiagramNode {
+			id: id.clone(),
+			label: format!("§{}", index + 1),
+			description: first_line(section)
+				.chars()
+				.take(DIAGRAM_DESCRIPTION_LENGTH)
+				.collect(),
+			fixed: c.fixed,
+			x: 0.0,
+			y: 0.0
+		})
+		.collect();
+	let edges = constraints.iter()
+		.enumerate()
+		.flat_map(|(index, c)|
+			c.before.iter()
+				.map(move |successor| (index, successor - 1))
+				.chain(c.after.iter()
+					.map(move |predecessor| (predecessor - 1, index))
+				)
+		)
+		.collect::<Vec<_>>();
+	Some(layout_diagram(&graph, nodes, &edges))
+}
+
+/// Find the shortest cycle through the [constraint](Constraints) specified by
+/// `index`, if any, which explains a paradox in the fewest steps. The answered
+/// [`Vec`] begins and ends with `index`.
+fn find_cycle(
+	graph: &DiGraph<usize, (), usize>,
+	index: NodeIndex<usize>
+) -> Option<Vec<NodeIndex<usize>>>
+{
+	// Search breadth first, so that the first return to the origin is by way
+	// of the fewest steps.
+	let mut previous = vec![None; graph.node_count()];
+	let mut pending = VecDeque::from([index]);
+	while let Some(node) = pending.pop_front()
+	{
+		for successor in graph.neighbors(node)
+		{
+			if successor == index
+			{
+				// Retrace the steps back to the origin.
+				let mut cycle = vec![index, node];
+				while let Some(step) = previous[cycle.last().unwrap().index()]
+				{
+					cycle.push(step);
+				}
+				cycle.reverse();
+				return Some(cycle)
+			}
+			if previous[successor.index()].is_none()
+			{
+				previous[successor.index()] = Some(node);
+				pending.push_back(successor);
+			}
+		}
+	}
+	None
+}
+
+/// Find the first [pinned](Constraints::pin) section that doesn't occupy its
+/// position within the specified ordering, given as **zero-based** section
+/// indices. `pins` supplies the **one-based** pinned position of each
+/// section, if any. Answer the **zero-based** index of the section and its
+/// **one-based** position, or `None` if every pinned section is in place.
+fn first_misplaced(
+	order: &[usize],
+	pins: &[Option<usize>]
+) -> Option<(usize, usize)>
+{
+	order.iter()
+		.enumerate()
+		.find_map(|(position, index)|
+			pins[*index]
+				.filter(|pin| *pin != position + 1)
+				.map(|pin| (*index, pin))
+		)
+}
+
+/// Find the first spacing requirement, i.e., a [proximity](Constraints::near),
+/// [separation](Constraints::apart), [offset](Constraints::offset_from), or
+/// [nonadjacency](Constraints::not_adjacent) requirement, that the specified
+/// ordering, given as **zero-based** section indices, violates. Answer an
+/// explanation, or `None` if every requirement is met.
+fn first_stray(order: &[usize], constraints: &[Constraints]) -> Option<String>
+{
+	let mut positions = vec![None; constraints.len()];
+	for (position, index) in order.iter().enumerate()
+	{
+		if let Some(slot) = positions.get_mut(*index)
+		{
+			*slot = Some(position);
+		}
+	}
+	// Answer the distance between the specified section and the section with
+	// the specified one-based number, if both are present.
+	let distance = |index: usize, number: &usize| match (
+		positions[index],
+		number.checked_sub(1).and_then(|other| positions.get(other))
+	)
+	{
+		(Some(here), Some(Some(there))) => Some(here.abs_diff(*there)),
+		_ => None
+	};
+	constraints.iter()
+		.enumerate()
+		.find_map(|(index, c)| c.near.iter()
+			.find(|n| distance(index, n).map_or(false, |d| d > c.near_distance))
+			.map(|n| format!(
+				"§{} must be within {} of §{}.",
+				index + 1,
+				places(c.near_distance),
+				n
+			))
+			.or_else(|| c.apart.iter()
+				.find(|n|
+					distance(index, n).map_or(false, |d| d <= c.apart_gap)
+				)
+				.map(|n| format!(
+					"§{} and §{} must be separated by at least {}.",
+					index + 1,
+					n,
+					others(c.apart_gap)
+				))
+			)
+			.or_else(|| c.offset_from
+				.filter(|n| match (
+					positions[index],
+					n.checked_sub(1).and_then(|other| positions.get(other))
+				)
+				{
+					(Some(here), Some(Some(there))) => here != there + c.offset,
+					_ => false
+				})
+				.map(|n| format!(
+					"§{} must land exactly {} after §{}.",
+					index + 1,
+					places(c.offset),
+					n
+				))
+			)
+			.or_else(|| c.not_adjacent.iter()
+				.find(|n| distance(index, n) == Some(1))
+				.map(|n| format!(
+					"§{} and §{} must never be adjacent.",
+					index + 1,
+					n
+				))
+			)
+		)
+}
+
+/// Move each section that must land an exact [offset](Constraints::offset)
+/// after another section into that place within the specified ordering, given
+/// as **zero-based** section indices. The anchors settle before the sections
+/// that follow them, so that chains of offsets settle in order. Moving one
+/// section can displace another, so the caller must still check the result,
+/// e.g., with [`first_stray`].
+fn tether(mut order: Vec<usize>, constraints: &[Constraints]) -> Vec<usize>
+{
+	let position = |order: &[usize], index: usize|
+		order.iter().position(|i| *i == index);
+	let mut tethers = constraints.iter()
+		.enumerate()
+		.filter_map(|(index, c)| c.offset_from
+			.and_then(|n| n.checked_sub(1))
+			.map(|anchor| (index, anchor, c.offset))
+		)
+		.collect::<Vec<_>>();
+	tethers.sort_by_key(|(_, anchor, _)| position(&order, *anchor));
+	for (index, anchor, offset) in tethers
+	{
+		let Some(from) = position(&order, index) else { continue };
+		order.remove(from);
+		match position(&order, anchor)
+		{
+			Some(there) =>
+				order.insert((there + offset).min(order.len()), index),
+			None => order.insert(from, index)
+		}
+	}
+	order
+}
+
+/// Answer `true` if the specified ordering, given as **zero-based** section
+/// indices, puts every section before its successors in the specified
+/// constraint graph.
+fn honors_graph(order: &[usize], graph: &DiGraph<usize, (), usize>) -> bool
+{
+	let mut positions = vec![usize::MAX; graph.node_count()];
+	for (position, index) in order.iter().enumerate()
+	{
+		if let Some(slot) = positions.get_mut(*index)
+		{
+			*slot = position;
+		}
+	}
+	graph.raw_edges().iter().all(|edge|
+		positions[edge.source().index()] < positions[edge.target().index()]
+	)
+}
+
+/// Everything that [shuffling](StoryShufflerApp::shuffle) needs to draw a
+/// random ordering of the sections, prepared once from the constraints, so
+/// that the [position&#32;heatmap](HeatmapJob) draws its samples exactly as
+/// shuffling does, even on another thread.
+struct ShufflePlan
+{
+	/// The constraint graph of the sections, which must not contain a cycle.
+	graph: DiGraph<usize, (), usize>,
+
+	/// The **zero-based** [block](StoryShufflerApp::section_blocks) of each
+	/// section, if the sections shuffle as blocks.
+	blocks: Option<Vec<usize>>,
+
+	/// The [constraint&#32;graph](story_graph) of the blocks, if any.
+	block_graph: Option<DiGraph<usize, (), usize>>,
+
+	/// The [policy](ChapterPolicy) of each block, if any.
+	policies: Vec<ChapterPolicy>,
+
+	/// The **one-based** [pinned](Constraints::pin) position of each section,
+	/// if any.
+	pins: Vec<Option<usize>>,
+
+	/// The [parity](Constraints::parity) of each section, if any.
+	parities: Vec<Option<Parity>>,
+
+	/// Whether each section is [omitted](Constraints::omit). Omitted sections
+	/// occupy no position, so the [pins](Self::pins), the
+	/// [parities](Self::parities), and the spacing requirements apply only
+	/// to the others.
+	omitted: Vec<bool>,
+
+	/// The [place&#32;preference](place_preferences) of each section, if any.
+	preferences: Vec<(PlacePreference, f32)>,
+
+	/// The [rotation&#32;groups](StoryShufflerApp::rotation_in_effect), if the
+	/// rotation governs shuffling.
+	groups: Option<Vec<Option<usize>>>,
+
+	/// The number of [rotation&#32;tags](StoryShufflerApp::rotation_tags).
+	tag_count: usize,
+
+	/// The [rotation&#32;mode](StoryShufflerApp::rotation_mode).
+	rotation_mode: RotationMode,
+
+	/// The [uniform&#32;sampler](UniformSampler), if
+	/// [uniform&#32;sampling](StoryShufflerApp::uniform_sampling) applies.
+	sampler: Option<UniformSampler>,
+
+	/// The [constraints](StoryShufflerApp::constraints) of the sections.
+	constraints: Vec<Constraints>,
+
+	/// The [tag&#32;constraints](StoryShufflerApp::tag_constraints).
+	tag_constraints: Vec<TagConstraint>,
+
+	/// The number of [attempts](Self::attempt) to make before giving up on a
+	/// single ordering.
+	retries: usize
+}
+
+impl ShufflePlan
+{
+	/// Draw a random ordering of the sections, trying a few times if random
+	/// choices lead to an impasse. The ordering contains the first appearance
+	/// of each section, less the [omitted](Constraints::omit) sections, as
+	/// **zero-based** section indices. Answer `None` if every attempt failed.
+	fn draw(&mut self, rng: &mut impl Rng) -> Option<Vec<usize>>
+	{
+		(0 .. self.retries).find_map(|_| self.attempt(rng))
+			.map(|indices| indices.into_iter()
+				.filter(|index| !self.omitted[*index])
+				.collect()
+			)
+	}
+
+	/// Answer how many more orderings the [sampler](Self::sampler) can
+	/// [draw](Self::draw) uniformly, or `None` if there's no limit, e.g.,
+	/// because uniform sampling doesn't apply.
+	fn uniform_samples(&self) -> Option<usize>
+	{
+		self.sampler.as_ref().and_then(UniformSampler::uniform_samples)
+	}
+
+	/// Attempt once to [draw](Self::draw) a random ordering of the sections.
+	/// Unlike a drawn ordering, the attempt includes the
+	/// [omitted](Constraints::omit) sections, in the places where they honor
+	/// the constraint graph.
+	fn attempt(&mut self, rng: &mut impl Rng) -> Option<Vec<usize>>
+	{
+		let pinned = self.pins.iter().any(Option::is_some);
+		let parity = self.parities.iter().any(Option::is_some);
+		let indices = match (&self.block_graph, &self.blocks, &self.groups)
+		{
+			(Some(block_graph), Some(blocks), _) => random_block_order(
+				&self.graph,
+				block_graph,
+				blocks,
+				&self.policies,
+				&self.preferences,
+				rng
+			),
+			// Random choices can paint the rotation into a corner, too.
+			(_, _, Some(groups)) =>
+				(0 .. MAXIMUM_ROTATION_RETRIES).find_map(|_|
+					match self.rotation_mode
+					{
+						RotationMode::Strict => random_rotation(
+							self.graph.clone(),
+							groups,
+							self.tag_count,
+							rng
+						),
+						RotationMode::NoRepeats => random_alternation(
+							self.graph.clone(),
+							groups,
+							self.tag_count,
+							rng
+						)
+					}
+				),
+			_ if pinned || parity => random_pinned_order(
+				self.graph.clone(),
+				&self.pins,
+				&self.parities,
+				&self.omitted,
+				&self.preferences,
+				rng
+			),
+			_ if self.sampler.is_some() => self.sampler.as_mut()
+				.map(|sampler| sampler.sample(rng)),
+			_ => Some(random_order(self.graph.clone(), &self.preferences, rng))
+		};
+		// Moving the sections within blocks or a rotation would break them up,
+		// so leave those to chance. Uniform sampling never tethers, because
+		// moving the sections would skew the sample. Offsets count only the
+		// sections that appear, so tether those, and then put the omitted
+		// sections back as early as the constraints allow.
+		let tethered = self.constraints.iter().any(|c| c.offset_from.is_some());
+		let indices = match indices
+		{
+			Some(indices)
+				if tethered && self.blocks.is_none() && self.groups.is_none() =>
+			{
+				let (omitted, shown): (Vec<usize>, Vec<usize>) = indices
+					.into_iter()
+					.partition(|index| self.omitted[*index]);
+				let mut indices = tether(shown, &self.constraints);
+				indices.extend(omitted);
+				let indices =
+					with_omissions_early(&self.graph, &indices, &self.omitted);
+				honors_graph(&indices, &self.graph).then_some(indices)
+			},
+			indices => indices
+		};
+		let indices = indices?;
+		let shown = indices.iter()
+			.copied()
+			.filter(|index| !self.omitted[*index])
+			.collect::<Vec<_>>();
+		(first_misplaced(&shown, &self.pins).is_none()
+			&& first_misparity(&shown, &self.parities).is_none()
+			&& first_stray(&shown, &self.constraints).is_none()
+			&& first_tag_stray(
+				&shown,
+				&self.tag_constraints,
+				&self.constraints
+			).is_none()
+		).then_some(indices)
+	}
+}
+
+/// A [preference](Constraints::prefer_before) that an ordering doesn't
+/// honor.
+#[derive(Clone, Copy, Debug)]
+struct Shortfall
+{
+	/// The **zero-based** index of the section that should come earlier.
+	earlier: usize,
+
+	/// The **zero-based** index of the section that should come later.
+	later: usize,
+
+	/// The number of places by which the [earlier](Self::earlier) section
+	/// follows the [later](Self::later) section.
+	places: usize,
+
+	/// The [weight](Constraints::preference_weight) of the preference.
+	weight: f32
+}
+
+impl Shortfall
+{
+	/// Answer the amount by which the shortfall reduces the score of an
+	/// ordering.
+	fn penalty(&self) -> f32
+	{
+		self.weight * self.places as f32
+	}
+}
+
+/// Answer every [preference](Constraints::prefer_before) that the specified
+/// ordering, given as **zero-based** section indices, doesn't honor.
+fn shortfalls(order: &[usize], constraints: &[Constraints]) -> Vec<Shortfall>
+{
+	let mut positions = vec![None; constraints.len()];
+	for (position, index) in order.iter().enumerate()
+	{
+		if let Some(slot) = positions.get_mut(*index)
+		{
+			*slot = Some(position);
+		}
+	}
+	constraints.iter()
+		.enumerate()
+		.flat_map(|(earlier, c)| c.prefer_before.iter()
+			.filter_map(|n| n.checked_sub(1))
+			.map(move |later| (earlier, later, c.preference_weight))
+		)
+		.filter_map(|(earlier, later, weight)|
+			match (positions[earlier], positions.get(later))
+			{
+				(Some(here), Some(Some(there))) if here > *there =>
+					Some(Shortfall {
+						earlier,
+						later,
+						places: here - there,
+						weight
+					}),
+				_ => None
+			}
+		)
+		.collect()
+}
+
+/// The outcome of a single soft constraint, i.e., a
+/// [preference](Constraints::prefer_before) or a
+/// [place&#32;preference](Constraints::place_preference), in a particular
+/// ordering.
+#[derive(Clone, Debug)]
+struct PreferenceOutcome
+{
+	/// The description of the outcome for the writer.
+	description: String,
+
+	/// The weight of the preference.
+	weight: f32,
+
+	/// Whether the ordering honors the preference.
+	honored: bool
+}
+
+/// Answer the [outcome](PreferenceOutcome) of every soft constraint in the
+/// specified ordering, given as **zero-based** section indices, in section
+/// order. A section that appears more than once counts from its first
+/// appearance.
+fn preference_outcomes(
+	order: &[usize],
+	constraints: &[Constraints]
+) -> Vec<PreferenceOutcome>
+{
+	let mut positions = vec![None; constraints.len()];
+	for (position, index) in order.iter().enumerate()
+	{
+		if let Some(slot) = positions.get_mut(*index)
+		{
+			slot.get_or_insert(position);
+		}
+	}
+	let last = order.len().saturating_sub(1).max(1) as f32;
+	let mut outcomes = vec![];
+	for (index, c) in constraints.iter().enumerate()
+	{
+		let Some(here) = positions[index] else { continue };
+		for n in &c.prefer_before
+		{
+			let Some(Some(there)) =
+				n.checked_sub(1).and_then(|other| positions.get(other))
+			else
+			{
+				continue
+			};
+			let honored = here < *there;
+			outcomes.push(PreferenceOutcome {
+				description: if honored
+				{
+					format!("§{} came before §{}", index + 1, n)
+				}
+				else
+				{
+					format!(
+						"§{} fell {} behind §{}",
+						index + 1,
+						places(here - there),
+						n
+					)
+				},
+				weight: c.preference_weight,
+				honored
+			});
+		}
+		if c.place_preference != PlacePreference::Anywhere
+		{
+			outcomes.push(PreferenceOutcome {
+				description: format!(
+					"§{} prefers to land {}, and landed at position {} of {}",
+					index + 1,
+					c.place_preference.name().to_lowercase(),
+					here + 1,
+					order.len()
+				),
+				weight: c.place_weight,
+				honored: c.place_preference.affinity(here as f32 / last) > 0.0
+			});
+		}
+	}
+	outcomes
+}
+
+/// Describe the specified [shortfalls](Shortfall) for the writer.
+fn describe_shortfalls(shortfalls: &[Shortfall]) -> String
+{
+	if shortfalls.is_empty()
+	{
+		return "Every preference was honored.".to_string()
+	}
+	let details = shortfalls.iter()
+		.map(|s| format!(
+			"§{} fell {} behind §{} (weight {})",
+			s.earlier + 1,
+			places(s.places),
+			s.later + 1,
+			s.weight
+		))
+		.collect::<Vec<_>>()
+		.join("; ");
+	match shortfalls.len()
+	{
+		1 => format!("1 preference went unmet: {}.", details),
+		count => format!("{} preferences went unmet: {}.", count, details)
+	}
+}
+
+/// Derive the constraint graph of the stories of an
+/// [anthology](StoryShufflerApp::anthology), or of any other
+/// [blocks](StoryShufflerApp::section_blocks), from the specified constraint
+/// graph of its sections, where `stories` supplies the **zero-based** story of
+/// each section. Each vertex encodes a **one-based** story number, and each
+/// edge represents some section of the predecessor being
+/// [prior](Constraints::before) to some section of the successor. The sections
+/// of a story never move relative to one another, unless `loose` says that
+/// the story shuffles within, e.g., because it's a [chapter](Chapter) whose
+/// [policy](ChapterPolicy::shuffles_sections) allows it; answer the first
+/// constraint that would reorder the sections of a story that doesn't, as a
+/// pair of **zero-based** section indices, if any. `loose` supplies the
+/// looseness of each story; stories beyond its end don't shuffle within.
+fn story_graph(
+	graph: &DiGraph<usize, (), usize>,
+	stories: &[usize],
+	loose: &[bool]
+) -> Result<DiGraph<usize, (), usize>, (usize, usize)>
+{
+	let mut story_graph = DiGraph::default();
+	let count = stories.iter().max().map_or(0, |last| last + 1);
+	for story in 0 .. count
+	{
+		story_graph.add_node(story + 1);
+	}
+	for edge in graph.edge_indices()
+	{
+		let (from, to) = graph.edge_endpoints(edge).unwrap();
+		let (from, to) = (from.index(), to.index());
+		if stories[from] != stories[to]
+		{
+			story_graph.update_edge(
+				NodeIndex::new(stories[from]),
+				NodeIndex::new(stories[to]),
+				()
+			);
+		}
+		else if from > to && !loose.get(stories[from]).copied().unwrap_or(false)
+		{
+			return Err((from, to))
+		}
+	}
+	Ok(story_graph)
+}
+
+/// Find the first ordering constraint of the specified constraint graph that
+/// the specified ordering, given as **zero-based** section indices, violates.
+/// Answer the violated constraint as a pair of **zero-based** section indices,
+/// the first of which must occur earlier, or `None` if the ordering honors
+/// every constraint.
+fn first_violation(
+	graph: &DiGraph<usize, (), usize>,
+	order: &[usize]
+) -> Option<(usize, usize)>
+{
+	let mut position = vec![0; order.len()];
+	for (index, section) in order.iter().enumerate()
+	{
+		position[*section] = index;
+	}
+	graph.edge_indices()
+		.map(|edge| graph.edge_endpoints(edge).unwrap())
+		.map(|(from, to)| (from.index(), to.index()))
+		.find(|(from, to)| position[*from] > position[*to])
+}
+
+/// Answer `true` if every [before&#32;list](Constraints::before) mentions only
+/// sections that actually exist, i.e., only **one-based** numbers from `1`
+/// through the number of sections, such that [`compute_graph`] is safe to
+/// call.
+fn references_are_in_bounds(constraints: &[Constraints]) -> bool
+{
+	let count = constraints.len();
+	constraints.iter()
+		.all(|c| c.references().all(|n| (1 ..= count).contains(&n)))
+}
+
+/// Answer the text of a comma-separated list of the specified **one-based**
+/// section numbers, as edited in a [text&#32;buffer](Constraints::text_buffer).
+fn section_list_text(sections: &[usize]) -> String
+{
+	sections.iter()
+		.map(|n| n.to_string())
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+/// Require the `earlier` section to precede the `later` one, both given as
+/// **zero-based** section indices. The requirement is recorded on the earlier
+/// section, unless that section is [fixed](Constraints::fixed), where its
+/// lists are hidden, in which case it's recorded on the later one. Answer
+/// `true` if the requirement is new.
+fn add_order_constraint(
+	constraints: &mut [Constraints],
+	earlier: usize,
+	later: usize
+) -> bool
+{
+	if !constraints[earlier].fixed
+	{
+		let c = &mut constraints[earlier];
+		if c.before.contains(&(later + 1))
+		{
+			return false
+		}
+		c.before.push(later + 1);
+		c.before.sort_unstable();
+		c.text_buffer = section_list_text(&c.before);
+		c.text_buffer_is_valid = true;
+	}
+	else
+	{
+		let c = &mut constraints[later];
+		if c.after.contains(&(earlier + 1))
+		{
+			return false
+		}
+		c.after.push(earlier + 1);
+		c.after.sort_unstable();
+		c.after_text_buffer = section_list_text(&c.after);
+		c.after_text_buffer_is_valid = true;
+	}
+	true
+}
+
+/// Describe the impact of a single constraint upon the number of
+/// [valid&#32;orderings](crate::analysis::OrderingCount), by comparing the
+/// [cached&#32;count](ImpactContext::orderings) for the specified
+/// [constraints](Constraints) against the count for a copy from which
+/// `remove` has removed the constraint of interest from the section at
+/// `index`. Both counts honor the whole
+/// [system&#32;of&#32;constraints](compute_constraint_graph). The latter
+/// count is kept with the [Ui], so that hovering doesn't recount it on every
+/// frame. Answer `None` if the constraints are malformed.
+fn describe_impact(
+	ui: &Ui,
+	constraints: &[Constraints],
+	context: &ImpactContext<'_>,
+	index: usize,
+	remove: impl FnOnce(&mut Constraints)
+) -> Option<String>
+{
+	if !references_are_in_bounds(constraints)
+	{
+		return None
+	}
+	let with = context.orderings?;
+	let mut relaxed = constraints.to_vec();
+	remove(&mut relaxed[index]);
+	let graph = compute_constraint_graph(
+		&relaxed,
+		context.tag_constraints,
+		context.chapters,
+		context.anthology
+	);
+	let key = OrderingCountKey::of(&graph);
+	let impact_id = Id::new("constraint_impact");
+	let cached = ui.data_mut(|data|
+		data.get_temp::<(OrderingCountKey, OrderingCount)>(impact_id)
+	);
+	let without = match cached
+	{
+		Some((counted, orderings)) if counted == key => orderings,
+		_ =>
+		{
+			let orderings = count_orderings(&graph);
+			ui.data_mut(|data| data.insert_temp(impact_id, (key, orderings)));
+			orderings
+		}
+	};
+	let description = match (with.is_zero(), without.is_zero())
+	{
+		(true, true) => "Even without this constraint, there is a paradox, so \
+			there are no valid orderings.".to_string(),
+		(true, false) => format!(
+			"This constraint participates in a paradox. Without it, there \
+			would be {} valid orderings.",
+			without
+		),
+		_ if with == without => format!(
+			"This constraint doesn't eliminate any orderings, so removing it \
+			wouldn't change anything. There are {} valid orderings.",
+			with
+		),
+		_ =>
+		{
+			let ratio = 10f64.powf(with.log10() - without.log10());
+			format!(
+				"This constraint eliminates {:.1}% of the possible orderings: \
+				there are {} valid orderings with it, but {} without it.",
+				((1.0 - ratio) * 100.0).max(0.0),
+				with,
+				without
+			)
+		}
+	};
+	Some(description)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Scenarios.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A named set of [constraints](Constraints) over the sections of the
+/// manuscript, e.g., "chronological" or "mirror structure", embodying one
+/// structural hypothesis. Each scenario keeps its own shuffle and
+/// [history](StoryShufflerApp::shuffle_history). The state of the
+/// [active&#32;scenario](StoryShufflerApp::active_scenario) lives in the
+/// [application&#32;state](StoryShufflerApp) proper, so its fields here are
+/// vacant until another scenario becomes active.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Scenario
+{
+	/// The name of the scenario.
+	name: String,
+
+	/// The [constraints](StoryShufflerApp::constraints), one per section.
+	constraints: Vec<Constraints>,
+
+	/// The [rotation](StoryShufflerApp::rotation).
+	rotation: String,
+
+	/// The [rotation&#32;mode](StoryShufflerApp::rotation_mode).
+	rotation_mode: RotationMode,
+
+	/// The [tag&#32;constraints](StoryShufflerApp::tag_constraints).
+	tag_constraints: Vec<TagConstraint>,
+
+	/// The [chapters](StoryShufflerApp::chapters).
+	chapters: Vec<Chapter>,
+
+	/// The [layers](StoryShufflerApp::layers).
+	layers: Vec<Layer>,
+
+	/// The
+	/// [current&#32;shuffle](StoryShufflerApp::shuffled_section_indices), if
+	/// any.
+	shuffled_section_indices: Option<Vec<usize>>,
+
+	/// The [shuffle&#32;history](StoryShufflerApp::shuffle_history).
+	history: Vec<Vec<usize>>
+}
+
+impl Scenario
+{
+	/// Create a vacant scenario with the specified name.
+	fn named(name: impl Into<String>) -> Self
+	{
+		Self { name: name.into(), ..Default::default() }
+	}
+}
+
+/// A named layer of constraints atop the
+/// [constraints](StoryShufflerApp::constraints) of a [scenario](Scenario),
+/// e.g., "strict chronology" or "loose", written as a constraint script. The
+/// writer toggles layers on and off before shuffling, so that structural
+/// hypotheses can be compared without deleting any work.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Layer
+{
+	/// The name of the layer.
+	name: String,
+
+	/// Whether the layer applies to the next
+	/// [shuffle](StoryShufflerApp::shuffle).
+	enabled: bool,
+
+	/// The constraints of the layer, one [statement](Statement) per line.
+	script: String
+}
+
+/// The record of one random reordering, i.e., a
+/// [shuffle](StoryShufflerApp::shuffle) or an
+/// [excerpt](StoryShufflerApp::excerpt), in the
+/// [shuffle&#32;log](StoryShufflerApp::shuffle_log).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ShuffleRecord
+{
+	/// When the reordering happened, as an ISO 8601 timestamp in UTC.
+	timestamp: String,
+
+	/// The seed of the pseudorandom generator that drew the reordering, which
+	/// reproduces it given the same sections, constraints, and settings.
+	seed: u64,
+
+	/// The name of the [scenario](Scenario) that was active.
+	scenario: String,
+
+	/// The resulting ordering, as **one-based** section numbers.
+	order: Vec<usize>,
+
+	/// The [identifiers](section_id) of the sections, in the resulting order,
+	/// which identify them even after the manuscript changes.
+	section_ids: Vec<String>
+}
+
+/// A reordering of the sections alone, i.e., without the sections themselves,
+/// so that it can be [applied](StoryShufflerApp::apply_recipe) again to a
+/// revised manuscript with the same number of sections, e.g., after line
+/// edits. Every field is required, so that a recipe is never mistaken for a
+/// [constraint&#32;set](ConstraintSet).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShuffleRecipe
+{
+	/// The ordering, as **one-based** section numbers.
+	order: Vec<usize>
+}
+
+impl StoryShufflerApp
+{
+	/// Make the specified [scenario](Self::scenarios) active, stashing the
+	/// state of the active one.
+	fn switch_scenario(&mut self, index: usize)
+	{
+		if index == self.active_scenario || index >= self.scenarios.len()
+		{
+			return
+		}
+		let active = &mut self.scenarios[self.active_scenario];
+		active.constraints = std::mem::take(&mut self.constraints);
+		active.rotation = std::mem::take(&mut self.rotation);
+		active.rotation_mode = self.rotation_mode;
+		active.tag_constraints = std::mem::take(&mut self.tag_constraints);
+		active.chapters = std::mem::take(&mut self.chapters);
+		active.layers = std::mem::take(&mut self.layers);
+		active.shuffled_section_indices = self.shuffled_section_indices.take();
+		active.history = std::mem::take(&mut self.shuffle_history);
+		self.load_scenario(index);
+	}
+
+	/// Create a new [scenario](Self::scenarios) from a copy of the constraints
+	/// of the active one, and make it active.
+	fn add_scenario(&mut self)
+	{
+		let mut scenario =
+			Scenario::named(format!("Scenario {}", self.scenarios.len() + 1));
+		scenario.constraints = self.constraints.clone();
+		scenario.rotation = self.rotation.clone();
+		scenario.rotation_mode = self.rotation_mode;
+		scenario.tag_constraints = self.tag_constraints.clone();
+		scenario.chapters = self.chapters.clone();
+		scenario.layers = self.layers.clone();
+		self.scenarios.push(scenario);
+		self.switch_scenario(self.scenarios.len() - 1);
+	}
+
+	/// Delete the active [scenario](Self::scenarios), unless it's the only
+	/// one, and make a neighbor active.
+	fn remove_scenario(&mut self)
+	{
+		if self.scenarios.len() < 2
+		{
+			return
+		}
+		let removed = self.scenarios.remove(self.active_scenario);
+		tracing::info!("Deleted the scenario “{}”.", removed.name);
+		self.load_scenario(self.active_scenario.min(self.scenarios.len() - 1));
+	}
+
+	/// Move the state of the specified [scenario](Self::scenarios) into the
+	/// application state proper, _without_ stashing the state of the active
+	/// one. Constraints and shuffles that no longer fit the sections, e.g.,
+	/// because the scenario was never active, are discarded.
+	fn load_scenario(&mut self, index: usize)
+	{
+		self.active_scenario = index;
+		let count = self.original_sections.len();
+		let scenario = &mut self.scenarios[index];
+		self.constraints = std::mem::take(&mut scenario.constraints);
+		if self.constraints.len() != count
+		{
+			self.constraints = vec![Constraints::default(); count];
+		}
+		self.rotation = std::mem::take(&mut scenario.rotation);
+		self.rotation_mode = scenario.rotation_mode;
+		self.tag_constraints = std::mem::take(&mut scenario.tag_constraints);
+		self.chapters = std::mem::take(&mut scenario.chapters);
+		self.layers = std::mem::take(&mut scenario.layers);
+		self.shuffle_history = std::mem::take(&mut scenario.history);
+		let order = scenario.shuffled_section_indices.take();
+		self.restore_order(order);
+		tracing::info!(
+			"Switched to the scenario “{}”.",
+			self.scenarios[index].name
+		);
+	}
+
+	/// Append the specified ordering to the
+	/// [shuffle&#32;history](Self::shuffle_history). An ordering already in
+	/// the history moves to the end instead, and the oldest orderings are
+	/// forgotten once the history grows too long.
+	fn record_order(&mut self, indices: &[usize])
+	{
+		self.shuffle_history.retain(|order| order != indices);
+		self.shuffle_history.push(indices.to_vec());
+		let excess = self.shuffle_history.len()
+			.saturating_sub(MAXIMUM_HISTORY_LENGTH);
+		self.shuffle_history.drain(.. excess);
+	}
+}
+
+/// The shape of a [constraint&#32;graph](StoryShufflerApp::constraint_graph),
+/// which alone determines its valid orderings, and so keys the cached
+/// [count](StoryShufflerApp::valid_orderings) of them. Edits that leave the
+/// graph alone, e.g., to the labels or the text buffers, keep the count.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct OrderingCountKey
+{
+	/// The number of sections.
+	sections: usize,
+
+	/// The edges of the graph, as sorted pairs of **zero-based** section
+	/// indices, the first of which must occur earlier.
+	edges: Vec<(usize, usize)>
+}
+
+impl OrderingCountKey
+{
+	/// Answer the key of the specified constraint graph.
+	fn of(graph: &DiGraph<usize, (), usize>) -> Self
+	{
+		let mut edges = graph.raw_edges().iter()
+			.map(|edge| (edge.source().index(), edge.target().index()))
+			.collect::<Vec<_>>();
+		edges.sort_unstable();
+		Self { sections: graph.node_count(), edges }
+	}
+}
+
+/// The statistics of one [scenario](Scenario), for comparison against the
+/// others.
+struct ScenarioComparison
+{
+	/// The name of the scenario.
+	name: String,
+
+	/// The number of sections when the comparison was made.
+	sections: usize,
+
+	/// The number of [valid&#32;orderings](OrderingCount), or `None` if some
+	/// constraint refers to a nonexistent section.
+	orderings: Option<OrderingCount>,
+
+	/// The [freedom](Freedom) of the sections, or `None` if the constraints
+	/// are paradoxical or malformed.
+	freedom: Option<Freedom>,
+
+	/// A sample ordering of the sections, as it's presented, as
+	/// **zero-based** section indices, or `None` if the constraints are
+	/// paradoxical or malformed.
+	sample: Option<Vec<usize>>
+}
+
+impl StoryShufflerApp
+{
+	/// [Compare](ScenarioComparison) every [scenario](Self::scenarios),
+	/// drawing a fresh sample ordering for each through its own
+	/// [shuffle&#32;plan](ShufflePlan), so that samples honor everything that
+	/// shuffling does.
+	fn compare_scenarios(&mut self)
+	{
+		let comparison = (0 .. self.scenarios.len())
+			.map(|index| {
+				let name = self.scenarios[index].name.clone();
+				self.with_scenario(index, |app| app.compare_constraints(name))
+			})
+			.collect::<Vec<_>>();
+		tracing::info!("Compared {} scenarios.", comparison.len());
+		self.comparison = Some(comparison);
+	}
+
+	/// Perform the specified action, e.g., a
+	/// [comparison](Self::compare_constraints), with the
+	/// [constraints](Self::constraints), [rotation](Self::rotation),
+	/// [tag&#32;constraints](Self::tag_constraints),
+	/// [chapters](Self::chapters), and [layers](Self::layers) of the specified
+	/// [scenario](Self::scenarios) in effect, and then restore those of the
+	/// active scenario, along with the [status](Self::status). A scenario
+	/// whose constraints don't fit the sections, e.g., because it was never
+	/// active, is unconstrained.
+	fn with_scenario<T>(
+		&mut self,
+		index: usize,
+		action: impl FnOnce(&mut Self) -> T
+	) -> T
+	{
+		if index == self.active_scenario
+		{
+			return action(self)
+		}
+		self.exchange_scenario(index);
+		let count = self.original_sections.len();
+		let stashed = (self.constraints.len() != count).then(|| std::mem::replace(
+			&mut self.constraints,
+			vec![Constraints::default(); count]
+		));
+		let status = self.status.take();
+		let result = action(self);
+		self.status = status;
+		if let Some(stashed) = stashed
+		{
+			self.constraints = stashed;
+		}
+		self.exchange_scenario(index);
+		result
+	}
+
+	/// Exchange the constraints of the specified inactive
+	/// [scenario](Self::scenarios) with those of the application state proper.
+	/// See [with_scenario](Self::with_scenario).
+	fn exchange_scenario(&mut self, index: usize)
+	{
+		let scenario = &mut self.scenarios[index];
+		std::mem::swap(&mut self.constraints, &mut scenario.constraints);
+		std::mem::swap(&mut self.rotation, &mut scenario.rotation);
+		std::mem::swap(&mut self.rotation_mode, &mut scenario.rotation_mode);
+		std::mem::swap(
+			&mut self.tag_constraints,
+			&mut scenario.tag_constraints
+		);
+		std::mem::swap(&mut self.chapters, &mut scenario.chapters);
+		std::mem::swap(&mut self.layers, &mut scenario.layers);
+	}
+
+	/// [Compare](ScenarioComparison) the constraints in effect, under the
+	/// specified scenario name.
+	fn compare_constraints(&mut self, name: String) -> ScenarioComparison
+	{
+		let sections = self.original_sections.len();
+		if !references_are_in_bounds(&self.constraints)
+		{
+			return ScenarioComparison {
+				name,
+				sections,
+				orderings: None,
+				freedom: None,
+				sample: None
+			}
+		}
+		let graph = self.constraint_graph();
+		let ranges = feasible_positions(&graph);
+		let sample = ranges.is_some().then(|| self.sample_order()).flatten();
+		ScenarioComparison {
+			name,
+			sections,
+			orderings: Some(count_orderings(&graph)),
+			freedom: ranges.as_deref().map(freedom),
+			sample
+		}
+	}
+
+	/// Draw a random ordering of the sections, as it's presented, through the
+	/// [shuffle&#32;plan](ShufflePlan) of the constraints in effect, under the
+	/// [layers](Self::layers), just as a single [shuffle](Self::shuffle)
+	/// attempt does. Answer `None` if no ordering honors the constraints.
+	fn sample_order(&mut self) -> Option<Vec<usize>>
+	{
+		let mut rng = StdRng::from_rng(&mut self.rng).unwrap();
+		self.with_layers(|app| {
+			let mut plan = app.mark_cycles()
+				.and_then(|graph| app.shuffle_plan(graph))?;
+			(0 .. plan.retries).find_map(|_| plan.attempt(&mut rng)
+				.and_then(|order| app.present_order(&order, &mut rng).ok())
+			)
+		}).flatten()
+	}
+}
+
+/// Describe the specified ordering, given as **zero-based** section indices,
+/// by its leading one-based section numbers, e.g., `§3 §1 §2 …`.
+fn describe_order(indices: &[usize]) -> String
+{
+	let mut description = indices.iter()
+		.take(HISTORY_PREVIEW_LENGTH)
+		.map(|index| format!("§{}", index + 1))
+		.collect::<Vec<_>>()
+		.join(" ");
+	if indices.len() > HISTORY_PREVIEW_LENGTH
+	{
+		description.push_str(" …");
+	}
+	description
+}
+
+/// Answer `true` if the specified ordering mentions every one of `count`
+/// sections exactly once.
+fn fits_sections(indices: &[usize], count: usize) -> bool
+{
+	let mut seen = vec![false; count];
+	indices.len() == count && indices.iter().all(|index|
+		*index < count && !std::mem::replace(&mut seen[*index], true)
+	)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Frame UI.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+impl App for StoryShufflerApp
+{
+	/// Update the UI and handle any pending user interaction. May be called
+	/// many times per second, so handle any slow activity asynchronously.
+	fn update(&mut self, ctx: &Context, frame: &mut Frame)
+	{
+		self.now = ctx.input(|input| input.time);
+		self.palette.install(ctx);
+		#[cfg(target_arch = "wasm32")]
+		self.present_banner(ctx);
+		self.clean_pasted_html(ctx);
+		self.present_menu_bar(ctx);
+		if self.diagnostics_open
+		{
+			self.present_diagnostics_panel(ctx);
+		}
+		{
+			profile_scope!("configuration sidebar");
+			self.present_configuration_sidebar(ctx);
+		}
+		{
+			profile_scope!("output sidebar");
+			self.present_output_sidebar(ctx);
+		}
+		{
+			// Note that the manuscript panel must be presented last, because
+			// the main component is a CentralPanel.
+			profile_scope!("manuscript panel");
+			self.present_manuscript_panel(ctx);
+		}
+		self.accept_dropped_files(ctx);
+		if ctx.input_mut(|input| input.consume_shortcut(&SAVE_SHORTCUT))
+		{
+			self.save_requested = true;
+		}
+		self.track_constraint_edits(ctx);
+		// Leave the shortcuts to any focused text field, which has its own
+		// undo.
+		if !self.review_mode && ctx.memory(|memory| memory.focus().is_none())
+		{
+			if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT))
+			{
+				self.undo_constraint_edit();
+			}
+			else if ctx.input_mut(|input|
+				input.consume_shortcut(&REDO_SHORTCUT)
+			)
+			{
+				self.redo_constraint_edit();
+			}
+		}
+		if self.save_requested
+		{
+			self.save_requested = false;
+			if let Some(storage) = frame.storage_mut()
+			{
+				self.save(storage);
+				storage.flush();
+			}
+		}
+	}
+
+	/// Called by the framework to save state before shutdown, and
+	/// periodically according to [`auto_save_interval`](Self::auto_save_interval).
+	fn save(&mut self, storage: &mut dyn eframe::Storage)
+	{
+		if self.ephemeral
+		{
+			return
+		}
+		self.last_saved = Some(self.now);
+		// In privacy mode, never persist the text of the manuscript.
+		let text = self.private.then(|| self.withhold_manuscript());
+		self.persist(storage);
+		if let Some(text) = text
+		{
+			self.restore_manuscript(text);
+		}
+	}
+
+	/// Answer how often the framework should [save](Self::save) the
+	/// application state.
+	fn auto_save_interval(&self) -> Duration
+	{
+		Duration::from_secs(self.autosave_interval.max(1))
+	}
+
+	/// Answer whether the framework should persist the size and position of
+	/// the window, which it shouldn't in an
+	/// [ephemeral](Self::ephemeral) session.
+	fn persist_native_window(&self) -> bool
+	{
+		!self.ephemeral
+	}
+
+	/// Answer whether the framework should persist its own memory, e.g., the
+	/// positions of windows, which it shouldn't in an
+	/// [ephemeral](Self::ephemeral) session.
+	fn persist_egui_memory(&self) -> bool
+	{
+		!self.ephemeral
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Banner UI.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(target_arch = "wasm32")]
+impl StoryShufflerApp
+{
+	/// Display a simple banner (in lieu of a title bar on a native build).
+	fn present_banner(&mut self, ctx: &Context)
+	{
+		TopBottomPanel::top("banner").show(ctx, |ui| {
+			ui.centered_and_justified(|ui| {
+				ui.set_height(50.0);
+				ui.heading(
+					RichText::new("Story Shuffler")
+						.strong()
+						.size(24.0)
+				);
+			});
+        });
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Menu bar UI.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Display the [menu&#32;bar](egui::menu::bar) and any windows opened
+	/// therefrom.
+	fn present_menu_bar(&mut self, ctx: &Context)
+	{
+		TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+			egui::menu::bar(ui, |ui| {
+				ui.menu_button("File", |ui| {
+					if ui.add_enabled(
+						!self.ephemeral,
+						Button::new("Save now")
+							.shortcut_text(ui.ctx().format_shortcut(&SAVE_SHORTCUT))
+					).on_hover_text(
+						"Remember everything right away, rather than waiting \
+						for the next automatic save."
+					).clicked()
+					{
+						self.save_requested = true;
+						ui.close_menu();
+					}
+					ui.horizontal(|ui| {
+						ui.label("Save automatically every");
+						ui.add(
+							DragValue::new(&mut self.autosave_interval)
+								.clamp_range(1 ..= 3600)
+								.suffix(" s")
+						);
+					}).response.on_hover_text(
+						"Set how often your work is remembered automatically. \
+						Your work is also remembered when you close the \
+						application normally."
+					);
+					ui.checkbox(&mut self.private, "Privacy mode")
+						.on_hover_text(
+							"Never remember the text of your manuscript, only \
+							your settings and constraints, e.g., because \
+							you're working under a nondisclosure agreement or \
+							in a shared browser. Next time, paste or open your \
+							manuscript again to restore its constraints."
+						);
+					ui.separator();
+					#[cfg(not(target_arch = "wasm32"))]
+					if ui.button("Open project…")
+						.on_hover_text(
+							"Replace your manuscript, constraints, and current \
+							shuffle with those of a Story Shuffler project \
+							file."
+						)
+						.clicked()
+					{
+						self.open_project();
+						ui.close_menu();
+					}
+					if ui.button("Save project as…")
+						.on_hover_text(
+							"Save your manuscript, delimiter settings, \
+							constraints, scenarios, and current shuffle as a \
+							Story Shuffler project file, e.g., to move your \
+							work to another computer or to keep it under \
+							version control."
+						)
+						.clicked()
+					{
+						self.save_project();
+						ui.close_menu();
+					}
+					if ui.button("Copy project to clipboard")
+						.on_hover_text(
+							"Copy your manuscript, constraints, and current \
+							shuffle as a compact block of text, which you can \
+							paste into Story Shuffler anywhere, e.g., to move \
+							between the web and desktop versions."
+						)
+						.clicked()
+					{
+						self.copy_project(ui, self.review_mode);
+						ui.close_menu();
+					}
+					#[cfg(target_arch = "wasm32")]
+					if ui.button("Copy link to project")
+						.on_hover_text(
+							"Copy a link that reproduces your manuscript, \
+							delimiter settings, constraints, and current \
+							shuffle in Story Shuffler on the web. The project \
+							rides in the link itself, so it never reaches a \
+							server, but anyone with the link can read it."
+						)
+						.clicked()
+					{
+						self.copy_project_link(ui);
+						ui.close_menu();
+					}
+					if ui.button("Copy project for review")
+						.on_hover_text(
+							"Copy your project like Copy project to \
+							clipboard, but such that whoever pastes it can \
+							read and reshuffle it without changing your \
+							manuscript or constraints."
+						)
+						.clicked()
+					{
+						self.copy_project(ui, true);
+						ui.close_menu();
+					}
+					if self.review_mode && ui.button("Leave review mode")
+						.on_hover_text(
+							"Edit this copy of the project freely. This never \
+							affects the original author's copy."
+						)
+						.clicked()
+					{
+						self.review_mode = false;
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						!self.review_mode,
+						Button::new("Import from clipboard…")
+					).on_hover_text(
+						"Replace your manuscript with one pasted from the \
+						clipboard, and choose how to split it into sections \
+						according to whether it looks like Markdown, a \
+						Fountain screenplay, or plain text."
+					).clicked()
+					{
+						self.import_window_open = true;
+						ui.close_menu();
+					}
+					if ui.button("Paste project from clipboard…")
+						.on_hover_text(
+							"Replace your manuscript, constraints, and current \
+							shuffle with a project copied from Story Shuffler."
+						)
+						.clicked()
+					{
+						self.paste_project_window_open = true;
+						ui.close_menu();
+					}
+					ui.separator();
+					let exportable = !self.original_sections.is_empty()
+						&& references_are_in_bounds(&self.constraints);
+					if ui.add_enabled(
+						exportable,
+						Button::new("Export constraints as JSON…")
+					).on_hover_text(
+						"Save just your ordering constraints, i.e., which \
+						sections are fixed and which sections must follow \
+						which, keyed by section number, so that a \
+						collaborator can use them without your manuscript."
+					).clicked()
+					{
+						self.export_constraints();
+						ui.close_menu();
+					}
+					#[cfg(not(target_arch = "wasm32"))]
+					if ui.add_enabled(
+						!self.review_mode && !self.original_sections.is_empty(),
+						Button::new("Import constraints from JSON…")
+					).on_hover_text(
+						"Replace your ordering constraints with those saved by \
+						Export constraints as JSON, e.g., by a collaborator. \
+						Labels, dates, and tags are untouched."
+					).clicked()
+					{
+						self.open_constraints();
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						!self.review_mode && self.original_sections.len() > 1,
+						Button::new("Edit constraints as text…")
+					).on_hover_text(
+						"Type your ordering constraints in a compact notation, \
+						one per line, e.g., 3 < 7, which is quicker than the \
+						fields of each section when there are many sections."
+					).clicked()
+					{
+						self.script = self.constraint_script();
+						self.script_errors.clear();
+						self.script_window_open = true;
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						self.original_sections.len() > 1,
+						Button::new("Constraint presets…")
+					).on_hover_text(
+						"Save your constraints under a name, so that you can \
+						reapply them later, to this manuscript or to another \
+						with as many sections."
+					).clicked()
+					{
+						self.presets_window_open = true;
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						!self.review_mode && self.original_sections.len() > 1,
+						Button::new("Suggest constraints from dates…")
+					).on_hover_text(
+						"Scan the opening of each section for dates, times, \
+						and relative markers like “the next morning” or \
+						“three years earlier”, and suggest constraints that \
+						you can accept or dismiss."
+					).clicked()
+					{
+						self.find_suggestions();
+						ui.close_menu();
+					}
+					ui.add_enabled_ui(
+						!self.review_mode && self.original_sections.len() > 1,
+						|ui| ui.menu_button("Start from a template", |ui| {
+							for template in ConstraintTemplate::ALL
+							{
+								if ui.button(template.name())
+									.on_hover_text(template.description())
+									.clicked()
+								{
+									self.apply_template(template);
+									ui.close_menu();
+								}
+							}
+						})
+					).response.on_hover_text(
+						"Replace your ordering constraints with a ready-made \
+						arrangement for a common narrative structure, which \
+						you can then tweak. Labels, dates, and tags are \
+						untouched."
+					);
+					for kind in [SVG, PNG]
+					{
+						if ui.add_enabled(
+							exportable,
+							Button::new(
+								format!("Export graph as {}…", kind.description)
+							)
+						).on_hover_text(
+							"Save a picture of the ordering constraints, with \
+							earlier sections above later ones. Paradoxes are \
+							drawn in orange."
+						).clicked()
+						{
+							self.export_graph(kind);
+							ui.close_menu();
+						}
+					}
+					ui.separator();
+					for kind in [MARKDOWN, PDF]
+					{
+						if ui.add_enabled(
+							!self.original_sections.is_empty(),
+							Button::new(format!(
+								"Export review packet as {}…",
+								kind.description
+							))
+						).on_hover_text(
+							"Save a document for a workshop or an editor to \
+							mark up: your sections in their original order, \
+							your constraints, and the current shuffle, noting \
+							how far each section moved."
+						).clicked()
+						{
+							let packet = self.review_packet();
+							let bytes = if kind == PDF
+							{
+								blocks_to_pdf(
+									"Review packet",
+									self.page_size,
+									&packet
+								)
+							}
+							else
+							{
+								blocks_to_markdown(&packet).into_bytes()
+							};
+							self.offer_file(
+								&format!("review-packet.{}", kind.extension),
+								kind,
+								&bytes
+							);
+							ui.close_menu();
+						}
+					}
+					if ui.add_enabled(
+						!self.shuffle_log.is_empty(),
+						Button::new("Export shuffle log as JSON…")
+					).on_hover_text(
+						"Save the timestamp, seed, scenario, and resulting \
+						order of every shuffle and excerpt, e.g., to find an \
+						ordering that you liked a few sessions ago."
+					).clicked()
+					{
+						self.export_shuffle_log();
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						self.current_order().is_some(),
+						Button::new("Export shuffle recipe as JSON…")
+					).on_hover_text(
+						"Save just the current ordering of the sections, so \
+						that you can apply it again after revising the text of \
+						your manuscript."
+					).clicked()
+					{
+						self.export_recipe();
+						ui.close_menu();
+					}
+					#[cfg(not(target_arch = "wasm32"))]
+					if ui.add_enabled(
+						!self.original_sections.is_empty(),
+						Button::new("Apply shuffle recipe from JSON…")
+					).on_hover_text(
+						"Reorder your sections according to a recipe saved by \
+						Export shuffle recipe as JSON, e.g., after line edits. \
+						The manuscript must still have the same number of \
+						sections."
+					).clicked()
+					{
+						self.open_recipe();
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						self.original_sections.len() > 1,
+						Button::new("Generate variants…")
+					).on_hover_text(
+						"Shuffle the manuscript several times and save each \
+						distinct shuffle as its own numbered file, e.g., to \
+						try different orderings on different readers."
+					).clicked()
+					{
+						self.variants_window_open = true;
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						self.shuffled_sections.is_some(),
+						Button::new("Export shuffle mapping as CSV…")
+					).on_hover_text(
+						"Save a spreadsheet of the current shuffle: the \
+						original number, new position, opening, and word count \
+						of every section, so that an editor can track what \
+						moved where."
+					).clicked()
+					{
+						self.export_mapping();
+						ui.close_menu();
+					}
+					if ui.add_enabled(
+						exportable,
+						Button::new("Copy graph as Mermaid")
+					).on_hover_text(
+						"Copy the ordering constraints as a Mermaid diagram, \
+						ready to paste into Markdown notes, e.g., in Obsidian \
+						or Notion."
+					).clicked()
+					{
+						if let Some(diagram) = diagram(
+							&self.original_sections,
+							&self.section_ids,
+							&self.constraints
+						)
+						{
+							ui.output_mut(|output| output.copied_text =
+								diagram_to_mermaid(&diagram)
+							);
+							self.status = Some(StatusMessage {
+								text: "Copied the graph as Mermaid".to_string(),
+								is_error: false
+							});
+						}
+						ui.close_menu();
+					}
+				});
+				ui.menu_button("View", |ui| {
+					if ui.button("Colors…").clicked()
+					{
+						self.colors_window_open = true;
+						ui.close_menu();
+					}
+					if ui.button("Constraint graph…")
+						.on_hover_text(
+							"Draw the ordering constraints as a graph, with \
+							earlier sections above later ones, so that you can \
+							see their structure at a glance."
+						)
+						.clicked()
+					{
+						self.graph_window_open = true;
+						ui.close_menu();
+					}
+					if ui.button("Position heatmap…")
+						.on_hover_text(
+							"Shuffle many times and show how often each \
+							section lands in each position."
+						)
+						.clicked()
+					{
+						self.heatmap_window_open = true;
+						ui.close_menu();
+					}
+					if ui.button("Compare scenarios…")
+						.on_hover_text(
+							"Show how much freedom each scenario leaves the \
+							sections, alongside a sample shuffle of each."
+						)
+						.clicked()
+					{
+						self.comparison_window_open = true;
+						ui.close_menu();
+					}
+					if ui.checkbox(&mut self.show_section_ids, "Section IDs")
+						.on_hover_text(
+							"Show the identifier of each section beside its \
+							number. Unlike the number, the identifier stays \
+							with the section when others are inserted or \
+							removed."
+						)
+						.clicked()
+					{
+						ui.close_menu();
+					}
+					if ui.checkbox(&mut self.diagnostics_open, "Diagnostics")
+						.on_hover_text(
+							"Show what the application has been doing recently. \
+							This is helpful for bug reports."
+						)
+						.clicked()
+					{
+						ui.close_menu();
+					}
+				});
+				#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+				ui.menu_button("Debug", |ui| {
+					if ui.checkbox(&mut self.profiler_open, "Profiler")
+						.on_hover_text(
+							"Measure where the time goes in each frame, using \
+							the puffin profiler."
+						)
+						.clicked()
+					{
+						puffin::set_scopes_on(self.profiler_open);
+						ui.close_menu();
+					}
+				});
+				ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+					self.present_save_indicator(ui);
+					self.present_status(ui);
+					if self.review_mode
+					{
+						ui.separator();
+						ui.label(RichText::new("📖 Review mode").strong())
+							.on_hover_text(
+								"You can read and reshuffle this project, but \
+								not change its manuscript or constraints. \
+								Choose File ▸ Leave review mode to edit it."
+							);
+					}
+				});
+			});
+		});
+		#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+		if self.profiler_open
+		{
+			self.profiler_open = puffin_egui::profiler_window(ctx);
+			if !self.profiler_open
+			{
+				puffin::set_scopes_on(false);
+			}
+		}
+		let mut open = self.colors_window_open;
+		Window::new("Colors")
+			.open(&mut open)
+			.resizable(false)
+			.show(ctx, |ui| self.present_colors_window(ui));
+		self.colors_window_open = open;
+		let mut open = self.heatmap_window_open;
+		Window::new("Position heatmap")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_heatmap_window(ui));
+		self.heatmap_window_open = open;
+		let mut open = self.comparison_window_open;
+		Window::new("Compare scenarios")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_comparison_window(ui));
+		self.comparison_window_open = open;
+		let mut open = self.paste_project_window_open;
+		Window::new("Paste project")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_paste_project_window(ui));
+		self.paste_project_window_open &= open;
+		let mut open = self.import_window_open;
+		Window::new("Import from clipboard")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_import_window(ui));
+		self.import_window_open &= open;
+		let mut open = self.variants_window_open;
+		Window::new("Generate variants")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_variants_window(ui));
+		self.variants_window_open &= open;
+		let mut open = self.graph_window_open;
+		Window::new("Constraint graph")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_graph_window(ui));
+		self.graph_window_open = open;
+		let mut open = self.script_window_open;
+		Window::new("Constraint script")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_script_window(ui));
+		self.script_window_open &= open;
+		let mut open = self.presets_window_open;
+		Window::new("Constraint presets")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_presets_window(ui));
+		self.presets_window_open &= open;
+		let mut open = self.suggestions_window_open;
+		Window::new("Suggested constraints")
+			.open(&mut open)
+			.show(ctx, |ui| self.present_suggestions_window(ui));
+		self.suggestions_window_open &= open;
+	}
+
+	/// Display how long ago the application state was last persisted, so that
+	/// the writer knows whether closing the application is safe.
+	fn present_save_indicator(&mut self, ui: &mut Ui)
+	{
+		if self.ephemeral
+		{
+			ui.label(RichText::new("Saving is off").small()).on_hover_text(
+				"Nothing is saved during this session, so your work vanishes \
+				when you close the application. To keep it, choose File ▸ \
+				Copy project to clipboard."
+			);
+			return
+		}
+		let text = match self.last_saved
+		{
+			None => "Not saved yet this session".to_string(),
+			Some(time) =>
+			{
+				let elapsed = (self.now - time).max(0.0) as u64;
+				// Keep the indicator fresh, without repainting excessively.
+				ui.ctx().request_repaint_after(Duration::from_secs(1));
+				match elapsed
+				{
+					0 ..= 4 => "Saved just now".to_string(),
+					5 ..= 59 => format!("Saved {} s ago", elapsed),
+					_ => format!("Saved {} min ago", elapsed / 60)
+				}
+			}
+		};
+		#[cfg(not(target_arch = "wasm32"))]
+		let location = self.custom_storage.as_ref()
+			.map(|storage| format!(
+				" Your work is kept in {}.",
+				storage.path().display()
+			))
+			.unwrap_or_default();
+		#[cfg(target_arch = "wasm32")]
+		let location = String::new();
+		let help = format!(
+			"Your work is saved automatically every {} s, when you close the \
+			application, and whenever you choose File ▸ Save now.{}",
+			self.autosave_interval,
+			location
+		);
+		ui.label(RichText::new(text).small()).on_hover_text(help);
+	}
+
+	/// Display the outcome of the most recent file operation, if any, along
+	/// with a button to dismiss it.
+	fn present_status(&mut self, ui: &mut Ui)
+	{
+		let Some(status) = &self.status else { return };
+		ui.separator();
+		let dismiss = ui.small_button("✖").on_hover_text("Dismiss this message.");
+		let text = RichText::new(&status.text).small();
+		ui.label(
+			if status.is_error { text.color(palette(ui).error) } else { text }
+		);
+		if dismiss.clicked()
+		{
+			self.status = None;
+		}
+	}
+
+	/// Copy the current [project](Project) to the clipboard, as a
+	/// [blob](encode_blob). If `review` is `true`, then the project opens in
+	/// [review&#32;mode](Self::review_mode) when pasted.
+	fn copy_project(&mut self, ui: &mut Ui, review: bool)
+	{
+		match encode_blob(&Project { review, ..self.project() })
+		{
+			Ok(blob) =>
+			{
+				ui.output_mut(|output| output.copied_text = blob);
+				self.status = Some(StatusMessage {
+					text: "Copied the project to the clipboard".to_string(),
+					is_error: false
+				});
+			},
+			Err(e) => self.report_failure(format!(
+				"Couldn't copy the project: {}",
+				e
+			))
+		}
+	}
+
+	/// Copy a link to the current [project](Project) to the clipboard, which
+	/// reproduces the project when opened in the web build. The project
+	/// travels as a [blob](encode_blob) in the fragment of the link. Only
+	/// available on the web.
+	#[cfg(target_arch = "wasm32")]
+	fn copy_project_link(&mut self, ui: &mut Ui)
+	{
+		match encode_blob(&self.project())
+		{
+			Ok(blob) =>
+			{
+				let link = blob_link(&self.page_url, &blob);
+				let length = link.len();
+				ui.output_mut(|output| output.copied_text = link);
+				self.status = Some(StatusMessage {
+					text: if length > MAXIMUM_PORTABLE_LINK_LENGTH
+					{
+						format!(
+							"Copied a link to the project, but at {} \
+							characters, some mail and chat applications might \
+							truncate it",
+							length
+						)
+					}
+					else
+					{
+						"Copied a link to the project".to_string()
+					},
+					is_error: false
+				});
+			},
+			Err(e) => self.report_failure(format!(
+				"Couldn't copy a link to the project: {}",
+				e
+			))
+		}
+	}
+
+	/// Replace the writer's work with the [project](Project) carried by the
+	/// specified [blob](encode_blob), which arrived in the fragment of a
+	/// [link](Self::copy_project_link), and forget the fragment, so that
+	/// reloading the page doesn't clobber subsequent work. Only available on
+	/// the web.
+	#[cfg(any(target_arch = "wasm32", test))]
+	fn adopt_project_link(&mut self, blob: &str)
+	{
+		let result = decode_blob::<Project>(blob)
+			.and_then(|project| self.adopt_project(project));
+		match result
+		{
+			Ok(()) =>
+			{
+				tracing::info!("Opened a project from a link.");
+				self.status = Some(StatusMessage {
+					text: "Opened the project from the link".to_string(),
+					is_error: false
+				});
+			},
+			Err(e) => self.report_failure(format!(
+				"Couldn't open the project from the link: {}",
+				e
+			))
+		}
+		#[cfg(target_arch = "wasm32")]
+		if let Err(e) = forget_fragment(&self.page_url)
+		{
+			tracing::warn!("Couldn't forget the link: {}", e);
+		}
+	}
+
+	/// Display the contents of the window for pasting a [project](Project),
+	/// which replaces the writer's work once the pasted
+	/// [blob](encode_blob) decodes successfully.
+	fn present_paste_project_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(
+			"Paste a project copied from Story Shuffler below. Loading it \
+			replaces your current manuscript, constraints, and shuffle."
+		);
+		ui.add(
+			TextEdit::multiline(&mut self.pasted_project)
+				.hint_text("story-shuffler:1:…")
+				.desired_rows(4)
+				.desired_width(f32::INFINITY)
+		);
+		if let Some(error) = &self.pasted_project_error
+		{
+			ui.label(RichText::new(error).color(palette(ui).error));
+		}
+		if ui.add_enabled(
+			!self.pasted_project.trim().is_empty(),
+			Button::new("Load project")
+		).clicked()
+		{
+			let result = decode_blob::<Project>(&self.pasted_project)
+				.and_then(|project| self.adopt_project(project));
+			match result
+			{
+				Ok(()) =>
+				{
+					tracing::info!("Loaded a project from the clipboard.");
+					self.pasted_project.clear();
+					self.pasted_project_error = None;
+					self.paste_project_window_open = false;
+				},
+				Err(e) => self.pasted_project_error = Some(e)
+			}
+		}
+	}
+
+	/// Display the contents of the window for importing a manuscript from the
+	/// clipboard, along with the [split&#32;mode](SplitMode) that suits it.
+	fn present_import_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(
+			"Paste your manuscript below. Importing it replaces your current \
+			manuscript."
+		);
+		ui.add(
+			TextEdit::multiline(&mut self.pasted_manuscript)
+				.desired_rows(8)
+				.desired_width(f32::INFINITY)
+		);
+		if self.pasted_manuscript.trim().is_empty()
+		{
+			return
+		}
+		let (mode, level) = self.detect_split_mode(&self.pasted_manuscript);
+		ui.label(match mode
+		{
+			SplitMode::Delimiter =>
+				"This looks like plain text, so it will be split at your \
+				section delimiter.".to_string(),
+			SplitMode::Headings => format!(
+				"This looks like Markdown, so it will be split at headings of \
+				level {} or less.",
+				level
+			),
+			SplitMode::Scenes =>
+				"This looks like a Fountain screenplay, so it will be split at \
+				scene headings.".to_string()
+		});
+		if ui.button("Import").clicked()
+		{
+			self.original_manuscript =
+				std::mem::take(&mut self.pasted_manuscript);
+			self.manuscript_file_name = None;
+			self.split_mode = mode;
+			if mode == SplitMode::Headings
+			{
+				self.heading_level = level;
+			}
+			self.update_sections();
+			tracing::info!("Imported a manuscript from the clipboard.");
+			self.status = Some(StatusMessage {
+				text: format!(
+					"Imported {} sections from the clipboard",
+					self.original_sections.len()
+				),
+				is_error: false
+			});
+			self.import_window_open = false;
+		}
+	}
+
+	/// Answer the [split&#32;mode](SplitMode) that suits the specified
+	/// manuscript, along with the [heading&#32;level](Self::heading_level) to
+	/// use for [headings](SplitMode::Headings). A manuscript with enough
+	/// [scene&#32;headings](is_scene_heading) is a Fountain screenplay; failing
+	/// that, one that contains the
+	/// [section&#32;delimiter](Self::delimiter_pattern) is split there; failing
+	/// that, one with enough Markdown headings is split at the shallowest level
+	/// that yields enough sections. Anything else is plain text.
+	fn detect_split_mode(&self, text: &str) -> (SplitMode, usize)
+	{
+		if split_at_scenes(text).count() >= MINIMUM_DETECTED_SECTIONS
+		{
+			return (SplitMode::Scenes, self.heading_level)
+		}
+		let delimited = if self.delimiter_pattern_is_regex
+		{
+			Regex::new(&self.delimiter_pattern)
+				.map_or(false, |regex| regex.is_match(text))
+		}
+		else
+		{
+			text.contains(self.delimiter_pattern.as_str())
+		};
+		if !self.delimiter_pattern.is_empty() && delimited
+		{
+			return (SplitMode::Delimiter, self.heading_level)
+		}
+		(1 ..= MAXIMUM_HEADING_LEVEL)
+			.find(|level|
+				split_at_headings(text, *level).count()
+					>= MINIMUM_DETECTED_SECTIONS
+			)
+			.map_or(
+				(SplitMode::Delimiter, self.heading_level),
+				|level| (SplitMode::Headings, level)
+			)
+	}
+
+	/// Replace any HTML markup pasted during the current frame, e.g., from a
+	/// word processor in a browser, with plain text, before any text editor
+	/// sees it. Horizontal rules and headings become section breaks suited to
+	/// the current [split&#32;mode](Self::split_mode): Markdown headings, if
+	/// the manuscript is split at headings; the
+	/// [section&#32;delimiter](Self::delimiter_pattern), if it's literal; or
+	/// else the [dinkus](DINKUS).
+	fn clean_pasted_html(&self, ctx: &Context)
+	{
+		let section_break = match self.split_mode
+		{
+			SplitMode::Headings => None,
+			SplitMode::Delimiter
+				if !self.delimiter_pattern_is_regex
+					&& !self.delimiter_pattern.trim().is_empty() =>
+				Some(self.delimiter_pattern.trim()),
+			_ => Some(DINKUS)
+		};
+		ctx.input_mut(|input| {
+			for event in &mut input.events
+			{
+				if let Event::Paste(text) = event
+				{
+					if is_html(text)
+					{
+						*text = html_to_text(text, section_break);
+						tracing::info!("Cleaned up pasted HTML.");
+					}
+				}
+			}
+		});
+	}
+
+	/// Export the ordering constraints alone as a
+	/// [constraint&#32;set](ConstraintSet) in JSON, e.g., to share them with a
+	/// collaborator who has the manuscript already.
+	fn export_constraints(&mut self)
+	{
+		match serde_json::to_string_pretty(&self.constraint_set())
+		{
+			Ok(json) => self.offer_file(
+				"constraints.json",
+				JSON,
+				json.as_bytes()
+			),
+			Err(e) => self.report_failure(format!(
+				"Couldn't export the constraints: {}",
+				e
+			))
+		}
+	}
+
+	/// Export the mapping of the most recent shuffle as comma-separated values,
+	/// one row per section in its new position, giving its original number,
+	/// its new position, the opening of its first line, and its word count, so
+	/// that an editor can track what moved where.
+	fn export_mapping(&mut self)
+	{
+		let (Some(indices), Some(shuffled)) =
+			(&self.presented_section_indices, &self.shuffled_sections)
+		else
+		{
+			return
+		};
+		let mut rows = vec![
+			["Original section", "New position", "Excerpt", "Words"]
+				.map(str::to_string)
+				.to_vec()
+		];
+		for (position, (index, section)) in
+			indices.iter().zip(shuffled).enumerate()
+		{
+			let line = first_line(section);
+			let mut excerpt =
+				line.chars().take(MAPPING_EXCERPT_LENGTH).collect::<String>();
+			if excerpt.len() < line.len()
+			{
+				excerpt.push('…');
+			}
+			rows.push(vec![
+				(index + 1).to_string(),
+				(position + 1).to_string(),
+				excerpt,
+				section.split_whitespace().count().to_string()
+			]);
+		}
+		self.offer_file(
+			"shuffle-mapping.csv",
+			CSV,
+			rows_to_csv(&rows).as_bytes()
+		);
+	}
+
+	/// Export the [shuffle&#32;log](Self::shuffle_log) as JSON, so that the
+	/// writer can revisit, and reproduce, an ordering from an earlier session.
+	fn export_shuffle_log(&mut self)
+	{
+		match serde_json::to_string_pretty(&self.shuffle_log)
+		{
+			Ok(json) => self.offer_file(
+				"shuffle-log.json",
+				JSON,
+				json.as_bytes()
+			),
+			Err(e) => self.report_failure(format!(
+				"Couldn't export the shuffle log: {}",
+				e
+			))
+		}
+	}
+
+	/// Export the current ordering alone as a [recipe](ShuffleRecipe) in
+	/// JSON, so that it can be applied again after the manuscript is revised.
+	fn export_recipe(&mut self)
+	{
+		let Some(indices) = self.current_order()
+		else
+		{
+			self.report_failure("Shuffle the manuscript first.".to_string());
+			return
+		};
+		let recipe = ShuffleRecipe {
+			order: indices.iter().map(|index| index + 1).collect()
+		};
+		match serde_json::to_string(&recipe)
+		{
+			Ok(json) => self.offer_file(
+				"shuffle-recipe.json",
+				JSON,
+				json.as_bytes()
+			),
+			Err(e) => self.report_failure(format!(
+				"Couldn't export the shuffle recipe: {}",
+				e
+			))
+		}
+	}
+
+	/// Display the contents of the window for
+	/// [generating](Self::generate_variants) variants.
+	fn present_variants_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(
+			"Shuffle the manuscript into several distinct variants, each of \
+			which honors your constraints, and save them as numbered files, \
+			e.g., to test different orderings on different readers. Your \
+			current shuffle is untouched, but every variant is logged."
+		);
+		ui.horizontal(|ui| {
+			ui.label("Variants:");
+			ui.add(
+				DragValue::new(&mut self.variant_count)
+					.clamp_range(1 ..= MAXIMUM_VARIANT_COUNT)
+			);
+		});
+		if ui.button("Generate").clicked()
+		{
+			self.generate_variants();
+		}
+	}
+
+	/// Shuffle the manuscript into the [chosen](Self::variant_count) number of
+	/// distinct variants, and offer them to the writer: on a native build, as
+	/// numbered files in a folder of the writer's choosing, and, on the web,
+	/// as a ZIP archive of the same. The current shuffle is restored
+	/// afterward. Fewer variants result if the constraints don't permit enough
+	/// distinct orderings.
+	fn generate_variants(&mut self)
+	{
+		let count = self.variant_count.clamp(1, MAXIMUM_VARIANT_COUNT);
+		let shuffled_sections = self.shuffled_sections.take();
+		let shuffled_section_indices = self.shuffled_section_indices.take();
+		let presented_section_indices = self.presented_section_indices.take();
+		let transitions = self.transitions.take();
+		let mut seen = HashSet::new();
+		let mut variants = vec![];
+		let mut failure = None;
+		for _ in 0 .. count * VARIANT_ATTEMPTS
+		{
+			match self.shuffle_headless()
+			{
+				Ok(variant) =>
+				{
+					if self.presented_section_indices.clone()
+						.map_or(false, |indices| seen.insert(indices))
+					{
+						variants.push(variant);
+					}
+				},
+				Err(e) =>
+				{
+					failure = Some(e);
+					break
+				}
+			}
+			if variants.len() == count
+			{
+				break
+			}
+		}
+		self.shuffled_sections = shuffled_sections;
+		self.shuffled_section_indices = shuffled_section_indices;
+		self.presented_section_indices = presented_section_indices;
+		self.transitions = transitions;
+		if let Some(e) = failure
+		{
+			self.report_failure(format!("Couldn't generate variants: {}", e));
+			return
+		}
+		if variants.len() < count
+		{
+			tracing::info!(
+				"The constraints permit only {} distinct variants.",
+				variants.len()
+			);
+		}
+		let extension = self.manuscript_kind().extension;
+		let files = variants.iter()
+			.enumerate()
+			.map(|(index, variant)| (
+				format!("variant-{:02}.{}", index + 1, extension),
+				variant.as_bytes()
+			))
+			.collect::<Vec<_>>();
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			let Some(folder) = choose_folder() else { return };
+			for (name, bytes) in &files
+			{
+				let path = folder.join(name);
+				if let Err(e) = std::fs::write(&path, bytes)
+				{
+					self.report_failure(format!(
+						"Couldn't write {}: {}",
+						path.display(),
+						e
+					));
+					return
+				}
+			}
+			tracing::info!(
+				"Wrote {} variants to {}.",
+				files.len(),
+				folder.display()
+			);
+			self.status = Some(StatusMessage {
+				text: format!(
+					"Wrote {} variants to {}",
+					files.len(),
+					folder.display()
+				),
+				is_error: false
+			});
+		}
+		#[cfg(target_arch = "wasm32")]
+		{
+			let mut archive = Archive::new();
+			for (name, bytes) in &files
+			{
+				archive.deflate(name, bytes);
+			}
+			self.offer_file("variants.zip", ZIP, &archive.finish());
+		}
+	}
+
+	/// Ask the writer to choose a [recipe](ShuffleRecipe) in JSON, and apply
+	/// it, as by [`apply_recipe`](Self::apply_recipe). Only available on
+	/// native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_recipe(&mut self)
+	{
+		match open_text_file(&[JSON])
+		{
+			Ok(Some((path, text))) => match serde_json::from_str(&text)
+			{
+				Ok(recipe) => self.apply_recipe(&path, &recipe),
+				Err(e) => self.report_failure(format!(
+					"Couldn't read the shuffle recipe {}: {}",
+					path,
+					e
+				))
+			},
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Reorder the current sections according to the specified
+	/// [recipe](ShuffleRecipe), which was read from the file at the specified
+	/// path. Refuse if the recipe doesn't order exactly the current sections,
+	/// or if its ordering would violate any
+	/// [constraint](Self::constraints), explaining why.
+	fn apply_recipe(&mut self, path: &str, recipe: &ShuffleRecipe)
+	{
+		let count = self.original_sections.len();
+		if recipe.order.len() != count
+		{
+			self.report_failure(format!(
+				"Couldn't apply {}: it orders {} sections, but the manuscript \
+				has {}.",
+				path,
+				recipe.order.len(),
+				count
+			));
+			return
+		}
+		let mut seen = vec![false; count];
+		for number in &recipe.order
+		{
+			if *number == 0 || *number > count || seen[number - 1]
+			{
+				self.report_failure(format!(
+					"Couldn't apply {}: §{} is out of range or repeated.",
+					path,
+					number
+				));
+				return
+			}
+			seen[number - 1] = true;
+		}
+		let Some(graph) = self.mark_cycles() else { return };
+		let indices = recipe.order.iter()
+			.map(|number| number - 1)
+			.collect::<Vec<_>>();
+		let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+		let stray = self.placement_violation(&indices)
+			.map(Err)
+			.unwrap_or_else(|| self.present_order(&indices, &mut rng));
+		match (first_violation(&graph, &indices), stray)
+		{
+			(Some((from, to)), _) => self.report_failure(format!(
+				"Couldn't apply {}: §{} must now come before §{}.",
+				path,
+				from + 1,
+				to + 1
+			)),
+			(None, Err(stray)) => self.report_failure(format!(
+				"Couldn't apply {}: {}",
+				path,
+				stray
+			)),
+			(None, Ok(presented)) =>
+			{
+				tracing::info!("Applied the shuffle recipe {}.", path);
+				self.adopt_order(indices, presented);
+				self.status = Some(StatusMessage {
+					text: format!("Applied the shuffle recipe {}", path),
+					is_error: false
+				});
+			}
+		}
+	}
+
+	/// Ask the writer to choose a [constraint&#32;set](ConstraintSet) in JSON,
+	/// and import it, as by [`import_constraints`](Self::import_constraints).
+	/// Only available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_constraints(&mut self)
+	{
+		match open_text_file(&[JSON])
+		{
+			Ok(Some((path, text))) => self.import_constraints(&path, &text),
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Apply the [constraint&#32;set](ConstraintSet) encoded as JSON by the
+	/// specified text, which was read from the file at the specified path, to
+	/// the current sections. Report the outcome, including any mismatches,
+	/// beside the constraints.
+	fn import_constraints(&mut self, path: &str, text: &str)
+	{
+		let result = serde_json::from_str::<ConstraintSet>(text)
+			.map_err(|e| vec![format!("The file is malformed: {}.", e)])
+			.and_then(|set| self.apply_constraint_set(&set));
+		let (imported, problems) = match result
+		{
+			Ok(mismatches) =>
+			{
+				tracing::info!("Imported the constraints from {}.", path);
+				self.status = Some(StatusMessage {
+					text: format!("Imported the constraints from {}", path),
+					is_error: false
+				});
+				(true, mismatches)
+			},
+			Err(problems) =>
+			{
+				self.report_failure(format!(
+					"Couldn't import the constraints from {}",
+					path
+				));
+				(false, problems)
+			}
+		};
+		self.constraints_report = Some(ConstraintsReport {
+			path: path.to_string(),
+			imported,
+			problems
+		});
+	}
+
+	/// Display the contents of the window that draws the
+	/// [constraints](Self::constraints) as a [diagram](Diagram). Hovering over
+	/// a section reveals its opening, and clicking it highlights its
+	/// constraints. Edges that participate in a paradox are drawn in the error
+	/// color.
+	fn present_graph_window(&mut self, ui: &mut Ui)
+	{
+		if self.original_sections.len() < 2
+		{
+			ui.label("There must be at least two sections to draw.");
+			return
+		}
+		let Some(diagram) = diagram(
+			&self.original_sections,
+			&self.section_ids,
+			&self.constraints
+		)
+		else
+		{
+			ui.label(
+				RichText::new(
+					"The constraints mention sections that don't exist."
+				).color(palette(ui).error)
+			);
+			return
+		};
+		ui.label(
+			"Earlier sections are drawn above later ones. Click a section to \
+			highlight its constraints."
+		);
+		let selected = self.selected_graph_node
+			.filter(|selected| *selected < diagram.nodes.len());
+		let visuals = ui.visuals().clone();
+		let error = palette(ui).error;
+		ScrollArea::both().show(ui, |ui| {
+			let (response, painter) = ui.allocate_painter(
+				vec2(diagram.width, diagram.height),
+				Sense::click()
+			);
+			let origin = response.rect.min;
+			let at = |(x, y): (f32, f32)| origin + vec2(x, y);
+			for edge in &diagram.edges
+			{
+				let geometry = edge_geometry(&diagram, edge);
+				let highlighted =
+					selected.map_or(false, |s| edge.from == s || edge.to == s);
+				let color = match (edge.in_cycle, highlighted)
+				{
+					(true, _) => error,
+					(false, true) => visuals.strong_text_color(),
+					(false, false) => visuals.weak_text_color()
+				};
+				let width = if highlighted
+				{
+					HIGHLIGHTED_GRAPH_EDGE_WIDTH
+				}
+				else
+				{
+					GRAPH_EDGE_WIDTH
+				};
+				painter.add(QuadraticBezierShape::from_points_stroke(
+					[
+						at(geometry.start),
+						at(geometry.control),
+						at(geometry.end)
+					],
+					false,
+					Color32::TRANSPARENT,
+					Stroke::new(width, color)
+				));
+				painter.add(Shape::convex_polygon(
+					vec![
+						at(geometry.end),
+						at(geometry.barbs[0]),
+						at(geometry.barbs[1])
+					],
+					color,
+					Stroke::NONE
+				));
+			}
+			let hovered = response.hover_pos().and_then(|pointer|
+				diagram.nodes.iter().position(|node|
+					at((node.x, node.y)).distance(pointer) <= NODE_RADIUS
+				)
+			);
+			for (index, node) in diagram.nodes.iter().enumerate()
+			{
+				let center = at((node.x, node.y));
+				let fill = if Some(index) == selected
+				{
+					visuals.selection.bg_fill
+				}
+				else
+				{
+					visuals.extreme_bg_color
+				};
+				let stroke = if node.fixed
+				{
+					Stroke::new(
+						FIXED_GRAPH_NODE_STROKE_WIDTH,
+						visuals.strong_text_color()
+					)
+				}
+				else
+				{
+					visuals.widgets.noninteractive.fg_stroke
+				};
+				painter.circle(center, NODE_RADIUS, fill, stroke);
+				painter.text(
+					center,
+					Align2::CENTER_CENTER,
+					&node.label,
+					FontId::proportional(GRAPH_LABEL_SIZE),
+					visuals.text_color()
+				);
+			}
+			if response.clicked()
+			{
+				self.selected_graph_node =
+					hovered.filter(|hovered| Some(*hovered) != selected);
+			}
+			if let Some(hovered) = hovered
+			{
+				let node = &diagram.nodes[hovered];
+				let fixed = if node.fixed { " (fixed)" } else { "" };
+				response.on_hover_text_at_pointer(
+					format!("{}{}: {}", node.label, fixed, node.description)
+				);
+			}
+		});
+		if diagram.edges.iter().any(|edge| edge.in_cycle)
+		{
+			ui.label(
+				RichText::new(
+					"The edges drawn in this color form a paradox, so no \
+					ordering satisfies them."
+				).color(error)
+			);
+		}
+	}
+
+	/// Display the contents of the window for editing the
+	/// [constraints](Self::constraints) as a [script](Self::script), along
+	/// with any syntax errors, which are checked as the writer types.
+	fn present_script_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(
+			"Type one constraint per line. Applying the script replaces your \
+			ordering constraints; labels, dates, and tags are untouched."
+		);
+		Grid::new("script_examples").num_columns(2).show(ui, |ui| {
+			for (example, meaning) in SCRIPT_EXAMPLES
+			{
+				ui.code(example);
+				ui.label(meaning);
+				ui.end_row();
+			}
+		});
+		let editor = ui.add(
+			TextEdit::multiline(&mut self.script)
+				.code_editor()
+				.desired_rows(12)
+				.desired_width(f32::INFINITY)
+		);
+		if editor.changed()
+		{
+			self.script_errors = parse_script(&self.script)
+				.err()
+				.unwrap_or_default();
+		}
+		let error = palette(ui).error;
+		for e in &self.script_errors
+		{
+			ui.label(RichText::new(e).color(error));
+		}
+		ui.horizontal(|ui| {
+			if ui.add_enabled(
+				self.script_errors.is_empty(),
+				Button::new("Apply")
+			).clicked()
+			{
+				self.apply_script();
+			}
+			if ui.add_enabled(
+				self.script_errors.is_empty() && !self.script.trim().is_empty(),
+				Button::new("Add as layer")
+			).on_hover_text(
+				"Keep these constraints in a new layer atop your constraints, \
+				which you can toggle before shuffling, instead of replacing \
+				your constraints with them."
+			).clicked()
+			{
+				self.add_layer_from_script();
+			}
+		});
+	}
+
+	/// Replace the ordering [constraints](Self::constraints) with those of
+	/// the [script](Self::script), or else record the
+	/// [problems](Self::script_errors) that prevented it.
+	fn apply_script(&mut self)
+	{
+		let result = parse_script(&self.script)
+			.and_then(|statements| self.script_constraint_set(&statements))
+			.and_then(|set| self.apply_constraint_set(&set));
+		match result
+		{
+			Ok(mismatches) =>
+			{
+				tracing::info!("Applied the constraint script.");
+				self.status = Some(StatusMessage {
+					text: "Applied the constraint script".to_string(),
+					is_error: false
+				});
+				self.constraints_report = (!mismatches.is_empty()).then(||
+					ConstraintsReport {
+						path: "the constraint script".to_string(),
+						imported: true,
+						problems: mismatches
+					}
+				);
+				self.script_window_open = false;
+			},
+			Err(problems) => self.script_errors = problems
+		}
+	}
+
+	/// Move the [script](Self::script) into a new, enabled
+	/// [layer](Self::layers), rather than replacing the ordering
+	/// [constraints](Self::constraints) with it.
+	fn add_layer_from_script(&mut self)
+	{
+		let name = format!("Layer {}", self.layers.len() + 1);
+		self.layers.push(Layer {
+			name: name.clone(),
+			enabled: true,
+			script: std::mem::take(&mut self.script)
+		});
+		tracing::info!("Added the layer “{}”.", name);
+		self.status = Some(StatusMessage {
+			text: format!("Added the layer “{}”", name),
+			is_error: false
+		});
+		self.script_window_open = false;
+	}
+
+	/// Perform the specified action, e.g., a [shuffle](Self::shuffle), with
+	/// the [enabled](Layer::enabled) [layers](Self::layers) merged into the
+	/// [constraints](Self::constraints), and then restore the constraints,
+	/// keeping any paradoxes that the action discovered. If a layer doesn't
+	/// parse, or contradicts the constraints or an earlier layer, then report
+	/// the failure and answer `None` without performing the action.
+	fn with_layers<T>(&mut self, action: impl FnOnce(&mut Self) -> T)
+		-> Option<T>
+	{
+		if !self.layers.iter().any(|layer| layer.enabled)
+		{
+			return Some(action(self))
+		}
+		let mut statements = parse_script(&self.constraint_script())
+			.unwrap_or_default();
+		let mut set = None;
+		for layer in self.layers.iter().filter(|layer| layer.enabled)
+		{
+			let result = parse_script(&layer.script)
+				.and_then(|layered| {
+					statements.extend(layered);
+					self.script_constraint_set(&statements)
+				});
+			match result
+			{
+				Ok(layered) => set = Some(layered),
+				Err(problems) =>
+				{
+					let text = format!(
+						"Couldn't apply the layer “{}”: {}",
+						layer.name.trim(),
+						problems.join(" ")
+					);
+					self.report_failure(text);
+					return None
+				}
+			}
+		}
+		let set = set?;
+		let saved = self.constraints.clone();
+		if let Err(problems) = self.apply_constraint_set(&set)
+		{
+			self.constraints = saved;
+			self.report_failure(format!(
+				"Couldn't apply the layers: {}",
+				problems.join(" ")
+			));
+			return None
+		}
+		let result = action(self);
+		let layered = std::mem::replace(&mut self.constraints, saved);
+		for (c, layered) in self.constraints.iter_mut().zip(layered)
+		{
+			c.paradox_error = layered.paradox_error;
+		}
+		Some(result)
+	}
+
+	/// Display the contents of the presets [window](Window), which saves the
+	/// current constraints as a [preset](Self::presets) and reapplies saved
+	/// presets.
+	fn present_presets_window(&mut self, ui: &mut Ui)
+	{
+		ui.horizontal(|ui| {
+			ui.label("Save as:");
+			let name = ui.add(
+				TextEdit::singleline(&mut self.preset_name)
+					.hint_text("preset name")
+			);
+			let save = ui.add_enabled(
+				!self.preset_name.trim().is_empty()
+					&& references_are_in_bounds(&self.constraints),
+				Button::new("💾 Save")
+			).on_hover_text(
+				"Save the current ordering constraints, tag constraints, and \
+				rotation under this name, replacing any preset of the same \
+				name."
+			);
+			if save.clicked()
+				|| name.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter))
+			{
+				self.save_preset();
+			}
+		});
+		if self.presets.is_empty()
+		{
+			ui.label(RichText::new("There are no presets yet.").weak());
+			return
+		}
+		let count = self.original_sections.len();
+		let mut applied = None;
+		let mut deleted = None;
+		Grid::new("presets").num_columns(3).show(ui, |ui| {
+			for (index, preset) in self.presets.iter().enumerate()
+			{
+				let size = preset.constraints.sections.len();
+				ui.label(&preset.name).on_hover_text(
+					format!("Saved from a manuscript of {} sections.", size)
+				);
+				let apply = ui.add_enabled(
+					!self.review_mode,
+					Button::new("Apply")
+				);
+				let apply = if size == count
+				{
+					apply
+				}
+				else
+				{
+					apply.on_hover_text(format!(
+						"This preset was saved from a manuscript of {} \
+						sections, but this one has {}.",
+						size,
+						count
+					))
+				};
+				if apply.clicked()
+				{
+					applied = Some(index);
+				}
+				if ui.small_button("🗑")
+					.on_hover_text("Delete this preset.")
+					.clicked()
+				{
+					deleted = Some(index);
+				}
+				ui.end_row();
+			}
+		});
+		if let Some(index) = applied
+		{
+			self.apply_preset(index);
+		}
+		if let Some(index) = deleted
+		{
+			let removed = self.presets.remove(index);
+			tracing::info!("Deleted the preset “{}”.", removed.name);
+		}
+	}
+
+	/// Save the current constraints as a [preset](Self::presets) named by the
+	/// [preset&#32;name](Self::preset_name), replacing any preset of the same
+	/// name.
+	fn save_preset(&mut self)
+	{
+		let name = self.preset_name.trim().to_string();
+		let preset = ConstraintPreset {
+			name: name.clone(),
+			constraints: ConstraintSet {
+				delimiter_pattern: None,
+				..self.constraint_set()
+			},
+			rotation: self.rotation.clone(),
+			rotation_mode: self.rotation_mode,
+			tag_constraints: self.tag_constraints.clone()
+		};
+		match self.presets.iter_mut().find(|p| p.name == name)
+		{
+			Some(existing) => *existing = preset,
+			None => self.presets.push(preset)
+		}
+		tracing::info!("Saved the preset “{}”.", name);
+		self.status = Some(StatusMessage {
+			text: format!("Saved the preset “{}”", name),
+			is_error: false
+		});
+		self.preset_name.clear();
+	}
+
+	/// Replace the constraints with those of the specified
+	/// [preset](Self::presets), given by its **zero-based** index, reporting
+	/// any mismatches, or else the problems that prevented the replacement.
+	fn apply_preset(&mut self, index: usize)
+	{
+		let preset = self.presets[index].clone();
+		let path = format!("the preset “{}”", preset.name);
+		let (imported, problems) =
+			match self.apply_constraint_set(&preset.constraints)
+			{
+				Ok(mismatches) =>
+				{
+					self.rotation = preset.rotation;
+					self.rotation_mode = preset.rotation_mode;
+					self.tag_constraints = preset.tag_constraints;
+					tracing::info!("Applied the preset “{}”.", preset.name);
+					let text = format!("Applied the preset “{}”", preset.name);
+					self.status = Some(StatusMessage { text, is_error: false });
+					(true, mismatches)
+				},
+				Err(problems) => (false, problems)
+			};
+		self.constraints_report = (!imported || !problems.is_empty())
+			.then_some(ConstraintsReport { path, imported, problems });
+	}
+
+	/// Replace the ordering [constraints](Self::constraints) with those that
+	/// the specified [template](ConstraintTemplate) prescribes for the current
+	/// sections. Labels, dates, and tags survive.
+	fn apply_template(&mut self, template: ConstraintTemplate)
+	{
+		let set = template.constraint_set(&self.constraints);
+		match self.apply_constraint_set(&set)
+		{
+			Ok(_) =>
+			{
+				tracing::info!("Applied the template: {}", template.name());
+				self.status = Some(StatusMessage {
+					text: format!("Applied the template: {}", template.name()),
+					is_error: false
+				});
+				self.constraints_report = None;
+			},
+			Err(problems) => self.report_failure(problems.join(" "))
+		}
+	}
+
+	/// Scan the sections for temporal cues and open the window that reviews
+	/// the resultant [suggestions](Self::suggestions). Suggestions that the
+	/// current constraints already imply or contradict, or that the writer
+	/// previously dismissed, are withheld.
+	fn find_suggestions(&mut self)
+	{
+		let graph = self.constraint_graph();
+		let ids = &self.section_ids;
+		let dismissed = &self.dismissed_suggestions;
+		self.suggestions = suggest_constraints(&self.original_sections)
+			.into_iter()
+			.filter(|s| {
+				let earlier = NodeIndex::new(s.earlier);
+				let later = NodeIndex::new(s.later);
+				!has_path_connecting(&graph, earlier, later, None)
+					&& !has_path_connecting(&graph, later, earlier, None)
+			})
+			.filter(|s| !dismissed.iter().any(|(earlier, later)|
+				ids.get(s.earlier) == Some(earlier)
+					&& ids.get(s.later) == Some(later)
+			))
+			.collect();
+		tracing::info!("Suggested {} constraints.", self.suggestions.len());
+		self.suggestions_window_open = true;
+	}
+
+	/// Display the [suggestions](Self::suggestions), along with the controls
+	/// to accept and dismiss them.
+	fn present_suggestions_window(&mut self, ui: &mut Ui)
+	{
+		if self.suggestions.is_empty()
+		{
+			ui.label(
+				RichText::new(
+					"There are no suggestions. Dates, times, and relative \
+					markers like “the next morning” are sought at the \
+					beginning of each section."
+				).weak()
+			);
+			return
+		}
+		let mut accepted = vec![];
+		let mut dismissed = vec![];
+		ui.add_enabled_ui(!self.review_mode, |ui| {
+			ui.horizontal(|ui| {
+				if ui.button("Accept all").clicked()
+				{
+					accepted.extend(0 .. self.suggestions.len());
+				}
+				if ui.button("Dismiss all").clicked()
+				{
+					dismissed.extend(0 .. self.suggestions.len());
+				}
+			});
+			ui.separator();
+			ScrollArea::vertical().show(ui, |ui| {
+				for (index, s) in self.suggestions.iter().enumerate()
+				{
+					ui.horizontal(|ui| {
+						if ui.small_button("✔")
+							.on_hover_text("Add this constraint.")
+							.clicked()
+						{
+							accepted.push(index);
+						}
+						if ui.small_button("✖")
+							.on_hover_text("Don't suggest this again.")
+							.clicked()
+						{
+							dismissed.push(index);
+						}
+						ui.label(format!(
+							"§{} before §{}",
+							s.earlier + 1,
+							s.later + 1
+						));
+					});
+					ui.label(RichText::new(&s.reason).weak());
+				}
+			});
+		});
+		if !accepted.is_empty()
+		{
+			self.accept_suggestions(&accepted);
+		}
+		else if !dismissed.is_empty()
+		{
+			self.dismiss_suggestions(&dismissed);
+		}
+	}
+
+	/// Add the [suggestions](Self::suggestions) at the specified indices to
+	/// the ordering [constraints](Self::constraints), skipping any that would
+	/// create a paradox, whether with the existing constraints or with the
+	/// suggestions accepted before them.
+	fn accept_suggestions(&mut self, indices: &[usize])
+	{
+		let mut skipped = 0;
+		for index in indices
+		{
+			let (earlier, later) = {
+				let s = &self.suggestions[*index];
+				(s.earlier, s.later)
+			};
+			let graph = self.constraint_graph();
+			if has_path_connecting(
+				&graph,
+				NodeIndex::new(later),
+				NodeIndex::new(earlier),
+				None
+			)
+			{
+				skipped += 1;
+				continue
+			}
+			add_order_constraint(&mut self.constraints, earlier, later);
+		}
+		self.forget_suggestions(indices);
+		let added = match indices.len() - skipped
+		{
+			1 => "Added 1 suggested constraint".to_string(),
+			n => format!("Added {} suggested constraints", n)
+		};
+		let text = match skipped
+		{
+			0 => added,
+			1 => format!(
+				"{}, but skipped 1 that would have created a paradox",
+				added
+			),
+			n => format!(
+				"{}, but skipped {} that would have created paradoxes",
+				added,
+				n
+			)
+		};
+		tracing::info!("{}.", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Dismiss the [suggestions](Self::suggestions) at the specified indices,
+	/// so that they aren't suggested again.
+	fn dismiss_suggestions(&mut self, indices: &[usize])
+	{
+		for index in indices
+		{
+			let s = &self.suggestions[*index];
+			if let (Some(earlier), Some(later)) =
+				(self.section_ids.get(s.earlier), self.section_ids.get(s.later))
+			{
+				self.dismissed_suggestions
+					.push((earlier.clone(), later.clone()));
+			}
+		}
+		self.forget_suggestions(indices);
+	}
+
+	/// Remove the [suggestions](Self::suggestions) at the specified indices.
+	fn forget_suggestions(&mut self, indices: &[usize])
+	{
+		let mut index = 0;
+		self.suggestions.retain(|_| {
+			index += 1;
+			!indices.contains(&(index - 1))
+		});
+	}
+
+	/// Export a [diagram](Diagram) of the ordering constraints as an image of
+	/// the specified [kind](FileKind).
+	fn export_graph(&mut self, kind: FileKind)
+	{
+		let Some(diagram) = diagram(
+			&self.original_sections,
+			&self.section_ids,
+			&self.constraints
+		)
+		else { return };
+		let bytes = if kind == PNG
+		{
+			diagram_to_png(&diagram)
+		}
+		else
+		{
+			Ok(diagram_to_svg(&diagram).into_bytes())
+		};
+		match bytes
+		{
+			Ok(bytes) => self.offer_file(
+				&format!("constraints.{}", kind.extension),
+				kind,
+				&bytes
+			),
+			Err(e) => self.report_failure(format!(
+				"Couldn't render the graph: {}",
+				e
+			))
+		}
+	}
+
+	/// Offer the specified bytes to the writer as a file, and report the
+	/// outcome in the menu bar.
+	fn offer_file(&mut self, file_name: &str, kind: FileKind, bytes: &[u8])
+	{
+		match save_file(file_name, kind, bytes)
+		{
+			Ok(Some(path)) =>
+			{
+				tracing::info!("Saved {}.", path);
+				self.status = Some(StatusMessage {
+					text: format!("Saved {}", path),
+					is_error: false
+				});
+			},
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Ask the writer to choose a manuscript file, and replace the
+	/// [manuscript](Self::original_manuscript) with its contents, reporting
+	/// the outcome in the menu bar. Only available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_manuscript(&mut self)
+	{
+		match open_text_file(&MANUSCRIPT_KINDS)
+		{
+			Ok(Some((path, text))) => self.adopt_manuscript_file(&path, text),
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Ask the writer to choose any number of manuscript files, and replace
+	/// the [manuscript](Self::original_manuscript) with their contents, as by
+	/// [`adopt_manuscript_files`](Self::adopt_manuscript_files). Only
+	/// available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_manuscripts(&mut self)
+	{
+		match open_text_files(&MANUSCRIPT_KINDS)
+		{
+			Ok(Some(files)) => self.adopt_manuscript_files(None, files),
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Ask the writer to choose a folder of manuscript files, and open it, as
+	/// by [`adopt_manuscript_folder`](Self::adopt_manuscript_folder). Only
+	/// available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_manuscript_folder(&mut self)
+	{
+		if let Some(folder) = choose_folder()
+		{
+			self.adopt_manuscript_folder(&folder);
+		}
+	}
+
+	/// Replace the [manuscript](Self::original_manuscript) with the contents
+	/// of the manuscript files directly within the specified folder, as by
+	/// [`adopt_manuscript_files`](Self::adopt_manuscript_files). Only
+	/// available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn adopt_manuscript_folder(&mut self, folder: &Path)
+	{
+		match read_folder(folder, &MANUSCRIPT_KINDS)
+		{
+			Ok(files) => self.adopt_manuscript_files(
+				Some(&folder.display().to_string()),
+				files
+			),
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Open the first of the files that the writer dropped onto the
+	/// application, if any, as by [`open_manuscript`](Self::open_manuscript).
+	/// While files hover over the application, say what dropping them will
+	/// do.
+	fn accept_dropped_files(&mut self, ctx: &Context)
+	{
+		if self.review_mode
+		{
+			// The manuscript is immutable during review.
+			return
+		}
+		let hovering = ctx.input(|input| !input.raw.hovered_files.is_empty());
+		if hovering
+		{
+			let painter = ctx.layer_painter(LayerId::new(
+				Order::Foreground,
+				Id::new("file_drop_target")
+			));
+			let screen = ctx.screen_rect();
+			painter.rect_filled(screen, 0.0, Color32::from_black_alpha(192));
+			painter.text(
+				screen.center(),
+				Align2::CENTER_CENTER,
+				"Drop a manuscript or project file to open it",
+				TextStyle::Heading.resolve(&ctx.style()),
+				Color32::WHITE
+			);
+		}
+		let dropped = ctx.input(|input| input.raw.dropped_files.clone());
+		let Some(file) = dropped.first() else { return };
+		if dropped.len() > 1
+		{
+			// Each of several files becomes a section.
+			match dropped.iter().map(read_dropped_file).collect()
+			{
+				Ok(files) => self.adopt_manuscript_files(None, files),
+				Err(e) => self.report_failure(e)
+			}
+			return
+		}
+		#[cfg(not(target_arch = "wasm32"))]
+		if let Some(folder) = file.path.as_ref().filter(|path| path.is_dir())
+		{
+			self.adopt_manuscript_folder(folder);
+			return
+		}
+		match read_dropped_file(file)
+		{
+			Ok((path, text))
+				if Path::new(&path).extension()
+					== Some(SHUFFLE.extension.as_ref()) =>
+				self.adopt_project_file(&path, &text),
+			Ok((path, text))
+				if Path::new(&path).extension()
+					== Some(JSON.extension.as_ref()) =>
+			{
+				match serde_json::from_str::<ShuffleRecipe>(&text)
+				{
+					Ok(recipe) => self.apply_recipe(&path, &recipe),
+					Err(_) => self.import_constraints(&path, &text)
+				}
+			},
+			Ok((path, text)) => self.adopt_manuscript_file(&path, text),
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Replace the [manuscript](Self::original_manuscript) with the specified
+	/// text, which was read from the file at the specified path, and report
+	/// the outcome in the menu bar.
+	fn adopt_manuscript_file(&mut self, path: &str, text: String)
+	{
+		tracing::info!("Opened {}.", path);
+		self.manuscript_file_name = Path::new(path).file_name()
+			.map(|name| name.to_string_lossy().into_owned());
+		self.original_manuscript = text;
+		self.update_sections();
+		self.status = Some(StatusMessage {
+			text: format!("Opened {}", path),
+			is_error: false
+		});
+	}
+
+	/// Replace the [manuscript](Self::original_manuscript) with the specified
+	/// files, each of which becomes one section, in order by file name, and
+	/// [labeled](Constraints::label) by its file name without extension. The
+	/// files are joined by the
+	/// [section&#32;delimiter](Self::delimiter_pattern), which becomes dinkus
+	/// if it was a regular expression. `folder` is the
+	/// path of the folder that contained the files, if any, after which the
+	/// shuffled manuscript is named. Report the outcome in the menu bar.
+	fn adopt_manuscript_files(
+		&mut self,
+		folder: Option<&str>,
+		mut files: Vec<(String, String)>
+	)
+	{
+		if files.is_empty()
+		{
+			self.report_failure(format!(
+				"Couldn't find any manuscripts in {}",
+				folder.unwrap_or("the selection")
+			));
+			return
+		}
+		let file_name = |path: &str| Path::new(path).file_name()
+			.map(|name| name.to_string_lossy().into_owned())
+			.unwrap_or_default();
+		files.sort_by_key(|(path, _)| file_name(path));
+		if self.delimiter_pattern_is_regex || self.delimiter_pattern.is_empty()
+		{
+			self.delimiter_pattern = DEFAULT_DELIMITER_PATTERN.to_string();
+			self.delimiter_pattern_is_regex = false;
+		}
+		self.split_mode = SplitMode::Delimiter;
+		self.manuscript_file_name = folder.map(file_name);
+		self.original_manuscript = files.iter()
+			.map(|(_, text)| text.trim())
+			.collect::<Vec<_>>()
+			.join(&format!("\n\n{}\n\n", self.delimiter_pattern));
+		self.update_sections();
+		let sections = self.original_sections.len();
+		if sections == files.len()
+		{
+			for (constraints, (path, _)) in
+				self.constraints.iter_mut().zip(&files)
+			{
+				constraints.label = Path::new(path).file_stem()
+					.map(|stem| stem.to_string_lossy().into_owned())
+					.unwrap_or_default();
+			}
+			tracing::info!("Opened {} files as sections.", files.len());
+			self.status = Some(StatusMessage {
+				text: format!("Opened {} files as sections", files.len()),
+				is_error: false
+			});
+		}
+		else
+		{
+			// Some file contains the delimiter, so sections and files no longer
+			// correspond.
+			self.report_failure(format!(
+				"Opened {} files, but they contain {} sections, because some \
+				contain the section delimiter",
+				files.len(),
+				sections
+			));
+		}
+	}
+
+	/// Ask the writer to choose a [project](Project) file, and open it. Only
+	/// available on native builds.
+	#[cfg(not(target_arch = "wasm32"))]
+	fn open_project(&mut self)
+	{
+		match open_text_file(&[SHUFFLE])
+		{
+			Ok(Some((path, text))) => self.adopt_project_file(&path, &text),
+			Ok(None) => {},
+			Err(e) => self.report_failure(e)
+		}
+	}
+
+	/// Replace the writer's work with the [project](Project) encoded by the
+	/// specified text, which was read from the file at the specified path, and
+	/// report the outcome in the menu bar.
+	fn adopt_project_file(&mut self, path: &str, text: &str)
+	{
+		let result = decode_project_file::<Project>(text)
+			.and_then(|project| self.adopt_project(project));
+		match result
+		{
+			Ok(()) =>
+			{
+				tracing::info!("Opened {}.", path);
+				// Name the shuffled manuscript after the project.
+				self.manuscript_file_name = Path::new(path).file_name()
+					.map(|name| name.to_string_lossy().into_owned());
+				self.status = Some(StatusMessage {
+					text: format!("Opened {}", path),
+					is_error: false
+				});
+			},
+			Err(e) => self.report_failure(format!(
+				"Couldn't open {}: {}",
+				path,
+				e
+			))
+		}
+	}
+
+	/// Offer the current [project](Project) to the writer as a project file,
+	/// named after the [original&#32;file](Self::manuscript_file_name), if
+	/// any.
+	fn save_project(&mut self)
+	{
+		let stem = self.manuscript_file_name.as_deref()
+			.and_then(|name| Path::new(name).file_stem())
+			.map_or_else(
+				|| DEFAULT_PROJECT_STEM.to_string(),
+				|stem| stem.to_string_lossy().into_owned()
+			);
+		match encode_project_file(&self.project())
+		{
+			Ok(text) => self.offer_file(
+				&format!("{}.{}", stem, SHUFFLE.extension),
+				SHUFFLE,
+				text.as_bytes()
+			),
+			Err(e) => self.report_failure(format!(
+				"Couldn't save the project: {}",
+				e
+			))
+		}
+	}
+
+	/// Answer the suggested name of the file of the specified
+	/// [kind](FileKind) in which to save the
+	/// [shuffled&#32;manuscript](Self::assembled_manuscript), derived from the
+	/// name of the [original&#32;file](Self::manuscript_file_name), e.g.,
+	/// `novel-shuffled.docx` for `novel.md`.
+	fn shuffled_file_name(&self, kind: FileKind) -> String
+	{
+		match self.manuscript_file_name.as_deref()
+			.and_then(|name| Path::new(name).file_stem())
+		{
+			Some(stem) => format!(
+				"{}-shuffled.{}",
+				stem.to_string_lossy(),
+				kind.extension
+			),
+			None => format!("{}.{}", SHUFFLED_MANUSCRIPT_STEM, kind.extension)
+		}
+	}
+
+	/// Answer the [kind](FileKind) of the
+	/// [original&#32;file](Self::manuscript_file_name), i.e., Markdown or
+	/// plain text.
+	fn manuscript_kind(&self) -> FileKind
+	{
+		let extension = self.manuscript_file_name.as_deref()
+			.and_then(|name| Path::new(name).extension())
+			.and_then(|extension| extension.to_str());
+		if extension == Some(MARKDOWN.extension) { MARKDOWN } else { TEXT }
+	}
+
+	/// Offer the specified assembled manuscript to the writer as a file of the
+	/// specified [kind](FileKind), i.e., as a Word, OpenDocument, or PDF
+	/// document, an e-book, a web page, or text.
+	fn offer_manuscript(&mut self, manuscript: &str, kind: FileKind)
+	{
+		let file_name = self.shuffled_file_name(kind);
+		let bytes = match kind
+		{
+			DOCX => manuscript_to_docx(
+				&self.manuscript_title(),
+				manuscript,
+				self.introduced_break()
+			),
+			ODT => manuscript_to_odt(
+				&self.manuscript_title(),
+				manuscript,
+				self.introduced_break()
+			),
+			EPUB => self.manuscript_epub(manuscript),
+			PDF => manuscript_to_pdf(
+				&self.manuscript_title(),
+				&format!(
+					"A shuffled draft, {}",
+					// Keep only the date.
+					&iso_timestamp(unix_time())[.. 10]
+				),
+				manuscript,
+				self.introduced_break(),
+				self.page_size
+			),
+			HTML => chapters_to_html(
+				&self.manuscript_title(),
+				&self.manuscript_chapters()
+			).into_bytes(),
+			_ => manuscript.as_bytes().to_vec()
+		};
+		self.offer_file(&file_name, kind, &bytes);
+	}
+
+	/// Answer the title of the manuscript for the metadata of exported
+	/// documents, i.e., the stem of the
+	/// [original&#32;file](Self::manuscript_file_name), if any.
+	fn manuscript_title(&self) -> String
+	{
+		self.manuscript_file_name.as_deref()
+			.and_then(|name| Path::new(name).file_stem())
+			.map_or_else(
+				|| DEFAULT_MANUSCRIPT_TITLE.to_string(),
+				|stem| stem.to_string_lossy().into_owned()
+			)
+	}
+
+	/// Answer a predicate that recognizes the paragraphs of an
+	/// [assembled&#32;manuscript](Self::assembled_manuscript) that the
+	/// assembly introduced, i.e., separators and chapter headings, which
+	/// exported documents center.
+	fn introduced_break(&self) -> impl Fn(&str) -> bool + '_
+	{
+		// Include the separator beyond the matches, i.e., dinkus.
+		let separators = (0 ..= self.delimiter_matches.len())
+			.filter_map(|gap| self.section_separator(gap))
+			.map(str::trim)
+			.collect::<HashSet<_>>();
+		let story_delimiter = self.anthology
+			.then(|| self.story_delimiter.trim());
+		let chapters = self.chapterize && !self.anthology;
+		move |paragraph: &str| {
+			separators.contains(paragraph)
+				|| Some(paragraph) == story_delimiter
+				|| chapters && paragraph.strip_prefix(CHAPTER_HEADING)
+					.map_or(false, |n| n.trim().parse::<usize>().is_ok())
+		}
+	}
+
+	/// Answer the [shuffled&#32;sections](Self::shuffled_sections) as
+	/// [chapters](epub::Chapter), one per section, each paired with the
+	/// one-based number of its original section. Each chapter is titled by the
+	/// [label](Constraints::label) of its section, if any, or else by the
+	/// Markdown heading that begins it, if any, or else by its position.
+	fn manuscript_chapters(&self) -> Vec<(usize, epub::Chapter)>
+	{
+		let indices = self.presented_order();
+		self.shuffled_sections.iter()
+			.flatten()
+			.enumerate()
+			.map(|(position, section)| {
+				let index = indices.as_ref().map(|indices| indices[position]);
+				let label = index
+					.map(|index| self.constraints[index].label.trim())
+					.filter(|label| !label.is_empty());
+				let (first, rest) = section.split_once('\n')
+					.unwrap_or((section, ""));
+				let heading = first.trim_start_matches('#');
+				let is_heading = heading.len() < first.len()
+					&& heading.starts_with(' ');
+				let chapter = match (label, is_heading)
+				{
+					(Some(label), _) => epub::Chapter {
+						title: label.to_string(),
+						text: section.clone()
+					},
+					(None, true) => epub::Chapter {
+						title: heading.trim().to_string(),
+						text: rest.to_string()
+					},
+					(None, false) => epub::Chapter {
+						title: format!("{} {}", CHAPTER_HEADING, position + 1),
+						text: section.clone()
+					}
+				};
+				(index.unwrap_or(position) + 1, chapter)
+			})
+			.collect()
+	}
+
+	/// Render the [shuffled&#32;sections](Self::shuffled_sections) as an
+	/// [e-book](chapters_to_epub), one [chapter](Self::manuscript_chapters)
+	/// per section. The specified assembled manuscript identifies the e-book.
+	fn manuscript_epub(&self, manuscript: &str) -> Vec<u8>
+	{
+		let chapters = self.manuscript_chapters().into_iter()
+			.map(|(_, chapter)| chapter)
+			.collect::<Vec<_>>();
+		let identifier = format!(
+			"urn:story-shuffler:{}",
+			section_id(manuscript, &HashSet::new())
+		);
+		chapters_to_epub(
+			&self.manuscript_title(),
+			&identifier,
+			&iso_timestamp(unix_time()),
+			&chapters
+		)
+	}
+
+	/// Report the failure of a file operation in the menu bar.
+	fn report_failure(&mut self, text: String)
+	{
+		tracing::warn!("{}", text);
+		self.status = Some(StatusMessage { text, is_error: true });
+	}
+
+	/// Display the contents of the colors [window](Window), which allows the
+	/// writer to choose a preset [palette](Palette) or customize the status
+	/// colors individually.
+	fn present_colors_window(&mut self, ui: &mut Ui)
+	{
+		ui.horizontal(|ui| {
+			ui.label("Presets:");
+			if ui.button("Standard").clicked()
+			{
+				self.palette = Palette::STANDARD;
+			}
+			if ui.button("Colorblind-safe")
+				.on_hover_text(
+					"Use colors that remain distinguishable under the common \
+					forms of color blindness."
+				)
+				.clicked()
+			{
+				self.palette = Palette::COLORBLIND_SAFE;
+			}
+		});
+		ui.separator();
+		egui::Grid::new("palette").num_columns(2).show(ui, |ui| {
+			let palette = &mut self.palette;
+			for (label, color, sample) in [
+				("Errors", &mut palette.error, "Invalid list of sections."),
+				("Warnings", &mut palette.warning, "⚠ Weak transition"),
+				("Validated", &mut palette.success, "✔"),
+				("Headings", &mut palette.heading, "Constraints"),
+				("Range bars", &mut palette.range_track, "▬ Infeasible"),
+				("Feasible ranges", &mut palette.range_fill, "▬ Feasible")
+			]
+			{
+				ui.label(label);
+				ui.horizontal(|ui| {
+					ui.color_edit_button_srgba(color);
+					ui.label(RichText::new(sample).color(*color));
+				});
+				ui.end_row();
+			}
+		});
+		// Apply any changes right away, rather than waiting for the next frame.
+		self.palette.install(ui.ctx());
+	}
+}
+
+impl StoryShufflerApp
+{
+	/// Display the contents of the scenario comparison [window](Window), which
+	/// sets the [scenarios](Self::scenarios) side by side.
+	fn present_comparison_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(
+			"Compare how much room each scenario leaves the story to breathe. \
+			Freedom is the average share of positions that each section could \
+			occupy."
+		);
+		if ui.add_enabled(
+			self.original_sections.len() > 1,
+			Button::new("🎲 Compare")
+		).on_hover_text(
+			"Count the orderings of every scenario, and draw a fresh sample \
+			shuffle of each, honoring its constraints and layers just as \
+			shuffling does."
+		).clicked()
+		{
+			self.compare_scenarios();
+		}
+		let Some(comparison) = self.comparison.as_ref() else { return };
+		let count = self.original_sections.len();
+		if comparison.len() != self.scenarios.len()
+			|| comparison.iter().any(|c| c.sections != count)
+		{
+			ui.label(
+				RichText::new(
+					"The scenarios or the manuscript have changed since this \
+					comparison, so compare again."
+				).color(palette(ui).warning)
+			);
+			return
+		}
+		let unknown = || RichText::new("—").weak();
+		let pair = comparison.len() > 1;
+		ScrollArea::both().show(ui, |ui| {
+			if pair
+			{
+				self.present_sample_pair(ui);
+				ui.separator();
+			}
+			let Some(comparison) = self.comparison.as_ref() else { return };
+			Grid::new("scenario_comparison")
+				.striped(true)
+				.show(ui, |ui| {
+					ui.label("");
+					for c in comparison
+					{
+						ui.label(RichText::new(&c.name).strong());
+					}
+					ui.end_row();
+					ui.label("Valid orderings");
+					for c in comparison
+					{
+						match c.orderings
+						{
+							Some(orderings) => ui.label(orderings.to_string()),
+							None => ui.label(unknown())
+						};
+					}
+					ui.end_row();
+					ui.label("Freedom");
+					for c in comparison
+					{
+						match c.freedom
+						{
+							Some(f) => ui.label(
+								format!("{:.0}%", f.mean * 100.0)
+							),
+							None => ui.label(unknown())
+						};
+					}
+					ui.end_row();
+					ui.label("Pinned sections");
+					for c in comparison
+					{
+						match c.freedom
+						{
+							Some(f) => ui.label(f.pinned.to_string()),
+							None => ui.label(unknown())
+						};
+					}
+					ui.end_row();
+					ui.label("Unconstrained sections");
+					for c in comparison
+					{
+						match c.freedom
+						{
+							Some(f) => ui.label(f.unconstrained.to_string()),
+							None => ui.label(unknown())
+						};
+					}
+					ui.end_row();
+					// Each scenario may omit or repeat different sections, so
+					// the samples may differ in length.
+					let longest = comparison.iter()
+						.flat_map(|c| &c.sample)
+						.map(Vec::len)
+						.max()
+						.unwrap_or(0);
+					for position in 0 .. longest
+					{
+						if position == 0 { ui.label("Sample shuffle"); }
+						else { ui.label(""); }
+						for c in comparison
+						{
+							match c.sample.as_ref()
+								.and_then(|sample| sample.get(position))
+							{
+								Some(&index) => ui.label(format!(
+									"{}. §{}",
+									position + 1,
+									index + 1
+								)).on_hover_text(first_line(
+									&self.original_sections[index]
+								)),
+								None => ui.label(unknown())
+							};
+						}
+						ui.end_row();
+					}
+				});
+		});
+	}
+
+	/// Display the sample shuffles of two of the compared
+	/// [scenarios](Self::scenarios) side by side, highlighting the sections
+	/// that land in different positions, so that the writer can judge which
+	/// set of constraints produces the better draft. The samples may differ in
+	/// length, because each scenario may [omit](Constraints::omit) or
+	/// [repeat](Constraints::repeats) different sections.
+	fn present_sample_pair(&mut self, ui: &mut Ui)
+	{
+		let Some(comparison) = self.comparison.as_ref() else { return };
+		let last = comparison.len() - 1;
+		let (mut left, mut right) = self.compared_pair;
+		left = left.min(last);
+		right = if right > last || right == left { (left + 1) % (last + 1) }
+			else { right };
+		ui.horizontal(|ui| {
+			let choose = |ui: &mut Ui, id: &str, chosen: &mut usize| {
+				ComboBox::from_id_source(id)
+					.selected_text(comparison[*chosen].name.as_str())
+					.show_ui(ui, |ui| {
+						for (index, c) in comparison.iter().enumerate()
+						{
+							ui.selectable_value(chosen, index, c.name.as_str());
+						}
+					});
+			};
+			ui.label("Set");
+			choose(ui, "compared_left", &mut left);
+			ui.label("beside");
+			choose(ui, "compared_right", &mut right);
+		});
+		self.compared_pair = (left, right);
+		let (Some(a), Some(b)) =
+			(&comparison[left].sample, &comparison[right].sample)
+		else
+		{
+			ui.label(
+				RichText::new(
+					"One of these scenarios has no sample shuffle, because \
+					its constraints are paradoxical or malformed."
+				).color(palette(ui).warning)
+			);
+			return
+		};
+		// The first position of each section in the first sample, if any.
+		let mut position_in_a = vec![None; self.original_sections.len()];
+		for (position, section) in a.iter().enumerate().rev()
+		{
+			if let Some(slot) = position_in_a.get_mut(*section)
+			{
+				*slot = Some(position);
+			}
+		}
+		let length = a.len().max(b.len());
+		let moved = (0 .. length)
+			.filter(|position| a.get(*position) != b.get(*position))
+			.count();
+		ui.label(match moved
+		{
+			0 => "Both samples arrange the sections identically.".to_string(),
+			_ => format!(
+				"{} of {} positions hold different sections, which are \
+				highlighted.",
+				moved,
+				length
+			)
+		});
+		let highlight = palette(ui).warning;
+		Grid::new("scenario_pair")
+			.striped(true)
+			.show(ui, |ui| {
+				ui.label("");
+				ui.label(RichText::new(&comparison[left].name).strong());
+				ui.label(RichText::new(&comparison[right].name).strong());
+				ui.end_row();
+				for position in 0 .. length
+				{
+					ui.label(format!("{}.", position + 1));
+					let (x, y) = (a.get(position), b.get(position));
+					let cell = |section: usize| {
+						let text = RichText::new(format!("§{}", section + 1));
+						if x == y { text }
+						else { text.color(highlight).strong() }
+					};
+					match x
+					{
+						Some(x) => ui.label(cell(*x)).on_hover_text(
+							first_line(&self.original_sections[*x])
+						),
+						None => ui.label(RichText::new("—").weak())
+					};
+					let Some(y) = y
+					else
+					{
+						ui.label(RichText::new("—").weak());
+						ui.end_row();
+						continue
+					};
+					let line = first_line(&self.original_sections[*y]);
+					let hover = match position_in_a[*y]
+					{
+						Some(p) if p == position => line.to_string(),
+						Some(p) => format!(
+							"{}\n\nPosition {} in “{}”.",
+							line,
+							p + 1,
+							comparison[left].name
+						),
+						None => format!(
+							"{}\n\nAbsent from “{}”.",
+							line,
+							comparison[left].name
+						)
+					};
+					ui.label(cell(*y)).on_hover_text(hover);
+					ui.end_row();
+				}
+			});
+	}
+
+	/// Display the contents of the position heatmap [window](Window), which
+	/// samples many shuffles and shows how often each section lands in each
+	/// position.
+	fn present_heatmap_window(&mut self, ui: &mut Ui)
+	{
+		ui.label(format!(
+			"Shuffle {} times, honoring the constraints and layers just as \
+			shuffling does, and count how often each section lands in each \
+			position. Brighter cells are more likely. Each sample is a single \
+			reordering, so the best-of choice doesn't apply, repeated sections \
+			count only their first appearances, and omitted sections never \
+			land.",
+			HEATMAP_SAMPLES
+		));
+		let running = self.heatmap.as_ref()
+			.map_or(false, |job| !job.is_complete());
+		ui.horizontal(|ui| {
+			if ui.add_enabled(
+				!running && self.can_shuffle(),
+				Button::new("🎲 Sample")
+			).clicked()
+			{
+				// Sample under the layers, just as shuffling does.
+				let plan = self.with_layers(|app| app.mark_cycles()
+					.and_then(|graph| app.shuffle_plan(graph))
+				).flatten();
+				if let Some(mut plan) = plan
+				{
+					self.heatmap = Some(HeatmapJob::start(
+						self.original_sections.len(),
+						HEATMAP_SAMPLES,
+						plan.uniform_samples(),
+						move |rng| plan.draw(rng),
+						StdRng::from_rng(&mut self.rng).unwrap()
+					));
+				}
+			}
+			if running && ui.button("Stop").clicked()
+			{
+				self.heatmap = None;
+			}
+		});
+		let Some(job) = self.heatmap.as_mut() else { return };
+		if !job.poll()
+		{
+			ui.add(egui::ProgressBar::new(job.progress()).show_percentage());
+			ui.ctx().request_repaint();
+		}
+		let heatmap = &job.heatmap;
+		let count = heatmap.counts.len();
+		if count != self.original_sections.len()
+		{
+			ui.label(
+				RichText::new(
+					"The manuscript has changed since this sampling, so sample \
+					again."
+				).color(palette(ui).warning)
+			);
+			return
+		}
+		if heatmap.failures != 0
+		{
+			ui.label(
+				RichText::new(format!(
+					"{} of the shuffles found no ordering that honors the \
+					constraints, so they aren't counted.",
+					heatmap.failures
+				)).color(palette(ui).warning)
+			);
+		}
+		let skewed = job.skewed_samples();
+		if skewed != 0
+		{
+			ui.label(
+				RichText::new(format!(
+					"{} of the shuffles came after the uniform sampler ran out \
+					of mixing steps, so they favor orderings like the ones \
+					before them.",
+					skewed
+				)).color(palette(ui).warning)
+			);
+		}
+		// Scale the colors by the most frequent cell, so that the contrast
+		// survives large manuscripts.
+		let hottest = (0 .. count)
+			.flat_map(|s| (0 .. count).map(move |p| (s, p)))
+			.map(|(s, p)| heatmap.frequency(s, p))
+			.fold(0.0, f32::max)
+			.max(f32::EPSILON);
+		let color = palette(ui).warning;
+		let background = ui.visuals().extreme_bg_color;
+		ScrollArea::both().show(ui, |ui| {
+			Grid::new("heatmap")
+				.spacing(vec2(1.0, 1.0))
+				.min_col_width(HEATMAP_CELL_SIZE)
+				.show(ui, |ui| {
+					ui.label("");
+					for position in 0 .. count
+					{
+						ui.label(
+							RichText::new((position + 1).to_string()).small()
+						);
+					}
+					ui.end_row();
+					for section in 0 .. count
+					{
+						ui.label(format!("§{}", section + 1));
+						for position in 0 .. count
+						{
+							let frequency =
+								heatmap.frequency(section, position);
+							let (rect, response) = ui.allocate_exact_size(
+								vec2(HEATMAP_CELL_SIZE, HEATMAP_CELL_SIZE),
+								Sense::hover()
+							);
+							ui.painter().rect_filled(rect, 0.0, background);
+							ui.painter().rect_filled(
+								rect,
+								0.0,
+								color.linear_multiply(frequency / hottest)
+							);
+							response.on_hover_text(format!(
+								"§{} landed in position {} in {:.1}% of the \
+								shuffles.",
+								section + 1,
+								position + 1,
+								frequency * 100.0
+							));
+						}
+						ui.end_row();
+					}
+				});
+		});
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                              Diagnostics UI.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Display the diagnostics [panel](TopBottomPanel), which presents the
+	/// [recently&#32;captured&#32;events](recent_events).
+	fn present_diagnostics_panel(&mut self, ctx: &Context)
+	{
+		TopBottomPanel::bottom("diagnostics_panel")
+			.resizable(true)
+			.default_height(150.0)
+			.show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					heading(ui, "Diagnostics").on_hover_text(
+						"These are the most recent events reported by the \
+						application. If you report a bug, please include them."
+					);
+					if ui.button("📋 Copy to clipboard").clicked()
+					{
+						let text = events_as_text();
+						ui.output_mut(|clipboard| clipboard.copied_text = text);
+					}
+					if ui.button("Clear").clicked()
+					{
+						clear_events();
+					}
+					if ui.button("Close").clicked()
+					{
+						self.diagnostics_open = false;
+					}
+				});
+				ui.separator();
+				let palette = palette(ui);
+				ScrollArea::vertical()
+					.id_source("diagnostics")
+					.stick_to_bottom(true)
+					.auto_shrink([false, false])
+					.show(ui, |ui| {
+						for entry in recent_events()
+						{
+							let color = match entry.level
+							{
+								Level::ERROR => palette.error,
+								Level::WARN => palette.warning,
+								_ => ui.visuals().text_color()
+							};
+							ui.label(
+								RichText::new(entry.to_line())
+									.monospace()
+									.color(color)
+							);
+						}
+					});
+			});
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                         Configuration sidebar UI.                          //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Display the [sidebar][SidePanel] and handle any interactions associated
+	/// therewith.
+	fn present_configuration_sidebar(&mut self, ctx: &Context)
+	{
+		SidePanel::left("configuration_panel").show(ctx, |ui| {
+			heading(ui, "Parsing").on_hover_ui(|ui| {
+				ui.horizontal_wrapped(|ui| {
+					ui.spacing_mut().item_spacing.x = 0.0;
+					ui.label(
+						"Here you can specify how your manuscript will be \
+						split into sections. Those sections will appear in the "
+					);
+					ui.label(RichText::new("Constraints").strong());
+					ui.label(
+						" section below whenever you edit these options "
+					);
+					ui.label(RichText::new("or").italics());
+					ui.label(" your manuscript.");
+				});
+			});
+			ui.spacing_mut().item_spacing.y = 3.0;
+			// The parsing options determine the sections, and thereby the
+			// constraints, so they can't change during review.
+			let editable = !self.review_mode;
+			ui.horizontal(|ui| {
+				ui.label("Split at:");
+				let mut changed = false;
+				ui.add_enabled_ui(editable, |ui| {
+					ComboBox::from_id_source("split_mode")
+						.selected_text(self.split_mode.name())
+						.show_ui(ui, |ui| {
+							for mode in SplitMode::ALL
+							{
+								changed |= ui.selectable_value(
+									&mut self.split_mode,
+									mode,
+									mode.name()
+								).changed();
+							}
+						});
+				});
+				if self.split_mode == SplitMode::Headings
+				{
+					ui.label("up to level");
+					changed |= ui.add_enabled(
+						editable,
+						DragValue::new(&mut self.heading_level)
+							.clamp_range(1 ..= MAXIMUM_HEADING_LEVEL)
+					).changed();
+				}
+				if changed
+				{
+					self.update_sections();
+				}
+			}).response.on_hover_text(
+				"Begin a new section at each occurrence of the section \
+				delimiter; at each Markdown heading, like # Chapter One or \
+				## The Lighthouse, up to the chosen level; or at each scene \
+				heading of a Fountain screenplay, like INT. LIGHTHOUSE - \
+				NIGHT. Each heading stays with its section."
+			);
+			// The delimiter doesn't matter when splitting at headings.
+			let delimiter_editable =
+				editable && self.split_mode == SplitMode::Delimiter;
+			ui.horizontal(|ui| {
+				if ui.add_enabled(
+					delimiter_editable,
+					Checkbox::without_text(&mut self.delimiter_pattern_is_regex)
+				).clicked()
+				{
+					// The user toggled the intention for the pattern (between
+					// plain and regex), so update the pattern accordingly.
+					self.update_sections();
+				}
+				ui.hyperlink_to(
+					"Use regex",
+					"https://docs.rs/regex/latest/regex/#syntax"
+				);
+			}).response.on_hover_text(
+				"Treat the section break as a regular expression rather \
+				than just plain text. Click the hyperlink for the official \
+				syntax reference."
+			);
+			ui.horizontal(|ui| {
+				ui.label("Section delimiter: ");
+				if ui.add_enabled(
+					delimiter_editable,
+					TextEdit::singleline(&mut self.delimiter_pattern)
+				).changed()
+				{
+					// The user changed the pattern, which might mandate a new
+					// regex, so update the pattern accordingly.
+					self.update_sections();
+				}
+			}).response.on_hover_text(
+				"Set this to the section break pattern. Your manuscript will \
+				be broken into sections at occurrences of this pattern, and \
+				whitespace will be trimmed from  the beginning and end of each \
+				section."
+			);
+			if ui.add_enabled(
+				editable,
+				Checkbox::new(&mut self.anthology, "Anthology")
+			).on_hover_text(
+				"Check this box if your manuscript is a collection of distinct \
+				stories. The sections of each story stay together and in \
+				order, and only the stories themselves are shuffled."
+			).changed()
+			{
+				self.update_sections();
+			}
+			if self.anthology
+			{
+				ui.horizontal(|ui| {
+					ui.label("Story delimiter: ");
+					if ui.add_enabled(
+						editable,
+						TextEdit::singleline(&mut self.story_delimiter)
+					).changed()
+					{
+						self.update_sections();
+					}
+				}).response.on_hover_text(
+					"Set this to the plain text that separates the stories of \
+					your anthology. Each story is then broken into sections at \
+					occurrences of the section delimiter."
+				);
+			}
+			self.present_boilerplate(ui);
+			ui.separator();
+			self.present_regex_error(ui);
+			self.present_constraints(ui);
+			// Retain additional space, to preserve repositioning of the sash.
+			ui.allocate_space(ui.available_size());
+		});
+	}
+
+	/// Summarize the [boilerplate](split_boilerplate) of the manuscript, if
+	/// any, so that the writer knows what won't be shuffled.
+	fn present_boilerplate(&self, ui: &mut Ui)
+	{
+		let (front, _, back) = split_boilerplate(&self.original_manuscript);
+		if front.is_empty() && back.is_empty()
+		{
+			return
+		}
+		let words = |text: &str| text.split_whitespace().count();
+		ui.label(
+			RichText::new(format!(
+				"Boilerplate: {} words above {}, {} words below {}",
+				words(front),
+				BEGIN_MARKER,
+				words(back),
+				END_MARKER
+			)).small().weak()
+		).on_hover_text(
+			"Everything above a line that reads ===BEGIN=== and below a line \
+			that reads ===END=== is boilerplate, e.g., a title page or a \
+			license. Boilerplate is never split into sections, and every new \
+			manuscript begins and ends with it, verbatim."
+		);
+	}
+
+	/// Display the specified [regular&#32;expression][Regex] compilation error
+	/// on the [UI](Ui).
+	fn present_regex_error(&self, ui: &mut Ui)
+	{
+		if let Some(ref error) = self.delimiter_regex_error
+		{
+			// The pattern is a regex and the regex is busted, so present the
+			// problem. Use monospace so that any syntax errors are properly
+			// displayed.
+			ui.label(
+				RichText::new(error.to_string())
+					.monospace()
+					.color(palette(ui).error)
+					.strong()
+			);
+			ui.separator();
+		}
+	}
+
+	/// Display the controls for choosing, adding, deleting, and renaming
+	/// [scenarios](Self::scenarios).
+	fn present_scenarios(&mut self, ui: &mut Ui)
+	{
+		let mut selected = self.active_scenario;
+		ui.horizontal(|ui| {
+			ui.label("Scenario:");
+			ComboBox::from_id_source("scenario")
+				.selected_text(self.scenarios[selected].name.as_str())
+				.show_ui(ui, |ui| {
+					for (index, scenario) in self.scenarios.iter().enumerate()
+					{
+						ui.selectable_value(
+							&mut selected,
+							index,
+							scenario.name.as_str()
+						);
+					}
+				})
+				.response
+				.on_hover_text(
+					"Keep several sets of constraints over the same sections, \
+					e.g., to compare a chronological structure against a \
+					mirrored one. Each scenario remembers its own shuffles."
+				);
+			ui.add_enabled_ui(!self.review_mode, |ui| {
+				if ui.button("➕").on_hover_text(
+					"Make a new scenario from a copy of this one."
+				).clicked()
+				{
+					self.add_scenario();
+					selected = self.active_scenario;
+				}
+				let remove = ui.add_enabled(
+					self.scenarios.len() > 1,
+					Button::new("🗑")
+				).on_hover_text("Delete this scenario.");
+				if remove.clicked()
+				{
+					self.remove_scenario();
+					selected = self.active_scenario;
+				}
+			});
+		});
+		self.switch_scenario(selected);
+		ui.add_enabled_ui(!self.review_mode, |ui| {
+			ui.horizontal(|ui| {
+				ui.label("Name:");
+				ui.text_edit_singleline(
+					&mut self.scenarios[self.active_scenario].name
+				);
+			});
+		});
+	}
+
+	/// Display the [outcome](ConstraintsReport) of the most recent
+	/// [import&#32;of&#32;constraints](Self::import_constraints), if any, until
+	/// the writer dismisses it.
+	fn present_constraints_report(&mut self, ui: &mut Ui)
+	{
+		let Some(ref report) = self.constraints_report else { return };
+		let palette = palette(ui);
+		let mut dismissed = false;
+		ui.group(|ui| {
+			ui.horizontal(|ui| {
+				if report.imported
+				{
+					ui.label(
+						format!("Imported constraints from {}.", report.path)
+					);
+				}
+				else
+				{
+					ui.label(
+						RichText::new(format!(
+							"Couldn't import constraints from {}:",
+							report.path
+						)).color(palette.error)
+					);
+				}
+				dismissed = ui.small_button("✖")
+					.on_hover_text("Dismiss this report.")
+					.clicked();
+			});
+			let color =
+				if report.imported { palette.warning } else { palette.error };
+			for problem in &report.problems
+			{
+				ui.label(RichText::new(problem).color(color));
+			}
+		});
+		if dismissed
+		{
+			self.constraints_report = None;
+		}
+	}
+
+	/// Remove the specified [redundant](Redundancy) constraints from the
+	/// before and after lists of their sections.
+	fn remove_redundancies(&mut self, redundancies: &[Redundancy])
+	{
+		for r in redundancies
+		{
+			let c = &mut self.constraints[r.section];
+			let list = if r.after { &mut c.after } else { &mut c.before };
+			if let Some(position) = list.iter().position(|n| *n == r.other)
+			{
+				list.remove(position);
+			}
+			c.text_buffer = section_list_text(&c.before);
+			c.text_buffer_is_valid = true;
+			c.after_text_buffer = section_list_text(&c.after);
+			c.after_text_buffer_is_valid = true;
+		}
+		let text = match redundancies.len()
+		{
+			1 => "Removed 1 redundant constraint".to_string(),
+			n => format!("Removed {} redundant constraints", n)
+		};
+		tracing::info!("{}.", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Reset the [constraints](Self::constraints) of every section, e.g., to
+	/// restart their design from scratch. The manuscript stays as it is, and
+	/// so do the labels, dates, and tags of its sections, which describe
+	/// rather than constrain them.
+	fn clear_constraints(&mut self)
+	{
+		for c in &mut self.constraints
+		{
+			*c = Constraints {
+				label: std::mem::take(&mut c.label),
+				date: std::mem::take(&mut c.date),
+				tags: std::mem::take(&mut c.tags),
+				..Default::default()
+			};
+		}
+		let text = "Cleared the constraints of every section.".to_string();
+		tracing::info!("{}", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Record any edit of the [constraints](Self::constraints) since the
+	/// last frame, so that it can be [undone](Self::undo_constraint_edit).
+	/// Typing into a text field is recorded as a single edit, once the field
+	/// loses focus. Switching [scenarios](Self::scenarios) forgets the edit
+	/// history, because the constraints of one scenario shouldn't be undone
+	/// into another.
+	fn track_constraint_edits(&mut self, ctx: &Context)
+	{
+		let (baseline, scenario) = &mut self.constraint_baseline;
+		if *scenario != self.active_scenario
+			|| baseline.len() != self.constraints.len()
+		{
+			self.forget_constraint_edits();
+			return
+		}
+		// Paradoxes are discovered, not edited, so they never count as
+		// edits.
+		for (old, new) in baseline.iter_mut().zip(&self.constraints)
+		{
+			old.paradox_error.clone_from(&new.paradox_error);
+		}
+		if *baseline == self.constraints
+			|| ctx.memory(|memory| memory.focus().is_some())
+		{
+			return
+		}
+		let previous = std::mem::replace(baseline, self.constraints.clone());
+		self.constraint_undo.push(previous);
+		let excess = self.constraint_undo.len()
+			.saturating_sub(MAXIMUM_CONSTRAINT_UNDO_LENGTH);
+		self.constraint_undo.drain(.. excess);
+		self.constraint_redo.clear();
+	}
+
+	/// Forget the edit history of the [constraints](Self::constraints), e.g.,
+	/// because a different project was opened.
+	fn forget_constraint_edits(&mut self)
+	{
+		self.constraint_undo.clear();
+		self.constraint_redo.clear();
+		self.constraint_baseline =
+			(self.constraints.clone(), self.active_scenario);
+	}
+
+	/// Undo the most recent [recorded](Self::track_constraint_edits) edit of
+	/// the [constraints](Self::constraints), if any.
+	fn undo_constraint_edit(&mut self)
+	{
+		let Some(previous) = self.constraint_undo.pop() else { return };
+		let current = std::mem::replace(&mut self.constraints, previous);
+		self.constraint_redo.push(current);
+		self.constraint_baseline.0 = self.constraints.clone();
+		tracing::info!("Undid a constraint edit.");
+	}
+
+	/// Redo the most recently [undone](Self::undo_constraint_edit) edit of
+	/// the [constraints](Self::constraints), if any.
+	fn redo_constraint_edit(&mut self)
+	{
+		let Some(next) = self.constraint_redo.pop() else { return };
+		let current = std::mem::replace(&mut self.constraints, next);
+		self.constraint_undo.push(current);
+		self.constraint_baseline.0 = self.constraints.clone();
+		tracing::info!("Redid a constraint edit.");
+	}
+
+	/// Display the buttons that [undo](Self::undo_constraint_edit) and
+	/// [redo](Self::redo_constraint_edit) edits of the
+	/// [constraints](Self::constraints).
+	fn present_constraint_undo(&mut self, ui: &mut Ui)
+	{
+		ui.horizontal(|ui| {
+			let shortcut = ui.ctx().format_shortcut(&UNDO_SHORTCUT);
+			if ui.add_enabled(
+				!self.constraint_undo.is_empty(),
+				Button::new("⟲ Undo")
+			).on_hover_text(format!(
+				"Undo your most recent change to the constraints ({}), e.g., \
+				an accidentally cleared list. Edits of the manuscript aren't \
+				affected.",
+				shortcut
+			)).clicked()
+			{
+				self.undo_constraint_edit();
+			}
+			let shortcut = ui.ctx().format_shortcut(&REDO_SHORTCUT);
+			if ui.add_enabled(
+				!self.constraint_redo.is_empty(),
+				Button::new("⟳ Redo")
+			).on_hover_text(format!(
+				"Redo the change to the constraints that you most recently \
+				undid ({}).",
+				shortcut
+			)).clicked()
+			{
+				self.redo_constraint_edit();
+			}
+		});
+	}
+
+	/// Display the [layers](Self::layers), along with the controls to toggle,
+	/// edit, add, and remove them.
+	fn present_layers(&mut self, ui: &mut Ui)
+	{
+		CollapsingHeader::new("Layers")
+			.default_open(!self.layers.is_empty())
+			.show(ui, |ui| {
+				let mut removed = None;
+				let error = palette(ui).error;
+				for (index, layer) in self.layers.iter_mut().enumerate()
+				{
+					ui.horizontal(|ui| {
+						ui.checkbox(&mut layer.enabled, "")
+							.on_hover_text(
+								"Check this box to apply this layer to the \
+								next shuffle."
+							);
+						ui.add(
+							TextEdit::singleline(&mut layer.name)
+								.hint_text("layer name")
+						);
+						if ui.small_button("🗑")
+							.on_hover_text("Delete this layer.")
+							.clicked()
+						{
+							removed = Some(index);
+						}
+					});
+					ui.add(
+						TextEdit::multiline(&mut layer.script)
+							.id_source(("layer", index))
+							.code_editor()
+							.hint_text("3 < 7 < 9")
+							.desired_rows(3)
+							.desired_width(f32::INFINITY)
+					);
+					for e in parse_script(&layer.script).err()
+						.into_iter()
+						.flatten()
+					{
+						ui.label(RichText::new(e).color(error));
+					}
+				}
+				if let Some(index) = removed
+				{
+					let removed = self.layers.remove(index);
+					tracing::info!("Deleted the layer “{}”.", removed.name);
+				}
+				if ui.button("➕ Add layer").clicked()
+				{
+					self.layers.push(Layer {
+						name: format!("Layer {}", self.layers.len() + 1),
+						enabled: true,
+						script: String::new()
+					});
+				}
+			})
+			.header_response
+			.on_hover_text(
+				"Keep constraints for competing structural hypotheses, e.g., \
+				strict chronology or loose, in named layers atop the \
+				constraints of the sections. Write each layer in the syntax \
+				of File ▸ Edit constraints as text…, and check the layers to \
+				apply to the next shuffle; the rest are kept but ignored."
+			);
+	}
+
+	/// Display the [tag&#32;constraints](Self::tag_constraints), along with the
+	/// controls to add and remove them.
+	fn present_tag_constraints(&mut self, ui: &mut Ui)
+	{
+		let count = self.original_sections.len();
+		CollapsingHeader::new("Tag constraints")
+			.default_open(!self.tag_constraints.is_empty())
+			.show(ui, |ui| {
+				let mut removed = None;
+				for (index, t) in self.tag_constraints.iter_mut().enumerate()
+				{
+					ui.horizontal(|ui| {
+						ui.label("Sections tagged");
+						ui.add(
+							TextEdit::singleline(&mut t.tag)
+								.hint_text("tag")
+								.desired_width(TAG_FIELD_WIDTH)
+						);
+						let n = t.kind.section().unwrap_or(1);
+						ComboBox::from_id_source(("tag_constraint", index))
+							.selected_text(t.kind.name())
+							.show_ui(ui, |ui| {
+								for kind in [
+									TagConstraintKind::After(n),
+									TagConstraintKind::Before(n),
+									TagConstraintKind::NeverAdjacent
+								]
+								{
+									ui.selectable_value(
+										&mut t.kind,
+										kind,
+										kind.name()
+									);
+								}
+							});
+						if let TagConstraintKind::After(n)
+							| TagConstraintKind::Before(n) = &mut t.kind
+						{
+							ui.add(
+								DragValue::new(n)
+									.clamp_range(1 ..= count.max(1))
+									.prefix("§")
+							);
+						}
+						if ui.small_button("🗑")
+							.on_hover_text("Delete this tag constraint.")
+							.clicked()
+						{
+							removed = Some(index);
+						}
+					});
+				}
+				if let Some(index) = removed
+				{
+					self.tag_constraints.remove(index);
+				}
+				if ui.button("➕ Add tag constraint").clicked()
+				{
+					self.tag_constraints.push(TagConstraint::default());
+				}
+			})
+			.header_response
+			.on_hover_text(
+				"Constrain every section with a tag at once, e.g., to keep \
+				all flashback sections after §3, or to keep any two dream \
+				sections from being adjacent. The constraints cover whichever \
+				sections bear the tag when you shuffle, including sections \
+				that you tag later. Set the tags of each section under its \
+				details."
+			);
+	}
+
+	/// Display the [chapters](Self::chapters) to which the writer assigned
+	/// sections, if any, each with its [policy](ChapterPolicy).
+	fn present_chapters(&mut self, ui: &mut Ui)
+	{
+		let names = chapter_names(&self.chapter_memberships()).into_iter()
+			.map(str::to_string)
+			.collect::<Vec<_>>();
+		if names.is_empty()
+		{
+			return
+		}
+		CollapsingHeader::new("Chapters")
+			.default_open(true)
+			.show(ui, |ui| {
+				Grid::new("chapters").num_columns(2).show(ui, |ui| {
+					for name in names
+					{
+						ui.label(&name);
+						let old = chapter_policy(&self.chapters, &name);
+						let mut policy = old;
+						ComboBox::from_id_source(("chapter_policy", &name))
+							.selected_text(policy.name())
+							.show_ui(ui, |ui| {
+								for choice in ChapterPolicy::ALL
+								{
+									ui.selectable_value(
+										&mut policy,
+										choice,
+										choice.name()
+									).on_hover_text(choice.description());
+								}
+							})
+							.response
+							.on_hover_text(policy.description());
+						if policy != old
+						{
+							match self.chapters.iter_mut()
+								.find(|chapter| chapter.name == name)
+							{
+								Some(chapter) => chapter.policy = policy,
+								None => self.chapters.push(
+									Chapter { name, policy }
+								)
+							}
+						}
+						ui.end_row();
+					}
+				});
+			})
+			.header_response
+			.on_hover_text(
+				"The chapters named under the details of the sections. The \
+				sections of a chapter always stay together. Choose whether \
+				each chapter stays in place while its sections shuffle, moves \
+				as a unit among the other moving chapters, or both."
+			);
+	}
+
+	/// Display the [original&#32;sections](Self::original_sections) along with
+	/// their [constraints](Self::constraints).
+	fn present_constraints(&mut self, ui: &mut Ui)
+	{
+		if self.original_sections.len() < 2
+		{
+			// There's no reason to present sections if there's only one
+			// section; this might even be confusing for the user.
+			return
+		}
+		heading(ui, "Constraints").on_hover_ui(|ui| {
+			ui.horizontal_wrapped(|ui| {
+				ui.spacing_mut().item_spacing.x = 0.0;
+				ui.label(
+					"Here you can specify how movement of your manuscript's \
+					sections can be constrained. When you are satisfied with \
+					the constraints, you can click "
+				);
+				ui.label(RichText::new("🎲 Shuffle").strong());
+				ui.label(
+					" to obtain a permutation of your \
+					manuscript that satisfies your constraints. Any ordering \
+					paradoxes will be reported beneath their respective \
+					constraints."
+				);
+			});
+		});
+		ui.spacing_mut().item_spacing.y = 3.0;
+		self.present_scenarios(ui);
+		// Compute the feasible position ranges afresh, because the constraints
+		// may have changed during the last frame. This is cheap enough for any
+		// reasonable manuscript. Don't bother if some constraint refers to a
+		// nonexistent section, because the graph would be malformed.
+		let graph = references_are_in_bounds(&self.constraints)
+			.then(|| self.constraint_graph());
+		let ranges = graph.as_ref().and_then(feasible_positions);
+		// Redundancy is meaningless amid paradoxes, when no position is
+		// feasible.
+		let redundancies = match (&graph, &ranges)
+		{
+			(Some(graph), Some(_)) =>
+				redundant_constraints(graph, &self.constraints),
+			_ => vec![]
+		};
+		// Present the constraints even during review, but don't allow them to
+		// change.
+		ui.add_enabled_ui(!self.review_mode, |ui| {
+			self.present_constraint_undo(ui);
+			if !self.anthology
+			{
+				ui.horizontal(|ui| {
+					ui.label("Rotate tags: ");
+					ui.text_edit_singleline(&mut self.rotation);
+					ComboBox::from_id_source("rotation_mode")
+						.selected_text(self.rotation_mode.name())
+						.show_ui(ui, |ui| {
+							for mode in RotationMode::ALL
+							{
+								ui.selectable_value(
+									&mut self.rotation_mode,
+									mode,
+									mode.name()
+								);
+							}
+						});
+				}).response.on_hover_text(
+					"Enter a comma-separated list of tags, like Ana, Ben, Cal, \
+					to make the sections with those tags appear in strict \
+					rotation, beginning with the first tag, or else to keep \
+					any two sections with the same tag from being adjacent, \
+					e.g., for the narrators of a dual-narrator novel. Untagged \
+					sections can appear anywhere. Set the tags of each \
+					section under its details."
+				);
+				if let Some(problem) = self.rotation_problem()
+				{
+					ui.label(RichText::new(problem).color(palette(ui).warning));
+				}
+				self.present_chapters(ui);
+			}
+			self.present_tag_constraints(ui);
+			self.present_layers(ui);
+			if ui.button("Detect front and back matter")
+				.on_hover_text(
+					"Look for title pages, dedications, epigraphs, \
+					acknowledgments, and the like at the beginning and end of \
+					your manuscript, and keep them there when shuffling. You \
+					can also set the part of each section under its details."
+				)
+				.clicked()
+			{
+				self.detect_matter();
+			}
+			if !redundancies.is_empty()
+			{
+				let text = match redundancies.len()
+				{
+					1 => "🗑 Remove 1 redundant constraint".to_string(),
+					n => format!("🗑 Remove {} redundant constraints", n)
+				};
+				if ui.button(text)
+					.on_hover_text(
+						"Remove the ordering constraints that your other \
+						constraints already imply, e.g., 1 before 3 when 1 \
+						comes before 2 and 2 before 3. The valid orderings \
+						stay exactly the same, but the constraints are easier \
+						to read."
+					)
+					.clicked()
+				{
+					self.remove_redundancies(&redundancies);
+				}
+			}
+			if self.clear_constraints_pending
+			{
+				ui.horizontal(|ui| {
+					ui.label(
+						RichText::new("Clear the constraints of every section?")
+							.color(palette(ui).warning)
+					);
+					if ui.button("Clear").clicked()
+					{
+						self.clear_constraints();
+						self.clear_constraints_pending = false;
+					}
+					if ui.button("Cancel").clicked()
+					{
+						self.clear_constraints_pending = false;
+					}
+				});
+			}
+			else if ui.button("🗑 Clear all constraints")
+				.on_hover_text(
+					"Reset the constraints of every section, e.g., to start \
+					over. The manuscript stays as it is, and so do the \
+					labels, dates, and tags of its sections. You'll be asked \
+					to confirm."
+				)
+				.clicked()
+			{
+				self.clear_constraints_pending = true;
+			}
+			self.present_constraints_report(ui);
+			let orderings = self.valid_orderings();
+			scrollable_sections(
+				ui,
+				&(0 .. self.original_sections.len()).collect::<Vec<_>>(),
+				&mut self.original_sections,
+				Some(&mut self.constraints),
+				self.sections_regex.as_ref(),
+				SectionAnnotations {
+					ids: self.show_section_ids.then_some(&self.section_ids[..]),
+					stories: self.anthology
+						.then_some(&self.section_stories[..]),
+					ranges: ranges.as_deref(),
+					transitions: None,
+					redundancies: Some(&redundancies),
+					impact: Some(ImpactContext {
+						tag_constraints: &self.tag_constraints,
+						chapters: &self.chapters,
+						anthology: self.anthology,
+						orderings
+					})
+				}
+			);
+		});
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                            Manuscript panel UI.                            //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Display the [manuscript&#32;panel][CentralPanel] and handle any
 	/// interactions associated therewith.
 	fn present_manuscript_panel(&mut self, ctx: &Context)
 	{
-		CentralPanel::default().show(ctx, |ui| {
-			ui.spacing_mut().item_spacing.y = 10.0;
-			ui.vertical_centered(|ui| {
-				heading(ui, "Manuscript").on_hover_ui(|ui| {
+		CentralPanel::default().show(ctx, |ui| {
+			ui.spacing_mut().item_spacing.y = 10.0;
+			ui.vertical_centered(|ui| {
+				heading(ui, "Manuscript").on_hover_ui(|ui| {
+					ui.label(
+						"Paste your manuscript in the large text area below, \
+						or drop a text, OpenDocument, or RTF file anywhere on \
+						the window, to get started."
+					);
+				});
+			});
+			#[cfg(not(target_arch = "wasm32"))]
+			ui.horizontal(|ui| {
+				if ui.add_enabled(
+					!self.review_mode,
+					Button::new("📂 Open…")
+				).on_hover_text(
+					"Replace the manuscript with the contents of a text, \
+					Markdown, OpenDocument, or RTF file, which is faster and \
+					more faithful than pasting a long manuscript. Formatting \
+					is stripped from OpenDocument and RTF files."
+				).clicked()
+				{
+					self.open_manuscript();
+				}
+				if ui.add_enabled(
+					!self.review_mode,
+					Button::new("🗂 Open files as sections…")
+				).on_hover_text(
+					"Replace the manuscript with several files, e.g., one per \
+					scene, each of which becomes one section, in order by file \
+					name, and labeled by its file name."
+				).clicked()
+				{
+					self.open_manuscripts();
+				}
+				if ui.add_enabled(
+					!self.review_mode,
+					Button::new("📁 Open folder as sections…")
+				).on_hover_text(
+					"Replace the manuscript with the files of a folder, e.g., \
+					one per scene, each of which becomes one section, in order \
+					by file name, and labeled by its file name."
+				).clicked()
+				{
+					self.open_manuscript_folder();
+				}
+			});
+			self.present_spelling_controls(ui);
+			// Temporarily take the speller, to allow the layouter to borrow it
+			// while the manuscript is mutated.
+			let taken_speller = self.speller.take();
+			let speller = taken_speller.as_ref()
+				.filter(|speller| self.spellcheck && speller.is_usable());
+			let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+				let style = ui.style();
+				let mut job = highlight_misspellings(
+					text,
+					speller.unwrap(),
+					TextStyle::Body.resolve(style),
+					style.visuals.override_text_color.unwrap_or_else(||
+						style.visuals.widgets.inactive.text_color()
+					),
+					palette(ui).error
+				);
+				job.wrap.max_width = wrap_width;
+				ui.fonts(|fonts| fonts.layout_job(job))
+			};
+			ScrollArea::vertical().max_height(550.0).show(ui, |ui| {
+				// During review, present the manuscript through an immutable
+				// buffer, which still permits selection and copying.
+				let mut readonly;
+				let mut editor = if self.review_mode
+				{
+					readonly = self.original_manuscript.as_str();
+					TextEdit::multiline(&mut readonly)
+				}
+				else
+				{
+					TextEdit::multiline(&mut self.original_manuscript)
+				}.desired_width(f32::INFINITY).desired_rows(30);
+				if speller.is_some()
+				{
+					// Only bother with the custom layout if there's actually
+					// some spelling to check.
+					editor = editor.layouter(&mut layouter);
+				}
+				let text_area = ui.add(editor);
+				text_area.clone().on_hover_ui(|ui| {
+					ui.vertical(|ui| {
+						ui.label("Here's the basic application workflow:");
+						ui.label("• Paste your manuscript here.");
+						ui.label("• Set the section delimiter.");
+						ui.label("• Set any ordering constraints.");
+						ui.horizontal_wrapped(|ui| {
+							ui.spacing_mut().item_spacing.x = 0.0;
+							ui.label("• ");
+							ui.label(RichText::new("🎲 Shuffle").strong());
+							ui.label(" until you get an ordering you like.");
+						});
+						ui.horizontal_wrapped(|ui| {
+							ui.spacing_mut().item_spacing.x = 0.0;
+							ui.label("• ");
+							ui.label(
+								RichText::new("📋 Copy to clipboard").strong()
+							);
+							ui.label(".");
+						});
+						ui.label(
+							"• Paste your new manuscript into an external document."
+						);
+						ui.label(
+							"You can hover almost every component or label \
+							for additional help."
+						);
+					});
+				});
+				if text_area.changed()
+				{
+					self.update_sections();
+				}
+			});
+			self.speller = taken_speller;
+			ui.vertical_centered(|ui| {
+				let button = ui.add_enabled(
+					self.can_shuffle(),
+					Button::new(RichText::new("🎲 Shuffle").strong())
+				);
+				button.clone().on_hover_ui(|ui| {
+					ui.horizontal_wrapped(|ui| {
+						ui.spacing_mut().item_spacing.x = 0.0;
+						ui.label(
+							"Produce a randomized reordering of the \
+							manuscript's sections that obeys the established \
+							constraints. This also clears any resolved error \
+							messages in the "
+						);
+						ui.label(RichText::new("Constraints").strong());
+						ui.label(
+							" section. If errors remain, then no reordering \
+							is performed."
+						);
+					});
+				});
+				if button.clicked()
+				{
+					self.with_layers(|app| {
+						if let Some(graph) = app.mark_cycles()
+						{
+							app.shuffle(graph);
+						}
+					});
+				}
+				ui.add_enabled(
+					!self.uniform_sampling,
+					DragValue::new(&mut self.shuffle_attempts)
+						.clamp_range(1 ..= MAXIMUM_SHUFFLE_ATTEMPTS)
+						.prefix("Best of ")
+				).on_hover_text(
+					"Produce this many random reorderings per shuffle, and keep \
+					only the one whose transitions between sections score best, \
+					i.e., with the fewest echoes, repeated words, and changes \
+					of tense or point of view."
+				).on_disabled_hover_text(
+					"Uniform shuffling draws a single reordering, because \
+					keeping the best of several would favor some reorderings."
+				);
+				ui.checkbox(&mut self.balance_lengths, "Balance lengths")
+					.on_hover_text(
+						"Discourage reorderings that place the two longest \
+						sections, or the two shortest sections, side by side, \
+						which can make the pacing lopsided purely by chance. \
+						This only takes effect when shuffling for the best of \
+						several reorderings."
+					);
+				self.present_ordering_count(ui);
+				let obstacle = self.uniform_obstacle();
+				ui.add_enabled(
+					obstacle.is_none(),
+					Checkbox::new(&mut self.uniform_sampling, "Uniform")
+				).on_hover_text(
+					"Make every reordering that obeys the constraints equally \
+					likely. Otherwise, reorderings that leave loosely \
+					constrained sections near the front are somewhat favored. \
+					Place preferences are ignored, and each shuffle draws a \
+					single reordering rather than the best of several."
+				).on_disabled_hover_text(format!(
+					"Uniform shuffling isn't possible here, because {}.",
+					obstacle.unwrap_or_default()
+				));
+			});
+			ui.with_layout(Layout::bottom_up(Align::Center), |ui| {
+				ui.spacing_mut().item_spacing.y = 3.0;
+				egui::warn_if_debug_build(ui);
+				ui.hyperlink_to(
+					"(Source on GitHub)",
+					"https://github.com/toddATavail/story-shuffler"
+				);
+				ui.separator();
+			});
+		});
+	}
+
+	/// Present the number of [valid&#32;orderings](Self::valid_orderings)
+	/// beside the shuffle button, so that the writer can tell whether the
+	/// constraints leave a handful of possibilities or millions.
+	fn present_ordering_count(&mut self, ui: &mut Ui)
+	{
+		if !self.can_shuffle() { return }
+		let Some(orderings) = self.valid_orderings() else { return };
+		let text = RichText::new(format!("Valid orderings: {}", orderings));
+		let text = if orderings.is_zero()
+		{
+			text.color(palette(ui).error)
+		}
+		else { text };
+		ui.label(text).on_hover_text(
+			"The number of orderings of the sections that obey the ordering \
+			constraints. Large numbers are estimated. Pinned positions, \
+			spacing, rotations, stories, and layers can narrow the \
+			possibilities further."
+		);
+	}
+
+	/// Answer the number of [valid&#32;orderings](OrderingCount) under the
+	/// [constraint&#32;graph](Self::constraint_graph), counting them only if
+	/// its [shape](OrderingCountKey) changed since the last count. Answer
+	/// `None` if some constraint refers to a missing section.
+	fn valid_orderings(&mut self) -> Option<OrderingCount>
+	{
+		if !references_are_in_bounds(&self.constraints)
+		{
+			return None
+		}
+		let graph = self.constraint_graph();
+		let key = OrderingCountKey::of(&graph);
+		match &self.ordering_count
+		{
+			Some((counted, orderings)) if *counted == key => Some(*orderings),
+			_ =>
+			{
+				let orderings = count_orderings(&graph);
+				self.ordering_count = Some((key, orderings));
+				Some(orderings)
+			}
+		}
+	}
+
+	/// Determine whether the model is correct and can be shuffled.
+	fn can_shuffle(&self) -> bool
+	{
+		let count = self.original_sections.len();
+		count > 1
+			&& self.constraints.iter()
+				.enumerate()
+				.all(|(index, c)| c.reference_problems(index, count).is_empty())
+			&& self.constraints.iter().all(|c|
+				c.text_buffer_is_valid
+					&& c.after_text_buffer_is_valid
+					&& c.near_text_buffer_is_valid
+					&& c.apart_text_buffer_is_valid
+					&& c.not_adjacent_text_buffer_is_valid
+					&& c.prefer_before_text_buffer_is_valid
+			)
+	}
+
+	/// Answer the constraint graph of the [constraints](Self::constraints),
+	/// including the ordering constraints that the
+	/// [tag&#32;constraints](Self::tag_constraints) and the
+	/// [chapters](Self::chapters) impose on their sections.
+	fn constraint_graph(&self) -> DiGraph<usize, (), usize>
+	{
+		compute_constraint_graph(
+			&self.constraints,
+			&self.tag_constraints,
+			&self.chapters,
+			self.anthology
+		)
+	}
+
+	/// Answer the [chapter](Constraints::chapter) of each section, if any, in
+	/// [section&#32;order](Self::original_sections).
+	fn chapter_memberships(&self) -> Vec<Option<&str>>
+	{
+		self.constraints.iter().map(Constraints::chapter).collect()
+	}
+
+	/// Mark any cycles in the specification of the whole system of
+	/// [constraints](Self::constraints). If there were no cycles, then answer
+	/// the [graph][DiGraph].
+	fn mark_cycles(&mut self) -> Option<DiGraph<usize, (), usize>>
+	{
+		profile_function!();
+		let graph = self.constraint_graph();
+		let mut cycle_count = 0;
+		for index in graph.node_indices()
+		{
+			if let Some(cycle) = find_cycle(&graph, index)
+			{
+				let mut error = String::from("Paradox detected:\n");
+				for step in cycle.windows(2)
+				{
+					// Adjust the indices to one-based for our target audience,
+					// i.e., writers.
+					error.push_str("\t§");
+					error.push_str(&(step[0].index() + 1).to_string());
+					error.push_str(" must come before §");
+					error.push_str(&(step[1].index() + 1).to_string());
+					error.push('\n');
+				}
+				self.constraints[index.index()].paradox_error = Some(error);
+				cycle_count += 1;
+			}
+			else
+			{
+				self.constraints[index.index()].paradox_error = None;
+			}
+		}
+		if cycle_count == 0
+		{
+			Some(graph)
+		}
+		else
+		{
+			tracing::warn!(
+				"Refused to shuffle: {} sections are involved in paradoxes.",
+				cycle_count
+			);
+			None
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Spelling UI.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Display the controls for spellchecking the
+	/// [manuscript](Self::original_manuscript), as well as the spelling
+	/// [window](Window) if it's open.
+	fn present_spelling_controls(&mut self, ui: &mut Ui)
+	{
+		ui.horizontal(|ui| {
+			if ui.checkbox(&mut self.spellcheck, "Check spelling")
+				.on_hover_text(
+					"Underline the words in your manuscript that don't appear \
+					in any of your dictionaries."
+				)
+				.changed() && self.spellcheck
+			{
+				self.ensure_speller();
+			}
+			if ui.button("📖 Dictionaries…")
+				.on_hover_text(
+					"Choose a dictionary, maintain your personal dictionary, \
+					and review the unknown words in your manuscript."
+				)
+				.clicked()
+			{
+				self.ensure_speller();
+				self.spelling_window_open = true;
+			}
+		});
+		let mut open = self.spelling_window_open;
+		Window::new("Spelling")
+			.open(&mut open)
+			.default_width(320.0)
+			.show(ui.ctx(), |ui| self.present_spelling_window(ui));
+		self.spelling_window_open = open;
+	}
+
+	/// Display the contents of the spelling [window](Window): the system
+	/// [dictionary](Dictionary) selector (native only), the personal
+	/// dictionary, and the unknown words of the
+	/// [manuscript](Self::original_manuscript).
+	fn present_spelling_window(&mut self, ui: &mut Ui)
+	{
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			let dictionaries = self.system_dictionaries
+				.get_or_insert_with(system_dictionaries)
+				.clone();
+			if dictionaries.is_empty()
+			{
+				ui.label(
+					"No system dictionaries were found, so only your personal \
+					dictionary is available. Installing Hunspell dictionaries \
+					will fix this."
+				);
+			}
+			else
+			{
+				let selected = self.spelling_dictionary.clone();
+				ComboBox::from_label("System dictionary")
+					.selected_text(selected.clone().unwrap_or_default())
+					.show_ui(ui, |ui| {
+						for (name, _) in &dictionaries
+						{
+							ui.selectable_value(
+								&mut self.spelling_dictionary,
+								Some(name.clone()),
+								name
+							);
+						}
+					});
+				if selected != self.spelling_dictionary
+				{
+					// The writer chose a different dictionary, so load it.
+					self.speller = None;
+					self.ensure_speller();
+				}
+			}
+		}
+		#[cfg(target_arch = "wasm32")]
+		ui.label(
+			"Browsers don't share their dictionaries, so only your personal \
+			dictionary is available on the web. You can paste a whole word \
+			list into it."
+		);
+		ui.label("Personal dictionary:").on_hover_text(
+			"Enter one word per line. Words here are always considered to be \
+			spelled correctly."
+		);
+		let personal = ScrollArea::vertical()
+			.id_source("personal_dictionary")
+			.max_height(150.0)
+			.show(ui, |ui| {
+				ui.add(
+					TextEdit::multiline(&mut self.personal_dictionary)
+						.desired_width(f32::INFINITY)
+						.desired_rows(6)
+				)
+			}).inner;
+		if personal.changed()
+		{
+			if let Some(speller) = self.speller.as_mut()
+			{
+				speller.personal = Dictionary::parse(&self.personal_dictionary);
+			}
+		}
+		ui.separator();
+		let unknown = match self.speller.as_ref()
+		{
+			Some(speller) if speller.is_usable() =>
+			{
+				let mut unknown = misspellings(&self.original_manuscript, speller)
+					.into_iter()
+					.map(|range| self.original_manuscript[range].to_string())
+					.collect::<Vec<_>>();
+				unknown.sort();
+				unknown.dedup();
+				unknown
+			},
+			_ =>
+			{
+				ui.label("Add some words to a dictionary to check spelling.");
+				return
+			}
+		};
+		ui.label(format!("Unknown words: {}", unknown.len()));
+		ScrollArea::vertical()
+			.id_source("unknown_words")
+			.max_height(200.0)
+			.show(ui, |ui| {
+				for word in unknown
+				{
+					ui.horizontal(|ui| {
+						if ui.small_button("➕")
+							.on_hover_text("Add this word to your personal dictionary.")
+							.clicked()
+						{
+							if !self.personal_dictionary.is_empty()
+								&& !self.personal_dictionary.ends_with('\n')
+							{
+								self.personal_dictionary.push('\n');
+							}
+							self.personal_dictionary.push_str(&word);
+							self.personal_dictionary.push('\n');
+							if let Some(speller) = self.speller.as_mut()
+							{
+								speller.personal.extend(&word);
+							}
+						}
+						ui.label(word);
+					});
+				}
+			});
+	}
+
+	/// Build the [speller](Self::speller) if necessary, by loading the
+	/// [selected&#32;dictionary](Self::spelling_dictionary) (or the most
+	/// appropriate one, if none is selected) and parsing the
+	/// [personal&#32;dictionary](Self::personal_dictionary).
+	fn ensure_speller(&mut self)
+	{
+		if self.speller.is_some()
+		{
+			return
+		}
+		#[allow(unused_mut)]
+		let mut speller = Speller {
+			personal: Dictionary::parse(&self.personal_dictionary),
+			..Default::default()
+		};
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			let dictionaries = self.system_dictionaries
+				.get_or_insert_with(system_dictionaries);
+			if self.spelling_dictionary.is_none()
+			{
+				self.spelling_dictionary = preferred_dictionary(dictionaries);
+			}
+			let path = dictionaries.iter()
+				.find(|(name, _)| Some(name) == self.spelling_dictionary.as_ref())
+				.map(|(_, path)| path.clone());
+			if let Some(path) = path
+			{
+				match load_dictionary(&path)
+				{
+					Ok(dictionary) =>
+					{
+						tracing::info!(
+							"Loaded dictionary {:?} ({} words).",
+							path,
+							dictionary.len()
+						);
+						speller.system_name = self.spelling_dictionary.clone();
+						speller.system = Some(dictionary);
+					},
+					Err(e) => tracing::warn!(
+						"Failed to load dictionary {:?}: {}",
+						path,
+						e
+					)
+				}
+			}
+		}
+		self.speller = Some(speller);
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                             Output sidebar UI.                             //
+////////////////////////////////////////////////////////////////////////////////
+
+impl StoryShufflerApp
+{
+	/// Shuffle the [sections](Self::original_sections) of the
+	/// [manuscript](Self::original_manuscript), in accordance with any
+	/// [constraints](Self::constraints) established by the user. If more than
+	/// one [attempt](Self::shuffle_attempts) is requested, then keep the
+	/// ordering whose [transitions](Transition) score best, penalizing
+	/// [lopsided](lopsided_adjacencies) orderings if
+	/// [balancing](Self::balance_lengths) is enabled and orderings that
+	/// [fall&#32;short](Shortfall) of the writer's preferences.
+	fn shuffle(&mut self, graph: DiGraph<usize, (), usize>)
+	{
+		profile_function!();
+		let Some(mut plan) = self.shuffle_plan(graph) else { return };
+		let lengths = self.balance_lengths.then(|| self.original_sections.iter()
+			.map(|section| section.split_whitespace().count())
+			.collect::<Vec<_>>()
+		);
+		// Draw a fresh seed for each shuffle, so that the log can reproduce it.
+		let seed = self.rng.gen::<u64>();
+		let mut rng = StdRng::seed_from_u64(seed);
+		let mut best: Option<(f32, Vec<usize>, Vec<String>)> = None;
+		let mut best_order = vec![];
+		let mut best_transitions = vec![];
+		let mut repeat_problem = None;
+		// Keeping the best of several uniform samples would favor some
+		// orderings, so draw just one.
+		let attempts = if plan.sampler.is_some() { 1 }
+			else { self.shuffle_attempts.max(1) };
+		let skewed = plan.uniform_samples() == Some(0);
+		for _ in 0 .. attempts
+		{
+			let drawn = (0 .. plan.retries).find_map(|_|
+				plan.attempt(&mut rng)
+					.and_then(|order| match self.present_order(&order, &mut rng)
+					{
+						Ok(indices) => Some((order, indices)),
+						Err(problem) =>
+						{
+							repeat_problem = Some(problem);
+							None
+						}
+					})
+			);
+			let Some((order, indices)) = drawn else { continue };
+			let shuffled = indices.iter()
+				.map(|index| self.original_sections[*index].clone())
+				.collect::<Vec<_>>();
+			let transitions = analyze_transitions(&shuffled);
+			let mut score = total_score(&transitions);
+			if let Some(ref lengths) = lengths
+			{
+				score -= LOPSIDED_PENALTY
+					* lopsided_adjacencies(lengths, &indices) as f32;
+			}
+			score -= shortfalls(&indices, &self.constraints).iter()
+				.map(Shortfall::penalty)
+				.sum::<f32>();
+			if best.as_ref().map(|(best, ..)| score > *best).unwrap_or(true)
+			{
+				best = Some((score, indices, shuffled));
+				best_order = order;
+				best_transitions = transitions;
+			}
+		}
+		let Some((score, indices, shuffled)) = best
+		else
+		{
+			if let Some(problem) = repeat_problem
+			{
+				self.report_failure(format!("Couldn't shuffle: {}", problem));
+				return
+			}
+			self.report_failure(
+				"Couldn't shuffle: no ordering was found that honors the \
+				constraints, including the rotation, the pinned positions, and \
+				the spacing requirements, if any.".to_string()
+			);
+			return
+		};
+		tracing::info!(
+			"Shuffled {} sections (best of {}, transition score {:.2}).",
+			indices.len(),
+			attempts,
+			score
+		);
+		let mut notes = vec![];
+		if self.constraints.iter().any(|c| !c.prefer_before.is_empty())
+		{
+			notes.push(describe_shortfalls(
+				&shortfalls(&indices, &self.constraints)
+			));
+		}
+		// The layers may have introduced what rules out uniform sampling.
+		if let Some(obstacle) =
+			self.uniform_sampling.then(|| self.uniform_obstacle()).flatten()
+		{
+			notes.push(format!(
+				"The shuffle wasn't uniform, because {}.",
+				obstacle
+			));
+		}
+		if skewed
+		{
+			notes.push(
+				"The shuffle wasn't uniform, because the uniform sampler ran \
+				out of mixing steps.".to_string()
+			);
+		}
+		if !notes.is_empty()
+		{
+			self.status = Some(StatusMessage {
+				text: notes.join(" "),
+				is_error: false
+			});
+		}
+		self.record_order(&best_order);
+		self.log_shuffle(seed, &indices);
+		self.shuffled_section_indices = Some(best_order);
+		self.presented_section_indices = Some(indices);
+		self.shuffled_sections = Some(shuffled);
+		self.transitions = Some(best_transitions);
+	}
+
+	/// Prepare to draw random orderings of the sections that honor the
+	/// [constraints](Self::constraints), given their acyclic constraint graph,
+	/// for [shuffling](Self::shuffle) or the
+	/// [position&#32;heatmap](HeatmapJob). Answer `None`, reporting why, if
+	/// the constraints conflict.
+	fn shuffle_plan(
+		&mut self,
+		graph: DiGraph<usize, (), usize>
+	) -> Option<ShufflePlan>
+	{
+		// Shuffle only the blocks, e.g., the stories of an anthology, if any.
+		let blocks = self.section_blocks();
+		let block_graph = match blocks
+		{
+			Some(ref blocks) => Some(self.block_graph(&graph, blocks)?),
+			None => None
+		};
+		if let Some(conflict) =
+			self.pin_conflict().or_else(|| self.offset_conflict())
+		{
+			self.report_failure(format!("Couldn't shuffle: {}", conflict));
+			return None
+		}
+		let policies = blocks.as_ref()
+			.map(|blocks| self.block_policies(blocks))
+			.unwrap_or_default();
+		let pins = self.pins();
+		let pinned = pins.iter().any(Option::is_some);
+		let parities = self.parities();
+		let parity = parities.iter().any(Option::is_some);
+		let spaced = self.constraints.iter().any(|c|
+				c.offset_from.is_some()
+					|| !c.near.is_empty()
+					|| !c.apart.is_empty()
+					|| !c.not_adjacent.is_empty()
+			)
+			|| self.tag_constraints.iter()
+				.any(|t| t.kind == TagConstraintKind::NeverAdjacent);
+		let repeated = self.constraints.iter().any(|c| c.repeats > 1);
+		let groups = self.rotation_in_effect();
+		// Random choices can paint the pinned positions into a corner, strand
+		// sections far from their neighbors, or leave no room for repeats, so
+		// try a few times before giving up on an ordering.
+		let retries = if pinned
+			|| parity
+			|| spaced
+			|| repeated
+			|| blocks.is_some()
+		{
+			MAXIMUM_PLACEMENT_RETRIES
+		}
+		else { 1 };
+		// Prepare for uniform sampling only if no other sampler applies.
+		let sampler = (self.uniform_sampling
+			&& self.uniform_obstacle().is_none())
+			.then(|| UniformSampler::new(graph.clone()));
+		Some(ShufflePlan {
+			graph,
+			blocks,
+			block_graph,
+			policies,
+			pins,
+			parities,
+			omitted: self.constraints.iter().map(|c| c.omit).collect(),
+			preferences: place_preferences(&self.constraints),
+			groups,
+			tag_count: self.rotation_tags().len(),
+			rotation_mode: self.rotation_mode,
+			sampler,
+			constraints: self.constraints.clone(),
+			tag_constraints: self.tag_constraints.clone(),
+			retries
+		})
+	}
+
+	/// Append a [record](ShuffleRecord) of the reordering drawn from the
+	/// specified seed, with the specified resulting ordering, as
+	/// **zero-based** section indices, to the
+	/// [shuffle&#32;log](Self::shuffle_log).
+	fn log_shuffle(&mut self, seed: u64, indices: &[usize])
+	{
+		self.shuffle_log.push(ShuffleRecord {
+			timestamp: iso_timestamp(unix_time()),
+			seed,
+			scenario: self.scenarios[self.active_scenario].name.clone(),
+			order: indices.iter().map(|index| index + 1).collect(),
+			section_ids: indices.iter()
+				.filter_map(|index| self.section_ids.get(*index).cloned())
+				.collect()
+		});
+		let excess = self.shuffle_log.len()
+			.saturating_sub(MAXIMUM_SHUFFLE_LOG_LENGTH);
+		self.shuffle_log.drain(.. excess);
+	}
+
+	/// [Detect](detect_matter) the front and back matter of the manuscript,
+	/// and mark the constraints of its sections accordingly.
+	fn detect_matter(&mut self)
+	{
+		let matter = detect_matter(&self.original_sections);
+		for (c, matter) in self.constraints.iter_mut().zip(&matter)
+		{
+			c.matter = *matter;
+		}
+		let count = |kind: Matter| match matter.iter()
+			.filter(|m| **m == kind)
+			.count()
+		{
+			1 => "1 section".to_string(),
+			n => format!("{} sections", n)
+		};
+		let text = if matter.iter().all(|m| *m == Matter::Body)
+		{
+			"Found no front or back matter.".to_string()
+		}
+		else
+		{
+			format!(
+				"Found front matter ({}) and back matter ({}).",
+				count(Matter::Front),
+				count(Matter::Back)
+			)
+		};
+		tracing::info!("{}", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Answer the [rotation](Self::rotation) tags, in rotation order.
+	fn rotation_tags(&self) -> Vec<&str>
+	{
+		self.rotation.split(',')
+			.map(str::trim)
+			.filter(|tag| !tag.is_empty())
+			.collect()
+	}
+
+	/// Answer the **zero-based** [rotation](Self::rotation) group of each
+	/// section, if any, i.e., the position of its first rotation tag among the
+	/// [rotation&#32;tags](Self::rotation_tags). Tags are compared without
+	/// regard to case.
+	fn rotation_groups(&self) -> Vec<Option<usize>>
+	{
+		let tags = self.rotation_tags();
+		self.constraints.iter()
+			.map(|c| c.tags().find_map(|tag|
+				tags.iter().position(|t| t.eq_ignore_ascii_case(tag))
+			))
+			.collect()
+	}
+
+	/// Answer the [rotation&#32;groups](Self::rotation_groups) of the
+	/// sections, but only if the [rotation](Self::rotation) actually governs
+	/// shuffling, i.e., if it names enough tags for its
+	/// [mode](Self::rotation_mode) and the sections don't shuffle as
+	/// [blocks](Self::section_blocks).
+	fn rotation_in_effect(&self) -> Option<Vec<Option<usize>>>
+	{
+		let minimum_tag_count = match self.rotation_mode
+		{
+			RotationMode::Strict => 2,
+			RotationMode::NoRepeats => 1
+		};
+		(self.section_blocks().is_none()
+			&& self.rotation_tags().len() >= minimum_tag_count)
+			.then(|| self.rotation_groups())
+	}
+
+	/// Explain why [uniform&#32;sampling](Self::uniform_sampling) can't apply
+	/// to the current constraints, if it can't: another sampler must honor
+	/// the [blocks](Self::section_blocks), the
+	/// [rotation](Self::rotation_in_effect), or the [pins](Self::pins) and
+	/// [parities](Self::parities), or [tethering](tether) would skew the
+	/// sample.
+	fn uniform_obstacle(&self) -> Option<&'static str>
+	{
+		if self.section_blocks().is_some()
+		{
+			Some("the sections shuffle as stories, chapters, or groups")
+		}
+		else if self.rotation_in_effect().is_some()
+		{
+			Some("the rotation decides the order of the tagged sections")
+		}
+		else if self.pins().iter().any(Option::is_some)
+		{
+			Some("some sections are fixed or pinned in place")
+		}
+		else if self.parities().iter().any(Option::is_some)
+		{
+			Some("some sections may only occupy odd or even positions")
+		}
+		else if self.constraints.iter().any(|c| c.offset_from.is_some())
+		{
+			Some("some sections must land an exact offset after others")
+		}
+		else { None }
+	}
+
+	/// Explain why the sizes of the [rotation](Self::rotation) groups make
+	/// perfect rotation impossible, if they do. Perfect rotation requires that
+	/// the groups differ in size by at most one section, with the larger
+	/// groups first. If instead
+	/// [no&#32;tag&#32;may&#32;repeat](RotationMode::NoRepeats), explain why
+	/// some group is too large to keep its sections apart.
+	fn rotation_problem(&self) -> Option<String>
+	{
+		let tags = self.rotation_tags();
+		let mut sizes = vec![0; tags.len()];
+		for group in self.rotation_groups().into_iter().flatten()
+		{
+			sizes[group] += 1;
+		}
+		if self.rotation_mode == RotationMode::NoRepeats
+		{
+			let count = self.constraints.len();
+			return tags.iter()
+				.zip(&sizes)
+				.find(|(_, size)| 2 * **size > count + 1)
+				.map(|(tag, size)| format!(
+					"The sections tagged {} can't all be kept apart: there \
+					are {} of them, but only {} to separate them.",
+					tag,
+					size,
+					others(count - size)
+				))
+		}
+		if tags.len() < 2
+		{
+			return None
+		}
+		let largest = sizes.iter().max().copied().unwrap_or_default();
+		let smallest = sizes.iter().min().copied().unwrap_or_default();
+		let perfect = largest - smallest <= 1
+			&& sizes.windows(2).all(|pair| pair[0] >= pair[1]);
+		(!perfect).then(|| format!(
+			"Perfect rotation is impossible with these tag counts: {}. Once a \
+			tag runs out of sections, the rotation continues without it.",
+			tags.iter()
+				.zip(&sizes)
+				.map(|(tag, size)| format!("{} ({})", tag, size))
+				.collect::<Vec<_>>()
+				.join(", ")
+		))
+	}
+
+	/// Answer the **zero-based** block of each section, if the sections
+	/// [shuffle](Self::shuffle) as blocks rather than individually: the
+	/// stories of an [anthology](Self::anthology), or else the
+	/// [chapters](Constraints::chapter) and [groups](Constraints::group), if
+	/// any. Each section in neither is a block unto itself. Blocks are
+	/// numbered in order of their first sections.
+	fn section_blocks(&self) -> Option<Vec<usize>>
+	{
+		if self.anthology
+		{
+			return Some(self.section_stories.clone())
+		}
+		if self.constraints.iter()
+			.all(|c| c.group().is_none() && c.chapter().is_none())
+		{
+			return None
+		}
+		// Key each block by whether it's a chapter, so that a chapter and a
+		// group may share a name.
+		let mut groups = HashMap::<(bool, &str), usize>::new();
+		let mut count = 0;
+		Some(
+			self.constraints.iter()
+				.map(|c| {
+					let fresh = count;
+					let key = c.chapter()
+						.map(|chapter| (true, chapter))
+						.or_else(|| c.group().map(|group| (false, group)));
+					let block = match key
+					{
+						Some(key) => *groups.entry(key).or_insert(fresh),
+						None => fresh
+					};
+					if block == fresh
+					{
+						count += 1;
+					}
+					block
+				})
+				.collect()
+		)
+	}
+
+	/// Answer the [policy](ChapterPolicy) of each of the specified
+	/// [blocks](Self::section_blocks). Blocks other than
+	/// [chapters](Self::chapters) move as units.
+	fn block_policies(&self, blocks: &[usize]) -> Vec<ChapterPolicy>
+	{
+		let count = blocks.iter().max().map_or(0, |last| last + 1);
+		let mut policies = vec![ChapterPolicy::Unit; count];
+		if !self.anthology
+		{
+			for (c, block) in self.constraints.iter().zip(blocks)
+			{
+				if let Some(chapter) = c.chapter()
+				{
+					policies[*block] = chapter_policy(&self.chapters, chapter);
+				}
+			}
+		}
+		policies
+	}
+
+	/// Derive the constraint graph of the specified
+	/// [blocks](Self::section_blocks) from the specified constraint graph of
+	/// the sections. Answer `None`, explaining why, if the constraints would
+	/// require any block to be broken up.
+	fn block_graph(
+		&mut self,
+		graph: &DiGraph<usize, (), usize>,
+		blocks: &[usize]
+	) -> Option<DiGraph<usize, (), usize>>
+	{
+		if !self.anthology
+		{
+			let both = self.constraints.iter()
+				.enumerate()
+				.find_map(|(index, c)| Some((index, c.chapter()?, c.group()?)));
+			if let Some((index, chapter, group)) = both
+			{
+				let text = format!(
+					"Couldn't shuffle: §{} belongs to both the chapter {} and \
+					the group {}, but a section can move with only one of \
+					them.",
+					index + 1,
+					chapter,
+					group
+				);
+				self.report_failure(text);
+				return None
+			}
+		}
+		let loose = self.block_policies(blocks).iter()
+			.map(|policy| policy.shuffles_sections())
+			.collect::<Vec<_>>();
+		let block_graph = match story_graph(graph, blocks, &loose)
+		{
+			Ok(block_graph) => block_graph,
+			Err((from, to)) =>
+			{
+				let text = if self.anthology
+				{
+					format!(
+						"Couldn't shuffle the anthology: §{} must come before \
+						§{}, but the sections of a story can't be reordered.",
+						from + 1,
+						to + 1
+					)
+				}
+				else if let Some(chapter) = self.constraints[from].chapter()
+				{
+					format!(
+						"Couldn't shuffle: §{} must come before §{}, but the \
+						sections of the chapter {} can't be reordered, \
+						because it moves as a unit.",
+						from + 1,
+						to + 1,
+						chapter
+					)
+				}
+				else
+				{
+					format!(
+						"Couldn't shuffle: §{} must come before §{}, but the \
+						sections of the group {} can't be reordered.",
+						from + 1,
+						to + 1,
+						self.constraints[from].group().unwrap_or_default()
+					)
+				};
+				self.report_failure(text);
+				return None
+			}
+		};
+		if let Err(cycle) = toposort(&block_graph, None)
+		{
+			let block = cycle.node_id().index();
+			let text = if self.anthology
+			{
+				format!(
+					"Couldn't shuffle the anthology: the constraints require \
+					story {} to come before itself.",
+					block + 1
+				)
+			}
+			else
+			{
+				let first = blocks.iter()
+					.position(|b| *b == block)
+					.unwrap_or_default();
+				format!(
+					"Couldn't shuffle: the constraints require the block that \
+					begins with §{} to come before itself.",
+					first + 1
+				)
+			};
+			self.report_failure(text);
+			return None
+		}
+		Some(block_graph)
+	}
+
+	/// Explain the first placement constraint, i.e., a locked
+	/// [position](locked_positions) or a spacing requirement of a
+	/// [section](first_stray) or [tag](first_tag_stray), that the specified
+	/// ordering, given as **zero-based** section indices, violates, if any.
+	/// Only the sections that aren't [omitted](Constraints::omit) occupy
+	/// positions.
+	fn placement_violation(&self, order: &[usize]) -> Option<String>
+	{
+		let order = &self.without_omissions(order.to_vec());
+		if let Some((index, position)) = first_misplaced(order, &self.pins())
+		{
+			return Some(format!(
+				"§{} must be at position {}.",
+				index + 1,
+				position
+			))
+		}
+		if let Some((index, parity)) = first_misparity(order, &self.parities())
+		{
+			return Some(format!(
+				"§{} must be at an {} position.",
+				index + 1,
+				parity.name()
+			))
+		}
+		first_stray(order, &self.constraints).or_else(||
+			first_tag_stray(order, &self.tag_constraints, &self.constraints)
+		)
+	}
+
+	/// Explain the first way in which the specified ordering, given as
+	/// **zero-based** section indices, breaks up a
+	/// [block](Self::section_blocks), reorders the sections of a block that
+	/// doesn't [shuffle&#32;within](ChapterPolicy::shuffles_sections), moves
+	/// a block that doesn't [move](ChapterPolicy::moves), or breaks the
+	/// [rotation](Self::rotation_in_effect), if any. The ordering may omit
+	/// sections, e.g., for an [excerpt](Self::excerpt), in which case only the
+	/// sections present are considered.
+	fn grouping_violation(&self, order: &[usize]) -> Option<String>
+	{
+		if let Some(blocks) = self.section_blocks()
+		{
+			let policies = self.block_policies(&blocks);
+			let describe = |index: usize|
+			{
+				let c = &self.constraints[index];
+				match (self.anthology, c.chapter(), c.group())
+				{
+					(true, ..) => "their story".to_string(),
+					(_, Some(chapter), _) => format!("the chapter {}", chapter),
+					(_, _, Some(group)) => format!("the group {}", group),
+					_ => "their block".to_string()
+				}
+			};
+			// The latest section of each block placed so far.
+			let mut latest = vec![None; policies.len()];
+			let mut sequence = Vec::<usize>::new();
+			for index in order
+			{
+				let block = blocks[*index];
+				if let Some(previous) = latest[block]
+				{
+					if sequence.last() != Some(&block)
+					{
+						return Some(format!(
+							"§{} and §{} must stay together in {}.",
+							previous + 1,
+							index + 1,
+							describe(*index)
+						))
+					}
+					if !policies[block].shuffles_sections() && previous > *index
+					{
+						return Some(format!(
+							"§{} must stay before §{} in {}.",
+							index + 1,
+							previous + 1,
+							describe(*index)
+						))
+					}
+				}
+				else
+				{
+					sequence.push(block);
+				}
+				latest[block] = Some(*index);
+			}
+			// A block that doesn't move keeps its rank among the blocks.
+			for (rank, block) in sequence.iter().enumerate()
+			{
+				let home = sequence.iter().filter(|b| *b < block).count();
+				if !policies[*block].moves() && rank != home
+				{
+					let index = blocks.iter().position(|b| b == block)?;
+					return Some(format!(
+						"{} must keep its place.",
+						describe(index).replacen("the", "The", 1)
+					))
+				}
+			}
+		}
+		let groups = self.rotation_in_effect()?;
+		let tags = self.rotation_tags();
+		match self.rotation_mode
+		{
+			RotationMode::Strict =>
+			{
+				let mut remaining = vec![0; tags.len()];
+				for group in order.iter().filter_map(|index| groups[*index])
+				{
+					remaining[group] += 1;
+				}
+				// The group whose turn it is, skipping any that have run out.
+				let mut turn = 0;
+				for index in order
+				{
+					let Some(group) = groups[*index] else { continue };
+					while remaining[turn] == 0
+					{
+						turn = (turn + 1) % tags.len();
+					}
+					if group != turn
+					{
+						return Some(format!(
+							"§{} breaks the rotation, because a section \
+							tagged {} must come first.",
+							index + 1,
+							tags[turn]
+						))
+					}
+					remaining[group] -= 1;
+					turn = (group + 1) % tags.len();
+				}
+				None
+			},
+			RotationMode::NoRepeats => order.windows(2)
+				.find(|pair| groups[pair[0]].is_some()
+					&& groups[pair[0]] == groups[pair[1]]
+				)
+				.map(|pair| format!(
+					"§{} and §{} are both tagged {}, so they can't be \
+					adjacent.",
+					pair[0] + 1,
+					pair[1] + 1,
+					groups[pair[0]].map_or("", |group| tags[group])
+				))
+		}
+	}
+
+	/// Answer the **one-based** [position](locked_positions) to which each
+	/// section is locked, if any.
+	fn pins(&self) -> Vec<Option<usize>>
+	{
+		locked_positions(&self.constraints)
+	}
+
+	/// Answer the [parity](Constraints::parity) of the positions that each
+	/// section may occupy, if restricted. [Omitted](Constraints::omit)
+	/// sections occupy no position, so they're never restricted.
+	fn parities(&self) -> Vec<Option<Parity>>
+	{
+		self.constraints.iter()
+			.map(|c| c.parity.filter(|_| !c.omit))
+			.collect()
+	}
+
+	/// Answer the number of sections that aren't
+	/// [omitted](Constraints::omit), i.e., the number of positions that the
+	/// [pins](Self::pins) and [parities](Self::parities) may claim.
+	fn shown_count(&self) -> usize
+	{
+		self.constraints.iter().filter(|c| !c.omit).count()
+	}
+
+	/// Answer an explanation if the locked [positions](locked_positions)
+	/// and [parities](Constraints::parity) can't all be honored at once, i.e.,
+	/// because two sections are locked to the same position, because a
+	/// section is pinned to a position beyond the end or of the wrong parity,
+	/// or because too many sections require positions of the same parity.
+	fn pin_conflict(&self) -> Option<String>
+	{
+		let count = self.shown_count();
+		let parities = self.parities();
+		let mut occupants = vec![None; count];
+		for (index, pin) in self.pins().into_iter().enumerate()
+		{
+			let position = match pin
+			{
+				Some(pin) if pin == 0 || pin > count => return Some(format!(
+					"§{} is pinned to position {}, but there are only {} \
+					sections.",
+					index + 1,
+					pin,
+					count
+				)),
+				Some(pin) => pin - 1,
+				None => continue
+			};
+			if let Some(other) = occupants[position].replace(index)
+			{
+				return Some(format!(
+					"§{} and §{} can't both occupy position {}.",
+					other + 1,
+					index + 1,
+					position + 1
+				))
+			}
+			if let Some(parity) = parities[index]
+				.filter(|parity| !parity.admits(position + 1))
+			{
+				return Some(format!(
+					"§{} is locked to position {}, which isn't {}.",
+					index + 1,
+					position + 1,
+					parity.name()
+				))
+			}
+		}
+		for parity in Parity::ALL
+		{
+			let demand = parities.iter()
+				.filter(|p| **p == Some(parity))
+				.count();
+			let capacity = parity.capacity(count);
+			if demand > capacity
+			{
+				return Some(format!(
+					"{} sections must occupy {} positions, but there are only \
+					{}.",
+					demand,
+					parity.name(),
+					capacity
+				))
+			}
+		}
+		None
+	}
+
+	/// Answer an explanation if the exact [offsets](Constraints::offset)
+	/// can't all be honored at once, i.e., because an offset reaches beyond
+	/// the end, because it contradicts the locked
+	/// [positions](locked_positions) or [parities](Constraints::parity)
+	/// of its sections, or because two sections must land at the same offset
+	/// from the same section. An offset from or to an
+	/// [omitted](Constraints::omit) section never applies.
+	fn offset_conflict(&self) -> Option<String>
+	{
+		let count = self.shown_count();
+		let pins = self.pins();
+		let parities = self.parities();
+		let mut claimed = vec![];
+		for (index, c) in self.constraints.iter().enumerate()
+		{
+			let Some(anchor) = c.offset_from
+				.and_then(|n| n.checked_sub(1))
+				.filter(|anchor| !c.omit && !self.constraints[*anchor].omit)
+			else
+			{
+				continue
+			};
+			let offset = places(c.offset);
+			if c.offset >= count
+			{
+				return Some(format!(
+					"§{} must land exactly {} after §{}, but there are only \
+					{} sections.",
+					index + 1,
+					offset,
+					anchor + 1,
+					count
+				))
+			}
+			if let (Some(here), Some(there)) = (pins[index], pins[anchor])
+			{
+				if here != there + c.offset
+				{
+					return Some(format!(
+						"§{} and §{} are locked to positions {} and {}, so \
+						§{} can't land exactly {} after §{}.",
+						anchor + 1,
+						index + 1,
+						there,
+						here,
+						index + 1,
+						offset,
+						anchor + 1
+					))
+				}
+			}
+			if let (Some(here), Some(there)) =
+				(parities[index], parities[anchor])
+			{
+				if (here == there) != (c.offset % 2 == 0)
+				{
+					return Some(format!(
+						"§{} occupies {} positions and §{} occupies {} \
+						positions, so §{} can't land exactly {} after §{}.",
+						anchor + 1,
+						there.name(),
+						index + 1,
+						here.name(),
+						index + 1,
+						offset,
+						anchor + 1
+					))
+				}
+			}
+			if let Some((_, _, other)) = claimed.iter()
+				.find(|(a, o, _)| *a == anchor && *o == c.offset)
+			{
+				return Some(format!(
+					"§{} and §{} can't both land exactly {} after §{}.",
+					other + 1,
+					index + 1,
+					offset,
+					anchor + 1
+				))
+			}
+			claimed.push((anchor, c.offset, index));
+		}
+		None
+	}
+
+	/// Reverse the [shuffled&#32;sections](Self::shuffled_sections), or the
+	/// [original&#32;sections](Self::original_sections) if the current
+	/// manuscript hasn't been shuffled, e.g., to preview a reverse chronology.
+	/// Refuse if the reversed order would violate any
+	/// [constraint](Self::constraints), explaining why.
+	fn reverse(&mut self, graph: DiGraph<usize, (), usize>)
+	{
+		let count = self.original_sections.len();
+		let mut indices = self.current_order()
+			.unwrap_or_else(|| (0 .. count).collect());
+		// Leave the front and back matter where they are.
+		let matter = |index: &&usize| self.constraints[**index].matter;
+		let front = indices.iter()
+			.take_while(|index| matter(index) == Matter::Front)
+			.count();
+		let back = indices[front ..].iter()
+			.rev()
+			.take_while(|index| matter(index) == Matter::Back)
+			.count();
+		// Reverse the blocks, e.g., the groups and chapters, as units, keeping
+		// the order of the sections within each.
+		let blocks = self.section_blocks()
+			.unwrap_or_else(|| (0 .. count).collect());
+		let mut runs = Vec::<Vec<usize>>::new();
+		for index in &indices[front .. count - back]
+		{
+			match runs.last_mut()
+			{
+				Some(run) if blocks[run[0]] == blocks[*index] =>
+					run.push(*index),
+				_ => runs.push(vec![*index])
+			}
+		}
+		indices.splice(
+			front .. count - back,
+			runs.into_iter().rev().flatten()
+		);
+		let stray = self.placement_violation(&indices)
+			.or_else(|| self.grouping_violation(&indices));
+		match first_violation(&graph, &indices)
+		{
+			Some((from, to)) => self.report_failure(format!(
+				"Couldn't reverse: §{} must come before §{}.",
+				from + 1,
+				to + 1
+			)),
+			None if stray.is_some() => self.report_failure(format!(
+				"Couldn't reverse: {}",
+				stray.unwrap_or_default()
+			)),
+			None =>
+			{
+				let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+				match self.present_order(&indices, &mut rng)
+				{
+					Ok(presented) =>
+					{
+						tracing::info!("Reversed {} sections.", count);
+						self.adopt_order(indices, presented);
+					},
+					Err(problem) => self.report_failure(
+						format!("Couldn't reverse: {}", problem)
+					)
+				}
+			}
+		}
+	}
+
+	/// Sort the [sections](Self::original_sections) by the chosen
+	/// [attribute](Self::sort_key), as far as the
+	/// [constraints](Self::constraints) allow. Ties keep their original order
+	/// unless they should be [shuffled](Self::shuffle_ties). Refuse if the
+	/// sorted order would break up a [block](Self::section_blocks) or the
+	/// [rotation](Self::rotation_in_effect), explaining why.
+	fn sort(&mut self, graph: DiGraph<usize, (), usize>)
+	{
+		let key = self.sort_key;
+		// Break ties by a random ranking if requested, or else by original
+		// position.
+		let mut ranks = (0 .. self.original_sections.len()).collect::<Vec<_>>();
+		if self.shuffle_ties
+		{
+			ranks.shuffle(&mut self.rng);
+		}
+		let compare = |a: usize, b: usize|
+			key.compare(&self.original_sections, &self.constraints, a, b)
+				.then(ranks[a].cmp(&ranks[b]));
+		let indices = sorted_order(graph, compare);
+		if let Some(stray) = self.placement_violation(&indices)
+			.or_else(|| self.grouping_violation(&indices))
+		{
+			self.report_failure(format!("Couldn't sort: {}", stray));
+			return
+		}
+		let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+		let presented = match self.present_order(&indices, &mut rng)
+		{
+			Ok(presented) => presented,
+			Err(problem) =>
+			{
+				self.report_failure(format!("Couldn't sort: {}", problem));
+				return
+			}
+		};
+		let mut unconstrained =
+			(0 .. self.original_sections.len()).collect::<Vec<_>>();
+		unconstrained.sort_by(|a, b| compare(*a, *b));
+		tracing::info!("Sorted {} sections by {}.", indices.len(), key.name());
+		self.status = (indices != unconstrained).then(|| StatusMessage {
+			text: format!(
+				"Sorted by {}, as far as the constraints allow.",
+				key.name().to_lowercase()
+			),
+			is_error: false
+		});
+		self.adopt_order(indices, presented);
+	}
+
+	/// Select a random subset of the [sections](Self::original_sections) of the
+	/// [chosen&#32;length](Self::excerpt_length), including every section that
+	/// [must&#32;be&#32;included](Constraints::must_include), and arrange them
+	/// in an order that honors the [constraints](Self::constraints), e.g., to
+	/// produce a reading-length excerpt of a longer work. Constraints that
+	/// relate chosen sections only through omitted ones are still honored, and
+	/// the chosen sections of each [block](Self::section_blocks) stay together
+	/// and observe the [rotation](Self::rotation_in_effect).
+	fn excerpt(&mut self, graph: DiGraph<usize, (), usize>)
+	{
+		let count = self.original_sections.len();
+		let length = self.excerpt_length.clamp(1, count);
+		let (required, optional): (Vec<usize>, Vec<usize>) = (0 .. count)
+			.filter(|index| !self.constraints[*index].omit)
+			.partition(|index| self.constraints[*index].must_include);
+		if required.len() > length
+		{
+			self.report_failure(format!(
+				"Couldn't excerpt: {} sections must be included, but the \
+				excerpt only has room for {}.",
+				required.len(),
+				length
+			));
+			return
+		}
+		let blocks = self.section_blocks();
+		let block_graph = match blocks
+		{
+			Some(ref blocks) => match self.block_graph(&graph, blocks)
+			{
+				Some(block_graph) => Some(block_graph),
+				None => return
+			},
+			None => None
+		};
+		let policies = blocks.as_ref()
+			.map(|blocks| self.block_policies(blocks))
+			.unwrap_or_default();
+		let groups = self.rotation_in_effect();
+		let tag_count = self.rotation_tags().len();
+		let preferences = place_preferences(&self.constraints);
+		let seed = self.rng.gen::<u64>();
+		let mut rng = StdRng::seed_from_u64(seed);
+		// Leaving sections out can break up a block or the rotation, so try a
+		// few selections.
+		let indices = (0 .. MAXIMUM_PLACEMENT_RETRIES).find_map(|_| {
+			let mut optional = optional.clone();
+			optional.shuffle(&mut rng);
+			let mut chosen = vec![false; count];
+			for index in required.iter().chain(&optional).take(length)
+			{
+				chosen[*index] = true;
+			}
+			// Shuffle the whole manuscript and then keep only the chosen
+			// sections, which preserves every constraint among them, even
+			// indirect ones.
+			let order = match (&blocks, &block_graph, &groups)
+			{
+				(Some(blocks), Some(block_graph), _) => random_block_order(
+					&graph,
+					block_graph,
+					blocks,
+					&policies,
+					&preferences,
+					&mut rng
+				)?,
+				(_, _, Some(groups)) => match self.rotation_mode
+				{
+					RotationMode::Strict => random_rotation(
+						graph.clone(),
+						groups,
+						tag_count,
+						&mut rng
+					)?,
+					RotationMode::NoRepeats => random_alternation(
+						graph.clone(),
+						groups,
+						tag_count,
+						&mut rng
+					)?
+				},
+				_ => random_order(graph.clone(), &preferences, &mut rng)
+			};
+			let indices = order.into_iter()
+				.filter(|index| chosen[*index])
+				.collect::<Vec<_>>();
+			self.grouping_violation(&indices).is_none().then_some(indices)
+		});
+		let Some(indices) = indices
+		else
+		{
+			self.report_failure(format!(
+				"Couldn't excerpt: no selection of {} sections was found that \
+				keeps the groups and chapters together and honors the \
+				rotation.",
+				length
+			));
+			return
+		};
+		let presented = match self.present_order(&indices, &mut rng)
+		{
+			Ok(presented) => presented,
+			Err(problem) =>
+			{
+				self.report_failure(format!("Couldn't excerpt: {}", problem));
+				return
+			}
+		};
+		tracing::info!("Excerpted {} of {} sections.", indices.len(), count);
+		self.log_shuffle(seed, &presented);
+		self.adopt_order(indices, presented);
+	}
+
+	/// Step from the current reordering to the next valid ordering, or to the
+	/// previous one if not `forward`, in a fixed sequence that begins with
+	/// the lexicographically least ordering of the section numbers, skipping
+	/// the orderings that violate the [placement](Self::placement_violation)
+	/// requirements, break up a [block](Self::section_blocks), or break the
+	/// [rotation](Self::rotation_in_effect), as well as those that differ
+	/// only in the places of [omitted](Constraints::omit) sections, so that
+	/// the writer can review every possibility in turn. The further
+	/// appearances of the [repeated](Constraints::repeats) sections are placed
+	/// at random, so they don't distinguish orderings.
+	/// Stepping past either end wraps around to the other.
+	fn step_order(&mut self, graph: DiGraph<usize, (), usize>, forward: bool)
+	{
+		let start = self.current_order()
+			.filter(|start| first_violation(&graph, start).is_none());
+		// Orderings that differ only in the places of omitted sections look
+		// the same, so consider only their canonical forms.
+		let shown = start.clone().map(|start| self.without_omissions(start));
+		let omitted = self.constraints.iter()
+			.map(|c| c.omit)
+			.collect::<Vec<_>>();
+		let omitting = omitted.contains(&true);
+		let mut order = start.clone();
+		let mut wrapped = false;
+		let mut repeat_problem = None;
+		let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+		for _ in 0 .. MAXIMUM_ORDERING_STEPS
+		{
+			let next = order.as_deref()
+				.and_then(|order| neighboring_order(&graph, order, forward));
+			let next = match next
+			{
+				Some(next) => next,
+				None =>
+				{
+					wrapped |= order.is_some();
+					extreme_completion(&graph, vec![], forward)
+				}
+			};
+			if start.as_ref() == Some(&next)
+			{
+				break
+			}
+			if shown.as_ref() != Some(&self.without_omissions(next.clone()))
+				&& (!omitting
+					|| with_omissions_early(&graph, &next, &omitted) == next)
+				&& self.placement_violation(&next).is_none()
+				&& self.grouping_violation(&next).is_none()
+			{
+				match self.present_order(&next, &mut rng)
+				{
+					Ok(presented) =>
+					{
+						let text = match (wrapped, forward)
+						{
+							(false, true) =>
+								"Stepped to the next valid ordering",
+							(false, false) =>
+								"Stepped to the previous valid ordering",
+							(true, true) =>
+								"Wrapped around to the first ordering",
+							(true, false) =>
+								"Wrapped around to the last ordering"
+						}.to_string();
+						tracing::info!("{}.", text);
+						self.status =
+							Some(StatusMessage { text, is_error: false });
+						self.adopt_order(next, presented);
+						return
+					},
+					Err(problem) => repeat_problem = Some(problem)
+				}
+			}
+			order = Some(next);
+		}
+		if let Some(problem) = repeat_problem
+		{
+			self.report_failure(format!(
+				"Couldn't step through the orderings: {}",
+				problem
+			));
+			return
+		}
+		self.report_failure(format!(
+			"Couldn't step through the orderings: no other ordering within {} \
+			steps honors the constraints",
+			MAXIMUM_ORDERING_STEPS
+		));
+	}
+
+	/// Answer the complete
+	/// [shuffled&#32;section&#32;indices](Self::shuffled_section_indices), but
+	/// only if they still describe the current
+	/// [sections](Self::original_sections), i.e., if the manuscript hasn't
+	/// changed since the last reordering, and the reordering includes every
+	/// section, i.e., it isn't an [excerpt](Self::excerpt).
+	fn current_order(&self) -> Option<Vec<usize>>
+	{
+		let count = self.original_sections.len();
+		self.presented_order()?;
+		self.shuffled_section_indices.clone()
+			.filter(|indices| fits_sections(indices, count))
+	}
+
+	/// Answer the
+	/// [presented&#32;section&#32;indices](Self::presented_section_indices),
+	/// but only if they still describe the current
+	/// [sections](Self::original_sections), i.e., if the manuscript hasn't
+	/// changed since the last reordering.
+	fn presented_order(&self) -> Option<Vec<usize>>
+	{
+		let indices = self.presented_section_indices.as_ref()?;
+		let shuffled = self.shuffled_sections.as_ref()?;
+		let current = indices.len() == shuffled.len()
+			&& indices.iter().zip(shuffled).all(|(index, section)|
+				self.original_sections.get(*index) == Some(section)
+			);
+		current.then(|| indices.clone())
+	}
+
+	/// Answer the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, less the sections that are
+	/// [omitted](Constraints::omit).
+	fn without_omissions(&self, indices: Vec<usize>) -> Vec<usize>
+	{
+		indices.into_iter()
+			.filter(|index| !self.constraints[*index].omit)
+			.collect()
+	}
+
+	/// Answer the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, as it's presented and assembled, i.e.,
+	/// [without](Self::without_omissions) the omitted sections, and with the
+	/// repeated sections [repeated](Self::repeat). Answer an explanation if
+	/// there's no room for the repeats.
+	fn present_order(
+		&self,
+		order: &[usize],
+		rng: &mut impl Rng
+	) -> Result<Vec<usize>, String>
+	{
+		self.repeat(self.without_omissions(order.to_vec()), rng)
+	}
+
+	/// Answer the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, with the further appearances of each
+	/// [repeated](Constraints::repeats) section inserted after its first, each
+	/// at least its [gap](Constraints::repeat_gap) from the one before. The
+	/// insertions never split a [block](Self::section_blocks) or a group,
+	/// never intrude upon the back matter or the sections
+	/// [locked](locked_positions) at the very end, which stay at the end,
+	/// and never displace any other section whose position is locked or
+	/// [restricted](Constraints::parity). The spacing requirements must still
+	/// hold among the first appearances. Answer an explanation if there's no
+	/// room.
+	fn repeat(
+		&self,
+		order: Vec<usize>,
+		rng: &mut impl Rng
+	) -> Result<Vec<usize>, String>
+	{
+		let repeated = order.iter()
+			.copied()
+			.filter(|index| self.constraints[*index].repeats > 1)
+			.collect::<Vec<_>>();
+		if repeated.is_empty()
+		{
+			return Ok(order)
+		}
+		let blocks = self.section_blocks();
+		let joined = |a: usize, b: usize|
+			blocks.as_ref().map_or(false, |blocks| blocks[a] == blocks[b])
+				|| self.constraints[a].group().is_some()
+					&& self.constraints[a].group()
+						== self.constraints[b].group();
+		let pins = self.pins();
+		let parities = self.parities();
+		let anchored = |i: &usize| pins[*i].is_some() || parities[*i].is_some();
+		let trailing = |i: &&usize|
+			self.constraints[**i].matter == Matter::Back || pins[**i].is_some();
+		let mut problem = String::new();
+		'attempts: for _ in 0 .. MAXIMUM_PLACEMENT_RETRIES
+		{
+			let mut expanded = order.clone();
+			for index in &repeated
+			{
+				let c = &self.constraints[*index];
+				for _ in 1 .. c.repeats
+				{
+					let latest = expanded.iter()
+						.rposition(|i| i == index)
+						.unwrap_or_default();
+					let end = expanded.len()
+						- expanded.iter().rev().take_while(trailing).count();
+					let start = expanded[.. end].iter()
+						.rposition(anchored)
+						.map_or(0, |position| position + 1)
+						.max(latest + c.repeat_gap + 1);
+					let slots = (start ..= end)
+						.filter(|slot| *slot == expanded.len()
+							|| !joined(expanded[slot - 1], expanded[*slot])
+						)
+						.filter(|slot| c.parity
+							.map_or(true, |parity| parity.admits(slot + 1))
+						)
+						.collect::<Vec<_>>();
+					match slots.choose(rng)
+					{
+						Some(slot) => expanded.insert(*slot, *index),
+						None =>
+						{
+							problem = format!(
+								"There's no room for §{} to appear {} times, \
+								separated by at least {}.",
+								index + 1,
+								c.repeats,
+								others(c.repeat_gap)
+							);
+							continue 'attempts
+						}
+					}
+				}
+			}
+			// Only the first appearances are subject to the spacing
+			// requirements of the sections, but every appearance bears the
+			// tags of its section.
+			let mut seen = vec![false; self.constraints.len()];
+			let firsts = expanded.iter()
+				.map(|index|
+					if std::mem::replace(&mut seen[*index], true) { usize::MAX }
+					else { *index }
+				)
+				.collect::<Vec<_>>();
+			let stray = first_stray(&firsts, &self.constraints)
+				.or_else(|| first_tag_stray(
+					&expanded,
+					&self.tag_constraints,
+					&self.constraints
+				));
+			match stray
+			{
+				Some(stray) => problem = stray,
+				None => return Ok(expanded)
+			}
+		}
+		Err(problem)
+	}
+
+	/// Freeze the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, into a chain of ordering
+	/// [constraints](Self::constraints), each section before the next, so that
+	/// the writer can relax a few of them and reshuffle locally. Each link is
+	/// recorded on the earlier section, unless that section is
+	/// [fixed](Constraints::fixed), where its lists are hidden, in which case
+	/// it's recorded on the later one. Links between two fixed sections are
+	/// already implied, so they're omitted.
+	fn freeze_order(&mut self, order: &[usize])
+	{
+		let mut added = 0;
+		for pair in order.windows(2)
+		{
+			if self.constraints[pair[1]].fixed
+				&& self.constraints[pair[0]].fixed
+			{
+				continue
+			}
+			if add_order_constraint(&mut self.constraints, pair[0], pair[1])
+			{
+				added += 1;
+			}
+		}
+		let text = match added
+		{
+			1 => "Froze the order with 1 new constraint".to_string(),
+			n => format!("Froze the order with {} new constraints", n)
+		};
+		tracing::info!("{}.", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Learn the fewest ordering [constraints](Self::constraints) that force
+	/// the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, e.g., one that the writer arranged by
+	/// hand. Each section must precede the next, but only the links that the
+	/// existing constraints don't already imply are recorded, so the rules
+	/// stay as few as possible. The ordering must honor the existing
+	/// constraints, or else nothing is learned.
+	fn learn_order(&mut self, order: &[usize])
+	{
+		let graph = self.constraint_graph();
+		let mut position = vec![0; order.len()];
+		for (p, index) in order.iter().enumerate()
+		{
+			position[*index] = p;
+		}
+		if let Some(edge) = graph.raw_edges().iter().find(|edge|
+			position[edge.source().index()] > position[edge.target().index()]
+		)
+		{
+			self.report_failure(format!(
+				"Couldn't learn from this order, because your constraints put \
+				§{} before §{}",
+				edge.source().index() + 1,
+				edge.target().index() + 1
+			));
+			return
+		}
+		let mut added = 0;
+		for pair in order.windows(2)
+		{
+			let (earlier, later) = (pair[0], pair[1]);
+			let implied = has_path_connecting(
+				&graph,
+				NodeIndex::new(earlier),
+				NodeIndex::new(later),
+				None
+			);
+			if !implied
+				&& add_order_constraint(&mut self.constraints, earlier, later)
+			{
+				added += 1;
+			}
+		}
+		let text = match added
+		{
+			0 => "Your constraints already force this order".to_string(),
+			1 => "Learned 1 constraint from the order".to_string(),
+			n => format!("Learned {} constraints from the order", n)
+		};
+		tracing::info!("{}.", text);
+		self.status = Some(StatusMessage { text, is_error: false });
+	}
+
+	/// Adopt the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, together with its
+	/// [presentation](Self::present_order), as the current reordering, and
+	/// record it in the [history](Self::shuffle_history). The ordering must
+	/// already honor the [constraints](Self::constraints).
+	fn adopt_order(&mut self, indices: Vec<usize>, presented: Vec<usize>)
+	{
+		self.record_order(&indices);
+		self.show_order(indices, presented);
+	}
+
+	/// Make the specified ordering of the
+	/// [original&#32;sections](Self::original_sections), given as
+	/// **zero-based** section indices, together with its
+	/// [presentation](Self::present_order), the current reordering, without
+	/// recording it in the [history](Self::shuffle_history).
+	fn show_order(&mut self, indices: Vec<usize>, presented: Vec<usize>)
+	{
+		self.shuffled_sections = Some(
+			presented.iter()
+				.map(|index| self.original_sections[*index].clone())
+				.collect()
+		);
+		self.shuffled_section_indices = Some(indices);
+		self.presented_section_indices = Some(presented);
+		// Analyze the transitions lazily.
+		self.transitions = None;
+	}
+
+	/// Make the specified complete ordering of the
+	/// [original&#32;sections](Self::original_sections), e.g., as saved with a
+	/// [project](Project) or a [scenario](Scenario), the current reordering,
+	/// if it still fits the sections and can be
+	/// [presented](Self::present_order), or else clear the current
+	/// reordering. The [repeated](Constraints::repeats) sections are placed
+	/// afresh; if there's no room for them, then explain why the reordering
+	/// was cleared.
+	fn restore_order(&mut self, order: Option<Vec<usize>>)
+	{
+		let count = self.original_sections.len();
+		let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+		let presented = order.as_ref()
+			.filter(|order| fits_sections(order, count))
+			.map(|order| self.present_order(order, &mut rng));
+		match (order, presented)
+		{
+			(Some(order), Some(Ok(presented))) =>
+				self.show_order(order, presented),
+			(_, presented) =>
+			{
+				if let Some(Err(problem)) = presented
+				{
+					self.report_failure(format!(
+						"Couldn't restore the reordering: {}",
+						problem
+					));
+				}
+				self.shuffled_section_indices = None;
+				self.presented_section_indices = None;
+				self.shuffled_sections = None;
+				self.transitions = None;
+			}
+		}
+	}
+
+	/// Display the [sidebar][SidePanel] and handle any interactions associated
+	/// therewith.
+	fn present_output_sidebar(&mut self, ctx: &Context)
+	{
+		SidePanel::right("output_panel").show(ctx, |ui| {
+			heading(ui, "Reordering").on_hover_ui(|ui| {
+				ui.horizontal_wrapped(|ui| {
+					ui.spacing_mut().item_spacing.x = 0.0;
+					ui.label("Here you ");
+					ui.label(
+						if self.shuffled_section_indices.is_none() { "will" }
+						else { "can" }
+					);
+					ui.label(
+						" see the latest shuffling of your manuscript, \
+						having enforced any constraints defined in the "
+					);
+					ui.label(RichText::new("Constraints").strong());
+					ui.label(" section. ");
+					ui.label(RichText::new("🎲 Shuffle").strong());
+					if self.shuffled_section_indices.is_none()
+					{
+						ui.label(" to get your first reordering.");
+					}
+					else
+					{
+						ui.label(" to get another reordering.");
+					}
 					ui.label(
-						"Paste your manuscript in the large text area below to \
-						get started."
+						" Configuration changes do not clear this area, only \
+						explicit reshuffles."
 					);
 				});
 			});
-			ScrollArea::vertical().max_height(550.0).show(ui, |ui| {
-				let text_area = ui.add(
-					TextEdit::multiline(&mut self.original_manuscript)
-						.desired_width(f32::INFINITY)
-						.desired_rows(30)
-				);
-				text_area.clone().on_hover_ui(|ui| {
-					ui.vertical(|ui| {
-						ui.label("Here's the basic application workflow:");
-						ui.label("• Paste your manuscript here.");
-						ui.label("• Set the section delimiter.");
-						ui.label("• Set any ordering constraints.");
-						ui.horizontal_wrapped(|ui| {
-							ui.spacing_mut().item_spacing.x = 0.0;
-							ui.label("• ");
-							ui.label(RichText::new("🎲 Shuffle").strong());
-							ui.label(" until you get an ordering you like.");
-						});
-						ui.horizontal_wrapped(|ui| {
-							ui.spacing_mut().item_spacing.x = 0.0;
-							ui.label("• ");
-							ui.label(
-								RichText::new("📋 Copy to clipboard").strong()
-							);
-							ui.label(".");
-						});
+			ui.spacing_mut().item_spacing.y = 3.0;
+			self.present_reordering_controls(ui);
+			self.present_history(ui);
+			self.present_scorecard(ui);
+			self.present_results(ui);
+			// Retain additional space, to preserve repositioning of the sash.
+			ui.allocate_space(ui.available_size());
+		});
+	}
+
+	/// Display which soft constraints, i.e., the
+	/// [preferences](Constraints::prefer_before) and
+	/// [place&#32;preferences](Constraints::place_preference), the current
+	/// reordering honors and which it doesn't, if there are any, so that the
+	/// writer can reshuffle until the trade-offs feel right.
+	fn present_scorecard(&mut self, ui: &mut Ui)
+	{
+		let Some(order) = &self.presented_section_indices else { return };
+		let outcomes = preference_outcomes(order, &self.constraints);
+		if outcomes.is_empty()
+		{
+			return
+		}
+		let honored = outcomes.iter().filter(|o| o.honored).count();
+		let palette = palette(ui);
+		CollapsingHeader::new(format!(
+			"Preferences ({} of {} honored)",
+			honored,
+			outcomes.len()
+		))
+			.id_source("preference_scorecard")
+			.show(ui, |ui| {
+				for outcome in &outcomes
+				{
+					ui.horizontal(|ui| {
 						ui.label(
-							"• Paste your new manuscript into an external document."
+							if outcome.honored
+							{
+								RichText::new("✔").color(palette.success)
+							}
+							else
+							{
+								RichText::new("✖").color(palette.warning)
+							}
 						);
+						ui.label(&outcome.description);
 						ui.label(
-							"You can hover almost every component or label \
-							for additional help."
+							RichText::new(format!("weight {}", outcome.weight))
+								.small()
+								.weak()
 						);
 					});
-				});
-				if text_area.changed()
+				}
+			})
+			.header_response
+			.on_hover_text(
+				"See which of your preferences this reordering honors. \
+				Preferences never override constraints, so reshuffle until \
+				the trade-offs feel right."
+			);
+	}
+
+	/// Display the [shuffle&#32;history](Self::shuffle_history) of the active
+	/// [scenario](Self::scenarios), newest first, and handle any requests to
+	/// restore an earlier ordering.
+	fn present_history(&mut self, ui: &mut Ui)
+	{
+		if self.shuffle_history.len() < 2
+		{
+			// The only ordering is the current one.
+			return
+		}
+		let count = self.original_sections.len();
+		let current = self.current_order();
+		let mut restore = None;
+		CollapsingHeader::new(
+			format!("History ({})", self.shuffle_history.len())
+		)
+			.id_source(("shuffle_history", self.active_scenario))
+			.show(ui, |ui| {
+				for order in self.shuffle_history.iter().rev()
 				{
-					self.update_sections();
+					ui.horizontal(|ui| {
+						let restorable = fits_sections(order, count)
+							&& current.as_ref() != Some(order);
+						if ui.add_enabled(restorable, Button::new("Restore"))
+							.on_hover_text(
+								"Make this the current reordering again."
+							)
+							.clicked()
+						{
+							restore = Some(order.clone());
+						}
+						// Describe only the sections that appear.
+						let shown = order.iter()
+							.copied()
+							.filter(|index| self.constraints.get(*index)
+								.map_or(true, |c| !c.omit)
+							)
+							.collect::<Vec<_>>();
+						ui.label(describe_order(&shown));
+					});
 				}
 			});
-			ui.vertical_centered(|ui| {
-				let button = ui.add_enabled(
-					self.can_shuffle(),
-					Button::new(RichText::new("🎲 Shuffle").strong())
-				);
-				button.clone().on_hover_ui(|ui| {
-					ui.horizontal_wrapped(|ui| {
-						ui.spacing_mut().item_spacing.x = 0.0;
-						ui.label(
-							"Produce a randomized reordering of the \
-							manuscript's sections that obeys the established \
-							constraints. This also clears any resolved error \
-							messages in the "
-						);
-						ui.label(RichText::new("Constraints").strong());
-						ui.label(
-							" section. If errors remain, then no reordering \
-							is performed."
-						);
-					});
-				});
-				if button.clicked()
+		if let Some(order) = restore
+		{
+			if let Some(graph) = self.mark_cycles()
+			{
+				let mut rng = StdRng::seed_from_u64(self.rng.gen::<u64>());
+				let stray = self.placement_violation(&order)
+					.map(Err)
+					.unwrap_or_else(|| self.present_order(&order, &mut rng));
+				match (first_violation(&graph, &order), stray)
 				{
-					if let Some(graph) = self.mark_cycles()
+					(Some((from, to)), _) => self.report_failure(format!(
+						"Couldn't restore: §{} must now come before §{}.",
+						from + 1,
+						to + 1
+					)),
+					(None, Err(stray)) => self.report_failure(format!(
+						"Couldn't restore: {}",
+						stray
+					)),
+					(None, Ok(presented)) =>
 					{
-						self.shuffle(graph);
+						tracing::info!("Restored an earlier reordering.");
+						self.adopt_order(order, presented);
 					}
 				}
-			});
-			ui.with_layout(Layout::bottom_up(Align::Center), |ui| {
-				ui.spacing_mut().item_spacing.y = 3.0;
-				egui::warn_if_debug_build(ui);
-				ui.hyperlink_to(
-					"(Source on GitHub)",
-					"https://github.com/toddATavail/story-shuffler"
-				);
-				ui.separator();
-			});
-		});
-	}
-
-	/// Determine whether the model is correct and can be shuffled.
-	fn can_shuffle(&self) -> bool
-	{
-		self.original_sections.len() > 1
-			&& self.constraints.iter().all(|c|
-				c.text_buffer_is_valid
-			)
+			}
+		}
 	}
 
-	/// Mark any cycles in the specification of the whole system of
-	/// [constraints](Self::constraints). If there were no cycles, then answer
-	/// the [graph][DiGraph].
-	fn mark_cycles(&mut self) -> Option<DiGraph<usize, (), usize>>
+	/// Display the controls for reordering the sections deterministically,
+	/// i.e., without shuffling.
+	fn present_reordering_controls(&mut self, ui: &mut Ui)
 	{
-		let graph: DiGraph<usize, (), usize> = compute_graph(&self.constraints);
-		let mut cycle_count = 0;
-		for index in graph.node_indices()
+		let current = self.current_order();
+		let freeze = ui.add_enabled(
+			current.is_some() && !self.review_mode,
+			Button::new("🔒 Freeze this order")
+		).on_hover_text(
+			"Turn the current reordering into constraints, each section \
+			before the next, so that you can relax a few of them in the \
+			Constraints section and shuffle again, e.g., to try a \
+			different place for one scene while keeping the rest."
+		);
+		if freeze.clicked()
 		{
-			let cycles = find_cycle(&graph, index);
-			if !cycles.is_empty()
+			if let Some(order) = current.as_deref()
 			{
-				let mut error = String::new();
-				for cycle in cycles
-				{
-					error.push_str("Paradox detected:\n");
-					let mut previous = cycle[0];
-					for step in cycle.iter().skip(1)
-					{
-						// Adjust the indices to one-based for our target
-						// audience, i.e., writers.
-						error.push_str("\t§");
-						error.push_str(&(previous.index() + 1).to_string());
-						error.push_str(" must come before §");
-						error.push_str(&(step.index() + 1).to_string());
-						error.push('\n');
-						previous = *step;
-					}
-				}
-				self.constraints[index.index()].paradox_error = Some(error);
-				cycle_count += 1;
+				self.freeze_order(order);
 			}
-			else
+		}
+		let learn = ui.add_enabled(
+			current.is_some() && !self.review_mode,
+			Button::new("🎓 Learn constraints from this order")
+		).on_hover_text(
+			"Turn the current reordering into the fewest constraints that \
+			force it, skipping any that your constraints already imply, e.g., \
+			to keep an arrangement that you made by hand or restored from the \
+			history as reusable rules."
+		);
+		if learn.clicked()
+		{
+			if let Some(order) = current.as_deref()
 			{
-				self.constraints[index.index()].paradox_error = None;
+				self.learn_order(order);
 			}
 		}
-		if cycle_count == 0
+		if self.anthology
 		{
-			Some(graph)
+			// These controls rearrange individual sections, which would break
+			// up the stories.
+			ui.label(
+				RichText::new(
+					"Reversing, sorting, and excerpting aren't available for \
+					anthologies."
+				).weak()
+			);
+			return
 		}
-		else
+		ui.add_enabled_ui(self.can_shuffle(), |ui| {
+			ui.horizontal(|ui| {
+				let previous = ui.button("◀ Previous").on_hover_text(
+					"Step back to the previous ordering that obeys the \
+					constraints, in a fixed sequence rather than at random, \
+					e.g., to review every possibility when only a few remain."
+				);
+				let next = ui.button("Next ▶").on_hover_text(
+					"Step ahead to the next ordering that obeys the \
+					constraints, in a fixed sequence rather than at random, \
+					e.g., to review every possibility when only a few remain. \
+					The sequence starts from the original order, when it's \
+					valid, and wraps around at either end."
+				);
+				for (step, forward) in [(previous, false), (next, true)]
+				{
+					if step.clicked()
+					{
+						self.with_layers(|app| {
+							if let Some(graph) = app.mark_cycles()
+							{
+								app.step_order(graph, forward);
+							}
+						});
+					}
+				}
+			});
+			ui.horizontal(|ui| {
+				let reverse = ui.button("🔃 Reverse").on_hover_text(
+					"Reverse the current reordering, or the original order if \
+					you haven't shuffled yet, e.g., to preview a reverse \
+					chronology. Groups and chapters are reversed as units, \
+					keeping their sections in order. The reversal is refused \
+					if it would violate any constraints, e.g., the rotation."
+				);
+				if reverse.clicked()
+				{
+					self.with_layers(|app| {
+						if let Some(graph) = app.mark_cycles()
+						{
+							app.reverse(graph);
+						}
+					});
+				}
+				let sort = ui.button("🔢 Sort by").on_hover_text(
+					"Order the sections by the chosen attribute instead of \
+					randomly. Set the label, date, and tags of each section \
+					under its details in the Constraints section. Where the \
+					constraints disagree with the attribute, the constraints \
+					win. The sort is refused if it would split a group or \
+					chapter, or break the rotation."
+				);
+				ComboBox::from_id_source("sort_key")
+					.selected_text(self.sort_key.name())
+					.show_ui(ui, |ui| {
+						for key in SortKey::ALL
+						{
+							ui.selectable_value(
+								&mut self.sort_key,
+								key,
+								key.name()
+							);
+						}
+					});
+				ui.checkbox(&mut self.shuffle_ties, "Shuffle ties")
+					.on_hover_text(
+						"Shuffle sections that tie on the chosen attribute \
+						instead of keeping them in their original order, e.g., \
+						to keep the days in order but scramble the scenes \
+						within each day."
+					);
+				if sort.clicked()
+				{
+					self.with_layers(|app| {
+						if let Some(graph) = app.mark_cycles()
+						{
+							app.sort(graph);
+						}
+					});
+				}
+			});
+			ui.horizontal(|ui| {
+				let excerpt = ui.button("✂ Excerpt").on_hover_text(
+					"Choose this many sections at random and arrange them in \
+					an order that obeys the constraints, e.g., to produce a \
+					reading-length excerpt of a longer work. Sections marked \
+					as always included under their details in the Constraints \
+					section are always chosen. Groups and chapters stay \
+					together, and the rotation holds among the chosen \
+					sections."
+				);
+				ui.add(
+					DragValue::new(&mut self.excerpt_length)
+						.clamp_range(1 ..= self.original_sections.len().max(1))
+						.suffix(" sections")
+				);
+				if excerpt.clicked()
+				{
+					self.with_layers(|app| {
+						if let Some(graph) = app.mark_cycles()
+						{
+							app.excerpt(graph);
+						}
+					});
+				}
+			});
+		});
+	}
+
+	/// Answer the text that separates the sections at the specified
+	/// **zero-based** position and the next of an
+	/// [assembled&#32;manuscript](Self::assembled_manuscript), or `None` if
+	/// the headings themselves [separate](SplitMode) them. When the section
+	/// delimiter is a regular expression, each position reuses the
+	/// [match](Self::delimiter_matches) at the same position in the original
+	/// manuscript, so that, e.g., numbered breaks stay in order; dinkus
+	/// separates any sections beyond the matches.
+	fn section_separator(&self, gap: usize) -> Option<&str>
+	{
+		match self.output_separator
 		{
-			None
+			Some(ref separator) => Some(separator),
+			None if self.split_mode != SplitMode::Delimiter => None,
+			None if self.delimiter_pattern_is_regex => Some(
+				self.delimiter_matches.get(gap).map_or(DINKUS, String::as_str)
+			),
+			None => Some(&self.delimiter_pattern)
 		}
 	}
-}
 
-////////////////////////////////////////////////////////////////////////////////
-//                             Output sidebar UI.                             //
-////////////////////////////////////////////////////////////////////////////////
+	/// Answer the text that separates the sections at the specified
+	/// **zero-based** position and the next of an
+	/// [assembled&#32;manuscript](Self::assembled_manuscript), as by
+	/// [`section_separator`](Self::section_separator), with surrounding blank
+	/// lines.
+	fn padded_section_separator(&self, gap: usize) -> String
+	{
+		match self.section_separator(gap)
+		{
+			Some(separator) => format!("\n\n{}\n\n", separator),
+			None => "\n\n".to_string()
+		}
+	}
 
-impl StoryShufflerApp
-{
-	/// Shuffle the [sections](Self::original_sections) of the
-	/// [manuscript](Self::original_manuscript), in accordance with any
-	/// [constraints](Self::constraints) established by the user.
-	fn shuffle(&mut self, mut graph: DiGraph<usize, (), usize>)
-	{
-		let mut indices = vec![];
-		let mut shuffled = vec![];
-		// The algorithm works by peeling off root sets until nothing remains.
-		while graph.node_count() != 0
-		{
-			// Find the roots of the graph, i.e., those vertices that have no
-			// ancestors. These are the sections that are not constrained to
-			// appear after some other section(s).
-			let roots = graph.node_indices()
-				.filter(|index|
-					graph.neighbors_directed(
-						*index,
-						petgraph::Direction::Incoming
-					).count() == 0
-				)
-				.collect::<Vec<NodeIndex<usize>>>();
-			// Shuffle the roots and pluck the first one.
-			let mut shuffled_roots = roots.clone();
-			shuffled_roots.shuffle(&mut thread_rng());
-			let root = shuffled_roots.first().unwrap();
-			let index = *graph.node_weight(*root).unwrap() - 1;
-			indices.push(index);
-			shuffled.push(self.original_sections[index].clone());
-			// Remove the root from the graph. New sections may become roots as
-			// a consequence.
-			graph.remove_node(*root);
+	/// Answer the [shuffled&#32;sections](Self::shuffled_sections) assembled
+	/// into a new manuscript, if any. The
+	/// [output&#32;separator](Self::output_separator), if any, separates the
+	/// sections. Otherwise, if the manuscript was
+	/// [split](Self::split_mode) at headings, then the headings
+	/// separate the sections; if the section delimiter is not a regular
+	/// expression, then it separates the sections verbatim; otherwise, the
+	/// [text&#32;that&#32;it&#32;matched](Self::delimiter_matches) separates
+	/// them. If [chapterization](Self::chapterize) is enabled, then headings
+	/// separate the chapters. The [boilerplate](split_boilerplate) of the
+	/// original manuscript, if any, begins and ends the new one.
+	fn assembled_manuscript(&self) -> Option<String>
+	{
+		let shuffled = self.shuffled_sections.as_ref()?;
+		if self.chapterize && !self.anthology
+		{
+			return Some(with_boilerplate(
+				&self.original_manuscript,
+				self.chaptered_manuscript(shuffled)
+			))
+		}
+		// Separate the stories of an anthology by the story delimiter instead.
+		let indices = self.anthology
+			.then(|| self.presented_order())
+			.flatten();
+		let story_delimiter = format!("\n\n{}\n\n", &self.story_delimiter);
+		let mut manuscript = String::new();
+		for (index, section) in shuffled.iter().enumerate()
+		{
+			if index > 0
+			{
+				match indices
+				{
+					Some(ref indices)
+						if self.section_stories[indices[index - 1]]
+							!= self.section_stories[indices[index]] =>
+						manuscript.push_str(&story_delimiter),
+					_ => manuscript.push_str(
+						&self.padded_section_separator(index - 1)
+					)
+				}
+			}
+			manuscript.push_str(section);
 		}
-		self.shuffled_section_indices = Some(indices);
-		self.shuffled_sections = Some(shuffled);
+		Some(with_boilerplate(&self.original_manuscript, manuscript))
 	}
 
-	/// Display the [sidebar][SidePanel] and handle any interactions associated
-	/// therewith.
-	fn present_output_sidebar(&mut self, ctx: &Context)
+	/// Assemble the specified sections into a new manuscript, grouping them
+	/// into [chapters](chapter_breaks) of about the
+	/// [target&#32;length](Self::chapter_length). A generated heading
+	/// introduces each chapter, and the
+	/// [section&#32;separator](Self::section_separator) separates the sections
+	/// within a chapter.
+	fn chaptered_manuscript(&self, sections: &[String]) -> String
 	{
-		SidePanel::right("output_panel").show(ctx, |ui| {
-			heading(ui, "Reordering").on_hover_ui(|ui| {
-				ui.horizontal_wrapped(|ui| {
-					ui.spacing_mut().item_spacing.x = 0.0;
-					ui.label("Here you ");
-					ui.label(
-						if self.shuffled_section_indices.is_none() { "will" }
-						else { "can" }
-					);
-					ui.label(
-						" see the latest shuffling of your manuscript, \
-						having enforced any constraints defined in the "
-					);
-					ui.label(RichText::new("Constraints").strong());
-					ui.label(" section. ");
-					ui.label(RichText::new("🎲 Shuffle").strong());
-					if self.shuffled_section_indices.is_none()
-					{
-						ui.label(" to get your first reordering.");
-					}
-					else
-					{
-						ui.label(" to get another reordering.");
-					}
-					ui.label(
-						" Configuration changes do not clear this area, only \
-						explicit reshuffles."
-					);
-				});
-			});
-			ui.spacing_mut().item_spacing.y = 3.0;
-			self.present_results(ui);
-			// Retain additional space, to preserve repositioning of the sash.
-			ui.allocate_space(ui.available_size());
-		});
+		let lengths = sections.iter()
+			.map(|section| section.split_whitespace().count())
+			.collect::<Vec<_>>();
+		let breaks = chapter_breaks(&lengths, self.chapter_length);
+		let mut manuscript = format!("{} 1\n\n", CHAPTER_HEADING);
+		let mut chapter = 1;
+		for (index, section) in sections.iter().enumerate()
+		{
+			if breaks.contains(&index)
+			{
+				chapter += 1;
+				manuscript.push_str(
+					&format!("\n\n{} {}\n\n", CHAPTER_HEADING, chapter)
+				);
+			}
+			else if index > 0
+			{
+				manuscript.push_str(&self.padded_section_separator(index - 1));
+			}
+			manuscript.push_str(section);
+		}
+		manuscript
 	}
 
 	/// Display the [shuffled&#32;sections](Self::shuffled_sections) along with
 	/// controls for manually tweaking their positions.
 	fn present_results(&mut self, ui: &mut Ui)
 	{
-		let delimiter =
-			if self.delimiter_pattern_is_regex { "\n\n* * *\n\n".to_string() }
-			else { format!("\n\n{}\n\n", &self.delimiter_pattern) };
+		let new_manuscript = self.assembled_manuscript();
+		let separator_in_effect =
+			self.section_separator(0).unwrap_or(DINKUS).to_string();
+		let text_kind = self.manuscript_kind();
+		let mut save_as = None;
 		if let Some(ref mut shuffled) = self.shuffled_sections.as_mut()
 		{
 			if shuffled.len() < 2
@@ -772,24 +11503,232 @@ impl StoryShufflerApp
 						manuscript and copy it to the system clipboard. If the \
 						section break is not a regular expression, then it \
 						separate sections in the new manuscript verbatim. \
-						Otherwise, dinkus ("
+						Otherwise, the breaks that it matched will separate \
+						the sections in their original order, and dinkus ("
 					);
 					ui.code("* * *");
-					ui.label(") will separate the sections.");
+					ui.label(") will separate any others.");
 				});
 			});
 			if button.clicked()
 			{
-				let new_manuscript = shuffled.join(&delimiter);
+				let new_manuscript = new_manuscript.clone().unwrap_or_default();
 				ui.output_mut(|clipboard| clipboard.copied_text = new_manuscript);
 			}
-			ui.separator();
-			scrollable_sections(
+			#[cfg(target_arch = "wasm32")]
+			if can_share() && ui.button("📤 Share")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					send it to another application, e.g., your notes or a \
+					messaging app."
+				)
+				.clicked()
+			{
+				if let Err(e) = share_text(
+					"Shuffled manuscript",
+					new_manuscript.as_deref().unwrap_or_default()
+				)
+				{
+					tracing::warn!("Couldn't share: {}", e);
+					self.status = Some(StatusMessage {
+						text: format!("Couldn't share: {}", e),
+						is_error: true
+					});
+				}
+			}
+			#[cfg(not(target_arch = "wasm32"))]
+			if ui.button("💾 Save as…")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					save it as a text file wherever you like."
+				)
+				.clicked()
+			{
+				// Defer saving until the shuffled sections are released.
+				save_as = Some(text_kind);
+			}
+			#[cfg(target_arch = "wasm32")]
+			if ui.button("⬇ Download")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					download it as a text file, e.g., if your browser won't \
+					copy to the clipboard."
+				)
+				.clicked()
+			{
+				save_as = Some(text_kind);
+			}
+			if ui.button("📚 Save as EPUB")
+				.on_hover_text(
+					"Save the reordered sections as an e-book, one chapter per \
+					section, with a table of contents, e.g., to send to beta \
+					readers' e-readers. Each chapter takes its title from the \
+					section's label, or else its opening Markdown heading."
+				)
+				.clicked()
+			{
+				save_as = Some(EPUB);
+			}
+			if ui.button("🌐 Save as HTML")
+				.on_hover_text(
+					"Save the reordered sections as a web page, with a table \
+					of contents, e.g., to post online for feedback. Each \
+					section is anchored by its original number, like #s7, so \
+					that readers can link to it."
+				)
+				.clicked()
+			{
+				save_as = Some(HTML);
+			}
+			if ui.button("📄 Save as DOCX")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					save it as a Word document in standard manuscript format, \
+					with each section break centered."
+				)
+				.clicked()
+			{
+				save_as = Some(DOCX);
+			}
+			if ui.button("📃 Save as ODT")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					save it as an OpenDocument text document, e.g., for \
+					LibreOffice, in standard manuscript format, with each \
+					section break centered."
+				)
+				.clicked()
+			{
+				save_as = Some(ODT);
+			}
+			if ui.button("🖨 Save as PDF")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript and \
+					save it as a PDF document with a title page, e.g., for \
+					workshop handouts."
+				)
+				.clicked()
+			{
+				save_as = Some(PDF);
+			}
+			ComboBox::from_id_source("page_size")
+				.selected_text(self.page_size.name())
+				.show_ui(ui, |ui| {
+					for size in PageSize::ALL
+					{
+						ui.selectable_value(
+							&mut self.page_size,
+							size,
+							size.name()
+						);
+					}
+				})
+				.response
+				.on_hover_text("The page size of PDF documents.");
+			#[cfg(not(target_arch = "wasm32"))]
+			if ui.button("📝 Open in editor")
+				.on_hover_text(
+					"Assemble the reordered sections into a new manuscript, \
+					save it as a temporary text file, and open it with your \
+					default application for text files."
+				)
+				.clicked()
+			{
+				let result = open_in_default_application(
+					TEMPORARY_MANUSCRIPT_NAME,
+					new_manuscript.as_deref().unwrap_or_default()
+				);
+				match result
+				{
+					Ok(path) => tracing::info!("Opened {}.", path),
+					Err(e) =>
+					{
+						tracing::warn!("{}", e);
+						self.status = Some(StatusMessage {
+							text: e,
+							is_error: true
+						});
+					}
+				}
+			}
+			ui.horizontal(|ui| {
+				ui.add_enabled(
+					!self.anthology,
+					Checkbox::new(&mut self.chapterize, "Chapters of about")
+				);
+				ui.add_enabled(
+					self.chapterize && !self.anthology,
+					DragValue::new(&mut self.chapter_length)
+						.clamp_range(
+							MINIMUM_CHAPTER_LENGTH ..= MAXIMUM_CHAPTER_LENGTH
+						)
+						.speed(50)
+						.suffix(" words")
+				);
+			}).response.on_hover_text(
+				"Group the reordered sections into chapters of roughly this \
+				many words when assembling the new manuscript, and introduce \
+				each chapter with a generated heading, e.g., “Chapter 2”. Not \
+				available for anthologies."
+			);
+			present_output_separator(
 				ui,
-				self.shuffled_section_indices.as_ref().unwrap(),
-				shuffled,
-				None,
-				None
+				&mut self.output_separator,
+				&separator_in_effect
+			);
+			ui.horizontal(|ui| {
+				ui.selectable_value(
+					&mut self.output_view,
+					OutputView::Sections,
+					"Sections"
+				).on_hover_text("Show each section in its new position.");
+				ui.selectable_value(
+					&mut self.output_view,
+					OutputView::Transitions,
+					"Transitions"
+				).on_hover_text(
+					"Show only the last line of each section next to the first \
+					line of the following section, so that you can audit every \
+					transition quickly."
+				);
+			});
+			ui.separator();
+			let indices = self.presented_section_indices.as_ref().unwrap();
+			let transitions = self.transitions
+				.get_or_insert_with(|| analyze_transitions(shuffled));
+			match self.output_view
+			{
+				OutputView::Sections =>
+				{
+					scrollable_sections(
+						ui,
+						indices,
+						shuffled,
+						None,
+						None,
+						SectionAnnotations {
+							ids: self.show_section_ids
+								.then_some(&self.section_ids[..]),
+							stories: self.anthology
+								.then_some(&self.section_stories[..]),
+							ranges: None,
+							transitions: Some(transitions),
+							redundancies: None,
+							impact: None
+						}
+					);
+				},
+				OutputView::Transitions =>
+				{
+					scrollable_transitions(ui, indices, shuffled, transitions);
+				}
+			}
+		}
+		if let Some(kind) = save_as
+		{
+			self.offer_manuscript(
+				new_manuscript.as_deref().unwrap_or_default(),
+				kind
 			);
 		}
 	}
@@ -799,24 +11738,318 @@ impl StoryShufflerApp
 //                              Custom widgets.                               //
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Display the controls for choosing the
+/// [output&#32;separator](StoryShufflerApp::output_separator): a menu of common
+/// separators, and a field for editing the chosen one. Choosing a custom
+/// separator starts from the one `in_effect`.
+fn present_output_separator(
+	ui: &mut Ui,
+	output_separator: &mut Option<String>,
+	in_effect: &str
+)
+{
+	ui.horizontal(|ui| {
+		ui.label("Separator:");
+		let selected = match output_separator
+		{
+			None => "Same as input",
+			Some(ref separator) => OUTPUT_SEPARATORS.iter()
+				.find(|(_, preset)| preset == separator)
+				.map_or("Custom", |(name, _)| name)
+		};
+		ComboBox::from_id_source("output_separator")
+			.selected_text(selected)
+			.show_ui(ui, |ui| {
+				ui.selectable_value(output_separator, None, "Same as input");
+				for (name, preset) in OUTPUT_SEPARATORS
+				{
+					ui.selectable_value(
+						output_separator,
+						Some(preset.to_string()),
+						name
+					);
+				}
+				if ui.selectable_label(selected == "Custom", "Custom")
+					.clicked()
+					&& selected != "Custom"
+				{
+					*output_separator = Some(in_effect.to_string());
+				}
+			});
+		if let Some(ref mut separator) = output_separator
+		{
+			ui.add(
+				TextEdit::singleline(separator)
+					.hint_text("blank line")
+					.desired_width(120.0)
+			);
+		}
+	}).response.on_hover_text(
+		"Choose what separates the sections of the new manuscript when you \
+		copy or save it, independently of the section delimiter that split \
+		your manuscript."
+	);
+}
+
+/// Display a field, beside the specified label, for editing a comma-separated
+/// list of **one-based** section numbers, e.g., the sections that must come
+/// [after](Constraints::before) a section. Each entry may instead be the
+/// [label](Constraints::label) of a section, as given by `labels`, and labels
+/// that complete the entry being typed are suggested beside the field. Keep
+/// `list` in step with `buffer` while the latter is well-formed, according to
+/// `sections_regex` or [`parse_section_list`], and clear `is_valid`
+/// otherwise. Answer the [response](Response) of the whole row.
+fn edit_section_list(
+	ui: &mut Ui,
+	label: &str,
+	buffer: &mut String,
+	is_valid: &mut bool,
+	list: &mut Vec<usize>,
+	sections_regex: Option<&Regex>,
+	labels: &[String]
+) -> Response
+{
+	ui.horizontal(|ui| {
+		ui.label(label);
+		let mut changed = ui.text_edit_singleline(buffer).changed();
+		if *is_valid && !list.is_empty()
+		{
+			ui.label(RichText::new("✔").color(palette(ui).success));
+		}
+		// Suggest the labels that complete the entry being typed, if it isn't
+		// already a section number or a label.
+		let partial = buffer.rsplit(',').next().unwrap_or_default().trim();
+		if !partial.is_empty()
+			&& partial.parse::<usize>().is_err()
+			&& section_named(partial, labels).is_none()
+		{
+			let lowered = partial.to_lowercase();
+			let suggestions = labels.iter()
+				.map(|label| label.trim())
+				.enumerate()
+				.filter(|(_, label)| label.to_lowercase().starts_with(&lowered))
+				.take(MAXIMUM_LABEL_SUGGESTIONS)
+				.map(|(index, label)| (index, label.to_string()))
+				.collect::<Vec<_>>();
+			for (index, suggestion) in suggestions
+			{
+				if ui.small_button(&suggestion)
+					.on_hover_text(format!("§{}", index + 1))
+					.clicked()
+				{
+					*buffer = match buffer.rsplit_once(',')
+					{
+						Some((kept, _)) => format!("{}, {}", kept, suggestion),
+						None => suggestion
+					};
+					changed = true;
+				}
+			}
+		}
+		if changed
+		{
+			if let Some(sections_regex) = sections_regex
+			{
+				// Note that we are storing these as one-based indices, not
+				// zero-based.
+				if sections_regex.is_match(buffer)
+				{
+					*is_valid = true;
+					*list = buffer.split(',')
+						.map(|s| s.trim().parse::<usize>().unwrap_or_default())
+						.filter(|n| *n != 0)
+						.collect();
+				}
+				else if let Some(sections) = parse_section_list(buffer, labels)
+				{
+					*is_valid = true;
+					*list = sections;
+				}
+				else
+				{
+					*is_valid = false;
+					list.clear();
+				}
+			}
+		}
+	}).response
+}
+
+/// Answer the **one-based** section numbers denoted by the specified
+/// comma-separated list, each entry of which is either a section number or
+/// the [label](Constraints::label) of a section, as given by `labels`. Answer
+/// `None` if any entry is neither.
+fn parse_section_list(buffer: &str, labels: &[String]) -> Option<Vec<usize>>
+{
+	buffer.split(',')
+		.map(str::trim)
+		.filter(|entry| !entry.is_empty())
+		.map(|entry| entry.parse::<usize>()
+			.ok()
+			.filter(|n| *n != 0)
+			.or_else(|| section_named(entry, labels))
+		)
+		.collect()
+}
+
+/// Answer the **one-based** number of the only section whose
+/// [label](Constraints::label), as given by `labels`, is the specified name,
+/// ignoring case, or `None` if no section or several sections have that label.
+fn section_named(name: &str, labels: &[String]) -> Option<usize>
+{
+	let name = name.to_lowercase();
+	let mut matches = labels.iter()
+		.enumerate()
+		.filter(|(_, label)| label.trim().to_lowercase() == name)
+		.map(|(index, _)| index + 1);
+	match (matches.next(), matches.next())
+	{
+		(Some(number), None) => Some(number),
+		_ => None
+	}
+}
+
 /// Add a common custom heading to the [UI](Ui).
 fn heading(ui: &mut Ui, text: impl Into<String>) -> Response
 {
-	ui.label(RichText::new(text).heading().color(hex_color!("#aaaaaa")))
+	let color = palette(ui).heading;
+	ui.label(RichText::new(text).heading().color(color))
+}
+
+/// Display a [bar](PositionRange) that depicts the feasible output positions of
+/// a section, relative to the total number of sections.
+fn position_range_bar(ui: &mut Ui, range: PositionRange, count: usize)
+	-> Response
+{
+	let (rect, response) = ui.allocate_exact_size(vec2(60.0, 8.0), Sense::hover());
+	if ui.is_rect_visible(rect)
+	{
+		let palette = palette(ui);
+		let painter = ui.painter();
+		painter.rect_filled(rect, 2.0, palette.range_track);
+		let slot = rect.width() / count as f32;
+		let feasible = Rect::from_min_max(
+			rect.left_top() + vec2(slot * range.earliest as f32, 0.0),
+			rect.left_bottom() + vec2(slot * (range.latest + 1) as f32, 0.0)
+		);
+		painter.rect_filled(feasible, 2.0, palette.range_fill);
+	}
+	// Writers are not necessarily programmers, so let's present one-based
+	// positions.
+	response.on_hover_text(
+		if range.earliest == range.latest
+		{
+			format!(
+				"This section can only occupy position {} of {}.",
+				range.earliest + 1,
+				count
+			)
+		}
+		else
+		{
+			format!(
+				"This section can occupy positions {} through {} of {}.",
+				range.earliest + 1,
+				range.latest + 1,
+				count
+			)
+		}
+	)
+}
+
+/// The optional, read-only annotations of the sections presented by
+/// [`scrollable_sections`].
+#[derive(Clone, Copy, Default)]
+struct SectionAnnotations<'a>
+{
+	/// The [identifiers](StoryShufflerApp::section_ids) of the original
+	/// sections, if they should be displayed.
+	ids: Option<&'a [String]>,
+
+	/// The [stories](StoryShufflerApp::section_stories) of the original
+	/// sections, if they should be displayed.
+	stories: Option<&'a [usize]>,
+
+	/// The [position&#32;ranges](PositionRange) of the sections, in
+	/// presentation order.
+	ranges: Option<&'a [PositionRange]>,
+
+	/// The [transitions](Transition) between adjacent sections, in
+	/// presentation order.
+	transitions: Option<&'a [Transition]>,
+
+	/// The [redundant](Redundancy) constraints of the sections, if they
+	/// should be flagged.
+	redundancies: Option<&'a [Redundancy]>,
+
+	/// What [describing](describe_impact) the impact of each constraint
+	/// requires, if the constraints are editable.
+	impact: Option<ImpactContext<'a>>
+}
+
+/// Everything beyond the [constraints](Constraints) of the sections that
+/// [describing](describe_impact) the impact of a constraint requires.
+#[derive(Clone, Copy)]
+struct ImpactContext<'a>
+{
+	/// The [tag&#32;constraints](StoryShufflerApp::tag_constraints).
+	tag_constraints: &'a [TagConstraint],
+
+	/// The [chapters](StoryShufflerApp::chapters).
+	chapters: &'a [Chapter],
+
+	/// Whether the manuscript is an [anthology](StoryShufflerApp::anthology).
+	anthology: bool,
+
+	/// The [cached](StoryShufflerApp::valid_orderings) count of the valid
+	/// orderings of the whole system of constraints.
+	orderings: Option<OrderingCount>
 }
 
 /// Display a [scrollable&#32;area][ScrollArea] containing the specified
 /// sections. If [constraints][Constraints] accompany the sections, then also
-/// present the constraints and handle any interactions therewith.
+/// present the constraints and handle any interactions therewith. If
+/// [identifiers](SectionAnnotations::ids) accompany the sections, then also
+/// present the identifier of each section. If
+/// [position&#32;ranges](PositionRange) accompany the sections, then also
+/// present a [bar](position_range_bar) for each section. If
+/// [transitions](Transition) accompany the sections, then also flag any
+/// problems between adjacent sections.
 fn scrollable_sections(
 	ui: &mut Ui,
 	indices: &[usize],
 	sections: &mut [String],
 	mut constraints: Option<&mut [Constraints]>,
-	sections_regex: Option<&Regex>
+	sections_regex: Option<&Regex>,
+	annotations: SectionAnnotations<'_>
 ) -> ScrollAreaOutput<()>
 {
-	ScrollArea::vertical().show(ui, |ui| {
+	let SectionAnnotations {
+		ids,
+		stories,
+		ranges,
+		transitions,
+		redundancies,
+		impact: impact_context
+	} = annotations;
+	profile_function!();
+	// Collect the labels up front, so that any section list can refer to
+	// sections by name.
+	let labels = constraints.as_ref().map_or_else(Vec::new, |constraints|
+		constraints.iter().map(|c| c.label.clone()).collect::<Vec<_>>()
+	);
+	// The section whose link handle the writer clicked, awaiting a click on
+	// the handle of another section, survives from frame to frame.
+	let link_id = Id::new("section_link");
+	let mut link_source = ui.data_mut(|data| data.get_temp::<usize>(link_id))
+		.filter(|source| *source < sections.len());
+	if ui.input(|input| input.key_pressed(Key::Escape))
+	{
+		link_source = None;
+	}
+	let mut handles = vec![None; sections.len()];
+	let mut hovered_handle = None;
+	let output = ScrollArea::vertical().show(ui, |ui| {
 		for (index, section) in sections.iter().enumerate()
 		{
 			ui.horizontal(|ui| {
@@ -826,62 +12059,514 @@ fn scrollable_sections(
 				ui.label(format!("§{}", adjusted));
 				if let Some(constraints) = constraints.as_mut()
 				{
-					let constraints = &mut constraints[index];
-					let fixed = &mut constraints.fixed;
-					if index == 0 || index == sections.len() - 1
+					let handle = ui.selectable_label(
+						link_source == Some(index),
+						"🔗"
+					);
+					handles[index] = Some(handle.rect);
+					if handle.hovered()
+					{
+						hovered_handle = Some(index);
+					}
+					let handle = match link_source
+					{
+						None => handle.on_hover_text(format!(
+							"Click here, and then on the 🔗 of another \
+							section, to require §{} to come before that \
+							section. The arrows show the sections that must \
+							come before and after this one.",
+							adjusted
+						)),
+						Some(source) if source == index => handle
+							.on_hover_text("Click again, or press Escape, to \
+								cancel the link."),
+						Some(source) => handle.on_hover_text(format!(
+							"Click here to require §{} to come before §{}.",
+							source + 1,
+							adjusted
+						))
+					};
+					if handle.clicked()
+					{
+						link_source = match link_source
+						{
+							None => Some(index),
+							Some(source) if source == index => None,
+							Some(source) =>
+							{
+								add_order_constraint(
+									constraints,
+									source,
+									index
+								);
+								None
+							}
+						};
+					}
+				}
+				if let Some(id) = ids.and_then(|ids| ids.get(indices[index]))
+				{
+					ui.label(RichText::new(id).small().weak().monospace())
+						.on_hover_text(
+							"This identifier stays with the section even when \
+							other sections are inserted or removed."
+						);
+				}
+				if let Some(story) =
+					stories.and_then(|stories| stories.get(indices[index]))
+				{
+					ui.label(
+						RichText::new(format!("Story {}", story + 1))
+							.small()
+							.weak()
+					);
+				}
+				if let Some(ranges) = ranges
+				{
+					position_range_bar(ui, ranges[index], sections.len());
+				}
+				if let Some(c) = constraints.as_ref()
+					.map(|constraints| &constraints[index])
+					.filter(|c| c.matter != Matter::Body)
+				{
+					ui.label(RichText::new(c.matter.name()).small().weak());
+				}
+				if let Some(constraints) = constraints.as_mut()
+				{
+					{
+						let checkbox = ui.checkbox(
+							&mut constraints[index].fixed,
+							"Fixed"
+						);
+						let impact = impact_context.as_ref()
+							.filter(|_| checkbox.hovered())
+							.and_then(|context| describe_impact(
+								ui,
+								constraints,
+								context,
+								index,
+								|c| c.fixed = false
+							));
+						checkbox.on_hover_ui(|ui| {
+							ui.label(format!(
+								"Check this box if section §{} should be fixed \
+								in place at its current position in the \
+								manuscript.",
+								adjusted
+							));
+							if constraints[index].fixed
+							{
+								if let Some(impact) = impact
+								{
+									ui.label(impact);
+								}
+							}
+						});
+					}
+					ui.checkbox(&mut constraints[index].omit, "Omit")
+						.on_hover_text(
+							"Check this box to leave this section out of every \
+							reordering, e.g., a deleted scene that you aren't \
+							ready to discard. It stays in your manuscript and \
+							keeps its constraints."
+						);
+					if !constraints[index].fixed
+					{
+						ui.horizontal(|ui| {
+							let c = &mut constraints[index];
+							if ui.checkbox(&mut c.before_all, "Before all")
+								.on_hover_text(
+									"Check this box if this section must come \
+									before every other section, wherever it \
+									sits in the manuscript."
+								)
+								.changed() && c.before_all
+							{
+								c.after_all = false;
+							}
+							if ui.checkbox(&mut c.after_all, "After all")
+								.on_hover_text(
+									"Check this box if this section must come \
+									after every other section, wherever it \
+									sits in the manuscript."
+								)
+								.changed() && c.after_all
+							{
+								c.before_all = false;
+							}
+						});
+						let before = {
+							let c = &mut constraints[index];
+							edit_section_list(
+								ui,
+								"Before §",
+								&mut c.text_buffer,
+								&mut c.text_buffer_is_valid,
+								&mut c.before,
+								sections_regex,
+								&labels
+							)
+						};
+						let impact = impact_context.as_ref()
+							.filter(|_| before.hovered())
+							.and_then(|context| describe_impact(
+								ui,
+								constraints,
+								context,
+								index,
+								|c| c.before.clear()
+							));
+						before.on_hover_ui(|ui| {
+							ui.label(
+								"This section must come before any sections \
+								mentioned in this comma-separated list of section \
+								numbers."
+							);
+							if !constraints[index].before.is_empty()
+							{
+								if let Some(impact) = impact
+								{
+									ui.label(impact);
+								}
+							}
+						});
+						let after = {
+							let c = &mut constraints[index];
+							edit_section_list(
+								ui,
+								"After §",
+								&mut c.after_text_buffer,
+								&mut c.after_text_buffer_is_valid,
+								&mut c.after,
+								sections_regex,
+								&labels
+							)
+						};
+						let impact = impact_context.as_ref()
+							.filter(|_| after.hovered())
+							.and_then(|context| describe_impact(
+								ui,
+								constraints,
+								context,
+								index,
+								|c| c.after.clear()
+							));
+						after.on_hover_ui(|ui| {
+							ui.label(
+								"This section must come after any sections \
+								mentioned in this comma-separated list of \
+								section numbers."
+							);
+							if !constraints[index].after.is_empty()
+							{
+								if let Some(impact) = impact
+								{
+									ui.label(impact);
+								}
+							}
+						});
+						let count = sections.len();
+						ui.horizontal(|ui| {
+							let c = &mut constraints[index];
+							let mut pinned = c.pin.is_some();
+							if ui.checkbox(&mut pinned, "Pin to position")
+								.changed()
+							{
+								c.pin = pinned.then_some(index + 1);
+							}
+							if let Some(ref mut pin) = c.pin
+							{
+								ui.add(
+									DragValue::new(pin)
+										.clamp_range(1 ..= count)
+								);
+							}
+						}).response.on_hover_text(
+							"Check this box to put this section at the chosen \
+							position in every shuffle, e.g., to make it the \
+							third section wherever the others land."
+						);
+						let c = &mut constraints[index];
+						ui.horizontal(|ui| {
+							ui.label("Positions");
+							ComboBox::from_id_source(("parity", index))
+								.selected_text(match c.parity
+								{
+									Some(parity) => parity.name(),
+									None => "any"
+								})
+								.show_ui(ui, |ui| {
+									ui.selectable_value(
+										&mut c.parity,
+										None,
+										"any"
+									);
+									for parity in Parity::ALL
+									{
+										ui.selectable_value(
+											&mut c.parity,
+											Some(parity),
+											parity.name()
+										);
+									}
+								});
+						}).response.on_hover_text(
+							"Restrict this section to odd or even positions, \
+							e.g., so that the sections of two interleaved \
+							storylines alternate strictly."
+						);
+						edit_section_list(
+							ui,
+							"Preferably before §",
+							&mut c.prefer_before_text_buffer,
+							&mut c.prefer_before_text_buffer_is_valid,
+							&mut c.prefer_before,
+							sections_regex,
+							&labels
+						).on_hover_text(
+							"This section should preferably come before any \
+							sections mentioned in this comma-separated list of \
+							section numbers. Unlike the Before list, this is \
+							only a preference: shuffling for the best of \
+							several reorderings favors those that honor it, \
+							but never fails for want of it."
+						);
+						if !c.prefer_before.is_empty()
+						{
+							ui.horizontal(|ui| {
+								ui.label("Weight");
+								ui.add(
+									DragValue::new(&mut c.preference_weight)
+										.speed(0.1)
+										.clamp_range(
+											MINIMUM_PREFERENCE_WEIGHT
+												..= MAXIMUM_PREFERENCE_WEIGHT
+										)
+								);
+							}).response.on_hover_text(
+								"How much these preferences matter, relative \
+								to those of other sections and to the quality \
+								of the transitions. Each place by which a \
+								reordering misses a preference costs this \
+								much of its score."
+							);
+						}
+						ui.horizontal(|ui| {
+							ui.label("Prefer to land");
+							ComboBox::from_id_source(("place", index))
+								.selected_text(c.place_preference.name())
+								.show_ui(ui, |ui| {
+									for place in PlacePreference::ALL
+									{
+										ui.selectable_value(
+											&mut c.place_preference,
+											place,
+											place.name()
+										);
+									}
+								});
+							if c.place_preference != PlacePreference::Anywhere
+							{
+								ui.label("Weight");
+								ui.add(
+									DragValue::new(&mut c.place_weight)
+										.speed(0.1)
+										.clamp_range(
+											MINIMUM_PLACE_WEIGHT
+												..= MAXIMUM_PLACE_WEIGHT
+										)
+								);
+							}
+						}).response.on_hover_text(
+							"Where this section should preferably land. \
+							Shuffling favors sections that suit the next \
+							place, more strongly as the weight grows, but \
+							never breaks a constraint for want of it. Within \
+							a chapter that shuffles within, the place is \
+							within the chapter."
+						);
+					}
+					let near = {
+						let c = &mut constraints[index];
+						edit_section_list(
+							ui,
+							"Near §",
+							&mut c.near_text_buffer,
+							&mut c.near_text_buffer_is_valid,
+							&mut c.near,
+							sections_regex,
+							&labels
+						)
+					};
+					near.on_hover_text(
+						"This section must land near any sections mentioned \
+						in this comma-separated list of section numbers, in \
+						either direction, e.g., to keep a payoff close to its \
+						setup."
+					);
+					let c = &mut constraints[index];
+					if !c.near.is_empty()
 					{
-						ui.checkbox(fixed, "Fixed").on_hover_text(
-							format!(
-								"Check this box if section §{} should be fixed \
-								in place at its current position in the \
-								manuscript. This constraint is only available \
-								for the first and last sections.",
-								adjusted
-							)
+						ui.horizontal(|ui| {
+							ui.label("Within");
+							ui.add(
+								DragValue::new(&mut c.near_distance)
+									.clamp_range(1 ..= sections.len())
+							);
+							ui.label("positions");
+						}).response.on_hover_text(
+							"The most positions that may separate this section \
+							from each of the sections near it. 1 means that \
+							they must be adjacent."
 						);
 					}
-					if !*fixed
+					let count = sections.len();
+					ui.horizontal(|ui| {
+						let c = &mut constraints[index];
+						let mut tethered = c.offset_from.is_some();
+						if ui.checkbox(&mut tethered, "Exactly").changed()
+						{
+							// Suggest the preceding section.
+							c.offset_from = tethered
+								.then_some(if index == 0 { 2 } else { index });
+						}
+						if let Some(ref mut from) = c.offset_from
+						{
+							ui.add(
+								DragValue::new(&mut c.offset)
+									.clamp_range(1 ..= count - 1)
+							);
+							ui.label("positions after §");
+							ui.add(
+								DragValue::new(from).clamp_range(1 ..= count)
+							);
+						}
+					}).response.on_hover_text(
+						"Check this box to make this section land exactly the \
+						chosen number of positions after the chosen section, \
+						e.g., an answer exactly two scenes after its question. \
+						1 means immediately after."
+					);
+					let apart = {
+						let c = &mut constraints[index];
+						edit_section_list(
+							ui,
+							"Apart from §",
+							&mut c.apart_text_buffer,
+							&mut c.apart_text_buffer_is_valid,
+							&mut c.apart,
+							sections_regex,
+							&labels
+						)
+					};
+					apart.on_hover_text(
+						"This section must land well away from any sections \
+						mentioned in this comma-separated list of section \
+						numbers, in either direction, e.g., to space out \
+						flashbacks or repeated motifs."
+					);
+					let c = &mut constraints[index];
+					if !c.apart.is_empty()
 					{
 						ui.horizontal(|ui| {
-							ui.label("Before §");
-							if ui.text_edit_singleline(
-								&mut constraints.text_buffer
-							).changed()
-							{
-								if let Some(sections_regex) =
-									sections_regex.as_ref()
+							ui.label("By at least");
+							ui.add(
+								DragValue::new(&mut c.apart_gap)
+									.clamp_range(1 ..= sections.len())
+							);
+							ui.label("sections");
+						}).response.on_hover_text(
+							"The fewest other sections that must separate this \
+							section from each of the sections apart from it."
+						);
+					}
+					let c = &mut constraints[index];
+					edit_section_list(
+						ui,
+						"Not beside §",
+						&mut c.not_adjacent_text_buffer,
+						&mut c.not_adjacent_text_buffer_is_valid,
+						&mut c.not_adjacent,
+						sections_regex,
+						&labels
+					).on_hover_text(
+						"This section must never come immediately before or \
+						after any sections mentioned in this comma-separated \
+						list of section numbers."
+					);
+					let c = &mut constraints[index];
+					ui.horizontal(|ui| {
+						ui.label("Appears");
+						ui.add(
+							DragValue::new(&mut c.repeats)
+								.clamp_range(1 ..= MAXIMUM_REPEATS)
+						);
+						ui.label("times");
+						if c.repeats > 1
+						{
+							ui.label(", at least");
+							ui.add(
+								DragValue::new(&mut c.repeat_gap)
+									.clamp_range(1 ..= sections.len())
+							);
+							ui.label("sections apart");
+						}
+					}).response.on_hover_text(
+						"How many times this section appears in every \
+						reordering, e.g., for a recurring refrain, and how \
+						many other sections must separate its appearances. \
+						The constraints above govern its first appearance, \
+						and the rest follow it."
+					);
+					let mut source = None;
+					ui.horizontal(|ui| {
+						ui.label("Copy constraints from");
+						ComboBox::from_id_source(("copy", index))
+							.selected_text("§…")
+							.show_ui(ui, |ui| {
+								for (other, label) in labels.iter().enumerate()
 								{
-									// Note that we are storing these as one-based
-									// indices, not zero-based.
-									if sections_regex.is_match(
-										&constraints.text_buffer
-									)
+									if other == index
+									{
+										continue
+									}
+									let text = if label.trim().is_empty()
+									{
+										format!("§{}", other + 1)
+									}
+									else
+									{
+										format!(
+											"§{}: {}",
+											other + 1,
+											label.trim()
+										)
+									};
+									if ui.selectable_label(false, text)
+										.clicked()
 									{
-										constraints.text_buffer_is_valid = true;
-										constraints.before =
-											constraints.text_buffer
-												.split(',')
-												.map(|s|
-													s.trim().parse::<usize>()
-														.unwrap_or_default()
-												)
-												.filter(|n| *n != 0)
-												.collect();
-									} else {
-										constraints.text_buffer_is_valid =
-											false;
-										constraints.before = vec![];
+										source = Some(other);
 									}
 								}
-							}
-						}).response.on_hover_text(
-							"This section must come before any sections \
-							mentioned in this comma-separated list of section \
-							numbers."
-						);
+							});
+					}).response.on_hover_text(
+						"Replace the section lists of this section with those \
+						of another section, e.g., to give every interlude the \
+						same constraints as the first. Mentions of this \
+						section itself are dropped."
+					);
+					if let Some(source) = source
+					{
+						let source = constraints[source].clone();
+						constraints[index]
+							.copy_constraints_from(&source, index);
 					}
 				}
 			});
+			if let Some(constraints) = constraints.as_mut()
+			{
+				present_section_details(ui, index, &mut constraints[index]);
+			}
 			let mut truncated: String = section.chars().take(79).collect();
 			truncated.push('…');
 			ui.add_enabled(
@@ -893,23 +12578,31 @@ fn scrollable_sections(
 			{
 				let constraints = &constraints[index];
 				if !constraints.text_buffer_is_valid
+					|| !constraints.after_text_buffer_is_valid
+					|| !constraints.near_text_buffer_is_valid
+					|| !constraints.apart_text_buffer_is_valid
+					|| !constraints.not_adjacent_text_buffer_is_valid
+					|| !constraints.prefer_before_text_buffer_is_valid
 				{
 					ui.label(
 						RichText::new("Invalid list of sections.")
-							.color(hex_color!("#aa0000"))
+							.color(palette(ui).error)
 							.strong()
 					).on_hover_ui(|ui| {
 						ui.horizontal_wrapped(|ui| {
 							ui.spacing_mut().item_spacing.x = 0.0;
 							ui.label(
 								"The section list must be given as a comma-\
-								separated list of section numbers, like "
+								separated list of section numbers or labels, \
+								like "
 							);
 							ui.code("1");
 							ui.label(" or ");
 							ui.code("2,3");
 							ui.label(" or ");
 							ui.code("1,3,7,10");
+							ui.label(" or ");
+							ui.code("Prologue, 4");
 							ui.label(
 								". You can also leave the list empty if \
 								you don't want to constrain the motion of \
@@ -920,11 +12613,29 @@ fn scrollable_sections(
 						});
 					});
 				}
+				let count = sections.len();
+				for problem in constraints.reference_problems(index, count)
+				{
+					ui.label(
+						RichText::new(problem)
+							.color(palette(ui).error)
+							.strong()
+					);
+				}
+				for redundancy in redundancies.into_iter()
+					.flatten()
+					.filter(|r| r.section == indices[index])
+				{
+					ui.label(
+						RichText::new(redundancy.describe())
+							.color(palette(ui).warning)
+					);
+				}
 				if let Some(error) = constraints.paradox_error.as_ref()
 				{
 					ui.label(
 						RichText::new(error.to_string())
-							.color(hex_color!("#aa0000"))
+							.color(palette(ui).error)
 							.strong()
 					).on_hover_ui(|ui| {
 						ui.horizontal_wrapped(|ui| {
@@ -941,19 +12652,909 @@ fn scrollable_sections(
 					});
 				}
 			}
+			if let Some(transition) = transitions.and_then(|t| t.get(index))
+			{
+				present_transition(ui, transition);
+			}
+			ui.separator();
+		}
+		// Draw the links of the pending or hovered section, and the pending
+		// link itself, atop the sections.
+		let Some(constraints) = constraints.as_ref() else { return };
+		let Some(focus) = link_source.or(hovered_handle) else { return };
+		let Some(origin) = handles[focus] else { return };
+		let color = ui.visuals().selection.bg_fill;
+		let painter = ui.painter();
+		let c = &constraints[focus];
+		for successor in &c.before
+		{
+			if let Some(target) = handles.get(*successor - 1).copied().flatten()
+			{
+				link_arrow(painter, origin, target, color);
+			}
+		}
+		for predecessor in &c.after
+		{
+			if let Some(source) =
+				handles.get(*predecessor - 1).copied().flatten()
+			{
+				link_arrow(painter, source, origin, color);
+			}
+		}
+		if link_source.is_some()
+		{
+			if let Some(pointer) = ui.ctx().pointer_hover_pos()
+			{
+				let target = Rect::from_center_size(pointer, Vec2::ZERO);
+				link_arrow(painter, origin, target, color);
+			}
+		}
+	});
+	ui.data_mut(|data| match link_source
+	{
+		Some(source) => data.insert_temp(link_id, source),
+		None => data.remove::<usize>(link_id)
+	});
+	output
+}
+
+/// Draw an arrow that curves from the right edge of the `from` rectangle to
+/// the right edge of the `to` rectangle, e.g., to depict a link between the
+/// handles of two sections.
+fn link_arrow(painter: &Painter, from: Rect, to: Rect, color: Color32)
+{
+	let start = from.right_center();
+	let end = to.right_center();
+	let bulge = LINK_ARROW_BULGE + (end.y - start.y).abs() / 4.0;
+	let control = start + (end - start) / 2.0 + vec2(bulge, 0.0);
+	painter.add(QuadraticBezierShape::from_points_stroke(
+		[start, control, end],
+		false,
+		Color32::TRANSPARENT,
+		Stroke::new(LINK_ARROW_WIDTH, color)
+	));
+	let direction = (end - control).normalized();
+	let back = end - direction * LINK_ARROWHEAD_LENGTH;
+	let across = direction.rot90() * LINK_ARROWHEAD_LENGTH / 2.0;
+	painter.add(Shape::convex_polygon(
+		vec![end, back + across, back - across],
+		color,
+		Stroke::NONE
+	));
+}
+
+/// Display the collapsible details of the section at the specified
+/// **zero-based** index, i.e., the descriptive attributes of its
+/// [constraints](Constraints), and handle any edits.
+fn present_section_details(ui: &mut Ui, index: usize, c: &mut Constraints)
+{
+	let title = if c.label.trim().is_empty() { "Details".to_string() }
+		else { format!("Details: {}", c.label.trim()) };
+	CollapsingHeader::new(title)
+		.id_source(("section_details", index))
+		.show(ui, |ui| {
+			Grid::new(("section_details_grid", index))
+				.num_columns(2)
+				.show(ui, |ui| {
+					ui.label("Label:").on_hover_text(
+						"A short name for this section, e.g., for sorting."
+					);
+					ui.text_edit_singleline(&mut c.label);
+					ui.end_row();
+					ui.label("Date:").on_hover_text(
+						"When this section takes place in the story, like \
+						1999-03-04 or Day 3. Numbers are compared numerically \
+						when sorting by date."
+					);
+					ui.text_edit_singleline(&mut c.date);
+					ui.end_row();
+					ui.label("Tags:").on_hover_text(
+						"A comma-separated list of tags for this section, like \
+						flashback, Ana. Sorting by tag uses the first tag."
+					);
+					ui.text_edit_singleline(&mut c.tags);
+					ui.end_row();
+					ui.label("Group:").on_hover_text(
+						"The name of a group of sections that move as a block \
+						when shuffled, staying together and in their original \
+						order, like a flashback told in several sections. \
+						Leave this empty to let this section move on its own."
+					);
+					ui.text_edit_singleline(&mut c.group);
+					ui.end_row();
+					ui.label("Chapter:").on_hover_text(
+						"The name of the chapter to which this section \
+						belongs. The sections of a chapter stay together when \
+						shuffled; choose whether they shuffle within the \
+						chapter, or the chapter moves as a unit, or both, \
+						under Chapters in the Constraints section."
+					);
+					ui.text_edit_singleline(&mut c.chapter);
+					ui.end_row();
+					ui.label("Part:").on_hover_text(
+						"Whether this section belongs to the body of the work, \
+						which is shuffled, or to the front matter or back \
+						matter, like a dedication or acknowledgments, which \
+						stay at the very beginning or end."
+					);
+					ComboBox::from_id_source(("section_matter", index))
+						.selected_text(c.matter.name())
+						.show_ui(ui, |ui| {
+							for matter in Matter::ALL
+							{
+								ui.selectable_value(
+									&mut c.matter,
+									matter,
+									matter.name()
+								);
+							}
+						});
+					ui.end_row();
+				});
+			ui.checkbox(&mut c.must_include, "Always include in excerpts")
+				.on_hover_text(
+					"Check this box if every excerpt of the manuscript should \
+					include this section."
+				);
+		});
+}
+
+/// Display a [scrollable&#32;area][ScrollArea] containing every transition
+/// between adjacent sections, i.e., the last line of each section alongside
+/// the first line of the next.
+fn scrollable_transitions(
+	ui: &mut Ui,
+	indices: &[usize],
+	sections: &[String],
+	transitions: &[Transition]
+) -> ScrollAreaOutput<()>
+{
+	profile_function!();
+	ScrollArea::vertical().show(ui, |ui| {
+		for (index, pair) in sections.windows(2).enumerate()
+		{
+			// Writers are not necessarily programmers, so let's present
+			// one-based indices.
+			ui.label(
+				RichText::new(format!(
+					"§{} → §{}",
+					indices[index] + 1,
+					indices[index + 1] + 1
+				)).strong()
+			);
+			ui.label(RichText::new(last_line(&pair[0])).italics());
+			ui.label(first_line(&pair[1]));
+			if let Some(transition) = transitions.get(index)
+			{
+				present_transition(ui, transition);
+			}
 			ui.separator();
 		}
 	})
 }
 
+/// Flag any problems with the specified [transition](Transition), i.e., from
+/// the section just presented to the next one.
+fn present_transition(ui: &mut Ui, transition: &Transition)
+{
+	if transition.is_poor()
+	{
+		ui.label(
+			RichText::new(format!(
+				"⚠ Weak transition (score {:.2})",
+				transition.score
+			))
+				.color(palette(ui).warning)
+				.strong()
+		).on_hover_text(
+			"The transition into the next section scored poorly according to \
+			some simple heuristics. These are just hints, so trust your ear."
+		);
+	}
+	for problem in &transition.problems
+	{
+		ui.label(
+			RichText::new(format!("⚠ {}", problem)).color(palette(ui).warning)
+		);
+	}
+	if !transition.echoes.is_empty()
+	{
+		let echoes = transition.echoes.iter()
+			.map(|echo| format!("“{}”", echo))
+			.collect::<Vec<_>>()
+			.join(", ");
+		ui.label(
+			RichText::new(format!("⚠ Echoes into the next section: {}", echoes))
+				.color(palette(ui).warning)
+		).on_hover_text(
+			"These distinctive words or phrases occur both near the end of \
+			this section and near the beginning of the next one. Repetition \
+			across a section break often reads badly, so you might want to \
+			revise one of them or reshuffle."
+		);
+	}
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 //                                 Constants.                                 //
 ////////////////////////////////////////////////////////////////////////////////
 
+/// The default interval between automatic saves, in seconds.
+const DEFAULT_AUTOSAVE_INTERVAL: u64 = 30;
+
+/// The keyboard shortcut for saving the application state right away.
+const SAVE_SHORTCUT: KeyboardShortcut =
+	KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+
+/// The keyboard shortcut for undoing the most recent edit of the
+/// [constraints](StoryShufflerApp::constraints).
+const UNDO_SHORTCUT: KeyboardShortcut =
+	KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+
+/// The keyboard shortcut for redoing the most recently undone edit of the
+/// [constraints](StoryShufflerApp::constraints).
+const REDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(
+	Modifiers::COMMAND.plus(Modifiers::SHIFT),
+	Key::Z
+);
+
+/// The minimum horizontal distance by which a [link&#32;arrow](link_arrow)
+/// curves away from the handles that it joins, in points.
+const LINK_ARROW_BULGE: f32 = 24.0;
+
+/// The width of a [link&#32;arrow](link_arrow), in points.
+const LINK_ARROW_WIDTH: f32 = 2.0;
+
+/// The length of the head of a [link&#32;arrow](link_arrow), in points.
+const LINK_ARROWHEAD_LENGTH: f32 = 8.0;
+
+/// The maximum number of characters of a section's opening to include in its
+/// [diagram](Diagram) node.
+const DIAGRAM_DESCRIPTION_LENGTH: usize = 60;
+
+/// The web query parameter that opens the application in
+/// [review&#32;mode](StoryShufflerApp::review_mode), e.g., `?review`.
+#[cfg(target_arch = "wasm32")]
+const REVIEW_PARAMETER: &str = "review";
+
+/// The length of a [link](StoryShufflerApp::copy_project_link), in bytes,
+/// beyond which some mail and chat applications truncate links. Only
+/// available on the web.
+#[cfg(target_arch = "wasm32")]
+const MAXIMUM_PORTABLE_LINK_LENGTH: usize = 2_000;
+
+/// The web query parameter that starts an
+/// [ephemeral](StoryShufflerApp::ephemeral) session, e.g., `?no-persist`.
+#[cfg(target_arch = "wasm32")]
+const NO_PERSIST_PARAMETER: &str = "no-persist";
+
+/// The file name of the temporary copy of the shuffled manuscript that is
+/// opened in the writer's editor.
+#[cfg(not(target_arch = "wasm32"))]
+const TEMPORARY_MANUSCRIPT_NAME: &str = "shuffled-manuscript.txt";
+
+/// The stem of the suggested file name of the shuffled manuscript, if the
+/// manuscript didn't come from a file.
+const SHUFFLED_MANUSCRIPT_STEM: &str = "shuffled-manuscript";
+
+/// The stem of the suggested file name of a [project](Project), if the
+/// manuscript didn't come from a file.
+const DEFAULT_PROJECT_STEM: &str = "manuscript";
+
+/// The title of exported documents, if the manuscript didn't come from a
+/// file.
+const DEFAULT_MANUSCRIPT_TITLE: &str = "Shuffled manuscript";
+
+/// The conventional section break, which separates the sections of an
+/// assembled manuscript beyond the matches of a delimiter that is a regular
+/// expression.
+const DINKUS: &str = "* * *";
+
+/// The common [output&#32;separators](StoryShufflerApp::output_separator),
+/// by name.
+const OUTPUT_SEPARATORS: [(&str, &str); 4] = [
+	("Dinkus (* * *)", DINKUS),
+	("Asterisks (***)", "***"),
+	("Pound sign (#)", "#"),
+	("Blank line", "")
+];
+
+/// The maximum length of the excerpt of each section in an
+/// [exported&#32;mapping](StoryShufflerApp::export_mapping), in characters.
+const MAPPING_EXCERPT_LENGTH: usize = 60;
+
+/// The default target length of a chapter, in words.
+const DEFAULT_CHAPTER_LENGTH: usize = 3000;
+
+/// The minimum target length of a chapter, in words.
+const MINIMUM_CHAPTER_LENGTH: usize = 100;
+
+/// The maximum target length of a chapter, in words.
+const MAXIMUM_CHAPTER_LENGTH: usize = 50_000;
+
+/// The word that introduces the generated heading of each chapter, which is
+/// followed by the number of the chapter.
+const CHAPTER_HEADING: &str = "Chapter";
+
+/// The default number of sections to select for an excerpt.
+const DEFAULT_EXCERPT_LENGTH: usize = 5;
+
+/// The default number of [variants](StoryShufflerApp::generate_variants) to
+/// generate.
+const DEFAULT_VARIANT_COUNT: usize = 5;
+
+/// The maximum number of [variants](StoryShufflerApp::generate_variants) to
+/// generate, such that two digits number them all.
+const MAXIMUM_VARIANT_COUNT: usize = 99;
+
+/// The number of shuffles to attempt per requested
+/// [variant](StoryShufflerApp::generate_variants) before concluding that the
+/// constraints permit no more distinct orderings.
+const VARIANT_ATTEMPTS: usize = 20;
+
+/// The maximum number of times to retry a random ordering that reached an
+/// impasse while honoring a [rotation](StoryShufflerApp::rotation).
+const MAXIMUM_ROTATION_RETRIES: usize = 100;
+
+/// The maximum number of times to retry a random ordering that reached an
+/// impasse while honoring the [pinned](Constraints::pin) positions, or that
+/// strayed from the [spacing](first_stray) requirements.
+const MAXIMUM_PLACEMENT_RETRIES: usize = 100;
+
+/// The default [distance](Constraints::near_distance) within which a section
+/// must land of the sections [near](Constraints::near) it.
+const DEFAULT_NEAR_DISTANCE: usize = 3;
+
+/// The default [offset](Constraints::offset) by which a section follows the
+/// section that it's [tethered](Constraints::offset_from) to.
+const DEFAULT_OFFSET: usize = 1;
+
+/// The width of an edge in the
+/// [graph&#32;window](StoryShufflerApp::present_graph_window), in points.
+const GRAPH_EDGE_WIDTH: f32 = 1.5;
+
+/// The width of an edge of the selected section in the
+/// [graph&#32;window](StoryShufflerApp::present_graph_window), in points.
+const HIGHLIGHTED_GRAPH_EDGE_WIDTH: f32 = 3.0;
+
+/// The width of the outline of a fixed section in the
+/// [graph&#32;window](StoryShufflerApp::present_graph_window), in points.
+const FIXED_GRAPH_NODE_STROKE_WIDTH: f32 = 3.0;
+
+/// The font size of the labels of the sections in the
+/// [graph&#32;window](StoryShufflerApp::present_graph_window), in points.
+const GRAPH_LABEL_SIZE: f32 = 14.0;
+
+/// The forms of the statements of a constraint script, each with an example
+/// and its meaning, for the writer's reference.
+const SCRIPT_EXAMPLES: [(&str, &str); 15] = [
+	("3 < 7 < 9", "§3 comes before §7, which comes before §9"),
+	("7 > 3", "§7 comes after §3"),
+	("fix 1", "§1 stays in place"),
+	("first 3", "§3 comes before every other section"),
+	("last 9", "§9 comes after every other section"),
+	("pin 7 = 3", "§7 is always the third section"),
+	("odd 2", "§2 only lands at odd positions, i.e., first, third, …"),
+	("repeat 4 = 3 apart 2", "§4 appears 3 times, at least 2 sections apart"),
+	("group A = 2, 4, 5", "§2, §4, and §5 shuffle as a block"),
+	("near(2, 5) <= 3", "§2 and §5 land within 3 places of each other"),
+	("apart(6, 9) >= 4", "at least 4 other sections separate §6 and §9"),
+	("apart(6, 9)", "§6 and §9 are never adjacent"),
+	("offset(3, 7) = 2", "§7 lands exactly 2 places after §3"),
+	("prefer 3 < 7 weight 2", "§3 should preferably come before §7"),
+	("prefer 4 early weight 2", "§4 should preferably land early")
+];
+
+/// The default [weight](Constraints::preference_weight) of a preference.
+const DEFAULT_PREFERENCE_WEIGHT: f32 = 1.0;
+
+/// The minimum [weight](Constraints::preference_weight) of a preference.
+const MINIMUM_PREFERENCE_WEIGHT: f32 = 0.1;
+
+/// The maximum [weight](Constraints::preference_weight) of a preference.
+const MAXIMUM_PREFERENCE_WEIGHT: f32 = 10.0;
+
+/// The default [weight](Constraints::place_weight) of a place preference.
+const DEFAULT_PLACE_WEIGHT: f32 = 1.0;
+
+/// The minimum [weight](Constraints::place_weight) of a place preference.
+const MINIMUM_PLACE_WEIGHT: f32 = 0.1;
+
+/// The maximum [weight](Constraints::place_weight) of a place preference.
+const MAXIMUM_PLACE_WEIGHT: f32 = 10.0;
+
+/// The default number of other sections that must
+/// [separate](Constraints::apart_gap) a section from the sections
+/// [apart](Constraints::apart) from it.
+const DEFAULT_APART_GAP: usize = 2;
+
+/// The default number of other sections that must
+/// [separate](Constraints::repeat_gap) the appearances of a
+/// [repeated](Constraints::repeats) section.
+const DEFAULT_REPEAT_GAP: usize = 2;
+
+/// The greatest number of times that a section may
+/// [appear](Constraints::repeats) in a reordering.
+const MAXIMUM_REPEATS: usize = 10;
+
+/// The deepest level of Markdown heading.
+const MAXIMUM_HEADING_LEVEL: usize = 6;
+
+/// The [kinds](FileKind) of files from which a manuscript may be opened. Only
+/// available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+const MANUSCRIPT_KINDS: [FileKind; 4] = [TEXT, MARKDOWN, ODT, RTF];
+
+/// The minimum number of sections that a manuscript imported from the
+/// clipboard must yield under a [split&#32;mode](SplitMode) for
+/// [detection](StoryShufflerApp::detect_split_mode) to choose it.
+const MINIMUM_DETECTED_SECTIONS: usize = 3;
+
+/// The words that begin a [Fountain](https://fountain.io) scene heading, in
+/// upper case.
+const SCENE_HEADING_PREFIXES: [&str; 6] =
+	["INT./EXT", "INT/EXT", "I/E", "INT", "EXT", "EST"];
+
+/// The name of the initial [scenario](Scenario).
+const DEFAULT_SCENARIO_NAME: &str = "Default";
+
+/// The maximum number of orderings to remember in the
+/// [shuffle&#32;history](StoryShufflerApp::shuffle_history) of each
+/// [scenario](Scenario).
+const MAXIMUM_HISTORY_LENGTH: usize = 20;
+
+/// The maximum number of orderings to consider when
+/// [stepping](StoryShufflerApp::step_order) to the next valid ordering, which
+/// bounds the search when the placement requirements reject most of them.
+const MAXIMUM_ORDERING_STEPS: usize = 100_000;
+
+/// The maximum number of [records](ShuffleRecord) to keep in the
+/// [shuffle&#32;log](StoryShufflerApp::shuffle_log).
+const MAXIMUM_SHUFFLE_LOG_LENGTH: usize = 1_000;
+
+/// The maximum number of edits of the
+/// [constraints](StoryShufflerApp::constraints) that can be
+/// [undone](StoryShufflerApp::undo_constraint_edit).
+const MAXIMUM_CONSTRAINT_UNDO_LENGTH: usize = 100;
+
+/// The number of leading sections by which to describe each ordering of the
+/// [shuffle&#32;history](StoryShufflerApp::shuffle_history).
+const HISTORY_PREVIEW_LENGTH: usize = 8;
+
+/// The number of shuffles to sample for the position heatmap.
+const HEATMAP_SAMPLES: usize = 10_000;
+
+/// The width and height of each cell of the position heatmap, in points.
+const HEATMAP_CELL_SIZE: f32 = 16.0;
+
+/// The maximum number of random orderings to produce per shuffle.
+const MAXIMUM_SHUFFLE_ATTEMPTS: usize = 100;
+
 /// The default section delimiter, which is _not_ a
 /// [regular&#32;expression](Regex). Defaults to dinkus, e.g., `* * *`.
 const DEFAULT_DELIMITER_PATTERN: &str = r#"* * *"#;
 
+/// The default story delimiter of an
+/// [anthology](StoryShufflerApp::anthology).
+const DEFAULT_STORY_DELIMITER: &str = "# # #";
+
+/// The maximum number of [labels](Constraints::label) to suggest beside a
+/// [section&#32;list](edit_section_list).
+const MAXIMUM_LABEL_SUGGESTIONS: usize = 5;
+
+/// The width of the tag field of a [tag&#32;constraint](TagConstraint), in
+/// points.
+const TAG_FIELD_WIDTH: f32 = 80.0;
+
 /// The [regular&#32;expression](Regex) for validating comma-separated lists of
 /// section numbers.
 const SECTIONS_LIST_PATTERN: &str = r#"^(?:\s*\d+\s*(?:,\s*\d+\s*)*)?$"#;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use crate::analysis::sample_positions;
+	use super::*;
+
+	/// Answer an application whose manuscript comprises the specified number
+	/// of sections.
+	fn app(count: usize) -> StoryShufflerApp
+	{
+		let mut app = StoryShufflerApp {
+			original_manuscript: (1 ..= count)
+				.map(|n| format!("Section {}.", n))
+				.collect::<Vec<_>>()
+				.join("\n\n* * *\n\n"),
+			..Default::default()
+		};
+		app.update_sections();
+		assert_eq!(app.constraints.len(), count);
+		app
+	}
+
+	#[test]
+	fn refuses_projects_that_refer_to_nonexistent_sections()
+	{
+		let mut app = app(3);
+		for bad in [0, 4]
+		{
+			let mut project = app.project();
+			project.constraints[0].before = vec![bad];
+			assert!(app.adopt_project(project).is_err(), "§{}", bad);
+			let mut project = app.project();
+			project.constraints[2].offset_from = Some(bad);
+			assert!(app.adopt_project(project).is_err(), "§{}", bad);
+		}
+		let mut project = app.project();
+		project.constraints[0].before = vec![3];
+		assert!(app.adopt_project(project).is_ok());
+	}
+
+	#[test]
+	fn recounts_orderings_only_when_the_graph_changes()
+	{
+		let mut app = app(3);
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(6)));
+		// Plant a bogus count, which survives as long as the graph does.
+		let key = OrderingCountKey::of(&app.constraint_graph());
+		app.ordering_count = Some((key, OrderingCount::Exact(42)));
+		app.constraints[0].label = "Opening".to_string();
+		app.constraints[1].text_buffer = "3, ".to_string();
+		app.constraints[2].paradox_error = Some("Paradox".to_string());
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(42)));
+		app.constraints[1].before = vec![3];
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(3)));
+	}
+
+	#[test]
+	fn samples_the_heatmap_as_shuffling_does()
+	{
+		let mut app = app(4);
+		app.constraints[2].pin = Some(1);
+		app.constraints[3].omit = true;
+		let graph = app.mark_cycles().unwrap();
+		let mut plan = app.shuffle_plan(graph).unwrap();
+		let heatmap = sample_positions(
+			4,
+			100,
+			&mut |rng| plan.draw(rng),
+			&mut StdRng::seed_from_u64(1)
+		);
+		assert_eq!(heatmap.samples, 100);
+		assert_eq!(heatmap.counts[2][0], 100);
+		assert!(heatmap.counts[3].iter().all(|count| *count == 0));
+	}
+
+	#[test]
+	fn places_sections_among_those_shown()
+	{
+		let mut app = app(5);
+		app.constraints[0].omit = true;
+		app.constraints[1].before = vec![4];
+		app.constraints[3].pin = Some(2);
+		app.constraints[4].fixed = true;
+		let graph = app.mark_cycles().unwrap();
+		let mut plan = app.shuffle_plan(graph).unwrap();
+		let mut rng = StdRng::seed_from_u64(3);
+		for _ in 0 .. 20
+		{
+			let order = plan.draw(&mut rng).unwrap();
+			assert_eq!(order.len(), 4);
+			assert_eq!(order[1], 3);
+			assert_eq!(order[3], 4);
+		}
+	}
+
+	#[test]
+	fn keeps_omitted_sections_in_the_complete_order()
+	{
+		let mut app = app(4);
+		app.constraints[1].omit = true;
+		let graph = app.mark_cycles().unwrap();
+		app.shuffle(graph.clone());
+		let order = app.current_order().unwrap();
+		assert_eq!(order.len(), 4);
+		assert_eq!(app.shuffled_sections.as_ref().map(Vec::len), Some(3));
+		assert!(!app.presented_section_indices.as_ref().unwrap().contains(&1));
+		// Reversing reverses the shuffle, not the original order.
+		app.reverse(graph);
+		let reversed = app.current_order().unwrap();
+		assert_eq!(reversed, order.into_iter().rev().collect::<Vec<_>>());
+		// Freezing chains every section, omitted or not.
+		app.freeze_order(&reversed);
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(1)));
+		// The shuffle survives a visit to another scenario.
+		app.add_scenario();
+		assert!(app.current_order().is_none());
+		app.switch_scenario(0);
+		assert_eq!(app.current_order(), Some(reversed));
+	}
+
+	#[test]
+	fn withholds_every_trace_of_the_manuscript()
+	{
+		let mut app = app(3);
+		app.manuscript_file_name = Some("Secret Novel.md".to_string());
+		app.delimiter_matches =
+			vec!["Secret break".to_string(), "Secret break".to_string()];
+		let text = app.withhold_manuscript();
+		let persisted = serde_json::to_string(&app).unwrap();
+		assert!(!persisted.contains("Secret"));
+		assert!(!persisted.contains("Section 1."));
+		app.restore_manuscript(text);
+		assert_eq!(app.manuscript_file_name.as_deref(), Some("Secret Novel.md"));
+		assert_eq!(app.delimiter_matches.len(), 2);
+		assert_eq!(app.original_sections.len(), 3);
+	}
+
+	#[test]
+	fn opens_projects_from_links()
+	{
+		let mut shared = app(3);
+		shared.constraints[2].before.push(1);
+		let blob = encode_blob(&shared.project()).unwrap();
+		let link = blob_link("https://example.com/", &blob);
+		// Some mail applications percent-encode the colons.
+		let fragment = link.split_once('#').unwrap().1.replace(':', "%3A");
+		let blob = blob_from_fragment(&fragment).unwrap();
+		let mut app = app(1);
+		app.adopt_project_link(&blob);
+		assert!(app.status.as_ref().map_or(false, |s| !s.is_error));
+		assert_eq!(app.original_sections, shared.original_sections);
+		assert_eq!(app.constraints[2].before, vec![1]);
+	}
+
+	#[test]
+	fn rejects_garbage_links()
+	{
+		let mut app = app(2);
+		for garbage in ["story-shuffler:1:garbage", "story-shuffler:1:"]
+		{
+			app.adopt_project_link(garbage);
+			assert!(app.status.as_ref().map_or(false, |s| s.is_error));
+			assert_eq!(app.original_sections.len(), 2);
+		}
+		// A link to a damaged project changes nothing either.
+		let mut damaged = app.project();
+		damaged.constraints[0].before.push(7);
+		app.adopt_project_link(&encode_blob(&damaged).unwrap());
+		assert!(app.status.as_ref().map_or(false, |s| s.is_error));
+		assert!(app.constraints[0].before.is_empty());
+	}
+
+	#[test]
+	fn compares_scenarios_by_their_own_constraints()
+	{
+		let mut app = app(4);
+		app.add_scenario();
+		app.constraints[3].pin = Some(1);
+		app.constraints[0].omit = true;
+		app.switch_scenario(0);
+		app.constraints[1].pin = Some(4);
+		app.compare_scenarios();
+		let comparison = app.comparison.take().unwrap();
+		let active = comparison[0].sample.as_ref().unwrap();
+		assert_eq!(active.len(), 4);
+		assert_eq!(active[3], 1);
+		let other = comparison[1].sample.as_ref().unwrap();
+		assert_eq!(other.len(), 3);
+		assert_eq!(other[0], 3);
+		assert!(!other.contains(&0));
+		// Comparing leaves the active scenario alone.
+		assert_eq!(app.constraints[1].pin, Some(4));
+		assert!(!app.constraints[0].omit);
+		app.switch_scenario(1);
+		assert_eq!(app.constraints[3].pin, Some(1));
+	}
+
+	#[test]
+	fn keeps_repeats_out_of_the_complete_order()
+	{
+		let mut app = app(4);
+		app.constraints[0].repeats = 2;
+		app.constraints[0].repeat_gap = 0;
+		let graph = app.mark_cycles().unwrap();
+		app.shuffle(graph.clone());
+		let order = app.current_order().unwrap();
+		assert!(fits_sections(&order, 4));
+		let presented = app.presented_order().unwrap();
+		assert_eq!(presented.iter().filter(|index| **index == 0).count(), 2);
+		// Reversing reverses the shuffle, and repeats the section afresh.
+		app.reverse(graph.clone());
+		let reversed = app.current_order().unwrap();
+		assert_eq!(reversed, order.into_iter().rev().collect::<Vec<_>>());
+		assert_eq!(app.presented_order().map(|p| p.len()), Some(5));
+		// Stepping works, too.
+		app.step_order(graph, true);
+		assert!(app.status.as_ref().map_or(false, |s| !s.is_error));
+		let stepped = app.current_order().unwrap();
+		assert_ne!(stepped, reversed);
+		// Learning from the order forces it.
+		app.learn_order(&stepped);
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(1)));
+		// The shuffle survives a visit to another scenario.
+		app.add_scenario();
+		app.switch_scenario(0);
+		assert_eq!(app.current_order(), Some(stepped));
+		assert_eq!(app.presented_order().map(|p| p.len()), Some(5));
+	}
+
+	#[test]
+	fn samples_uniformly_only_when_nothing_else_governs()
+	{
+		let mut app = app(4);
+		app.uniform_sampling = true;
+		let graph = app.mark_cycles().unwrap();
+		assert!(app.uniform_obstacle().is_none());
+		assert!(app.shuffle_plan(graph.clone()).unwrap().sampler.is_some());
+		app.constraints[1].pin = Some(3);
+		assert!(app.uniform_obstacle().is_some());
+		assert!(app.shuffle_plan(graph).unwrap().sampler.is_none());
+	}
+
+	#[test]
+	fn round_trips_the_constraint_script()
+	{
+		let mut original = app(5);
+		let c = &mut original.constraints;
+		c[0].before = vec![3];
+		c[0].place_preference = PlacePreference::Late;
+		c[1].pin = Some(4);
+		c[1].repeats = 2;
+		c[1].repeat_gap = 1;
+		c[2].parity = Some(Parity::Odd);
+		c[2].group = "A".to_string();
+		c[3].near = vec![5];
+		c[3].near_distance = 2;
+		c[3].prefer_before = vec![1];
+		c[4].after = vec![2];
+		c[4].offset_from = Some(1);
+		c[4].offset = 2;
+		c[4].group = "A".to_string();
+		let script = original.constraint_script();
+		let statements = parse_script(&script).unwrap();
+		let set = original.script_constraint_set(&statements).unwrap();
+		let mut copy = app(5);
+		assert!(copy.apply_constraint_set(&set).is_ok());
+		assert_eq!(copy.constraint_script(), script);
+		assert_eq!(
+			serde_json::to_string(&copy.constraint_set()).unwrap(),
+			serde_json::to_string(&original.constraint_set()).unwrap()
+		);
+	}
+
+	#[test]
+	fn reports_conflicting_statements_by_line()
+	{
+		let app = app(3);
+		let statements = parse_script("pin 1 = 2\n1 < 3\n\npin 1 = 3").unwrap();
+		assert_eq!(
+			app.script_constraint_set(&statements).err(),
+			Some(vec![
+				"Line 4: §1 is already pinned to position 2.".to_string()
+			])
+		);
+	}
+
+	#[test]
+	fn counts_orderings_of_the_whole_system_of_constraints()
+	{
+		let mut app = app(4);
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(24)));
+		// A tag constraint puts §4 after §1.
+		app.constraints[3].tags = "x".to_string();
+		app.tag_constraints.push(TagConstraint {
+			tag: "x".to_string(),
+			kind: TagConstraintKind::After(1)
+		});
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(12)));
+		// A chapter that moves as a unit keeps §1 before §2.
+		app.constraints[0].chapter = "A".to_string();
+		app.constraints[1].chapter = "A".to_string();
+		app.chapters.push(Chapter {
+			name: "A".to_string(),
+			policy: ChapterPolicy::Unit
+		});
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(8)));
+		// An anthology ignores the chapters.
+		app.anthology = true;
+		assert_eq!(app.valid_orderings(), Some(OrderingCount::Exact(12)));
+	}
+
+	#[test]
+	fn remaps_constraints_to_follow_their_sections()
+	{
+		let mut constraints = vec![Constraints::default(); 3];
+		constraints[0].before = vec![3];
+		constraints[0].near = vec![2];
+		constraints[1].after = vec![1];
+		// The old §3 comes first, then the old §1; the old §2 is gone.
+		let remapped = remap_constraints(&constraints, &[Some(2), Some(0)]);
+		assert_eq!(remapped.len(), 2);
+		assert!(remapped[0].before.is_empty());
+		assert_eq!(remapped[1].before, vec![1]);
+		assert_eq!(remapped[1].text_buffer, "1");
+		assert!(remapped[1].near.is_empty());
+	}
+
+	#[test]
+	fn remaps_tag_constraints_to_follow_their_sections()
+	{
+		let tag_constraint = |kind| TagConstraint {
+			tag: "x".to_string(),
+			kind
+		};
+		let remapped = remap_tag_constraints(
+			&[
+				tag_constraint(TagConstraintKind::After(1)),
+				tag_constraint(TagConstraintKind::Before(2)),
+				tag_constraint(TagConstraintKind::NeverAdjacent)
+			],
+			3,
+			&[Some(2), Some(0)]
+		);
+		assert_eq!(remapped, vec![
+			tag_constraint(TagConstraintKind::After(2)),
+			tag_constraint(TagConstraintKind::NeverAdjacent)
+		]);
+	}
+
+	#[test]
+	fn keeps_chapters_together()
+	{
+		let mut app = app(4);
+		app.constraints[0].chapter = "A".to_string();
+		app.constraints[1].chapter = "A".to_string();
+		app.chapters.push(Chapter {
+			name: "A".to_string(),
+			policy: ChapterPolicy::Unit
+		});
+		assert!(app.grouping_violation(&[0, 2, 1, 3]).is_some());
+		assert!(app.grouping_violation(&[1, 0, 2, 3]).is_some());
+		assert!(app.grouping_violation(&[2, 0, 1, 3]).is_none());
+	}
+
+	#[test]
+	fn reports_every_constraint_in_the_review_packet()
+	{
+		let mut app = app(4);
+		app.constraints[0].prefer_before = vec![2];
+		let packet = app.review_packet();
+		assert!(!packet.contains(&Block::Paragraph(
+			"There are no constraints, so every ordering is valid.".to_string()
+		)));
+		app.constraints[1].parity = Some(Parity::Even);
+		app.constraints[2].omit = true;
+		app.constraints[3].tags = "x".to_string();
+		app.tag_constraints.push(TagConstraint {
+			tag: "x".to_string(),
+			kind: TagConstraintKind::After(1)
+		});
+		let packet = app.review_packet();
+		for bullet in [
+			"§2 may only occupy even positions.",
+			"§3 is omitted from the shuffle.",
+			"§4 is tagged x.",
+			"Every section tagged x must come after §1."
+		]
+		{
+			assert!(
+				packet.contains(&Block::Bullet(bullet.to_string())),
+				"{}",
+				bullet
+			);
+		}
+		// The count agrees with the sidebar, which honors the tag constraint.
+		let orderings = app.valid_orderings().unwrap();
+		assert_eq!(orderings, OrderingCount::Exact(12));
+		let count = format!(": {}.", orderings);
+		assert!(packet.iter().any(|block| matches!(
+			block,
+			Block::Paragraph(text) if text.contains(&count)
+		)));
+	}
+}
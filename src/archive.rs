@@ -0,0 +1,353 @@
+/*
+ * archive.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Archives.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A ZIP archive under construction, e.g., the package of an office document
+/// or an e-book. Entries are written in the order of their addition, which
+/// matters for formats that require a particular first entry.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Archive
+{
+	/// The serialized local headers and data of the entries so far.
+	bytes: Vec<u8>,
+
+	/// The serialized central directory records of the entries so far.
+	directory: Vec<u8>,
+
+	/// The number of entries so far.
+	count: u16
+}
+
+impl Archive
+{
+	/// Create an empty archive.
+	pub(crate) fn new() -> Self
+	{
+		Default::default()
+	}
+
+	/// Add an entry with the specified name and uncompressed contents, as
+	/// some formats require for particular entries, e.g., the `mimetype` of
+	/// an EPUB.
+	pub(crate) fn store(&mut self, name: &str, contents: &[u8]) -> &mut Self
+	{
+		self.add(name, contents, contents, STORED)
+	}
+
+	/// Add an entry with the specified name and contents, compressed with
+	/// DEFLATE.
+	pub(crate) fn deflate(&mut self, name: &str, contents: &[u8]) -> &mut Self
+	{
+		let compressed = miniz_oxide::deflate::compress_to_vec(
+			contents,
+			COMPRESSION_LEVEL
+		);
+		self.add(name, contents, &compressed, DEFLATED)
+	}
+
+	/// Add an entry with the specified name, uncompressed contents, encoded
+	/// data, and compression method.
+	fn add(
+		&mut self,
+		name: &str,
+		contents: &[u8],
+		data: &[u8],
+		method: u16
+	) -> &mut Self
+	{
+		let offset = self.bytes.len() as u32;
+		let crc = crc32(contents);
+		// The fields common to the local header and the directory record,
+		// from the version needed to extract through the length of the name.
+		let mut common = vec![];
+		common.extend(VERSION_NEEDED.to_le_bytes());
+		common.extend(UTF8_NAMES.to_le_bytes());
+		common.extend(method.to_le_bytes());
+		common.extend(DOS_TIME.to_le_bytes());
+		common.extend(DOS_DATE.to_le_bytes());
+		common.extend(crc.to_le_bytes());
+		common.extend((data.len() as u32).to_le_bytes());
+		common.extend((contents.len() as u32).to_le_bytes());
+		common.extend((name.len() as u16).to_le_bytes());
+		self.bytes.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+		self.bytes.extend(&common);
+		// There is no extra field.
+		self.bytes.extend(0u16.to_le_bytes());
+		self.bytes.extend(name.as_bytes());
+		self.bytes.extend(data);
+		self.directory.extend(DIRECTORY_SIGNATURE.to_le_bytes());
+		self.directory.extend(VERSION_MADE_BY.to_le_bytes());
+		self.directory.extend(&common);
+		// There is no extra field or comment, the entry begins on the first
+		// disk, and there are no attributes.
+		self.directory.extend([0u8; 12]);
+		self.directory.extend(offset.to_le_bytes());
+		self.directory.extend(name.as_bytes());
+		self.count += 1;
+		self
+	}
+
+	/// Answer the finished archive.
+	pub(crate) fn finish(&self) -> Vec<u8>
+	{
+		let mut bytes = self.bytes.clone();
+		let directory_offset = bytes.len() as u32;
+		bytes.extend(&self.directory);
+		bytes.extend(END_SIGNATURE.to_le_bytes());
+		// The archive occupies a single disk.
+		bytes.extend([0u8; 4]);
+		bytes.extend(self.count.to_le_bytes());
+		bytes.extend(self.count.to_le_bytes());
+		bytes.extend((self.directory.len() as u32).to_le_bytes());
+		bytes.extend(directory_offset.to_le_bytes());
+		// There is no comment.
+		bytes.extend(0u16.to_le_bytes());
+		bytes
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Extraction.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the specified bytes look like a ZIP archive.
+pub(crate) fn is_archive(bytes: &[u8]) -> bool
+{
+	bytes.starts_with(&LOCAL_HEADER_SIGNATURE.to_le_bytes())
+}
+
+/// Extract the entry with the specified name from the specified ZIP archive.
+/// Answer the uncompressed contents of the entry, or `None` if there is no
+/// such entry. Answer an explanation if the archive is damaged or uses an
+/// unsupported compression method.
+pub(crate) fn extract(
+	archive: &[u8],
+	name: &str
+) -> Result<Option<Vec<u8>>, String>
+{
+	let damaged = || "the archive is damaged".to_string();
+	let u16_at = |offset: usize| archive.get(offset .. offset + 2)
+		.map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+		.ok_or_else(damaged);
+	let u32_at = |offset: usize| archive.get(offset .. offset + 4)
+		.map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+		.ok_or_else(damaged);
+	// The end of the central directory is followed only by a comment, so
+	// search backward for its signature.
+	let end = (0 .. archive.len().saturating_sub(END_LENGTH - 1))
+		.rev()
+		.find(|offset| u32_at(*offset) == Ok(END_SIGNATURE as usize))
+		.ok_or_else(damaged)?;
+	let count = u16_at(end + 10)?;
+	let mut record = u32_at(end + 16)?;
+	for _ in 0 .. count
+	{
+		if u32_at(record)? != DIRECTORY_SIGNATURE as usize
+		{
+			return Err(damaged())
+		}
+		let method = u16_at(record + 10)? as u16;
+		let crc = u32_at(record + 16)? as u32;
+		let compressed_length = u32_at(record + 20)?;
+		let name_length = u16_at(record + 28)?;
+		let extra_length = u16_at(record + 30)?;
+		let comment_length = u16_at(record + 32)?;
+		let offset = u32_at(record + 42)?;
+		let entry_name = archive.get(record + 46 .. record + 46 + name_length)
+			.ok_or_else(damaged)?;
+		record += 46 + name_length + extra_length + comment_length;
+		if entry_name != name.as_bytes()
+		{
+			continue
+		}
+		// The local header has its own name and extra field, whose lengths
+		// may differ from those of the directory record.
+		let start = offset + 30 + u16_at(offset + 26)? + u16_at(offset + 28)?;
+		let data = archive.get(start .. start + compressed_length)
+			.ok_or_else(damaged)?;
+		let contents = match method
+		{
+			STORED => data.to_vec(),
+			DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(
+				data,
+				MAXIMUM_ENTRY_SIZE
+			).map_err(|_| damaged())?,
+			_ => return Err(format!(
+				"the archive uses an unsupported compression method ({})",
+				method
+			))
+		};
+		if crc32(&contents) != crc
+		{
+			return Err(damaged())
+		}
+		return Ok(Some(contents))
+	}
+	Ok(None)
+}
+
+/// Compute the CRC-32 of the specified bytes, as required by ZIP.
+fn crc32(bytes: &[u8]) -> u32
+{
+	!bytes.iter().fold(!0u32, |crc, byte| {
+		(0 .. 8).fold(crc ^ *byte as u32, |crc, _|
+			if crc & 1 == 1 { (crc >> 1) ^ CRC_POLYNOMIAL } else { crc >> 1 }
+		)
+	})
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The signature of a local file header.
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+
+/// The signature of a central directory record.
+const DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+
+/// The signature of the end of the central directory.
+const END_SIGNATURE: u32 = 0x0605_4b50;
+
+/// The length of the end of the central directory, excluding its comment.
+const END_LENGTH: usize = 22;
+
+/// The version of the specification needed to extract an entry, i.e., 2.0,
+/// which introduced DEFLATE.
+const VERSION_NEEDED: u16 = 20;
+
+/// The version of the specification that made the archive, i.e., 2.0, on
+/// MS-DOS, which means that there are no file attributes to interpret.
+const VERSION_MADE_BY: u16 = 20;
+
+/// The general purpose flag that marks entry names as UTF-8.
+const UTF8_NAMES: u16 = 1 << 11;
+
+/// The compression method for uncompressed entries.
+const STORED: u16 = 0;
+
+/// The compression method for DEFLATE.
+const DEFLATED: u16 = 8;
+
+/// The modification time of every entry, i.e., midnight, so that the same
+/// contents always produce the same archive.
+const DOS_TIME: u16 = 0;
+
+/// The modification date of every entry, i.e., January 1, 1980, which is the
+/// earliest that the format can express.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// The DEFLATE compression level, from `0` (none) to `10` (best).
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// The reversed polynomial of the CRC-32 used by ZIP.
+const CRC_POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// The maximum size of an extracted entry, in bytes, which guards against
+/// maliciously crafted archives.
+const MAXIMUM_ENTRY_SIZE: usize = 64 * 1024 * 1024;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Answer an archive with one stored entry and one deflated entry.
+	fn archive() -> Vec<u8>
+	{
+		Archive::new()
+			.store("mimetype", b"text/plain")
+			.deflate("dir/café.txt", "Hello, world! ".repeat(100).as_bytes())
+			.finish()
+	}
+
+	#[test]
+	fn round_trips_entries()
+	{
+		let archive = archive();
+		assert!(is_archive(&archive));
+		assert_eq!(
+			extract(&archive, "mimetype").unwrap().as_deref(),
+			Some(&b"text/plain"[..])
+		);
+		assert_eq!(
+			extract(&archive, "dir/café.txt").unwrap(),
+			Some("Hello, world! ".repeat(100).into_bytes())
+		);
+		assert_eq!(extract(&archive, "missing").unwrap(), None);
+		// An empty archive is still an archive.
+		let empty = Archive::new().finish();
+		assert_eq!(extract(&empty, "mimetype").unwrap(), None);
+	}
+
+	#[test]
+	fn computes_crc32()
+	{
+		assert_eq!(crc32(b""), 0);
+		assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+	}
+
+	#[test]
+	fn rejects_corrupt_archives()
+	{
+		let archive = archive();
+		assert!(!is_archive(b"PK"));
+		assert!(extract(b"Not an archive.", "mimetype").is_err());
+		// Truncation loses the central directory.
+		assert!(extract(&archive[.. archive.len() / 2], "mimetype").is_err());
+		// Corrupt data fails its checksum.
+		let mut corrupt = archive.clone();
+		let data = 30 + "mimetype".len();
+		corrupt[data] ^= 0xff;
+		assert!(extract(&corrupt, "mimetype").is_err());
+		// A corrupt directory record is detected.
+		let mut corrupt = archive.clone();
+		let end = archive.len() - END_LENGTH;
+		let directory = u32::from_le_bytes(
+			archive[end + 16 .. end + 20].try_into().unwrap()
+		) as usize;
+		corrupt[directory] ^= 0xff;
+		assert!(extract(&corrupt, "mimetype").is_err());
+		// An unsupported compression method is explained.
+		let mut unsupported = archive;
+		unsupported[directory + 10] = 99;
+		assert!(extract(&unsupported, "mimetype").unwrap_err()
+			.contains("unsupported compression method (99)"));
+	}
+}
@@ -0,0 +1,259 @@
+/*
+ * chapter.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use serde::{Deserialize, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Chapters.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A chapter of the manuscript, i.e., the sections whose chapter bears the
+/// chapter's name, and how those sections shuffle.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Chapter
+{
+	/// The name of the chapter, which identifies its sections.
+	pub(crate) name: String,
+
+	/// How the chapter shuffles.
+	pub(crate) policy: ChapterPolicy
+}
+
+/// How the sections of a [chapter](Chapter) shuffle. Whatever the policy, the
+/// sections of a chapter stay together.
+#[derive(
+	Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize
+)]
+pub(crate) enum ChapterPolicy
+{
+	/// The chapter stays in its place among the chapters, but its sections
+	/// shuffle within it.
+	#[default]
+	Within,
+
+	/// The chapter moves among the chapters as a unit, but its sections keep
+	/// their original order.
+	Unit,
+
+	/// The chapter moves among the chapters, and its sections shuffle within
+	/// it.
+	Both
+}
+
+impl ChapterPolicy
+{
+	/// Every policy, in presentation order.
+	pub(crate) const ALL: [ChapterPolicy; 3] =
+		[ChapterPolicy::Within, ChapterPolicy::Unit, ChapterPolicy::Both];
+
+	/// Answer the human-readable name of the policy.
+	pub(crate) fn name(self) -> &'static str
+	{
+		match self
+		{
+			ChapterPolicy::Within => "Shuffle within",
+			ChapterPolicy::Unit => "Move as a unit",
+			ChapterPolicy::Both => "Both"
+		}
+	}
+
+	/// Answer a description of the policy, suitable for a tooltip.
+	pub(crate) fn description(self) -> &'static str
+	{
+		match self
+		{
+			ChapterPolicy::Within =>
+				"The chapter stays in its place among the chapters, but its \
+				sections shuffle within it.",
+			ChapterPolicy::Unit =>
+				"The chapter trades places with the other moving chapters, \
+				but its sections keep their order.",
+			ChapterPolicy::Both =>
+				"The chapter trades places with the other moving chapters, \
+				and its sections shuffle within it."
+		}
+	}
+
+	/// Answer `true` if the chapter trades places with other chapters.
+	pub(crate) fn moves(self) -> bool
+	{
+		self != ChapterPolicy::Within
+	}
+
+	/// Answer `true` if the sections of the chapter shuffle within it.
+	pub(crate) fn shuffles_sections(self) -> bool
+	{
+		self != ChapterPolicy::Unit
+	}
+}
+
+/// Answer the [policy](ChapterPolicy) of the [chapter](Chapter) with the
+/// specified name, or the default policy if the writer hasn't chosen one.
+pub(crate) fn chapter_policy(chapters: &[Chapter], name: &str) -> ChapterPolicy
+{
+	chapters.iter()
+		.find(|chapter| chapter.name == name)
+		.map_or_else(ChapterPolicy::default, |chapter| chapter.policy)
+}
+
+/// Answer the names of the chapters to which the specified sections belong,
+/// in order of their first sections. `memberships` supplies the chapter of
+/// each section, if any, in manuscript order.
+pub(crate) fn chapter_names<'a>(memberships: &[Option<&'a str>]) -> Vec<&'a str>
+{
+	let mut names = vec![];
+	for name in memberships.iter().flatten()
+	{
+		if !names.contains(name)
+		{
+			names.push(*name);
+		}
+	}
+	names
+}
+
+/// Answer the ordering constraints that the specified chapters impose on
+/// their sections, as pairs of **zero-based** section indices, the first of
+/// which must occur earlier. The sections of a chapter that
+/// [moves&#32;as&#32;a&#32;unit](ChapterPolicy::Unit) keep their original
+/// order, and the chapters that [stay&#32;in&#32;place](ChapterPolicy::Within)
+/// keep theirs. `memberships` supplies the chapter of each section, if any, in
+/// manuscript order.
+pub(crate) fn chapter_edges(
+	memberships: &[Option<&str>],
+	chapters: &[Chapter]
+) -> Vec<(usize, usize)>
+{
+	let mut edges = vec![];
+	let mut previous_stationary = None::<Vec<usize>>;
+	for name in chapter_names(memberships)
+	{
+		let members = memberships.iter()
+			.enumerate()
+			.filter(|(_, chapter)| **chapter == Some(name))
+			.map(|(index, _)| index)
+			.collect::<Vec<_>>();
+		let policy = chapter_policy(chapters, name);
+		if !policy.shuffles_sections()
+		{
+			edges.extend(members.windows(2).map(|pair| (pair[0], pair[1])));
+		}
+		if !policy.moves()
+		{
+			// Every section of the previous stationary chapter precedes every
+			// section of this one.
+			if let Some(previous) = previous_stationary.replace(members.clone())
+			{
+				for earlier in &previous
+				{
+					edges.extend(
+						members.iter().map(|later| (*earlier, *later))
+					);
+				}
+			}
+		}
+	}
+	edges
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// The chapters of five sections: two in `A`, one in neither, and two in
+	/// `B`.
+	const MEMBERSHIPS: [Option<&str>; 5] =
+		[Some("A"), Some("A"), None, Some("B"), Some("B")];
+
+	/// Answer the chapters `A` and `B` with the specified policies.
+	fn chapters(a: ChapterPolicy, b: ChapterPolicy) -> Vec<Chapter>
+	{
+		vec![
+			Chapter { name: "A".to_string(), policy: a },
+			Chapter { name: "B".to_string(), policy: b }
+		]
+	}
+
+	#[test]
+	fn keeps_stationary_chapters_in_order()
+	{
+		assert_eq!(
+			chapter_edges(&MEMBERSHIPS, &[]),
+			vec![(0, 3), (0, 4), (1, 3), (1, 4)]
+		);
+		assert_eq!(
+			chapter_edges(
+				&MEMBERSHIPS,
+				&chapters(ChapterPolicy::Within, ChapterPolicy::Within)
+			),
+			vec![(0, 3), (0, 4), (1, 3), (1, 4)]
+		);
+	}
+
+	#[test]
+	fn keeps_the_sections_of_units_in_order()
+	{
+		assert_eq!(
+			chapter_edges(
+				&MEMBERSHIPS,
+				&chapters(ChapterPolicy::Unit, ChapterPolicy::Unit)
+			),
+			vec![(0, 1), (3, 4)]
+		);
+		// A unit moves, so it imposes no order on the stationary chapter.
+		assert_eq!(
+			chapter_edges(
+				&MEMBERSHIPS,
+				&chapters(ChapterPolicy::Unit, ChapterPolicy::Within)
+			),
+			vec![(0, 1)]
+		);
+	}
+
+	#[test]
+	fn frees_chapters_that_move_and_shuffle()
+	{
+		assert_eq!(
+			chapter_edges(
+				&MEMBERSHIPS,
+				&chapters(ChapterPolicy::Both, ChapterPolicy::Both)
+			),
+			vec![]
+		);
+		assert_eq!(chapter_names(&MEMBERSHIPS), vec!["A", "B"]);
+	}
+}
@@ -0,0 +1,569 @@
+/*
+ * chronology.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+use std::cmp::Ordering;
+use regex::{Captures, Regex};
+
+////////////////////////////////////////////////////////////////////////////////
+//                               Suggestions.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// An ordering constraint [suggested](suggest_constraints) by the temporal
+/// cues of a manuscript, which the writer may accept or dismiss.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Suggestion
+{
+	/// The zero-based index of the section that should occur earlier.
+	pub(crate) earlier: usize,
+
+	/// The zero-based index of the section that should occur later.
+	pub(crate) later: usize,
+
+	/// The reason for the suggestion, suitable for a writer. It doesn't refer
+	/// to the sections by number, so that it survives renumbering.
+	pub(crate) reason: String
+}
+
+/// Scan the openings of the specified sections, given in manuscript order, for
+/// temporal cues, and suggest ordering constraints accordingly:
+///
+/// * A section whose opening gives a date, e.g., `March 3, 1987`,
+///   `1987-03-03`, or `Day 3`, should follow the sections with earlier
+///   dates. Clock times, e.g., `9:30 p.m.`, order sections of the same date.
+///   Only the steps between consecutive dates are suggested, because the rest
+///   follow from them.
+/// * A section that opens with a relative marker, e.g., `the next morning` or
+///   `three years earlier`, should follow the section before it in the
+///   manuscript, on which the marker depends.
+pub(crate) fn suggest_constraints(sections: &[String]) -> Vec<Suggestion>
+{
+	let cues = Cues::new();
+	let openings = sections.iter()
+		.map(|section| opening(section))
+		.collect::<Vec<_>>();
+	let moments = openings.iter()
+		.map(|opening| cues.moment(opening))
+		.collect::<Vec<_>>();
+	let precedes = |a: usize, b: usize| match (&moments[a], &moments[b])
+	{
+		(Some(a), Some(b)) => a.compare(b) == Some(Ordering::Less),
+		_ => false
+	};
+	let mut suggestions = vec![];
+	let count = sections.len();
+	for later in 0 .. count
+	{
+		for earlier in 0 .. count
+		{
+			if precedes(earlier, later)
+				&& !(0 .. count).any(|between|
+					precedes(earlier, between) && precedes(between, later)
+				)
+			{
+				suggestions.push(Suggestion {
+					earlier,
+					later,
+					reason: format!(
+						"They open with “{}” and “{}”, respectively.",
+						moments[earlier].as_ref().unwrap().cue,
+						moments[later].as_ref().unwrap().cue
+					)
+				});
+			}
+		}
+		if later > 0
+		{
+			if let Some(marker) = cues.relative_marker(&openings[later])
+			{
+				let suggestion = Suggestion {
+					earlier: later - 1,
+					later,
+					reason: format!(
+						"The latter opens with “{}”, which depends on the \
+						former.",
+						marker
+					)
+				};
+				match suggestions.iter_mut().find(|s|
+					s.earlier == later - 1 && s.later == later
+				)
+				{
+					Some(s) => *s = suggestion,
+					None => suggestions.push(suggestion)
+				}
+			}
+		}
+	}
+	suggestions
+}
+
+/// Answer the opening of the specified section, i.e., its first
+/// [`OPENING_WORDS`] words, separated by single spaces. Datelines and relative
+/// markers customarily appear there.
+fn opening(section: &str) -> String
+{
+	section.split_whitespace()
+		.take(OPENING_WORDS)
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Moments.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A moment in the story's internal chronology, as given by the opening of a
+/// section. Any part may be missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Moment
+{
+	/// The text that gave the moment, for explaining suggestions.
+	cue: String,
+
+	/// The year, if any.
+	year: Option<u32>,
+
+	/// The one-based month, if any.
+	month: Option<u32>,
+
+	/// The one-based day of the month, if any.
+	day: Option<u32>,
+
+	/// The one-based day of the story, e.g., from `Day 3`, if any.
+	story_day: Option<u32>,
+
+	/// The minutes since midnight, if any.
+	minutes: Option<u32>
+}
+
+impl Moment
+{
+	/// Compare the receiver with the specified moment, answering `None` if
+	/// they are incomparable, i.e., because one gives a part of the date that
+	/// the other omits, or because neither gives a date at all. Clock times
+	/// only order moments of the same date.
+	fn compare(&self, other: &Self) -> Option<Ordering>
+	{
+		let dates = [
+			(self.year, other.year),
+			(self.month, other.month),
+			(self.day, other.day),
+			(self.story_day, other.story_day)
+		];
+		let mut dated = false;
+		for pair in dates
+		{
+			match pair
+			{
+				(Some(a), Some(b)) if a != b => return Some(a.cmp(&b)),
+				(Some(_), Some(_)) => dated = true,
+				(None, None) => {},
+				_ => return None
+			}
+		}
+		if !dated
+		{
+			return None
+		}
+		match (self.minutes, other.minutes)
+		{
+			(Some(a), Some(b)) => Some(a.cmp(&b)),
+			(None, None) => Some(Ordering::Equal),
+			_ => None
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Cues.                                    //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The compiled [regular&#32;expressions](Regex) that recognize temporal cues.
+struct Cues
+{
+	/// Recognizes an ISO 8601 date, e.g., `1987-03-03`.
+	iso_date: Regex,
+
+	/// Recognizes a date that begins with the month, e.g., `March 3, 1987`,
+	/// `March 1987`, or `March 3`.
+	month_first: Regex,
+
+	/// Recognizes a date that begins with the day, e.g., `3 March 1987`.
+	day_first: Regex,
+
+	/// Recognizes a year by itself, e.g., `1987`.
+	year: Regex,
+
+	/// Recognizes a day of the story, e.g., `Day 3`.
+	story_day: Regex,
+
+	/// Recognizes a clock time, e.g., `9:30 p.m.`, `21:30`, or `9 p.m.`.
+	time: Regex,
+
+	/// Recognizes a marker that places a section relative to what precedes
+	/// it, e.g., `the next morning` or `three years earlier`.
+	relative: Regex
+}
+
+impl Cues
+{
+	/// Compile the regular expressions.
+	fn new() -> Self
+	{
+		let months = MONTHS.join("|");
+		let regex = |pattern: &str| Regex::new(pattern).unwrap();
+		Self {
+			iso_date: regex(r"\b(\d{4})-(\d{1,2})-(\d{1,2})\b"),
+			month_first: regex(&format!(
+				r"(?i)\b({})\b\.?{}{}",
+				months,
+				r"(?:\s+(\d{1,2})(?:st|nd|rd|th)?\b)?",
+				r"(?:,?\s+(\d{4})\b)?"
+			)),
+			day_first: regex(&format!(
+				r"(?i)\b(\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?({})\b{}",
+				months,
+				r"\.?,?\s+(\d{4})\b"
+			)),
+			year: regex(r"\b(1[5-9]\d\d|20\d\d)\b"),
+			story_day: regex(r"(?i)\bday\s+(\d{1,4})\b"),
+			time: regex(concat!(
+				r"(?i)\b(\d{1,2})(?::(\d{2}))?\s*([ap])\.?m\b\.?",
+				r"|\b(\d{1,2}):(\d{2})\b"
+			)),
+			relative: regex(&format!(
+				r"(?i)\b(?:{})\b",
+				[
+					format!(
+						r"the\s+(?:next|following|previous|preceding)\s+(?:{})",
+						PERIODS
+					),
+					format!(
+						r"the\s+(?:{})\s+(?:after|before)",
+						PERIODS
+					),
+					format!(
+						r"(?:later|earlier)\s+(?:that|the\s+same)\s+(?:{})",
+						PERIODS
+					),
+					format!(
+						r"(?:a\s+few|\w+)\s+(?:{})s\s+{}",
+						UNITS,
+						DIRECTIONS
+					),
+					format!(
+						r"(?:an?|one)\s+(?:{})\s+{}",
+						UNITS,
+						DIRECTIONS
+					),
+					r"meanwhile|afterwards?".to_string()
+				].join("|")
+			))
+		}
+	}
+
+	/// Answer the [moment](Moment) given by the specified opening, if any.
+	fn moment(&self, opening: &str) -> Option<Moment>
+	{
+		let number = |captures: &Captures<'_>, group: usize|
+			captures.get(group).and_then(|m| m.as_str().parse::<u32>().ok());
+		// Impossible dates, e.g., `1987-13-40`, aren't dates at all.
+		let mut moment = if let Some(c) = self.iso_date.captures_iter(opening)
+			.find(|c| is_possible_date(number(c, 2), number(c, 3)))
+		{
+			Moment {
+				cue: c[0].to_string(),
+				year: number(&c, 1),
+				month: number(&c, 2),
+				day: number(&c, 3),
+				..Default::default()
+			}
+		}
+		else if let Some(c) = self.day_first.captures_iter(opening)
+			.find(|c| is_possible_date(month(&c[2]), number(c, 1)))
+		{
+			Moment {
+				cue: c[0].to_string(),
+				year: number(&c, 3),
+				month: month(&c[2]),
+				day: number(&c, 1),
+				..Default::default()
+			}
+		}
+		else if let Some(c) = self.month_first.captures_iter(opening)
+			// A month without a day or a year is probably a word, e.g.,
+			// “may” or “march”.
+			.find(|c| (c.get(2).is_some() || c.get(3).is_some())
+				&& is_possible_date(month(&c[1]), number(c, 2))
+			)
+		{
+			Moment {
+				cue: c[0].to_string(),
+				year: number(&c, 3),
+				month: month(&c[1]),
+				day: number(&c, 2),
+				..Default::default()
+			}
+		}
+		else if let Some(c) = self.year.captures(opening)
+		{
+			Moment {
+				cue: c[0].to_string(),
+				year: number(&c, 1),
+				..Default::default()
+			}
+		}
+		else if let Some(c) = self.story_day.captures(opening)
+		{
+			Moment {
+				cue: c[0].to_string(),
+				story_day: number(&c, 1),
+				..Default::default()
+			}
+		}
+		else
+		{
+			return None
+		};
+		if let Some(c) = self.time.captures(opening)
+		{
+			let (hour, minute) = if c.get(3).is_some()
+			{
+				let pm = c[3].eq_ignore_ascii_case("p");
+				let hour = number(&c, 1)
+					.filter(|h| (1 ..= 12).contains(h))
+					.map(|h| h % 12 + if pm { 12 } else { 0 });
+				(hour, number(&c, 2).unwrap_or(0))
+			}
+			else
+			{
+				(number(&c, 4).filter(|h| *h < 24), number(&c, 5).unwrap_or(0))
+			};
+			if let Some(hour) = hour.filter(|_| minute < 60)
+			{
+				let minutes = hour * 60 + minute;
+				moment.cue = format!("{}, {}", moment.cue, &c[0]);
+				moment.minutes = Some(minutes);
+			}
+		}
+		Some(moment)
+	}
+
+	/// Answer the first relative marker of the specified opening, if any.
+	fn relative_marker<'a>(&self, opening: &'a str) -> Option<&'a str>
+	{
+		self.relative.find(opening).map(|m| m.as_str())
+	}
+}
+
+/// Answer `true` if the specified one-based month and day of the month, either
+/// of which may be missing, could occur together in some year.
+fn is_possible_date(month: Option<u32>, day: Option<u32>) -> bool
+{
+	let days = match month
+	{
+		Some(2) => 29,
+		Some(4 | 6 | 9 | 11) => 30,
+		Some(1 ..= 12) | None => 31,
+		Some(_) => return false
+	};
+	day.map_or(true, |day| (1 ..= days).contains(&day))
+}
+
+/// Answer the one-based month named by the specified word, if any, which may
+/// be abbreviated.
+fn month(name: &str) -> Option<u32>
+{
+	let name = name.to_lowercase();
+	MONTHS.iter()
+		.position(|month| name.starts_with(&month[.. 3]))
+		.map(|index| index as u32 + 1)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The number of words at the beginning of a section that are scanned for
+/// temporal cues.
+const OPENING_WORDS: usize = 40;
+
+/// The patterns that recognize the names of months, longest first, so that
+/// the whole name is captured.
+const MONTHS: &[&str] = &[
+	"january", "february", "march", "april", "may", "june", "july",
+	"august", "september", "october", "november", "december",
+	"jan", "feb", "mar", "apr", "jun", "jul", "aug", "sept", "sep", "oct",
+	"nov", "dec"
+];
+
+/// The periods that relative markers name, e.g., the `morning` of
+/// `the next morning`.
+const PERIODS: &str =
+	"morning|afternoon|evening|night|day|week|month|year|spring|summer|\
+	autumn|fall|winter";
+
+/// The units by which relative markers measure time, e.g., the `year` of
+/// `three years earlier`. Each also occurs in the plural, by appending `s`.
+const UNITS: &str = "second|minute|hour|day|night|week|month|year|decade";
+
+/// The directions of relative markers, e.g., the `earlier` of
+/// `three years earlier`.
+const DIRECTIONS: &str = "(?:later|earlier|before|after|ago)";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// The year, month, day, story day, and minutes of a [moment](Moment).
+	type Parts =
+		(Option<u32>, Option<u32>, Option<u32>, Option<u32>, Option<u32>);
+
+	/// Answer the [parts](Parts) of the [moment](Moment) given by the
+	/// specified opening, if any.
+	fn parts(opening: &str) -> Option<Parts>
+	{
+		Cues::new().moment(opening)
+			.map(|m| (m.year, m.month, m.day, m.story_day, m.minutes))
+	}
+
+	#[test]
+	fn parses_dates()
+	{
+		let date = Some((Some(1987), Some(3), Some(3), None, None));
+		assert_eq!(parts("1987-03-03. The rain."), date);
+		assert_eq!(parts("On March 3, 1987, it rained."), date);
+		assert_eq!(parts("3rd of March 1987: rain."), date);
+		assert_eq!(parts("Mar. 3rd, 1987"), date);
+		assert_eq!(
+			parts("Day 12. The water ran out."),
+			Some((None, None, None, Some(12), None))
+		);
+		assert_eq!(
+			parts("March 3, 9:30 p.m. The door opened."),
+			Some((None, Some(3), Some(3), None, Some(21 * 60 + 30)))
+		);
+		assert_eq!(
+			parts("1987-03-03 at 12 a.m."),
+			Some((Some(1987), Some(3), Some(3), None, Some(0)))
+		);
+		assert_eq!(parts("Nothing happened."), None);
+	}
+
+	#[test]
+	fn parses_partial_dates()
+	{
+		assert_eq!(
+			parts("By 1987, she had left."),
+			Some((Some(1987), None, None, None, None))
+		);
+		assert_eq!(
+			parts("In March 1987, she left."),
+			Some((Some(1987), Some(3), None, None, None))
+		);
+		assert_eq!(
+			parts("On March 3 she left."),
+			Some((None, Some(3), Some(3), None, None))
+		);
+		// A month by itself is probably a word.
+		assert_eq!(parts("You may march now."), None);
+	}
+
+	#[test]
+	fn rejects_malformed_dates_and_times()
+	{
+		// An impossible date leaves only its year, if any.
+		assert_eq!(
+			parts("1987-13-40"),
+			Some((Some(1987), None, None, None, None))
+		);
+		assert_eq!(
+			parts("February 30, 1987"),
+			Some((Some(1987), None, None, None, None))
+		);
+		assert_eq!(parts("April 31"), None);
+		assert_eq!(parts("45 March"), None);
+		// Impossible times are ignored.
+		let day = Some((None, None, None, Some(2), None));
+		assert_eq!(parts("Day 2, 25:30."), day);
+		assert_eq!(parts("Day 2, 13 p.m."), day);
+		assert_eq!(parts("Day 2, 9:75."), day);
+		assert_eq!(parts("1066 is too early, and 2100 too late."), None);
+	}
+
+	#[test]
+	fn orders_moments()
+	{
+		let cues = Cues::new();
+		let compare = |a: &str, b: &str| cues.moment(a).unwrap()
+			.compare(&cues.moment(b).unwrap());
+		assert_eq!(
+			compare("1987-03-03", "March 4, 1987"),
+			Some(Ordering::Less)
+		);
+		assert_eq!(compare("1988", "1987"), Some(Ordering::Greater));
+		assert_eq!(compare("Day 3", "Day 3"), Some(Ordering::Equal));
+		assert_eq!(
+			compare("Day 3, 9:00", "Day 3, 8 p.m."),
+			Some(Ordering::Less)
+		);
+		// A different year decides, whatever the other parts.
+		assert_eq!(compare("1988", "1987-12-31"), Some(Ordering::Greater));
+		// Clock times only order moments of the same date.
+		assert_eq!(compare("Day 3, 9:00", "Day 3"), None);
+		assert_eq!(compare("Day 4, 9:00", "Day 3"), Some(Ordering::Greater));
+		// Partial dates are incomparable with dates that give other parts.
+		assert_eq!(compare("March 3", "1987-03-03"), None);
+		assert_eq!(compare("Day 3", "1987"), None);
+	}
+
+	#[test]
+	fn suggests_steps_between_dates_and_relative_markers()
+	{
+		let sections = [
+			"1987-03-05. Last.",
+			"1987-03-03. First.",
+			"1987-03-04. Middle.",
+			"The next morning, more.",
+			"No cue here."
+		].map(String::from);
+		let pairs = suggest_constraints(&sections).into_iter()
+			.map(|s| (s.earlier, s.later))
+			.collect::<Vec<_>>();
+		assert_eq!(pairs, vec![(2, 0), (1, 2), (2, 3)]);
+	}
+}
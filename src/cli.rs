@@ -0,0 +1,576 @@
+/*
+ * cli.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::app::{ConstraintSet, StoryShufflerApp};
+use crate::config::{Config, default_config_path};
+
+////////////////////////////////////////////////////////////////////////////////
+//                              Command line.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The options of a batch run, as parsed from the command line.
+#[derive(Clone, Debug, Default)]
+struct BatchOptions
+{
+	/// The directory of manuscripts to shuffle.
+	input: PathBuf,
+
+	/// The directory to receive the shuffled manuscripts. Defaults to
+	/// [`DEFAULT_OUTPUT_DIRECTORY`] within the [input](Self::input) directory.
+	output: Option<PathBuf>,
+
+	/// The [constraints](ConstraintSet) file to apply to every manuscript that
+	/// lacks its own.
+	constraints: Option<PathBuf>,
+
+	/// The section delimiter, if it should override that of the constraints.
+	delimiter: Option<String>,
+
+	/// Whether the [delimiter](Self::delimiter) is a regular expression.
+	regex: bool,
+
+	/// The number of shuffled variants to produce per manuscript.
+	variants: usize
+}
+
+/// The parsed command line, which determines whether the application runs
+/// interactively or headlessly.
+#[derive(Clone, Debug)]
+pub struct CommandLine
+{
+	/// The [configuration](Config) file, if it should override the
+	/// [default](default_config_path).
+	config: Option<PathBuf>,
+
+	/// The directory in which to persist the application state, if it should
+	/// override both the environment and the [configuration](Config).
+	storage: Option<PathBuf>,
+
+	/// Whether to neither load nor save the application state, regardless of
+	/// the [configuration](Config).
+	ephemeral: bool,
+
+	/// What to do.
+	action: Action
+}
+
+/// What the [command&#32;line](CommandLine) asks the application to do.
+#[derive(Clone, Debug)]
+enum Action
+{
+	/// Open the user interface.
+	Interactive,
+
+	/// Print the usage message.
+	Help,
+
+	/// Shuffle a directory of manuscripts without a user interface.
+	Batch(BatchOptions)
+}
+
+impl CommandLine
+{
+	/// Parse the specified command-line arguments, excluding the name of the
+	/// program. Answer an explanation, followed by the usage message, if the
+	/// arguments are malformed.
+	pub fn parse(args: &[String]) -> Result<Self, String>
+	{
+		parse_arguments(args).map_err(|e| format!("{}\n\n{}", e, usage()))
+	}
+
+	/// Answer `true` if the application should run headlessly, i.e., via
+	/// [`run_headless`](Self::run_headless), without a user interface.
+	pub fn is_headless(&self) -> bool
+	{
+		!matches!(self.action, Action::Interactive)
+	}
+
+	/// Answer the [configuration](Config) for an interactive run. A malformed
+	/// configuration file is reported, and then ignored. The storage directory
+	/// comes from the command line, if specified, or else from the environment
+	/// variable [`STORAGE_VARIABLE`], if set, or else from the configuration.
+	pub fn config(&self) -> Config
+	{
+		let mut config = Config::load(self.config.as_deref())
+			.unwrap_or_else(|e| {
+				tracing::warn!("Ignoring the configuration: {}", e);
+				Default::default()
+			});
+		let storage = self.storage.clone().or_else(||
+			env::var_os(STORAGE_VARIABLE)
+				.filter(|directory| !directory.is_empty())
+				.map(PathBuf::from)
+		);
+		if storage.is_some()
+		{
+			config.storage_directory = storage;
+		}
+		config.ephemeral |= self.ephemeral;
+		config
+	}
+
+	/// Run the application headlessly. Answer the process exit code.
+	pub fn run_headless(&self) -> i32
+	{
+		let options = match self.action
+		{
+			Action::Batch(ref options) => options,
+			_ =>
+			{
+				println!("{}", usage());
+				return 0
+			}
+		};
+		let result = Config::load(self.config.as_deref())
+			.and_then(|config| run_batch(options, &config));
+		match result
+		{
+			Ok(()) => 0,
+			Err(e) =>
+			{
+				eprintln!("{}", e);
+				1
+			}
+		}
+	}
+}
+
+/// Parse the specified command-line arguments into a
+/// [command&#32;line](CommandLine). Answer an explanation if the arguments are
+/// malformed.
+fn parse_arguments(args: &[String]) -> Result<CommandLine, String>
+{
+	let mut config = None;
+	let mut storage = None;
+	let mut ephemeral = false;
+	let mut options = BatchOptions { variants: 1, ..Default::default() };
+	let mut input = None;
+	let mut batch_only = None;
+	let mut args = args.iter();
+	while let Some(arg) = args.next()
+	{
+		let mut value = |name: &str| args.next()
+			.cloned()
+			.ok_or_else(|| format!("{} requires a value.", name));
+		match arg.as_str()
+		{
+			"-h" | "--help" => return Ok(CommandLine {
+				config,
+				storage,
+				ephemeral,
+				action: Action::Help
+			}),
+			"--config" => config = Some(PathBuf::from(value(arg)?)),
+			"--storage" => storage = Some(PathBuf::from(value(arg)?)),
+			"--no-persist" => ephemeral = true,
+			"--batch" => input = Some(PathBuf::from(value(arg)?)),
+			"--out" => options.output = Some(PathBuf::from(value(arg)?)),
+			"--constraints" =>
+				options.constraints = Some(PathBuf::from(value(arg)?)),
+			"--delimiter" => options.delimiter = Some(value(arg)?),
+			"--regex" => options.regex = true,
+			"--variants" =>
+			{
+				options.variants = value(arg)?.parse::<usize>()
+					.ok()
+					.filter(|n| *n != 0)
+					.ok_or_else(||
+						"--variants requires a positive number.".to_string()
+					)?;
+			},
+			_ => return Err(format!("Unrecognized argument: {}", arg))
+		}
+		if !["--config", "--storage", "--no-persist", "--batch"]
+			.contains(&arg.as_str())
+		{
+			batch_only.get_or_insert(arg);
+		}
+	}
+	let action = match (input, batch_only)
+	{
+		(Some(input), _) => Action::Batch(BatchOptions { input, ..options }),
+		(None, Some(arg)) => return Err(format!("{} requires --batch.", arg)),
+		(None, None) => Action::Interactive
+	};
+	Ok(CommandLine { config, storage, ephemeral, action })
+}
+
+/// Answer the usage message, which mentions the default location of the
+/// [configuration](Config) file.
+fn usage() -> String
+{
+	let path = default_config_path()
+		.map(|path| path.display().to_string())
+		.unwrap_or_else(|| "none".to_string());
+	format!("{}\n\nThe default configuration file is {}.", USAGE, path)
+}
+
+/// Shuffle every manuscript in the input directory according to the specified
+/// [options](BatchOptions) and [configuration](Config), writing the variants
+/// of each to the output directory. A manuscript named `story.txt` uses the
+/// constraints in `story.txt.ron` or `story.txt.json`, if present, or else the
+/// shared constraints, if any. The output directory must differ from the
+/// input directory, lest the variants be shuffled again by the next run.
+/// Failures are reported as they occur, without stopping the run; answer an
+/// explanation if any manuscript failed.
+fn run_batch(options: &BatchOptions, config: &Config) -> Result<(), String>
+{
+	let output = options.output.clone()
+		.unwrap_or_else(|| options.input.join(DEFAULT_OUTPUT_DIRECTORY));
+	if same_file(&output, &options.input)
+	{
+		return Err(format!(
+			"The output directory must differ from the input directory, {}.",
+			options.input.display()
+		))
+	}
+	let shared = match options.constraints
+	{
+		Some(ref path) => Some(read_constraints(path)?),
+		None => None
+	};
+	let excluded = options.constraints.iter()
+		.cloned()
+		.chain([output.clone()])
+		.collect::<Vec<_>>();
+	let manuscripts = manuscripts(&options.input, &excluded)?;
+	if manuscripts.is_empty()
+	{
+		return Err(format!(
+			"There are no manuscripts in {}.",
+			options.input.display()
+		))
+	}
+	fs::create_dir_all(&output)
+		.map_err(|e| format!("Couldn't create {}: {}", output.display(), e))?;
+	let delimiter = options.delimiter.clone()
+		.map(|delimiter| (delimiter, options.regex));
+	let mut failures = 0;
+	for path in &manuscripts
+	{
+		let result = shuffle_file(
+			path,
+			&output,
+			shared.as_ref(),
+			delimiter.clone(),
+			config,
+			options.variants
+		);
+		match result
+		{
+			Ok(written) =>
+			{
+				for path in written
+				{
+					println!("Wrote {}.", path.display());
+				}
+			},
+			Err(e) =>
+			{
+				eprintln!("Couldn't shuffle {}: {}", path.display(), e);
+				failures += 1;
+			}
+		}
+	}
+	if failures == 0
+	{
+		Ok(())
+	}
+	else
+	{
+		Err(format!(
+			"{} of {} manuscripts couldn't be shuffled.",
+			failures,
+			manuscripts.len()
+		))
+	}
+}
+
+/// Answer the paths of the manuscripts in the specified directory, in order by
+/// name. Subdirectories, hidden files, constraints files, and the specified
+/// excluded paths, e.g., the shared constraints file, are skipped.
+fn manuscripts(
+	directory: &Path,
+	excluded: &[PathBuf]
+) -> Result<Vec<PathBuf>, String>
+{
+	let entries = fs::read_dir(directory)
+		.map_err(|e| format!("Couldn't read {}: {}", directory.display(), e))?;
+	let mut paths = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.is_file())
+		.filter(|path| {
+			let name = path.file_name()
+				.map(|name| name.to_string_lossy().into_owned())
+				.unwrap_or_default();
+			!name.starts_with('.')
+				&& !CONSTRAINTS_EXTENSIONS.iter()
+					.any(|extension| name.ends_with(extension))
+		})
+		.filter(|path| !excluded.iter().any(|other| same_file(path, other)))
+		.collect::<Vec<_>>();
+	paths.sort();
+	Ok(paths)
+}
+
+/// Read a [constraint&#32;set](ConstraintSet) from the specified
+/// [RON](ron) file, or JSON file, as exported by the application.
+fn read_constraints(path: &Path) -> Result<ConstraintSet, String>
+{
+	let text = fs::read_to_string(path)
+		.map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+	let parsed = if path.extension().map_or(false, |e| e == "json")
+	{
+		serde_json::from_str(&text).map_err(|e| e.to_string())
+	}
+	else
+	{
+		ron::from_str(&text).map_err(|e| e.to_string())
+	};
+	parsed.map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))
+}
+
+/// Answer `true` if the specified paths name the same file or directory,
+/// even if they spell it differently. Paths that don't exist are compared as
+/// written.
+fn same_file(path: &Path, other: &Path) -> bool
+{
+	match (path.canonicalize(), other.canonicalize())
+	{
+		(Ok(path), Ok(other)) => path == other,
+		_ => path == other
+	}
+}
+
+/// Answer the path of the manuscript's own constraints file, i.e., the first
+/// of its possible [sidecars](CONSTRAINTS_EXTENSIONS) that exists, if any.
+fn own_constraints(path: &Path) -> Option<PathBuf>
+{
+	CONSTRAINTS_EXTENSIONS.iter()
+		.map(|extension| {
+			let mut own = path.as_os_str().to_owned();
+			own.push(extension);
+			PathBuf::from(own)
+		})
+		.find(|own| own.is_file())
+}
+
+/// Shuffle the manuscript at the specified path into the specified number of
+/// variants, writing each to the output directory. Use the manuscript's own
+/// constraints file, if any, or else the `shared` constraints, if any. Answer
+/// the paths of the variants.
+fn shuffle_file(
+	path: &Path,
+	output: &Path,
+	shared: Option<&ConstraintSet>,
+	delimiter: Option<(String, bool)>,
+	config: &Config,
+	variants: usize
+) -> Result<Vec<PathBuf>, String>
+{
+	let manuscript = fs::read_to_string(path)
+		.map_err(|e| format!("Couldn't read the manuscript: {}", e))?;
+	let constraints = match own_constraints(path)
+	{
+		Some(own) => read_constraints(&own)?,
+		None => shared.cloned().unwrap_or_default()
+	};
+	let mut app = StoryShufflerApp::headless(
+		manuscript,
+		&constraints,
+		delimiter,
+		config
+	)?;
+	let stem = path.file_stem()
+		.map(|stem| stem.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	let extension = path.extension()
+		.map(|extension| format!(".{}", extension.to_string_lossy()))
+		.unwrap_or_default();
+	let mut written = vec![];
+	for variant in 1 ..= variants
+	{
+		let shuffled = app.shuffle_headless()?;
+		let target = output.join(format!("{}-{}{}", stem, variant, extension));
+		fs::write(&target, shuffled)
+			.map_err(|e| format!("Couldn't write {}: {}", target.display(), e))?;
+		written.push(target);
+	}
+	Ok(written)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The usage message.
+const USAGE: &str = "\
+Usage: story_shuffler [--config FILE] [--storage DIR] [--no-persist]
+       story_shuffler [--config FILE] --batch DIR [OPTIONS]
+
+Open the user interface, or shuffle every manuscript in DIR without opening a
+window.
+
+Options:
+  --config FILE        Read the default settings from FILE (TOML).
+  --storage DIR        Keep the application state, including autosaves, in
+                       DIR, e.g., a synced folder. Overrides the environment
+                       variable STORY_SHUFFLER_STORAGE and the configuration.
+  --no-persist         Neither load nor save the application state, so that no
+                       manuscript text is left behind, e.g., on a shared
+                       machine.
+  --out DIR            Write the shuffled manuscripts to DIR
+                       (default: DIR/shuffled), which must differ from the
+                       input DIR.
+  --constraints FILE   Apply the constraints in FILE (RON or JSON) to every
+                       manuscript that lacks its own. A manuscript named
+                       story.txt uses story.txt.ron or story.txt.json, if
+                       present.
+  --delimiter TEXT     Split sections at TEXT (default: that of the
+                       constraints or configuration, or else * * *).
+  --regex              Treat the delimiter as a regular expression.
+  --variants N         Produce N shuffled variants per manuscript (default: 1).
+  -h, --help           Show this message.";
+
+/// The suffixes of a manuscript's own constraints file, in order of
+/// preference, which follow the full name of the manuscript, e.g.,
+/// `story.txt.ron` or `story.txt.json`.
+const CONSTRAINTS_EXTENSIONS: [&str; 2] = [".ron", ".json"];
+
+/// The name of the default output directory, within the input directory.
+const DEFAULT_OUTPUT_DIRECTORY: &str = "shuffled";
+
+/// The environment variable that names the directory in which to persist the
+/// application state.
+const STORAGE_VARIABLE: &str = "STORY_SHUFFLER_STORAGE";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Parse the specified whitespace-separated arguments.
+	fn parse(args: &str) -> Result<CommandLine, String>
+	{
+		parse_arguments(
+			&args.split_whitespace().map(str::to_string).collect::<Vec<_>>()
+		)
+	}
+
+	#[test]
+	fn parses_batch_options()
+	{
+		let command = parse(
+			"--config a.toml --batch in --out out --constraints c.json \
+			--delimiter # --regex --variants 3"
+		).unwrap();
+		assert!(command.is_headless());
+		assert_eq!(command.config, Some(PathBuf::from("a.toml")));
+		let Action::Batch(options) = command.action
+		else { panic!("Expected a batch run") };
+		assert_eq!(options.input, PathBuf::from("in"));
+		assert_eq!(options.output, Some(PathBuf::from("out")));
+		assert_eq!(options.constraints, Some(PathBuf::from("c.json")));
+		assert_eq!(options.delimiter.as_deref(), Some("#"));
+		assert!(options.regex);
+		assert_eq!(options.variants, 3);
+	}
+
+	#[test]
+	fn parses_interactive_options()
+	{
+		let command = parse("--storage dir --no-persist").unwrap();
+		assert!(!command.is_headless());
+		assert_eq!(command.storage, Some(PathBuf::from("dir")));
+		assert!(command.ephemeral);
+		assert!(matches!(parse("--help").unwrap().action, Action::Help));
+	}
+
+	#[test]
+	fn rejects_malformed_arguments()
+	{
+		assert_eq!(
+			parse("--out out").unwrap_err(),
+			"--out requires --batch."
+		);
+		assert_eq!(
+			parse("--batch").unwrap_err(),
+			"--batch requires a value."
+		);
+		assert_eq!(
+			parse("--batch in --variants 0").unwrap_err(),
+			"--variants requires a positive number."
+		);
+		assert_eq!(
+			parse("--frobnicate").unwrap_err(),
+			"Unrecognized argument: --frobnicate"
+		);
+	}
+
+	#[test]
+	fn finds_only_manuscripts()
+	{
+		let directory = env::temp_dir()
+			.join(format!("story-shuffler-cli-{}", std::process::id()));
+		let output = directory.join(DEFAULT_OUTPUT_DIRECTORY);
+		fs::create_dir_all(&output).unwrap();
+		for name in [
+			"b.txt",
+			"a.md",
+			"a.md.json",
+			"b.txt.ron",
+			"shared.ron",
+			"rules.cfg",
+			".hidden"
+		]
+		{
+			fs::write(directory.join(name), "text").unwrap();
+		}
+		let shared = directory.join("rules.cfg");
+		let found = manuscripts(&directory, &[shared.clone(), output.clone()]);
+		let own = own_constraints(&directory.join("a.md"));
+		let same = same_file(&directory, &output.join(".."));
+		fs::remove_dir_all(&directory).unwrap();
+		assert_eq!(
+			found.unwrap(),
+			vec![directory.join("a.md"), directory.join("b.txt")]
+		);
+		assert_eq!(own, Some(directory.join("a.md.json")));
+		assert!(same);
+	}
+}
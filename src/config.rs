@@ -0,0 +1,214 @@
+/*
+ * config.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}};
+#[cfg(not(target_arch = "wasm32"))]
+use directories_next::ProjectDirs;
+use egui::Visuals;
+use serde::Deserialize;
+
+////////////////////////////////////////////////////////////////////////////////
+//                              Configuration.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The writer's preferred defaults, as read from an optional [TOML](toml)
+/// configuration file. Every setting is optional; an absent setting leaves the
+/// built-in default in force.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config
+{
+	/// The section delimiter of a fresh manuscript.
+	pub(crate) delimiter: Option<String>,
+
+	/// Whether the [delimiter](Self::delimiter) is a regular expression.
+	pub(crate) delimiter_is_regex: bool,
+
+	/// The text that separates the shuffled sections of a new manuscript,
+	/// instead of the section delimiter.
+	pub(crate) output_separator: Option<String>,
+
+	/// The [theme](Theme) of the user interface. If absent, the framework
+	/// follows the system's preference, if it has one.
+	pub(crate) theme: Option<Theme>,
+
+	/// The directory in which to persist the application state, including
+	/// autosaves, instead of the platform's default location.
+	#[cfg(not(target_arch = "wasm32"))]
+	pub(crate) storage_directory: Option<PathBuf>,
+
+	/// The seed of the random number generator, which makes every session
+	/// shuffle identically, e.g., for reproducible demonstrations. If absent,
+	/// the generator is seeded from system entropy.
+	pub(crate) seed: Option<u64>,
+
+	/// Whether to neither load nor save the application state, e.g., for a
+	/// demonstration on a shared machine, so that no manuscript text is left
+	/// behind.
+	pub(crate) ephemeral: bool
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Config
+{
+	/// Read the configuration from the specified file, or from the
+	/// [default&#32;location](default_config_path) if unspecified. A missing
+	/// default file is not an error, since the configuration file is optional,
+	/// but a missing specified file is. Answer an explanation on failure.
+	pub(crate) fn load(path: Option<&Path>) -> Result<Self, String>
+	{
+		match path
+		{
+			Some(path) => Self::read(path, true),
+			None => match default_config_path()
+			{
+				Some(path) => Self::read(&path, false),
+				None => Ok(Default::default())
+			}
+		}
+	}
+
+	/// Read the configuration from the specified file. If the file is missing,
+	/// answer the default configuration, unless the file is `required`.
+	/// Answer an explanation on failure.
+	fn read(path: &Path, required: bool) -> Result<Self, String>
+	{
+		let text = match fs::read_to_string(path)
+		{
+			Ok(text) => text,
+			Err(e) if !required && e.kind() == ErrorKind::NotFound =>
+				return Ok(Default::default()),
+			Err(e) =>
+				return Err(format!("Couldn't read {}: {}", path.display(), e))
+		};
+		toml::from_str(&text)
+			.map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))
+	}
+}
+
+/// The theme of the user interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Theme
+{
+	/// Light text on a dark background.
+	Dark,
+
+	/// Dark text on a light background.
+	Light
+}
+
+impl Theme
+{
+	/// Answer the [visuals](Visuals) of the theme.
+	pub(crate) fn visuals(self) -> Visuals
+	{
+		match self
+		{
+			Theme::Dark => Visuals::dark(),
+			Theme::Light => Visuals::light()
+		}
+	}
+}
+
+/// Answer the default location of the configuration file, according to the
+/// conventions of the platform, e.g., `~/.config/storyshuffler/config.toml` on
+/// Linux. Answer `None` if the home directory is unknown.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn default_config_path() -> Option<PathBuf>
+{
+	ProjectDirs::from("", "", APPLICATION_NAME)
+		.map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The name of the application, which determines the platform's directories
+/// for it. The framework uses the same name for its own storage.
+#[cfg(not(target_arch = "wasm32"))]
+const APPLICATION_NAME: &str = "Story Shuffler";
+
+/// The name of the configuration file.
+#[cfg(not(target_arch = "wasm32"))]
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn tolerates_only_a_missing_default_file()
+	{
+		let path = std::env::temp_dir().join(format!(
+			"story-shuffler-missing-{}.toml",
+			std::process::id()
+		));
+		assert!(Config::read(&path, false).is_ok());
+		let error = Config::load(Some(&path)).unwrap_err();
+		assert!(error.starts_with("Couldn't read"), "{}", error);
+	}
+
+	#[test]
+	fn reads_settings()
+	{
+		let config = toml::from_str::<Config>(
+			"delimiter = \"#\"\ntheme = \"dark\"\nseed = 7\nephemeral = true"
+		).unwrap();
+		assert_eq!(config.delimiter.as_deref(), Some("#"));
+		assert_eq!(config.theme, Some(Theme::Dark));
+		assert_eq!(config.seed, Some(7));
+		assert!(config.ephemeral);
+		assert!(!config.delimiter_is_regex);
+	}
+
+	#[test]
+	fn rejects_unknown_settings()
+	{
+		let path = std::env::temp_dir().join(format!(
+			"story-shuffler-config-{}.toml",
+			std::process::id()
+		));
+		fs::write(&path, "delimiter = \"#\"\ndelimeter = \"*\"\n").unwrap();
+		let result = Config::load(Some(&path));
+		fs::remove_file(&path).unwrap();
+		let error = result.unwrap_err();
+		assert!(error.contains("unknown field `delimeter`"), "{}", error);
+	}
+}
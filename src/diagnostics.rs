@@ -0,0 +1,160 @@
+/*
+ * diagnostics.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::{Debug, Write};
+use std::sync::Mutex;
+use tracing::{Event, Level, Subscriber, field::{Field, Visit}};
+use tracing_subscriber::layer::{Context, Layer};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Log capture.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A single captured [event](Event), reduced to its essentials for
+/// presentation.
+#[derive(Clone, Debug)]
+pub(crate) struct LogEntry
+{
+	/// The severity of the event.
+	pub(crate) level: Level,
+
+	/// The module that issued the event.
+	pub(crate) target: String,
+
+	/// The message, followed by any other fields as `name=value` pairs.
+	pub(crate) message: String
+}
+
+impl LogEntry
+{
+	/// Render the entry as a single line of text, e.g., for a bug report.
+	pub(crate) fn to_line(&self) -> String
+	{
+		format!("{:>5} {}: {}", self.level, self.target, self.message)
+	}
+}
+
+/// A [layer](Layer) that retains the most recent [events](Event) in memory, so
+/// that the application can present them to the user. Install it alongside the
+/// usual subscriber layers.
+pub struct LogCapture;
+
+impl<S: Subscriber> Layer<S> for LogCapture
+{
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>)
+	{
+		let metadata = event.metadata();
+		let mut visitor = MessageVisitor::default();
+		event.record(&mut visitor);
+		let entry = LogEntry {
+			level: *metadata.level(),
+			target: metadata.target().to_string(),
+			message: visitor.message
+		};
+		if let Ok(mut entries) = RECENT_EVENTS.lock()
+		{
+			if entries.len() >= MAXIMUM_RETAINED_EVENTS
+			{
+				entries.remove(0);
+			}
+			entries.push(entry);
+		}
+	}
+}
+
+/// Assembles the message of an [event](Event) from its fields.
+#[derive(Default)]
+struct MessageVisitor
+{
+	/// The message assembled so far.
+	message: String
+}
+
+impl Visit for MessageVisitor
+{
+	fn record_debug(&mut self, field: &Field, value: &dyn Debug)
+	{
+		if !self.message.is_empty()
+		{
+			self.message.push(' ');
+		}
+		if field.name() == "message"
+		{
+			let _ = write!(self.message, "{:?}", value);
+		}
+		else
+		{
+			let _ = write!(self.message, "{}={:?}", field.name(), value);
+		}
+	}
+
+	fn record_str(&mut self, field: &Field, value: &str)
+	{
+		// Avoid the quotes that the Debug representation would add.
+		self.record_debug(field, &format_args!("{}", value));
+	}
+}
+
+/// Answer a copy of the [captured](LogCapture) events, oldest first.
+pub(crate) fn recent_events() -> Vec<LogEntry>
+{
+	RECENT_EVENTS.lock().map(|entries| entries.clone()).unwrap_or_default()
+}
+
+/// Discard all [captured](LogCapture) events.
+pub(crate) fn clear_events()
+{
+	if let Ok(mut entries) = RECENT_EVENTS.lock()
+	{
+		entries.clear();
+	}
+}
+
+/// Render all [captured](LogCapture) events as text, one per line.
+pub(crate) fn events_as_text() -> String
+{
+	recent_events().iter().fold(String::new(), |mut text, entry| {
+		text.push_str(&entry.to_line());
+		text.push('\n');
+		text
+	})
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The maximum number of events retained by [`LogCapture`]. Older events are
+/// discarded first.
+const MAXIMUM_RETAINED_EVENTS: usize = 500;
+
+/// The events retained by [`LogCapture`], oldest first.
+static RECENT_EVENTS: Mutex<Vec<LogEntry>> = Mutex::new(Vec::new());
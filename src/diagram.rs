@@ -0,0 +1,203 @@
+/*
+ * diagram.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use petgraph::{
+	algo::tarjan_scc,
+	Direction::Incoming,
+	graph::DiGraph
+};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Diagrams.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A laid-out picture of a constraint graph, independent of any particular
+/// rendering. Sections are arranged in layers from top to bottom, such that
+/// every edge points downward, except for edges that participate in a cycle.
+#[derive(Clone, Debug)]
+pub(crate) struct Diagram
+{
+	/// The nodes, one per section, in section order.
+	pub(crate) nodes: Vec<DiagramNode>,
+
+	/// The edges, each from a section that must occur earlier to a section
+	/// that must occur later.
+	pub(crate) edges: Vec<DiagramEdge>,
+
+	/// The width of the diagram, in points.
+	pub(crate) width: f32,
+
+	/// The height of the diagram, in points.
+	pub(crate) height: f32
+}
+
+/// A section within a [diagram](Diagram).
+#[derive(Clone, Debug)]
+pub(crate) struct DiagramNode
+{
+	/// The stable identifier of the section, suitable for use as an element
+	/// identifier in exported formats.
+	pub(crate) id: String,
+
+	/// The short label drawn inside the node, e.g., `§3`.
+	pub(crate) label: String,
+
+	/// The longer description of the node, e.g., the opening of the section.
+	pub(crate) description: String,
+
+	/// Whether the section is locked in place.
+	pub(crate) fixed: bool,
+
+	/// The horizontal coordinate of the center of the node, in points. Assigned
+	/// by [`layout_diagram`].
+	pub(crate) x: f32,
+
+	/// The vertical coordinate of the center of the node, in points. Assigned
+	/// by [`layout_diagram`].
+	pub(crate) y: f32
+}
+
+/// An ordering constraint within a [diagram](Diagram).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DiagramEdge
+{
+	/// The zero-based index of the section that must occur earlier.
+	pub(crate) from: usize,
+
+	/// The zero-based index of the section that must occur later.
+	pub(crate) to: usize,
+
+	/// Whether the edge participates in a cycle, i.e., a paradox.
+	pub(crate) in_cycle: bool
+}
+
+/// Lay out a [diagram](Diagram) of the specified constraint graph. `nodes`
+/// supplies the node for each vertex, in vertex order, whose coordinates are
+/// then assigned. Only the specified `edges` are drawn, which permits the
+/// caller to omit implied edges, e.g., those that arise from a fixed beginning
+/// or ending, but every edge of `graph` contributes to the layering.
+pub(crate) fn layout_diagram(
+	graph: &DiGraph<usize, (), usize>,
+	mut nodes: Vec<DiagramNode>,
+	edges: &[(usize, usize)]
+) -> Diagram
+{
+	// Collapse each strongly connected component into a single layer, so that
+	// cycles don't prevent layering. Note that the components are answered in
+	// reverse topological order.
+	let components = tarjan_scc(graph);
+	let mut component_of = vec![0; graph.node_count()];
+	for (component, members) in components.iter().enumerate()
+	{
+		for member in members
+		{
+			component_of[member.index()] = component;
+		}
+	}
+	// Assign each component to the layer after the latest of its
+	// predecessors'.
+	let mut layer_of_component = vec![0; components.len()];
+	for (component, members) in components.iter().enumerate().rev()
+	{
+		let layer = members.iter()
+			.flat_map(|member| graph.neighbors_directed(*member, Incoming))
+			.map(|p| component_of[p.index()])
+			.filter(|p| *p != component)
+			.map(|p| layer_of_component[p] + 1)
+			.max()
+			.unwrap_or(0);
+		layer_of_component[component] = layer;
+	}
+	let layer_count = layer_of_component.iter().max().map_or(0, |m| m + 1);
+	let mut layers = vec![vec![]; layer_count];
+	for index in graph.node_indices()
+	{
+		layers[layer_of_component[component_of[index.index()]]]
+			.push(index.index());
+	}
+	// Keep the members of each component together, in the order of their
+	// earliest members, so that the edges of a cycle stay short.
+	let mut earliest_of_component = vec![usize::MAX; components.len()];
+	for index in graph.node_indices()
+	{
+		let earliest =
+			&mut earliest_of_component[component_of[index.index()]];
+		*earliest = (*earliest).min(index.index());
+	}
+	for members in &mut layers
+	{
+		members.sort_by_key(|m|
+			(earliest_of_component[component_of[*m]], *m)
+		);
+	}
+	// Center each layer horizontally within the widest one.
+	let widest = layers.iter().map(Vec::len).max().unwrap_or(0);
+	let width = 2.0 * DIAGRAM_MARGIN
+		+ widest.saturating_sub(1) as f32 * NODE_SPACING;
+	let height = 2.0 * DIAGRAM_MARGIN
+		+ layer_count.saturating_sub(1) as f32 * LAYER_SPACING;
+	for (layer, members) in layers.iter().enumerate()
+	{
+		let indent = (widest - members.len()) as f32 * NODE_SPACING / 2.0;
+		for (column, member) in members.iter().enumerate()
+		{
+			let node = &mut nodes[*member];
+			node.x = DIAGRAM_MARGIN + indent + column as f32 * NODE_SPACING;
+			node.y = DIAGRAM_MARGIN + layer as f32 * LAYER_SPACING;
+		}
+	}
+	let edges = edges.iter()
+		.map(|(from, to)| DiagramEdge {
+			from: *from,
+			to: *to,
+			in_cycle: component_of[*from] == component_of[*to]
+		})
+		.collect();
+	Diagram { nodes, edges, width, height }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The radius of each node of a [diagram](Diagram), in points.
+pub(crate) const NODE_RADIUS: f32 = 20.0;
+
+/// The horizontal distance between the centers of adjacent nodes in the same
+/// layer, in points.
+const NODE_SPACING: f32 = 64.0;
+
+/// The vertical distance between the centers of nodes in adjacent layers, in
+/// points.
+const LAYER_SPACING: f32 = 80.0;
+
+/// The margin around the nodes of a [diagram](Diagram), in points.
+const DIAGRAM_MARGIN: f32 = 40.0;
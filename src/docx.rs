@@ -0,0 +1,262 @@
+/*
+ * docx.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+use std::fmt::Write;
+use crate::archive::Archive;
+use crate::export::{escape_xml, paragraphs};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  DOCX.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified manuscript as a Word document, in standard manuscript
+/// format: double-spaced 12-point Times New Roman, with indented paragraphs.
+/// Blank lines separate paragraphs, and line breaks within a paragraph are
+/// kept. A paragraph for which `centered` answers `true`, e.g., a dinkus
+/// between sections, is centered instead of indented.
+pub(crate) fn manuscript_to_docx(
+	title: &str,
+	manuscript: &str,
+	centered: impl Fn(&str) -> bool
+) -> Vec<u8>
+{
+	let mut body = String::new();
+	for paragraph in paragraphs(manuscript)
+	{
+		body.push_str("<w:p>");
+		if centered(paragraph)
+		{
+			body.push_str(r#"<w:pPr><w:pStyle w:val="Centered"/></w:pPr>"#);
+		}
+		for (index, line) in paragraph.lines().enumerate()
+		{
+			body.push_str("<w:r>");
+			if index > 0
+			{
+				body.push_str("<w:br/>");
+			}
+			for (index, run) in line.split('\t').enumerate()
+			{
+				if index > 0
+				{
+					body.push_str("<w:tab/>");
+				}
+				let _ = write!(
+					body,
+					r#"<w:t xml:space="preserve">{}</w:t>"#,
+					escape_xml(run)
+				);
+			}
+			body.push_str("</w:r>");
+		}
+		body.push_str("</w:p>");
+	}
+	let document = format!(
+		r#"{}<w:document xmlns:w="{}"><w:body>{}<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/></w:sectPr></w:body></w:document>"#,
+		XML_DECLARATION,
+		WORDPROCESSING_NAMESPACE,
+		body
+	);
+	let core = format!(
+		r#"{}<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>{}</dc:title></cp:coreProperties>"#,
+		XML_DECLARATION,
+		escape_xml(title)
+	);
+	let styles = format!(
+		r#"{}<w:styles xmlns:w="{}"><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Times New Roman" w:hAnsi="Times New Roman" w:cs="Times New Roman"/><w:sz w:val="24"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="0" w:line="480" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:pPr><w:ind w:firstLine="720"/></w:pPr></w:style><w:style w:type="paragraph" w:styleId="Centered"><w:name w:val="Centered"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:firstLine="0"/><w:jc w:val="center"/></w:pPr></w:style></w:styles>"#,
+		XML_DECLARATION,
+		WORDPROCESSING_NAMESPACE
+	);
+	Archive::new()
+		.deflate("[Content_Types].xml", CONTENT_TYPES.as_bytes())
+		.deflate("_rels/.rels", PACKAGE_RELATIONSHIPS.as_bytes())
+		.deflate("docProps/core.xml", core.as_bytes())
+		.deflate("word/_rels/document.xml.rels", DOCUMENT_RELATIONSHIPS.as_bytes())
+		.deflate("word/document.xml", document.as_bytes())
+		.deflate("word/styles.xml", styles.as_bytes())
+		.finish()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The XML declaration that begins every part.
+const XML_DECLARATION: &str =
+	r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+
+/// The namespace of WordprocessingML.
+const WORDPROCESSING_NAMESPACE: &str =
+	"http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// The content types of the parts of the package.
+const CONTENT_TYPES: &str = concat!(
+	r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+	r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+	r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+	r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+	r#"<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>"#,
+	r#"<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>"#,
+	r#"<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>"#,
+	"</Types>"
+);
+
+/// The relationships of the package, which identify the main document and
+/// the core properties.
+const PACKAGE_RELATIONSHIPS: &str = concat!(
+	r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+	r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+	r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>"#,
+	r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>"#,
+	"</Relationships>"
+);
+
+/// The relationships of the main document, which identify its styles.
+const DOCUMENT_RELATIONSHIPS: &str = concat!(
+	r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+	r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+	r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+	"</Relationships>"
+);
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use crate::archive::extract;
+	use crate::export::unescape_xml;
+	use super::*;
+
+	/// Answer the specified part of the specified Word document.
+	fn part(docx: &[u8], name: &str) -> String
+	{
+		String::from_utf8(extract(docx, name).unwrap().unwrap()).unwrap()
+	}
+
+	/// Read the paragraphs of the specified main document part back as plain
+	/// text, the way a word processor would.
+	fn document_to_text(document: &str) -> String
+	{
+		let mut paragraphs = vec![];
+		let mut paragraph = String::new();
+		let mut in_text = false;
+		let mut rest = document;
+		while let Some(open) = rest.find('<')
+		{
+			if in_text
+			{
+				paragraph.push_str(&unescape_xml(&rest[.. open]));
+			}
+			let close = open + rest[open ..].find('>').unwrap();
+			let tag = &rest[open + 1 .. close];
+			rest = &rest[close + 1 ..];
+			match tag.split_whitespace().next().unwrap_or_default()
+			{
+				"w:t" => in_text = true,
+				"/w:t" => in_text = false,
+				"w:tab/" => paragraph.push('\t'),
+				"w:br/" => paragraph.push('\n'),
+				"/w:p" => paragraphs.push(std::mem::take(&mut paragraph)),
+				_ => {}
+			}
+		}
+		paragraphs.join("\n\n")
+	}
+
+	#[test]
+	fn escapes_xml()
+	{
+		let docx = manuscript_to_docx(
+			"Tom & Jerry",
+			"<b>Fish</b> & 'chips'",
+			|_| false
+		);
+		let document = part(&docx, "word/document.xml");
+		assert!(document.contains(
+			"&lt;b&gt;Fish&lt;/b&gt; &amp; &apos;chips&apos;"
+		));
+		assert!(!document.contains("<b>"));
+		assert!(part(&docx, "docProps/core.xml").contains("Tom &amp; Jerry"));
+	}
+
+	#[test]
+	fn writes_one_paragraph_per_paragraph()
+	{
+		let docx = manuscript_to_docx(
+			"",
+			"One,\ntwo.\n\n* * *\n\nThree.",
+			|paragraph| paragraph == "* * *"
+		);
+		let document = part(&docx, "word/document.xml");
+		assert_eq!(document.matches("<w:p>").count(), 3);
+		assert_eq!(document.matches("<w:br/>").count(), 1);
+		assert_eq!(document.matches(r#"w:val="Centered""#).count(), 1);
+		assert!(document.contains(
+			r#"<w:p><w:pPr><w:pStyle w:val="Centered"/></w:pPr><w:r><w:t xml:space="preserve">* * *</w:t></w:r></w:p>"#
+		));
+	}
+
+	#[test]
+	fn packages_every_related_part()
+	{
+		let docx = manuscript_to_docx("Title", "Text.", |_| false);
+		// Document relationships are relative to the main document.
+		for (relationships, base) in [
+			(PACKAGE_RELATIONSHIPS, ""),
+			(DOCUMENT_RELATIONSHIPS, "word/")
+		]
+		{
+			for target in relationships.split(r#"Target=""#).skip(1)
+			{
+				let target = &target[.. target.find('"').unwrap()];
+				let name = format!("{}{}", base, target);
+				assert!(extract(&docx, &name).unwrap().is_some(), "{}", name);
+			}
+		}
+		assert!(extract(&docx, "[Content_Types].xml").unwrap().is_some());
+	}
+
+	#[test]
+	fn round_trips_manuscripts()
+	{
+		let manuscript = "“Tom & Jerry” <3\n\nTabbed\tand   spaced.\nBroken \
+			line.\n\n* * *\n\nThe end.";
+		let docx = manuscript_to_docx("Title", manuscript, |_| false);
+		assert_eq!(
+			document_to_text(&part(&docx, "word/document.xml")),
+			manuscript
+		);
+	}
+}
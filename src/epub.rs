@@ -0,0 +1,270 @@
+/*
+ * epub.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+use std::fmt::Write;
+use crate::archive::Archive;
+use crate::export::{escape_xml, paragraphs};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  EPUB.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A chapter of an [e-book](chapters_to_epub).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Chapter
+{
+	/// The title of the chapter, as it appears in the table of contents and
+	/// atop the chapter.
+	pub(crate) title: String,
+
+	/// The text of the chapter. Blank lines separate paragraphs.
+	pub(crate) text: String
+}
+
+/// Render the specified [chapters](Chapter) as an EPUB 3 e-book with the
+/// specified title, identifier, e.g., a URN, and modification timestamp, in
+/// ISO 8601 format. The table of contents lists every chapter, both as an
+/// EPUB 3 navigation document and as an NCX, for older readers.
+pub(crate) fn chapters_to_epub(
+	title: &str,
+	identifier: &str,
+	modified: &str,
+	chapters: &[Chapter]
+) -> Vec<u8>
+{
+	let title = escape_xml(title);
+	let identifier = escape_xml(identifier);
+	let mut manifest = String::new();
+	let mut spine = String::new();
+	let mut navigation = String::new();
+	let mut points = String::new();
+	let mut archive = Archive::new();
+	// The media type must come first, uncompressed, so that it can be sniffed.
+	archive.store("mimetype", MEDIA_TYPE.as_bytes());
+	archive.deflate("META-INF/container.xml", CONTAINER.as_bytes());
+	for (index, chapter) in chapters.iter().enumerate()
+	{
+		let number = index + 1;
+		let heading = escape_xml(&chapter.title);
+		let _ = write!(
+			manifest,
+			r#"<item id="c{n}" href="chapter-{n}.xhtml" media-type="application/xhtml+xml"/>"#,
+			n = number
+		);
+		let _ = write!(spine, r#"<itemref idref="c{}"/>"#, number);
+		let _ = write!(
+			navigation,
+			r#"<li><a href="chapter-{}.xhtml">{}</a></li>"#,
+			number,
+			heading
+		);
+		let _ = write!(
+			points,
+			r#"<navPoint id="p{n}" playOrder="{n}"><navLabel><text>{}</text></navLabel><content src="chapter-{n}.xhtml"/></navPoint>"#,
+			heading,
+			n = number
+		);
+		let mut body = String::new();
+		for paragraph in paragraphs(&chapter.text)
+		{
+			let lines = paragraph.lines().map(escape_xml).collect::<Vec<_>>();
+			let _ = write!(body, "<p>{}</p>", lines.join("<br/>"));
+		}
+		let page = format!(
+			r#"{}<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><head><title>{heading}</title><link rel="stylesheet" type="text/css" href="style.css"/></head><body><section epub:type="chapter"><h1>{heading}</h1>{}</section></body></html>"#,
+			XHTML_PROLOGUE,
+			body,
+			heading = heading
+		);
+		archive.deflate(
+			&format!("OEBPS/chapter-{}.xhtml", number),
+			page.as_bytes()
+		);
+	}
+	let package = format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?><package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id"><metadata xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:identifier id="id">{}</dc:identifier><dc:title>{}</dc:title><dc:language>en</dc:language><meta property="dcterms:modified">{}</meta></metadata><manifest><item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/><item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/><item id="css" href="style.css" media-type="text/css"/>{}</manifest><spine toc="ncx">{}</spine></package>"#,
+		identifier,
+		title,
+		escape_xml(modified),
+		manifest,
+		spine
+	);
+	let nav = format!(
+		r#"{}<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><head><title>{t}</title></head><body><nav epub:type="toc" id="toc"><h1>{t}</h1><ol>{}</ol></nav></body></html>"#,
+		XHTML_PROLOGUE,
+		navigation,
+		t = title
+	);
+	let ncx = format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?><ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1"><head><meta name="dtb:uid" content="{}"/></head><docTitle><text>{}</text></docTitle><navMap>{}</navMap></ncx>"#,
+		identifier,
+		title,
+		points
+	);
+	archive.deflate("OEBPS/content.opf", package.as_bytes())
+		.deflate("OEBPS/nav.xhtml", nav.as_bytes())
+		.deflate("OEBPS/toc.ncx", ncx.as_bytes())
+		.deflate("OEBPS/style.css", STYLE.as_bytes())
+		.finish()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The media type of an EPUB, which is also the content of its `mimetype`
+/// entry.
+const MEDIA_TYPE: &str = "application/epub+zip";
+
+/// The container document, which locates the package document.
+const CONTAINER: &str = concat!(
+	r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+	r#"<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">"#,
+	r#"<rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>"#,
+	"</container>"
+);
+
+/// The XML declaration and document type that begin every XHTML document.
+const XHTML_PROLOGUE: &str =
+	r#"<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE html>"#;
+
+/// The style sheet of every chapter, which indents paragraphs in the manner of
+/// a printed book.
+const STYLE: &str = "h1 { text-align: center; margin: 2em 0; }\n\
+	p { margin: 0; text-indent: 1.5em; }\n\
+	h1 + p { text-indent: 0; }\n";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use crate::archive::extract;
+	use super::*;
+
+	/// Answer an e-book of the specified chapters.
+	fn epub(chapters: &[Chapter]) -> Vec<u8>
+	{
+		chapters_to_epub(
+			"Tom & Jerry",
+			"urn:uuid:0",
+			"2023-04-01T00:00:00Z",
+			chapters
+		)
+	}
+
+	/// Answer the specified part of the specified e-book.
+	fn part(epub: &[u8], name: &str) -> String
+	{
+		String::from_utf8(extract(epub, name).unwrap().unwrap()).unwrap()
+	}
+
+	/// Answer the values of the specified attribute, in order of appearance.
+	fn values<'a>(xml: &'a str, attribute: &str) -> Vec<&'a str>
+	{
+		xml.split(&format!(r#" {}=""#, attribute))
+			.skip(1)
+			.map(|value| &value[.. value.find('"').unwrap()])
+			.collect()
+	}
+
+	#[test]
+	fn stores_the_media_type_first()
+	{
+		let epub = epub(&[]);
+		// The first local header names the media type, which is stored
+		// without compression, so that the content follows the name directly.
+		let name_length = u16::from_le_bytes([epub[26], epub[27]]) as usize;
+		let extra_length = u16::from_le_bytes([epub[28], epub[29]]) as usize;
+		assert_eq!(&epub[8 .. 10], &[0, 0]);
+		assert_eq!(&epub[30 .. 30 + name_length], b"mimetype");
+		let start = 30 + name_length + extra_length;
+		assert_eq!(
+			&epub[start .. start + MEDIA_TYPE.len()],
+			MEDIA_TYPE.as_bytes()
+		);
+	}
+
+	#[test]
+	fn lists_every_chapter_in_the_manifest_and_spine()
+	{
+		let chapters = (1 ..= 3)
+			.map(|n| Chapter {
+				title: format!("§{}", n),
+				text: format!("Chapter {}.", n)
+			})
+			.collect::<Vec<_>>();
+		let epub = epub(&chapters);
+		let package = part(&epub, "OEBPS/content.opf");
+		let manifest = &package[package.find("<manifest>").unwrap()
+			.. package.find("</manifest>").unwrap()];
+		let spine = &package[package.find("<spine").unwrap() ..];
+		let ids = values(manifest, "id");
+		let hrefs = values(manifest, "href");
+		let idrefs = values(spine, "idref");
+		assert_eq!(idrefs, vec!["c1", "c2", "c3"]);
+		for idref in &idrefs
+		{
+			let index = ids.iter().position(|id| id == idref).unwrap();
+			let text = part(&epub, &format!("OEBPS/{}", hrefs[index]));
+			assert!(text.contains(&format!("Chapter {}.", &idref[1 ..])));
+		}
+		// Every item of the manifest is in the package.
+		for href in hrefs
+		{
+			let name = format!("OEBPS/{}", href);
+			assert!(extract(&epub, &name).unwrap().is_some(), "{}", name);
+		}
+		let container = part(&epub, "META-INF/container.xml");
+		assert_eq!(values(&container, "full-path"), vec!["OEBPS/content.opf"]);
+	}
+
+	#[test]
+	fn escapes_titles_and_text()
+	{
+		let epub = epub(&[Chapter {
+			title: "<Cats> & \"Dogs\"".to_string(),
+			text: "Fish & chips <3".to_string()
+		}]);
+		let escaped = "&lt;Cats&gt; &amp; &quot;Dogs&quot;";
+		let chapter = part(&epub, "OEBPS/chapter-1.xhtml");
+		assert!(chapter.contains(&format!("<title>{}</title>", escaped)));
+		assert!(chapter.contains(&format!("<h1>{}</h1>", escaped)));
+		assert!(chapter.contains("<p>Fish &amp; chips &lt;3</p>"));
+		assert!(part(&epub, "OEBPS/nav.xhtml").contains(escaped));
+		assert!(part(&epub, "OEBPS/toc.ncx").contains(escaped));
+		assert!(part(&epub, "OEBPS/content.opf").contains("Tom &amp; Jerry"));
+		assert!(!chapter.contains("<Cats>"));
+	}
+}
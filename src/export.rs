@@ -0,0 +1,842 @@
+/*
+ * export.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::Write;
+use ab_glyph::{Font, FontRef, point, PxScale, ScaleFont};
+use egui::FontDefinitions;
+use tiny_skia::{
+	FillRule,
+	Paint,
+	PathBuilder,
+	Pixmap,
+	PremultipliedColorU8,
+	Stroke,
+	Transform
+};
+use crate::diagram::{Diagram, DiagramEdge, NODE_RADIUS};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Documents.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A block of a simple text document, e.g., a review packet, which can be
+/// rendered as [Markdown](blocks_to_markdown) or
+/// [PDF](crate::pdf::blocks_to_pdf).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Block
+{
+	/// A heading of the specified level, from `1` (the title) to `3`.
+	Heading(u8, String),
+
+	/// A paragraph of running text.
+	Paragraph(String),
+
+	/// An item of a bulleted list.
+	Bullet(String)
+}
+
+/// Render the specified [blocks](Block) as Markdown.
+pub(crate) fn blocks_to_markdown(blocks: &[Block]) -> String
+{
+	let mut markdown = String::new();
+	for (index, block) in blocks.iter().enumerate()
+	{
+		match block
+		{
+			Block::Heading(level, text) =>
+			{
+				let _ = writeln!(
+					markdown,
+					"{} {}\n",
+					"#".repeat(*level as usize),
+					text
+				);
+			},
+			Block::Paragraph(text) =>
+			{
+				let _ = writeln!(markdown, "{}\n", text);
+			},
+			Block::Bullet(text) =>
+			{
+				let _ = writeln!(markdown, "- {}", text);
+				// Separate the list from whatever follows it.
+				if !matches!(blocks.get(index + 1), Some(Block::Bullet(_)))
+				{
+					markdown.push('\n');
+				}
+			}
+		}
+	}
+	markdown
+}
+
+/// Answer the paragraphs of the specified text, i.e., its runs of nonblank
+/// lines, trimmed of surrounding whitespace.
+pub(crate) fn paragraphs(text: &str) -> impl Iterator<Item = &str>
+{
+	let mut paragraphs = vec![];
+	let mut start = None;
+	let mut offset = 0;
+	for line in text.split_inclusive('\n')
+	{
+		match (line.trim().is_empty(), start)
+		{
+			(true, Some(from)) =>
+			{
+				paragraphs.push(&text[from .. offset]);
+				start = None;
+			},
+			(false, None) => start = Some(offset),
+			_ => {}
+		}
+		offset += line.len();
+	}
+	if let Some(from) = start
+	{
+		paragraphs.push(&text[from ..]);
+	}
+	paragraphs.into_iter().map(str::trim)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   CSV.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified rows, the first of which is usually a header, as
+/// comma-separated values, per
+/// [RFC&#32;4180](https://www.rfc-editor.org/rfc/rfc4180). Fields that contain
+/// commas, quotes, or line breaks are quoted.
+pub(crate) fn rows_to_csv(rows: &[Vec<String>]) -> String
+{
+	let mut csv = String::new();
+	for row in rows
+	{
+		let fields = row.iter().map(|field| escape_csv(field))
+			.collect::<Vec<_>>();
+		csv.push_str(&fields.join(","));
+		csv.push_str("\r\n");
+	}
+	csv
+}
+
+/// Escape the specified field for inclusion in comma-separated values.
+fn escape_csv(field: &str) -> String
+{
+	if field.contains(['"', ',', '\r', '\n'])
+	{
+		format!("\"{}\"", field.replace('"', "\"\""))
+	}
+	else
+	{
+		field.to_string()
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Timestamps.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer the current time, in whole seconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn unix_time() -> u64
+{
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Answer the current time, in whole seconds since the Unix epoch. The
+/// standard library's clock is unavailable on the web, so ask the browser.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn unix_time() -> u64
+{
+	(js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Format the specified time, in seconds since the Unix epoch, as an ISO 8601
+/// timestamp in UTC, e.g., `2023-04-01T12:34:56Z`.
+pub(crate) fn iso_timestamp(seconds: u64) -> String
+{
+	let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+	// Convert the day count to a civil date, per Howard Hinnant's algorithm.
+	let days = days as i64 + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+		- day_of_era / 146_096) / 365;
+	let day_of_year =
+		day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 { shifted_month + 3 }
+		else { shifted_month - 9 };
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+		year,
+		month,
+		day,
+		seconds / 3_600,
+		seconds / 60 % 60,
+		seconds % 60
+	)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                              Edge geometry.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The shape of a [diagram&#32;edge](DiagramEdge): a quadratic Bézier curve
+/// from the boundary of one node to the boundary of another, plus an
+/// arrowhead. Straight edges simply place the control point at the midpoint.
+pub(crate) struct EdgeGeometry
+{
+	/// The start of the curve.
+	pub(crate) start: (f32, f32),
+
+	/// The control point of the curve.
+	pub(crate) control: (f32, f32),
+
+	/// The end of the curve, which is also the tip of the arrowhead.
+	pub(crate) end: (f32, f32),
+
+	/// The two back corners of the arrowhead.
+	pub(crate) barbs: [(f32, f32); 2]
+}
+
+/// Compute the [geometry](EdgeGeometry) of the specified edge of the specified
+/// diagram. Edges between nodes of the same layer, which arise only from
+/// cycles, bow so that they don't pass through intervening nodes.
+pub(crate) fn edge_geometry(
+	diagram: &Diagram,
+	edge: &DiagramEdge
+) -> EdgeGeometry
+{
+	let from = &diagram.nodes[edge.from];
+	let to = &diagram.nodes[edge.to];
+	let (start, control, end) = if edge.from == edge.to
+	{
+		// Draw a self-loop as a small arch above the node.
+		let offset = NODE_RADIUS * std::f32::consts::FRAC_1_SQRT_2;
+		(
+			(from.x - offset, from.y - offset),
+			(from.x, from.y - 3.0 * NODE_RADIUS),
+			(from.x + offset, from.y - offset)
+		)
+	}
+	else
+	{
+		let (dx, dy) = (to.x - from.x, to.y - from.y);
+		let control = if dy.abs() < f32::EPSILON
+		{
+			// Rightward edges bow upward and leftward edges bow downward, so
+			// that the two halves of a two-cycle remain distinct.
+			((from.x + to.x) / 2.0, from.y - dx * EDGE_BOW)
+		}
+		else
+		{
+			((from.x + to.x) / 2.0, (from.y + to.y) / 2.0)
+		};
+		(
+			toward((from.x, from.y), control, NODE_RADIUS),
+			control,
+			toward((to.x, to.y), control, NODE_RADIUS)
+		)
+	};
+	let (ux, uy) = unit(control, end);
+	let base = (end.0 - ux * ARROW_LENGTH, end.1 - uy * ARROW_LENGTH);
+	let half = ARROW_WIDTH / 2.0;
+	EdgeGeometry {
+		start,
+		control,
+		end,
+		barbs: [
+			(base.0 - uy * half, base.1 + ux * half),
+			(base.0 + uy * half, base.1 - ux * half)
+		]
+	}
+}
+
+/// Answer the point at the specified distance from `origin` in the direction
+/// of `target`.
+fn toward(origin: (f32, f32), target: (f32, f32), distance: f32) -> (f32, f32)
+{
+	let (ux, uy) = unit(origin, target);
+	(origin.0 + ux * distance, origin.1 + uy * distance)
+}
+
+/// Answer the unit vector from `from` toward `to`.
+fn unit(from: (f32, f32), to: (f32, f32)) -> (f32, f32)
+{
+	let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+	let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+	(dx / length, dy / length)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   SVG.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified [diagram](Diagram) as a standalone SVG document. Each
+/// node carries its description as a tooltip.
+pub(crate) fn diagram_to_svg(diagram: &Diagram) -> String
+{
+	let mut svg = String::new();
+	let _ = writeln!(
+		svg,
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="{size}">"#,
+		w = diagram.width,
+		h = diagram.height,
+		size = LABEL_SIZE
+	);
+	let _ = writeln!(
+		svg,
+		r#"<rect width="100%" height="100%" fill="{}"/>"#,
+		hex(BACKGROUND_COLOR)
+	);
+	for edge in &diagram.edges
+	{
+		let geometry = edge_geometry(diagram, edge);
+		let color = hex(if edge.in_cycle { CYCLE_COLOR } else { EDGE_COLOR });
+		let _ = writeln!(
+			svg,
+			r#"<path d="M {} {} Q {} {} {} {}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+			geometry.start.0, geometry.start.1,
+			geometry.control.0, geometry.control.1,
+			geometry.end.0, geometry.end.1,
+			color,
+			EDGE_WIDTH
+		);
+		let _ = writeln!(
+			svg,
+			r#"<polygon points="{},{} {},{} {},{}" fill="{}"/>"#,
+			geometry.end.0, geometry.end.1,
+			geometry.barbs[0].0, geometry.barbs[0].1,
+			geometry.barbs[1].0, geometry.barbs[1].1,
+			color
+		);
+	}
+	for node in &diagram.nodes
+	{
+		let _ = writeln!(
+			svg,
+			r#"<g id="s{}"><title>{}</title><circle cx="{}" cy="{}" r="{}" fill="{}" stroke="{}" stroke-width="{}"/><text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" fill="{}">{}</text></g>"#,
+			escape_xml(&node.id),
+			escape_xml(&node.description),
+			node.x, node.y, NODE_RADIUS,
+			hex(if node.fixed { FIXED_FILL_COLOR } else { NODE_FILL_COLOR }),
+			hex(NODE_STROKE_COLOR),
+			if node.fixed { FIXED_STROKE_WIDTH } else { NODE_STROKE_WIDTH },
+			node.x, node.y,
+			hex(TEXT_COLOR),
+			escape_xml(&node.label)
+		);
+	}
+	svg.push_str("</svg>\n");
+	svg
+}
+
+/// Render the specified color as a CSS hex color.
+fn hex((r, g, b): (u8, u8, u8)) -> String
+{
+	format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escape the specified text for inclusion in XML character data or
+/// attribute values.
+pub(crate) fn escape_xml(text: &str) -> String
+{
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars()
+	{
+		match c
+		{
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+/// Replace the predefined entities and character references of the specified
+/// XML character data with the characters that they denote.
+pub(crate) fn unescape_xml(text: &str) -> String
+{
+	let mut unescaped = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(ampersand) = rest.find('&')
+	{
+		unescaped.push_str(&rest[.. ampersand]);
+		rest = &rest[ampersand ..];
+		let Some(semicolon) = rest.find(';') else { break };
+		let entity = &rest[1 .. semicolon];
+		let c = match entity
+		{
+			"amp" => Some('&'),
+			"lt" => Some('<'),
+			"gt" => Some('>'),
+			"quot" => Some('"'),
+			"apos" => Some('\''),
+			_ => entity.strip_prefix("#x")
+				.map(|hex| u32::from_str_radix(hex, 16))
+				.or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+				.and_then(Result::ok)
+				.and_then(char::from_u32)
+		};
+		match c
+		{
+			Some(c) =>
+			{
+				unescaped.push(c);
+				rest = &rest[semicolon + 1 ..];
+			},
+			None =>
+			{
+				unescaped.push('&');
+				rest = &rest[1 ..];
+			}
+		}
+	}
+	unescaped.push_str(rest);
+	unescaped
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Mermaid.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified [diagram](Diagram) as a Mermaid `graph TD` snippet,
+/// fenced for Markdown, e.g., for notes in Obsidian or Notion. Each node is
+/// identified by its stable identifier, so that hand-written links survive
+/// renumbering, and labeled with both its label and its description. The
+/// layout is left to Mermaid.
+pub(crate) fn diagram_to_mermaid(diagram: &Diagram) -> String
+{
+	let mut mermaid = String::from("```mermaid\ngraph TD\n");
+	for node in &diagram.nodes
+	{
+		let label = if node.description.is_empty()
+		{
+			node.label.clone()
+		}
+		else
+		{
+			format!("{}: {}", node.label, node.description)
+		};
+		let _ = writeln!(
+			mermaid,
+			"    s{}[\"{}\"]",
+			node.id,
+			escape_mermaid(&label)
+		);
+	}
+	for edge in &diagram.edges
+	{
+		let _ = writeln!(
+			mermaid,
+			"    s{} --> s{}",
+			diagram.nodes[edge.from].id,
+			diagram.nodes[edge.to].id
+		);
+	}
+	let fixed = diagram.nodes.iter()
+		.filter(|node| node.fixed)
+		.map(|node| format!("s{}", node.id))
+		.collect::<Vec<_>>();
+	if !fixed.is_empty()
+	{
+		let _ = writeln!(
+			mermaid,
+			"    classDef fixed fill:{},stroke-width:{}px",
+			hex(FIXED_FILL_COLOR),
+			FIXED_STROKE_WIDTH
+		);
+		let _ = writeln!(mermaid, "    class {} fixed", fixed.join(","));
+	}
+	// Mermaid identifies links by their order of appearance.
+	let cycles = diagram.edges.iter()
+		.enumerate()
+		.filter(|(_, edge)| edge.in_cycle)
+		.map(|(index, _)| index.to_string())
+		.collect::<Vec<_>>();
+	if !cycles.is_empty()
+	{
+		let _ = writeln!(
+			mermaid,
+			"    linkStyle {} stroke:{}",
+			cycles.join(","),
+			hex(CYCLE_COLOR)
+		);
+	}
+	mermaid.push_str("```\n");
+	mermaid
+}
+
+/// Escape the specified text for inclusion in a quoted Mermaid label, using
+/// Mermaid's entity codes.
+fn escape_mermaid(text: &str) -> String
+{
+	text.replace('"', "#quot;")
+		.replace('<', "#lt;")
+		.replace('>', "#gt;")
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   PNG.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified [diagram](Diagram) as a PNG image, at
+/// [`PNG_SCALE`] pixels per point. Labels are drawn with the proportional
+/// font that ships with [`egui`], so the image looks the same everywhere.
+pub(crate) fn diagram_to_png(diagram: &Diagram) -> Result<Vec<u8>, String>
+{
+	let width = (diagram.width * PNG_SCALE).ceil() as u32;
+	let height = (diagram.height * PNG_SCALE).ceil() as u32;
+	let mut pixmap = Pixmap::new(width.max(1), height.max(1))
+		.ok_or_else(|| "The graph is too large to render.".to_string())?;
+	let (r, g, b) = BACKGROUND_COLOR;
+	pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 0xff));
+	let transform = Transform::from_scale(PNG_SCALE, PNG_SCALE);
+	for edge in &diagram.edges
+	{
+		let geometry = edge_geometry(diagram, edge);
+		let paint = paint(if edge.in_cycle { CYCLE_COLOR } else { EDGE_COLOR });
+		let mut curve = PathBuilder::new();
+		curve.move_to(geometry.start.0, geometry.start.1);
+		curve.quad_to(
+			geometry.control.0, geometry.control.1,
+			geometry.end.0, geometry.end.1
+		);
+		if let Some(curve) = curve.finish()
+		{
+			let stroke = Stroke { width: EDGE_WIDTH, ..Stroke::default() };
+			pixmap.stroke_path(&curve, &paint, &stroke, transform, None);
+		}
+		let mut arrow = PathBuilder::new();
+		arrow.move_to(geometry.end.0, geometry.end.1);
+		arrow.line_to(geometry.barbs[0].0, geometry.barbs[0].1);
+		arrow.line_to(geometry.barbs[1].0, geometry.barbs[1].1);
+		arrow.close();
+		if let Some(arrow) = arrow.finish()
+		{
+			pixmap.fill_path(&arrow, &paint, FillRule::Winding, transform, None);
+		}
+	}
+	for node in &diagram.nodes
+	{
+		if let Some(circle) = PathBuilder::from_circle(node.x, node.y, NODE_RADIUS)
+		{
+			let fill = if node.fixed { FIXED_FILL_COLOR } else { NODE_FILL_COLOR };
+			pixmap.fill_path(
+				&circle,
+				&paint(fill),
+				FillRule::Winding,
+				transform,
+				None
+			);
+			let stroke = Stroke {
+				width: if node.fixed { FIXED_STROKE_WIDTH }
+					else { NODE_STROKE_WIDTH },
+				..Stroke::default()
+			};
+			pixmap.stroke_path(
+				&circle,
+				&paint(NODE_STROKE_COLOR),
+				&stroke,
+				transform,
+				None
+			);
+		}
+	}
+	let fonts = FontDefinitions::default();
+	let font = fonts.font_data.get(LABEL_FONT)
+		.and_then(|data| FontRef::try_from_slice(&data.font).ok())
+		.ok_or_else(|| "The label font is unavailable.".to_string())?;
+	for node in &diagram.nodes
+	{
+		draw_centered_text(
+			&mut pixmap,
+			&font,
+			&node.label,
+			(node.x * PNG_SCALE, node.y * PNG_SCALE),
+			LABEL_SIZE * PNG_SCALE
+		);
+	}
+	pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+/// Answer an anti-aliased [paint](Paint) of the specified opaque color.
+fn paint((r, g, b): (u8, u8, u8)) -> Paint<'static>
+{
+	let mut paint = Paint::default();
+	paint.set_color_rgba8(r, g, b, 0xff);
+	paint.anti_alias = true;
+	paint
+}
+
+/// Draw the specified text in [`TEXT_COLOR`], centered on the specified
+/// point, which is given in pixels. The pixmap must be opaque.
+fn draw_centered_text(
+	pixmap: &mut Pixmap,
+	font: &FontRef<'_>,
+	text: &str,
+	(x, y): (f32, f32),
+	size: f32
+)
+{
+	let scaled = font.as_scaled(PxScale::from(size));
+	let width = text.chars()
+		.map(|c| scaled.h_advance(font.glyph_id(c)))
+		.sum::<f32>();
+	let baseline = y + (scaled.ascent() + scaled.descent()) / 2.0;
+	let mut caret = x - width / 2.0;
+	let pixmap_width = pixmap.width() as i32;
+	let pixmap_height = pixmap.height() as i32;
+	let pixels = pixmap.pixels_mut();
+	let (r, g, b) = TEXT_COLOR;
+	for c in text.chars()
+	{
+		let id = font.glyph_id(c);
+		let glyph = id.with_scale_and_position(
+			PxScale::from(size),
+			point(caret, baseline)
+		);
+		caret += scaled.h_advance(id);
+		let Some(outline) = font.outline_glyph(glyph) else { continue };
+		let bounds = outline.px_bounds();
+		outline.draw(|gx, gy, coverage| {
+			let px = bounds.min.x as i32 + gx as i32;
+			let py = bounds.min.y as i32 + gy as i32;
+			if px < 0 || py < 0 || px >= pixmap_width || py >= pixmap_height
+			{
+				return
+			}
+			let pixel = &mut pixels[(py * pixmap_width + px) as usize];
+			let blend = |under: u8, over: u8|
+				(under as f32 * (1.0 - coverage) + over as f32 * coverage)
+					.round() as u8;
+			if let Some(blended) = PremultipliedColorU8::from_rgba(
+				blend(pixel.red(), r),
+				blend(pixel.green(), g),
+				blend(pixel.blue(), b),
+				0xff
+			)
+			{
+				*pixel = blended;
+			}
+		});
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// How far a same-layer edge bows upward, as a fraction of its length.
+const EDGE_BOW: f32 = 0.3;
+
+/// The length of an arrowhead, in points.
+const ARROW_LENGTH: f32 = 9.0;
+
+/// The width of the back of an arrowhead, in points.
+const ARROW_WIDTH: f32 = 7.0;
+
+/// The width of an edge, in points.
+const EDGE_WIDTH: f32 = 1.5;
+
+/// The width of the outline of a node, in points.
+const NODE_STROKE_WIDTH: f32 = 1.5;
+
+/// The width of the outline of a fixed node, in points.
+const FIXED_STROKE_WIDTH: f32 = 3.0;
+
+/// The font size of node labels, in points.
+const LABEL_SIZE: f32 = 14.0;
+
+/// The name of the [`egui`] font with which to draw node labels in PNG images.
+const LABEL_FONT: &str = "Ubuntu-Light";
+
+/// The number of pixels per point in PNG images.
+const PNG_SCALE: f32 = 2.0;
+
+/// The background color of exported diagrams. Exported diagrams always use a
+/// light scheme, irrespective of the application theme, because they are
+/// likely to be printed or pasted into documents.
+const BACKGROUND_COLOR: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+/// The color of an ordinary edge.
+const EDGE_COLOR: (u8, u8, u8) = (0x55, 0x55, 0x55);
+
+/// The color of an edge that participates in a cycle. Vermilion remains
+/// distinguishable under the common forms of color blindness.
+const CYCLE_COLOR: (u8, u8, u8) = (0xd5, 0x5e, 0x00);
+
+/// The fill color of an ordinary node.
+const NODE_FILL_COLOR: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+/// The fill color of a fixed node.
+const FIXED_FILL_COLOR: (u8, u8, u8) = (0xe4, 0xe4, 0xe4);
+
+/// The outline color of a node.
+const NODE_STROKE_COLOR: (u8, u8, u8) = (0x33, 0x33, 0x33);
+
+/// The color of node labels.
+const TEXT_COLOR: (u8, u8, u8) = (0x00, 0x00, 0x00);
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use crate::diagram::DiagramNode;
+	use super::*;
+
+	/// Build a diagram of nodes with the specified labels and descriptions,
+	/// laid out in a row, joined by the specified edges, which participate in
+	/// a cycle if so flagged.
+	fn diagram(nodes: &[(&str, &str)], edges: &[(usize, usize, bool)])
+		-> Diagram
+	{
+		Diagram {
+			nodes: nodes.iter()
+				.enumerate()
+				.map(|(index, (label, description))| DiagramNode {
+					id: format!("{:06x}", index),
+					label: label.to_string(),
+					description: description.to_string(),
+					fixed: false,
+					x: 50.0 + 100.0 * index as f32,
+					y: 50.0
+				})
+				.collect(),
+			edges: edges.iter()
+				.map(|(from, to, in_cycle)| DiagramEdge {
+					from: *from,
+					to: *to,
+					in_cycle: *in_cycle
+				})
+				.collect(),
+			width: 100.0 * nodes.len() as f32,
+			height: 100.0
+		}
+	}
+
+	#[test]
+	fn escapes_svg_labels_and_descriptions()
+	{
+		let svg = diagram_to_svg(
+			&diagram(&[("§1 <&>", "\"Hi,\" she said. 'Bye.'")], &[])
+		);
+		assert!(svg.contains(
+			"<title>&quot;Hi,&quot; she said. &apos;Bye.&apos;</title>"
+		));
+		assert!(svg.contains(">§1 &lt;&amp;&gt;</text>"));
+		assert!(!svg.contains("<&>"));
+	}
+
+	#[test]
+	fn renders_empty_diagrams_as_png()
+	{
+		let png = diagram_to_png(&diagram(&[], &[])).unwrap();
+		assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+	}
+
+	#[test]
+	fn escapes_mermaid_labels()
+	{
+		assert_eq!(
+			escape_mermaid("\"Hi\" <b>"),
+			"#quot;Hi#quot; #lt;b#gt;"
+		);
+		let mermaid = diagram_to_mermaid(&diagram(&[("§1", "<\"x\">")], &[]));
+		assert!(
+			mermaid.contains("    s000000[\"§1: #lt;#quot;x#quot;#gt;\"]\n")
+		);
+	}
+
+	#[test]
+	fn styles_cycles_and_fixed_sections_in_mermaid()
+	{
+		let mut diagram = diagram(
+			&[("§1", ""), ("§2", ""), ("§3", "")],
+			&[(0, 1, false), (1, 2, true), (2, 1, true)]
+		);
+		let mermaid = diagram_to_mermaid(&diagram);
+		assert!(mermaid.contains("    s000000[\"§1\"]\n"));
+		assert!(mermaid.contains(&format!(
+			"    linkStyle 1,2 stroke:{}\n",
+			hex(CYCLE_COLOR)
+		)));
+		assert!(!mermaid.contains("classDef"));
+		diagram.nodes[0].fixed = true;
+		diagram.nodes[2].fixed = true;
+		let mermaid = diagram_to_mermaid(&diagram);
+		assert!(mermaid.contains(&format!(
+			"    classDef fixed fill:{},stroke-width:{}px\n    class \
+			s000000,s000002 fixed\n",
+			hex(FIXED_FILL_COLOR),
+			FIXED_STROKE_WIDTH
+		)));
+	}
+
+	#[test]
+	fn quotes_csv_fields_as_needed()
+	{
+		let rows = vec![
+			vec!["Plain".to_string(), "A, B".to_string()],
+			vec!["Say \"hi\"".to_string(), "Two\nlines".to_string()]
+		];
+		assert_eq!(
+			rows_to_csv(&rows),
+			"Plain,\"A, B\"\r\n\"Say \"\"hi\"\"\",\"Two\nlines\"\r\n"
+		);
+		assert_eq!(escape_csv("Carriage\rreturn"), "\"Carriage\rreturn\"");
+		assert_eq!(escape_csv(""), "");
+	}
+
+	#[test]
+	fn formats_timestamps_as_civil_dates()
+	{
+		assert_eq!(iso_timestamp(0), "1970-01-01T00:00:00Z");
+		assert_eq!(iso_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+		assert_eq!(iso_timestamp(1_709_210_096), "2024-02-29T12:34:56Z");
+		assert_eq!(iso_timestamp(1_704_067_199), "2023-12-31T23:59:59Z");
+		// 2100 isn't a leap year, so February ends on the 28th.
+		assert_eq!(iso_timestamp(4_107_456_000), "2100-02-28T00:00:00Z");
+		assert_eq!(iso_timestamp(4_107_542_400), "2100-03-01T00:00:00Z");
+	}
+}
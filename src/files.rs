@@ -0,0 +1,504 @@
+/*
+ * files.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use egui::DroppedFile;
+use crate::archive::{extract, is_archive};
+use crate::odt::{is_odt, odt_to_text};
+use crate::rtf::{is_rtf, rtf_to_text};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Saving.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A kind of file that the application can produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FileKind
+{
+	/// The human-readable name of the kind, e.g., for the filter of a file
+	/// dialog.
+	pub(crate) description: &'static str,
+
+	/// The conventional file extension, without the leading period.
+	pub(crate) extension: &'static str,
+
+	/// The MIME type.
+	pub(crate) mime: &'static str
+}
+
+/// Offer the specified bytes to the writer as a file of the specified
+/// [kind](FileKind). On a native build, ask the writer where to save the file,
+/// suggesting `file_name`; on the web, download the file as `file_name`.
+/// Answer the name or path of the saved file, or `None` if the writer
+/// cancelled. Answer an explanation on failure.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_file(
+	file_name: &str,
+	kind: FileKind,
+	bytes: &[u8]
+) -> Result<Option<String>, String>
+{
+	let Some(path) = rfd::FileDialog::new()
+		.set_file_name(file_name)
+		.add_filter(kind.description, &[kind.extension])
+		.save_file()
+	else
+	{
+		return Ok(None)
+	};
+	std::fs::write(&path, bytes)
+		.map(|_| Some(path.display().to_string()))
+		.map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+}
+
+/// Offer the specified bytes to the writer as a file of the specified
+/// [kind](FileKind). On a native build, ask the writer where to save the file,
+/// suggesting `file_name`; on the web, download the file as `file_name`.
+/// Answer the name or path of the saved file, or `None` if the writer
+/// cancelled. Answer an explanation on failure.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn save_file(
+	file_name: &str,
+	kind: FileKind,
+	bytes: &[u8]
+) -> Result<Option<String>, String>
+{
+	use wasm_bindgen::JsCast;
+	let describe = |e: wasm_bindgen::JsValue| format!("{:?}", e);
+	let document = web_sys::window()
+		.and_then(|window| window.document())
+		.ok_or_else(|| "The document is unavailable.".to_string())?;
+	let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+	let mut options = web_sys::BlobPropertyBag::new();
+	options.type_(kind.mime);
+	let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(
+		&parts,
+		&options
+	).map_err(describe)?;
+	let url = web_sys::Url::create_object_url_with_blob(&blob)
+		.map_err(describe)?;
+	// Downloads are triggered by clicking a transient anchor.
+	let anchor = document.create_element("a")
+		.map_err(describe)?
+		.dyn_into::<web_sys::HtmlAnchorElement>()
+		.map_err(|_| "Couldn't create a download link.".to_string())?;
+	anchor.set_href(&url);
+	anchor.set_download(file_name);
+	anchor.click();
+	let _ = web_sys::Url::revoke_object_url(&url);
+	Ok(Some(file_name.to_string()))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Opening.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Ask the writer to choose a text file of one of the specified
+/// [kinds](FileKind), and read it. Answer the path and contents of the file,
+/// or `None` if the writer cancelled. Answer an explanation on failure. Only
+/// available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn open_text_file(
+	kinds: &[FileKind]
+) -> Result<Option<(String, String)>, String>
+{
+	let Some(path) = text_file_dialog(kinds).pick_file()
+		else { return Ok(None) };
+	read_text_file(&path).map(Some)
+}
+
+/// Ask the writer to choose any number of text files of the specified
+/// [kinds](FileKind), and read them. Answer the path and contents of each
+/// file, in the order chosen, or `None` if the writer cancelled. Answer an
+/// explanation on failure. Only available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn open_text_files(
+	kinds: &[FileKind]
+) -> Result<Option<Vec<(String, String)>>, String>
+{
+	let Some(paths) = text_file_dialog(kinds).pick_files()
+		else { return Ok(None) };
+	paths.iter().map(|path| read_text_file(path)).collect::<Result<_, _>>()
+		.map(Some)
+}
+
+/// Ask the writer to choose a folder. Answer its path, or `None` if the writer
+/// cancelled. Only available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn choose_folder() -> Option<PathBuf>
+{
+	rfd::FileDialog::new().pick_folder()
+}
+
+/// Read the text files of the specified [kinds](FileKind) directly within
+/// the specified folder, ignoring hidden files and subfolders. Answer the
+/// path and contents of each file, in no particular order, or an explanation
+/// on failure. Only available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn read_folder(
+	folder: &Path,
+	kinds: &[FileKind]
+) -> Result<Vec<(String, String)>, String>
+{
+	let describe = |e: std::io::Error|
+		format!("Couldn't read {}: {}", folder.display(), e);
+	let mut files = vec![];
+	for entry in std::fs::read_dir(folder).map_err(describe)?
+	{
+		let path = entry.map_err(describe)?.path();
+		let hidden = path.file_name()
+			.map_or(true, |name| name.to_string_lossy().starts_with('.'));
+		let supported = path.extension()
+			.and_then(|extension| extension.to_str())
+			.map_or(false, |extension| kinds.iter()
+				.any(|kind| kind.extension.eq_ignore_ascii_case(extension))
+			);
+		if !hidden && supported && path.is_file()
+		{
+			files.push(read_text_file(&path)?);
+		}
+	}
+	Ok(files)
+}
+
+/// Answer a file dialog that offers the specified [kinds](FileKind) of files,
+/// first all at once, then each separately. Only available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+fn text_file_dialog(kinds: &[FileKind]) -> rfd::FileDialog
+{
+	let extensions = kinds.iter()
+		.map(|kind| kind.extension)
+		.collect::<Vec<_>>();
+	kinds.iter().fold(
+		rfd::FileDialog::new().add_filter(SUPPORTED_FILES, &extensions),
+		|dialog, kind| dialog.add_filter(kind.description, &[kind.extension])
+	)
+}
+
+/// Read the text file at the specified path. Answer the path and contents of
+/// the file, or an explanation on failure. Only available on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_text_file(path: &Path) -> Result<(String, String), String>
+{
+	let bytes = std::fs::read(path)
+		.map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+	let text = decode_text(&bytes)
+		.map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+	Ok((path.display().to_string(), text))
+}
+
+/// Read the specified file, which the writer dropped onto the application.
+/// On a native build, the file is read from its path; on the web, the browser
+/// supplies its contents. Answer the name and contents of the file, or an
+/// explanation on failure.
+pub(crate) fn read_dropped_file(
+	file: &DroppedFile
+) -> Result<(String, String), String>
+{
+	let name = match file.path
+	{
+		Some(ref path) => path.display().to_string(),
+		None => file.name.clone()
+	};
+	let bytes = match (&file.bytes, &file.path)
+	{
+		(Some(bytes), _) => bytes.to_vec(),
+		#[cfg(not(target_arch = "wasm32"))]
+		(None, Some(path)) => std::fs::read(path)
+			.map_err(|e| format!("Couldn't read {}: {}", name, e))?,
+		_ => return Err(format!("Couldn't read {}: it's unavailable.", name))
+	};
+	let text = decode_text(&bytes)
+		.map_err(|e| format!("Couldn't read {}: {}", name, e))?;
+	Ok((name, text))
+}
+
+/// Decode the specified bytes as text. Honor a byte order mark, so that
+/// UTF-16 files exported by word processors survive, but otherwise insist on
+/// UTF-8, rather than silently mangling the manuscript. Convert a Rich Text
+/// Format document to [plain&#32;text](rtf_to_text), and read the
+/// [text](odt_to_text) of an OpenDocument text document, but reject any other
+/// archive, e.g., a damaged one, rather than decoding it as text. Answer an
+/// explanation on failure.
+fn decode_text(bytes: &[u8]) -> Result<String, String>
+{
+	if is_rtf(bytes)
+	{
+		return Ok(rtf_to_text(bytes))
+	}
+	if is_odt(bytes)
+	{
+		return odt_to_text(bytes)
+	}
+	if is_archive(bytes)
+	{
+		return Err(extract(bytes, "mimetype").err().unwrap_or_else(||
+			"it isn't an OpenDocument text document".to_string()
+		))
+	}
+	let utf16 = |bytes: &[u8], decode: fn([u8; 2]) -> u16| {
+		let units = bytes.chunks_exact(2)
+			.map(|pair| decode([pair[0], pair[1]]))
+			.collect::<Vec<_>>();
+		String::from_utf16(&units)
+			.map_err(|_| "it isn't valid UTF-16 text".to_string())
+	};
+	match bytes
+	{
+		[0xef, 0xbb, 0xbf, rest @ ..] => decode_utf8(rest),
+		[0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+		[0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+		_ => decode_utf8(bytes)
+	}
+}
+
+/// Decode the specified bytes as UTF-8 text. Answer an explanation, including
+/// the offending position, on failure.
+fn decode_utf8(bytes: &[u8]) -> Result<String, String>
+{
+	String::from_utf8(bytes.to_vec()).map_err(|e| format!(
+		"it isn't UTF-8 text (the first bad byte is at offset {})",
+		e.utf8_error().valid_up_to()
+	))
+}
+
+/// Write the specified text to a file with the specified name in the
+/// temporary directory, and open it with the system's default application for
+/// its type, e.g., the writer's preferred editor for text files. Answer the
+/// path of the file, or an explanation on failure. Only available on native
+/// builds.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn open_in_default_application(
+	file_name: &str,
+	text: &str
+) -> Result<String, String>
+{
+	use std::process::Command;
+	let path = std::env::temp_dir().join(file_name);
+	std::fs::write(&path, text)
+		.map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+	let mut command = if cfg!(target_os = "windows")
+	{
+		// The empty argument is the window title, which start would otherwise
+		// take from a quoted path.
+		let mut command = Command::new("cmd");
+		command.args(["/C", "start", ""]);
+		command
+	}
+	else if cfg!(target_os = "macos")
+	{
+		Command::new("open")
+	}
+	else
+	{
+		Command::new("xdg-open")
+	};
+	command.arg(&path)
+		.spawn()
+		.map(|_| path.display().to_string())
+		.map_err(|e| format!("Couldn't open {}: {}", path.display(), e))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Scalable vector graphics.
+pub(crate) const SVG: FileKind = FileKind
+{
+	description: "SVG image",
+	extension: "svg",
+	mime: "image/svg+xml"
+};
+
+/// Portable network graphics.
+pub(crate) const PNG: FileKind = FileKind
+{
+	description: "PNG image",
+	extension: "png",
+	mime: "image/png"
+};
+
+/// Plain text.
+pub(crate) const TEXT: FileKind = FileKind
+{
+	description: "Text document",
+	extension: "txt",
+	mime: "text/plain"
+};
+
+/// Markdown.
+pub(crate) const MARKDOWN: FileKind = FileKind
+{
+	description: "Markdown document",
+	extension: "md",
+	mime: "text/markdown"
+};
+
+/// Hypertext markup language, i.e., a web page.
+pub(crate) const HTML: FileKind = FileKind
+{
+	description: "Web page",
+	extension: "html",
+	mime: "text/html"
+};
+
+/// Rich text format.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const RTF: FileKind = FileKind
+{
+	description: "Rich text document",
+	extension: "rtf",
+	mime: "application/rtf"
+};
+
+/// Office Open XML word processing document, i.e., Microsoft Word.
+pub(crate) const DOCX: FileKind = FileKind
+{
+	description: "Word document",
+	extension: "docx",
+	mime: "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+};
+
+/// OpenDocument text document, e.g., LibreOffice Writer.
+pub(crate) const ODT: FileKind = FileKind
+{
+	description: "OpenDocument text",
+	extension: "odt",
+	mime: "application/vnd.oasis.opendocument.text"
+};
+
+/// Electronic publication, i.e., an e-book.
+pub(crate) const EPUB: FileKind = FileKind
+{
+	description: "EPUB e-book",
+	extension: "epub",
+	mime: "application/epub+zip"
+};
+
+/// Comma-separated values, e.g., for spreadsheets.
+pub(crate) const CSV: FileKind = FileKind
+{
+	description: "CSV spreadsheet",
+	extension: "csv",
+	mime: "text/csv"
+};
+
+/// JavaScript object notation, e.g., for data exchanged with other tools.
+pub(crate) const JSON: FileKind = FileKind
+{
+	description: "JSON document",
+	extension: "json",
+	mime: "application/json"
+};
+
+/// Story Shuffler project, i.e., a manuscript with its constraints and
+/// shuffles.
+pub(crate) const SHUFFLE: FileKind = FileKind
+{
+	description: "Story Shuffler project",
+	extension: "shuffle",
+	mime: "application/x-story-shuffler"
+};
+
+/// ZIP archive, e.g., for several files downloaded at once.
+#[cfg(target_arch = "wasm32")]
+pub(crate) const ZIP: FileKind = FileKind
+{
+	description: "ZIP archive",
+	extension: "zip",
+	mime: "application/zip"
+};
+
+/// Portable document format.
+pub(crate) const PDF: FileKind = FileKind
+{
+	description: "PDF document",
+	extension: "pdf",
+	mime: "application/pdf"
+};
+
+/// The description of the filter that offers every supported kind of file.
+#[cfg(not(target_arch = "wasm32"))]
+const SUPPORTED_FILES: &str = "Supported documents";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use crate::archive::Archive;
+	use crate::epub::chapters_to_epub;
+	use crate::odt::manuscript_to_odt;
+	use super::*;
+
+	#[test]
+	fn decodes_archives_and_encodings()
+	{
+		let manuscript = "“One” & two.\n\n* * *\n\nThree.";
+		let odt = manuscript_to_odt("Title", manuscript, |_| false);
+		assert_eq!(decode_text(&odt).unwrap(), manuscript);
+		assert_eq!(
+			decode_text(b"{\\rtf1 One.\\par Two.}").unwrap(),
+			"One.\n\nTwo."
+		);
+		assert_eq!(decode_text("\u{feff}Café".as_bytes()).unwrap(), "Café");
+		let mut utf16 = vec![0xff, 0xfe];
+		utf16.extend("Café".encode_utf16().flat_map(u16::to_le_bytes));
+		assert_eq!(decode_text(&utf16).unwrap(), "Café");
+		let mut utf16 = vec![0xfe, 0xff];
+		utf16.extend("Café".encode_utf16().flat_map(u16::to_be_bytes));
+		assert_eq!(decode_text(&utf16).unwrap(), "Café");
+	}
+
+	#[test]
+	fn rejects_corrupt_files()
+	{
+		// Truncation loses the central directory of the archive.
+		let odt = manuscript_to_odt("Title", "Text.", |_| false);
+		let truncated = &odt[.. odt.len() - 10];
+		assert!(decode_text(truncated).unwrap_err().contains("damaged"));
+		// An archive whose media type claims OpenDocument but that has no
+		// content.
+		let hollow = Archive::new()
+			.store("mimetype", b"application/vnd.oasis.opendocument.text")
+			.finish();
+		assert!(decode_text(&hollow).unwrap_err().contains("no content"));
+		// Other archives aren't manuscripts.
+		let epub = chapters_to_epub("Title", "urn:uuid:0", "", &[]);
+		assert!(decode_text(&epub).unwrap_err().contains("OpenDocument"));
+		assert!(decode_text(b"ab\xffcd").unwrap_err().contains("offset 2"));
+		assert!(decode_text(&[0xff, 0xfe, 0x00, 0xd8]).is_err());
+	}
+}
@@ -0,0 +1,365 @@
+/*
+ * html.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::Write;
+use crate::epub::Chapter;
+use crate::export::{escape_xml, paragraphs, unescape_xml};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  HTML.                                     //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified [chapters](Chapter) as a standalone HTML document with
+/// the specified title, suitable for posting online. Each chapter is paired
+/// with the one-based number of its original section, which becomes the
+/// identifier of its `<section>`, e.g., `s7`, so that readers can link to, and
+/// comment on, particular sections regardless of their positions. A table of
+/// contents links to every chapter.
+pub(crate) fn chapters_to_html(
+	title: &str,
+	chapters: &[(usize, Chapter)]
+) -> String
+{
+	let title = escape_xml(title);
+	let mut contents = String::new();
+	let mut body = String::new();
+	for (number, chapter) in chapters
+	{
+		let heading = escape_xml(&chapter.title);
+		let _ = writeln!(
+			contents,
+			r##"<li><a href="#s{}">{}</a></li>"##,
+			number,
+			heading
+		);
+		let _ = writeln!(
+			body,
+			"<section id=\"s{}\">\n<h2>{}</h2>",
+			number,
+			heading
+		);
+		for paragraph in paragraphs(&chapter.text)
+		{
+			let lines = paragraph.lines().map(escape_xml).collect::<Vec<_>>();
+			let _ = writeln!(body, "<p>{}</p>", lines.join("<br>"));
+		}
+		body.push_str("</section>\n");
+	}
+	format!(
+		"<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n\
+			<meta charset=\"utf-8\">\n\
+			<meta name=\"viewport\" \
+			content=\"width=device-width, initial-scale=1\">\n\
+			<title>{t}</title>\n<style>\n{}</style>\n</head>\n<body>\n\
+			<h1>{t}</h1>\n<nav id=\"contents\">\n<h2>Contents</h2>\n\
+			<ol>\n{}</ol>\n</nav>\n{}</body>\n</html>\n",
+		STYLE,
+		contents,
+		body,
+		t = title
+	)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Import.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the specified text looks like HTML markup, e.g., because
+/// it was copied from a word processor in a browser, rather than prose that
+/// happens to mention a tag.
+pub(crate) fn is_html(text: &str) -> bool
+{
+	let text = text.trim_start();
+	if !text.starts_with('<')
+	{
+		return false
+	}
+	let text = text.to_ascii_lowercase();
+	HTML_MARKERS.iter().any(|marker| text.contains(marker))
+}
+
+/// Read the specified HTML markup as plain text, discarding all formatting,
+/// as well as the contents of the head, style sheets, and scripts. Blank lines
+/// separate paragraphs, and line breaks survive within them. Horizontal rules
+/// become the specified section break. Headings begin new sections, too: if
+/// there's a section break, then it precedes every heading but the first;
+/// otherwise, each heading becomes a Markdown heading of the same level.
+pub(crate) fn html_to_text(html: &str, section_break: Option<&str>) -> String
+{
+	let mut text = HtmlText::default();
+	// The depth of nested elements whose content isn't body text.
+	let mut skipped = 0usize;
+	let mut rest = html;
+	loop
+	{
+		let open = rest.find('<').unwrap_or(rest.len());
+		if skipped == 0
+		{
+			text.push(&rest[.. open]);
+		}
+		rest = &rest[open ..];
+		if rest.is_empty()
+		{
+			break
+		}
+		if let Some(comment) = rest.strip_prefix("<!--")
+		{
+			rest = comment.find("-->").map_or("", |end| &comment[end + 3 ..]);
+			continue
+		}
+		let Some(close) = rest.find('>') else { break };
+		let tag = &rest[1 .. close];
+		rest = &rest[close + 1 ..];
+		let (closing, tag) = match tag.strip_prefix('/')
+		{
+			Some(tag) => (true, tag),
+			None => (false, tag)
+		};
+		let empty = tag.ends_with('/');
+		let name = tag.trim_end_matches('/')
+			.split_whitespace()
+			.next()
+			.unwrap_or_default()
+			.to_ascii_lowercase();
+		if SKIPPED_HTML_ELEMENTS.contains(&name.as_str())
+		{
+			match (closing, empty)
+			{
+				(false, false) => skipped += 1,
+				(true, _) => skipped = skipped.saturating_sub(1),
+				_ => {}
+			}
+			continue
+		}
+		if skipped > 0
+		{
+			continue
+		}
+		let level = name.strip_prefix('h')
+			.and_then(|level| level.parse::<usize>().ok())
+			.filter(|level| (1 ..= 6).contains(level));
+		match (name.as_str(), level)
+		{
+			("br", _) => text.paragraph.push('\n'),
+			("hr", _) =>
+			{
+				text.end_paragraph();
+				if let Some(section_break) = section_break
+				{
+					text.paragraphs.push(section_break.to_string());
+				}
+			},
+			(_, Some(level)) =>
+			{
+				text.end_paragraph();
+				if !closing
+				{
+					match section_break
+					{
+						Some(section_break) if !text.paragraphs.is_empty() =>
+							text.paragraphs.push(section_break.to_string()),
+						Some(_) => {},
+						None => text.heading = Some(level)
+					}
+				}
+			},
+			(name, _) if BLOCK_HTML_ELEMENTS.contains(&name) =>
+				text.end_paragraph(),
+			_ => {}
+		}
+	}
+	text.end_paragraph();
+	// Don't let consecutive rules and headings introduce empty sections.
+	if let Some(section_break) = section_break
+	{
+		text.paragraphs.dedup_by(|next, previous|
+			next == section_break && previous == section_break
+		);
+	}
+	text.paragraphs.join("\n\n")
+}
+
+/// The plain text read so far from some HTML markup.
+#[derive(Default)]
+struct HtmlText
+{
+	/// The complete paragraphs.
+	paragraphs: Vec<String>,
+
+	/// The paragraph in progress.
+	paragraph: String,
+
+	/// The level of the heading that the paragraph in progress introduces, if
+	/// any.
+	heading: Option<usize>
+}
+
+impl HtmlText
+{
+	/// Append the specified HTML character data to the paragraph in progress,
+	/// replacing entities with the characters that they denote and collapsing
+	/// runs of whitespace, including line breaks, into single spaces, as a
+	/// browser would.
+	fn push(&mut self, text: &str)
+	{
+		let mut text = text.to_string();
+		for (entity, replacement) in HTML_ENTITIES
+		{
+			text = text.replace(entity, replacement);
+		}
+		for c in unescape_xml(&text).chars()
+		{
+			if !c.is_whitespace()
+			{
+				self.paragraph.push(c);
+			}
+			else if !self.paragraph.is_empty()
+				&& !self.paragraph.ends_with(char::is_whitespace)
+			{
+				self.paragraph.push(' ');
+			}
+		}
+	}
+
+	/// End the paragraph in progress, keeping it unless it's blank. If it
+	/// introduces a heading, then it becomes a Markdown heading of the same
+	/// level.
+	fn end_paragraph(&mut self)
+	{
+		let lines = self.paragraph.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty())
+			.collect::<Vec<_>>();
+		if !lines.is_empty()
+		{
+			let prefix = self.heading.map_or_else(String::new, |level|
+				format!("{} ", "#".repeat(level))
+			);
+			self.paragraphs.push(format!("{}{}", prefix, lines.join("\n")));
+		}
+		self.paragraph.clear();
+		self.heading = None;
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The style sheet of the document, which sets the text in a readable column
+/// and indents paragraphs in the manner of a printed book.
+const STYLE: &str = "body { max-width: 36em; margin: 2em auto; padding: 0 1em; \
+	font-family: Georgia, serif; line-height: 1.5; }\n\
+	h1, section > h2 { text-align: center; }\n\
+	section { margin-top: 3em; }\n\
+	section > p { margin: 0; text-indent: 1.5em; }\n\
+	section > h2 + p { text-indent: 0; }\n";
+
+/// Fragments, in lowercase, whose presence at the beginning of some text
+/// suggests that it's HTML markup.
+const HTML_MARKERS: &[&str] = &[
+	"<!doctype html", "<html", "<meta", "<body",
+	"</p>", "</div>", "</span>", "</li>", "<br", "<hr",
+	"</h1>", "</h2>", "</h3>", "</h4>", "</h5>", "</h6>"
+];
+
+/// The HTML elements whose content (and any nested elements) isn't body text.
+const SKIPPED_HTML_ELEMENTS: &[&str] =
+	&["head", "title", "style", "script", "template", "noscript"];
+
+/// The HTML elements, besides headings and rules, that begin and end
+/// paragraphs.
+const BLOCK_HTML_ELEMENTS: &[&str] = &[
+	"p", "div", "li", "tr", "blockquote", "pre", "section", "article",
+	"header", "footer", "ul", "ol", "table", "dt", "dd", "figcaption"
+];
+
+/// The named HTML entities that commonly occur in prose, beyond those
+/// predefined by XML, and their replacements. Non-breaking spaces become
+/// ordinary spaces.
+const HTML_ENTITIES: &[(&str, &str)] = &[
+	("&nbsp;", " "),
+	("&lsquo;", "‘"),
+	("&rsquo;", "’"),
+	("&ldquo;", "“"),
+	("&rdquo;", "”"),
+	("&ndash;", "–"),
+	("&mdash;", "—"),
+	("&hellip;", "…")
+];
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn escapes_section_text()
+	{
+		let html = chapters_to_html(
+			"Tom & Jerry",
+			&[(7, Chapter {
+				title: "<script>alert(1)</script>".to_string(),
+				text: "Fish & \"chips\" <3\nnext line\n\n<b>bold?</b>"
+					.to_string()
+			})]
+		);
+		assert!(html.contains("<title>Tom &amp; Jerry</title>"));
+		assert!(html.contains(
+			"<h2>&lt;script&gt;alert(1)&lt;/script&gt;</h2>"
+		));
+		assert!(html.contains(
+			"<p>Fish &amp; &quot;chips&quot; &lt;3<br>next line</p>"
+		));
+		assert!(html.contains("<p>&lt;b&gt;bold?&lt;/b&gt;</p>"));
+		assert!(!html.contains("<script>"));
+		assert!(!html.contains("<b>"));
+	}
+
+	#[test]
+	fn anchors_sections_by_number()
+	{
+		let chapters = [3, 1].map(|number| (number, Chapter {
+			title: format!("§{}", number),
+			text: "Text.".to_string()
+		}));
+		let html = chapters_to_html("Title", &chapters);
+		let first = html.find(r#"<section id="s3">"#).unwrap();
+		let second = html.find(r#"<section id="s1">"#).unwrap();
+		assert!(first < second);
+		assert!(html.contains(r##"<li><a href="#s3">§3</a></li>"##));
+		assert!(html.contains(r##"<li><a href="#s1">§1</a></li>"##));
+	}
+}
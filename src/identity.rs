@@ -0,0 +1,282 @@
+/*
+ * identity.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+////////////////////////////////////////////////////////////////////////////////
+//                           Section identifiers.                             //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer a short identifier for a section with the specified content, derived
+/// from a hash of the content, that is not already `taken`. Identical
+/// sections are disambiguated by salting the hash.
+pub(crate) fn section_id(content: &str, taken: &HashSet<String>) -> String
+{
+	let mut salt = 0u64;
+	loop
+	{
+		let hash = fnv1a(content.as_bytes(), salt);
+		let id = format!("{:06x}", hash & ID_MASK);
+		if !taken.contains(&id)
+		{
+			return id
+		}
+		salt += 1;
+	}
+}
+
+/// Answer distinct [identifiers](section_id) for the specified sections.
+pub(crate) fn fresh_section_ids(sections: &[String]) -> Vec<String>
+{
+	let mut taken = HashSet::new();
+	sections.iter()
+		.map(|section| {
+			let id = section_id(section, &taken);
+			taken.insert(id.clone());
+			id
+		})
+		.collect()
+}
+
+/// Match each of the `new` sections to one of the `old` sections, so that the
+/// identity of each section survives insertions, deletions, and edits that
+/// renumber its neighbors. A new section matches an old section
+/// [by&#32;content](match_sections_by_content), if possible; failing that, it
+/// matches the unmatched old section at the same position, on the theory that
+/// the writer rewrote it in place. Answer the index of the matching old
+/// section, if any, for each new section.
+pub(crate) fn match_sections(
+	old: &[String],
+	new: &[String]
+) -> Vec<Option<usize>>
+{
+	let mut matches = match_sections_by_content(old, new);
+	let mut matched = vec![false; old.len()];
+	for index in matches.iter().flatten()
+	{
+		matched[*index] = true;
+	}
+	for (index, m) in matches.iter_mut().enumerate()
+	{
+		if m.is_none() && index < old.len() && !matched[index]
+		{
+			*m = Some(index);
+			matched[index] = true;
+		}
+	}
+	matches
+}
+
+/// Match each of the `new` sections to one of the `old` sections by content
+/// alone, never by position. A new section matches an old section with the
+/// same content, if any; failing that, it matches the unmatched old section
+/// whose words it [resembles](similarity) most, on the theory that the writer
+/// revised it, even if it also moved. Answer the index of the matching old
+/// section, if any, for each new section.
+pub(crate) fn match_sections_by_content(
+	old: &[String],
+	new: &[String]
+) -> Vec<Option<usize>>
+{
+	// Index the old sections by content, preserving their order, so that
+	// duplicates match in order.
+	let mut by_content = HashMap::<&str, Vec<usize>>::new();
+	for (index, section) in old.iter().enumerate().rev()
+	{
+		by_content.entry(section.as_str()).or_default().push(index);
+	}
+	let mut matches = new.iter()
+		.map(|section| by_content.get_mut(section.as_str())
+			.and_then(|candidates| candidates.pop())
+		)
+		.collect::<Vec<_>>();
+	let mut matched = vec![false; old.len()];
+	for index in matches.iter().flatten()
+	{
+		matched[*index] = true;
+	}
+	// Pair the most similar of the remaining sections first.
+	let words = |section: &String| section.split_whitespace()
+		.map(str::to_lowercase)
+		.collect::<HashSet<_>>();
+	let old_words = old.iter().map(words).collect::<Vec<_>>();
+	let mut candidates = vec![];
+	for (new_index, section) in new.iter().enumerate()
+	{
+		if matches[new_index].is_some()
+		{
+			continue
+		}
+		let new_words = words(section);
+		for (old_index, old_words) in old_words.iter().enumerate()
+		{
+			if matched[old_index]
+			{
+				continue
+			}
+			let score = similarity(&new_words, old_words);
+			if score >= MINIMUM_SIMILARITY
+			{
+				candidates.push((score, new_index, old_index));
+			}
+		}
+	}
+	candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+	for (_, new_index, old_index) in candidates
+	{
+		if matches[new_index].is_none() && !matched[old_index]
+		{
+			matches[new_index] = Some(old_index);
+			matched[old_index] = true;
+		}
+	}
+	matches
+}
+
+/// Answer the similarity of two sections, given as their sets of words, as
+/// the fraction of their combined vocabulary that they share, from `0.0` to
+/// `1.0`.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32
+{
+	let shared = a.intersection(b).count();
+	let combined = a.len() + b.len() - shared;
+	if combined == 0 { 1.0 } else { shared as f32 / combined as f32 }
+}
+
+/// Compute the 64-bit FNV-1a hash of the specified bytes, mixing in the
+/// specified salt first. Unlike that of
+/// [`DefaultHasher`](std::collections::hash_map::DefaultHasher), the result is
+/// stable across Rust releases, which matters because identifiers are
+/// persistent.
+fn fnv1a(bytes: &[u8], salt: u64) -> u64
+{
+	salt.to_le_bytes().iter()
+		.chain(bytes)
+		.fold(FNV_OFFSET_BASIS, |hash, byte|
+			(hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+		)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The mask to apply to a hash to obtain an identifier. The identifiers of a
+/// manuscript need only be distinct from one another, so 24 bits suffice.
+const ID_MASK: u64 = 0xff_ffff;
+
+/// The minimum [similarity] at which an edited section still matches its
+/// earlier self.
+const MINIMUM_SIMILARITY: f32 = 0.5;
+
+/// The 64-bit FNV offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The 64-bit FNV prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Convert the specified sections to owned strings.
+	fn sections(sections: &[&str]) -> Vec<String>
+	{
+		sections.iter().map(|section| section.to_string()).collect()
+	}
+
+	#[test]
+	fn salts_identical_sections_to_distinct_ids()
+	{
+		let ids = fresh_section_ids(&sections(&["Same.", "Same.", "Same."]));
+		assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 3);
+		assert!(ids.iter().all(|id| id.len() == 6));
+		// The first occurrence gets the unsalted identifier.
+		assert_eq!(ids[0], section_id("Same.", &HashSet::new()));
+	}
+
+	#[test]
+	fn keeps_ids_stable_across_runs()
+	{
+		// Identifiers persist, so they must never change between releases.
+		assert_eq!(fnv1a(b"", 0), 0xa8c7_f832_281a_39c5);
+		let manuscript = sections(&["Once upon a time.", "The end."]);
+		let ids = fresh_section_ids(&manuscript);
+		assert_eq!(ids, fresh_section_ids(&manuscript));
+		assert_eq!(ids[0], "d63456");
+	}
+
+	#[test]
+	fn matches_rewritten_sections_by_position()
+	{
+		let old = sections(&["Alpha.", "Beta.", "Gamma."]);
+		let new = sections(&["Gamma.", "Something else entirely.", "Alpha."]);
+		assert_eq!(
+			match_sections(&old, &new),
+			vec![Some(2), Some(1), Some(0)]
+		);
+		// A section beyond the end of the old manuscript matches nothing.
+		let new = sections(&["Alpha.", "Beta.", "Gamma.", "Delta."]);
+		assert_eq!(
+			match_sections(&old, &new),
+			vec![Some(0), Some(1), Some(2), None]
+		);
+	}
+
+	#[test]
+	fn matches_revised_sections_that_moved()
+	{
+		let old = sections(&[
+			"The rain fell on the quiet harbor all night long.",
+			"Morning came and the boats went out.",
+			"She never wrote back."
+		]);
+		// The first section is lightly revised and moves to the end, and an
+		// unrelated section takes its place.
+		let new = sections(&[
+			"A stranger arrived with a parcel.",
+			"Morning came and the boats went out.",
+			"The rain fell on the silent harbor all night long."
+		]);
+		assert_eq!(
+			match_sections_by_content(&old, &new),
+			vec![None, Some(1), Some(0)]
+		);
+		// Too heavy a revision no longer resembles its earlier self.
+		let new = sections(&["The sun rose over the harbor."]);
+		assert_eq!(match_sections_by_content(&old, &new), vec![None]);
+	}
+}
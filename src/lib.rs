@@ -1,4 +1,57 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+/// Open a profiling scope that lasts until the end of the enclosing function,
+/// but only if the `profiling` feature is enabled.
+macro_rules! profile_function
+{
+	() =>
+	{
+		#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+		puffin::profile_function!();
+	}
+}
+
+/// Open a named profiling scope that lasts until the end of the enclosing
+/// block, but only if the `profiling` feature is enabled.
+macro_rules! profile_scope
+{
+	($name:expr) =>
+	{
+		#[cfg(all(feature = "profiling", not(target_arch = "wasm32")))]
+		puffin::profile_scope!($name);
+	}
+}
+
+mod analysis;
 mod app;
+mod archive;
+mod chapter;
+mod chronology;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod config;
+mod diagnostics;
+mod diagram;
+mod docx;
+mod epub;
+mod export;
+mod files;
+mod html;
+mod identity;
+mod matter;
+mod odt;
+mod ordering;
+mod pdf;
+mod rtf;
+mod script;
+mod sharing;
+mod spelling;
+#[cfg(not(target_arch = "wasm32"))]
+mod storage;
+mod theme;
+mod transitions;
 pub use app::StoryShufflerApp;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cli::CommandLine;
+pub use config::Config;
+pub use diagnostics::LogCapture;
@@ -36,13 +36,39 @@
 
 #[cfg(not(target_arch = "wasm32"))]
 use eframe::IconData;
+use tracing_subscriber::prelude::*;
 
-/// Entry point for native execution. Set the environment variable `RUST_LOG` to
-/// `debug` to log to standard output.
+/// Entry point for native execution. Log to standard output, and capture
+/// recent events for the in-app diagnostics panel. If the command line asks
+/// for a headless run, then run headlessly instead, logging only warnings to
+/// standard error.
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()>
 {
-	tracing_subscriber::fmt::init();
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	let command_line = match story_shuffler::CommandLine::parse(&args)
+	{
+		Ok(command_line) => command_line,
+		Err(e) =>
+		{
+			eprintln!("{}", e);
+			std::process::exit(2);
+		}
+	};
+	if command_line.is_headless()
+	{
+		tracing_subscriber::registry()
+			.with(tracing_subscriber::filter::LevelFilter::WARN)
+			.with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+			.init();
+		std::process::exit(command_line.run_headless());
+	}
+	tracing_subscriber::registry()
+		.with(tracing_subscriber::filter::LevelFilter::INFO)
+		.with(tracing_subscriber::fmt::layer())
+		.with(story_shuffler::LogCapture)
+		.init();
+	let config = command_line.config();
 	eframe::run_native(
 		"Story Shuffler",
 		eframe::NativeOptions
@@ -55,18 +81,24 @@ fn main() -> eframe::Result<()>
 			}),
 			..Default::default()
 		},
-		Box::new(|cc| Box::new(story_shuffler::StoryShufflerApp::new(cc)))
+		Box::new(|cc| Box::new(
+			story_shuffler::StoryShufflerApp::with_config(cc, config)
+		))
 	)
 }
 
 /// Entry point for web execution. Hook panic reporting and general logging to
-/// the web console. Use the name `app-canvas` to bind `eframe` to the DOM;
-/// obviously, there needs to be an eponymous canvas in `index.html`.
+/// the web console, and capture recent events for the in-app diagnostics
+/// panel. Use the name `app-canvas` to bind `eframe` to the DOM; obviously,
+/// there needs to be an eponymous canvas in `index.html`.
 #[cfg(target_arch = "wasm32")]
 fn main()
 {
 	console_error_panic_hook::set_once();
-	tracing_wasm::set_as_global_default();
+	tracing_subscriber::registry()
+		.with(tracing_wasm::WASMLayer::default())
+		.with(story_shuffler::LogCapture)
+		.init();
 	let web_options = eframe::WebOptions::default();
 	wasm_bindgen_futures::spawn_local(async {
 		eframe::start_web(
@@ -0,0 +1,388 @@
+/*
+ * matter.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+use serde::{Deserialize, Serialize};
+use crate::transitions::first_line;
+
+////////////////////////////////////////////////////////////////////////////////
+//                           Front and back matter.                           //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The part of a book to which a section belongs. Front matter, e.g., a title
+/// page or a dedication, is anchored at the very beginning of the manuscript,
+/// and back matter, e.g., acknowledgments, at the very end, in their original
+/// order. Only the body is shuffled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Matter
+{
+	/// The body of the work, which may be shuffled.
+	Body,
+
+	/// Front matter, e.g., a title page, dedication, or epigraph.
+	Front,
+
+	/// Back matter, e.g., acknowledgments or an afterword.
+	Back
+}
+
+impl Matter
+{
+	/// Every kind of matter, in presentation order.
+	pub(crate) const ALL: [Matter; 3] =
+		[Matter::Body, Matter::Front, Matter::Back];
+
+	/// Answer the human-readable name of the matter.
+	pub(crate) fn name(self) -> &'static str
+	{
+		match self
+		{
+			Matter::Body => "Body",
+			Matter::Front => "Front matter",
+			Matter::Back => "Back matter"
+		}
+	}
+}
+
+/// Guess the [matter](Matter) of each of the specified sections, which are
+/// given in manuscript order. Only a leading run of sections can be front
+/// matter, and only a trailing run can be back matter; every other section is
+/// body.
+pub(crate) fn detect_matter(sections: &[String]) -> Vec<Matter>
+{
+	let mut matter = vec![Matter::Body; sections.len()];
+	for (index, section) in sections.iter().enumerate()
+	{
+		if !is_front_matter(section, index)
+		{
+			break
+		}
+		matter[index] = Matter::Front;
+	}
+	for (index, section) in sections.iter().enumerate().rev()
+	{
+		if matter[index] != Matter::Body || !is_back_matter(section)
+		{
+			break
+		}
+		matter[index] = Matter::Back;
+	}
+	matter
+}
+
+/// Answer `true` if the specified section, at the specified **zero-based**
+/// index, looks like front matter: a section with a conventional heading, a
+/// leading title page, a dedication, or an epigraph.
+fn is_front_matter(section: &str, index: usize) -> bool
+{
+	let heading = normalized_heading(section);
+	let words = section.split_whitespace().count();
+	FRONT_MATTER_HEADINGS.iter().any(|h| heading.starts_with(h))
+		// A title page names the author.
+		|| (index == 0
+			&& words <= TITLE_PAGE_MAXIMUM_WORDS
+			&& section.lines()
+				.any(|line| line.trim().to_lowercase().starts_with("by ")))
+		// A dedication is addressed to someone.
+		|| (words <= DEDICATION_MAXIMUM_WORDS
+			&& DEDICATION_OPENINGS.iter().any(|o| heading.starts_with(o)))
+		// An epigraph ends with an attribution.
+		|| (words <= EPIGRAPH_MAXIMUM_WORDS
+			&& section.lines()
+				.rev()
+				.map(str::trim)
+				.find(|line| !line.is_empty())
+				.map_or(false, |line| line.starts_with(ATTRIBUTION_DASHES)))
+}
+
+/// Answer `true` if the specified section looks like back matter, i.e., a
+/// section with a conventional heading.
+fn is_back_matter(section: &str) -> bool
+{
+	let heading = normalized_heading(section);
+	BACK_MATTER_HEADINGS.iter().any(|h| heading.starts_with(h))
+}
+
+/// Answer the first line of the specified section, in lowercase, without any
+/// Markdown heading or emphasis markers.
+fn normalized_heading(section: &str) -> String
+{
+	first_line(section)
+		.trim_start_matches(['#', '*', '_'])
+		.trim()
+		.to_lowercase()
+		.replace('’', "'")
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Boilerplate.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Divide the specified manuscript into its leading boilerplate, its body, and
+/// its trailing boilerplate. Everything before a line that consists of
+/// [`BEGIN_MARKER`] is leading boilerplate, and everything after a line that
+/// consists of [`END_MARKER`] is trailing boilerplate; the markers themselves
+/// belong to none of the parts. Boilerplate, e.g., a title page or a license,
+/// is never split into sections, and every new manuscript reproduces it
+/// verbatim. Either marker may be absent, in which case the corresponding
+/// boilerplate is empty.
+pub(crate) fn split_boilerplate(manuscript: &str) -> (&str, &str, &str)
+{
+	let (front, body) = match marker_line(manuscript, BEGIN_MARKER)
+	{
+		Some((start, end)) => (&manuscript[.. start], &manuscript[end ..]),
+		None => ("", manuscript)
+	};
+	let (body, back) = match marker_line(body, END_MARKER)
+	{
+		Some((start, end)) => (&body[.. start], &body[end ..]),
+		None => (body, "")
+	};
+	(front.trim_end(), body, back.trim_start())
+}
+
+/// Wrap the specified body of a new manuscript in the leading and trailing
+/// [boilerplate](split_boilerplate) of the specified original manuscript,
+/// separating each part from the next by a blank line.
+pub(crate) fn with_boilerplate(manuscript: &str, body: String) -> String
+{
+	let (front, _, back) = split_boilerplate(manuscript);
+	if front.is_empty() && back.is_empty()
+	{
+		return body
+	}
+	[front, &body, back].iter()
+		.filter(|part| !part.is_empty())
+		.copied()
+		.collect::<Vec<_>>()
+		.join("\n\n")
+}
+
+/// Answer the byte range of the first line of the specified text that
+/// consists of the specified marker, surrounding whitespace aside, including
+/// its line terminator, if any.
+fn marker_line(text: &str, marker: &str) -> Option<(usize, usize)>
+{
+	let mut start = 0;
+	for line in text.split_inclusive('\n')
+	{
+		let end = start + line.len();
+		if line.trim() == marker
+		{
+			return Some((start, end))
+		}
+		start = end;
+	}
+	None
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The conventional headings of front matter, in lowercase.
+const FRONT_MATTER_HEADINGS: &[&str] = &[
+	"title page", "copyright", "dedication", "epigraph", "contents",
+	"table of contents", "foreword", "preface",
+	"content warning", "content note"
+];
+
+/// The conventional headings of back matter, in lowercase.
+const BACK_MATTER_HEADINGS: &[&str] = &[
+	"acknowledgments", "acknowledgements", "about the author", "afterword",
+	"author's note", "a note from the author", "glossary", "appendix",
+	"also by", "bibliography", "further reading"
+];
+
+/// The conventional openings of a dedication, in lowercase.
+const DEDICATION_OPENINGS: &[&str] = &["for ", "to ", "in memory of "];
+
+/// The dashes that introduce the attribution of an epigraph.
+const ATTRIBUTION_DASHES: &[char] = &['—', '―', '–', '-', '~'];
+
+/// The maximum number of words of a title page.
+const TITLE_PAGE_MAXIMUM_WORDS: usize = 30;
+
+/// The maximum number of words of a dedication.
+const DEDICATION_MAXIMUM_WORDS: usize = 25;
+
+/// The maximum number of words of an epigraph.
+const EPIGRAPH_MAXIMUM_WORDS: usize = 80;
+
+/// The line that ends the leading [boilerplate](split_boilerplate) of a
+/// manuscript.
+pub(crate) const BEGIN_MARKER: &str = "===BEGIN===";
+
+/// The line that begins the trailing [boilerplate](split_boilerplate) of a
+/// manuscript.
+pub(crate) const END_MARKER: &str = "===END===";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Detect the matter of the specified sections.
+	fn detect(sections: &[&str]) -> Vec<Matter>
+	{
+		let sections = sections.iter()
+			.map(|section| section.to_string())
+			.collect::<Vec<_>>();
+		detect_matter(&sections)
+	}
+
+	#[test]
+	fn detects_only_a_leading_run_of_front_matter()
+	{
+		assert_eq!(
+			detect(&[
+				"# Preface\n\nWhy.",
+				"It began.",
+				"# Foreword\n\nLate.",
+				"End."
+			]),
+			vec![Matter::Front, Matter::Body, Matter::Body, Matter::Body]
+		);
+	}
+
+	#[test]
+	fn detects_title_pages_only_at_the_beginning()
+	{
+		assert_eq!(
+			detect(&["The Book\nby Ann", "It began.", "End."]),
+			vec![Matter::Front, Matter::Body, Matter::Body]
+		);
+		assert_eq!(
+			detect(&["For Ben.", "The Book\nby Ann", "End."]),
+			vec![Matter::Front, Matter::Body, Matter::Body]
+		);
+	}
+
+	#[test]
+	fn limits_the_length_of_dedications_and_epigraphs()
+	{
+		let long = format!("For {}", "word ".repeat(DEDICATION_MAXIMUM_WORDS));
+		assert_eq!(
+			detect(&["For my mother.", "It began."]),
+			vec![Matter::Front, Matter::Body]
+		);
+		assert_eq!(detect(&[&long, "It began."]), vec![Matter::Body; 2]);
+		let epigraph = "All happy families are alike.\n— Tolstoy";
+		let long = format!(
+			"{}\n— Tolstoy",
+			"word ".repeat(EPIGRAPH_MAXIMUM_WORDS)
+		);
+		assert_eq!(
+			detect(&[epigraph, "It began."]),
+			vec![Matter::Front, Matter::Body]
+		);
+		assert_eq!(detect(&[&long, "It began."]), vec![Matter::Body; 2]);
+	}
+
+	#[test]
+	fn detects_a_trailing_run_of_back_matter()
+	{
+		assert_eq!(
+			detect(&[
+				"It began.",
+				"# Appendix\n\nMaps.",
+				"It ended.",
+				"# Afterword\n\nThanks.",
+				"## About the Author\n\nAnn writes."
+			]),
+			vec![
+				Matter::Body,
+				Matter::Body,
+				Matter::Body,
+				Matter::Back,
+				Matter::Back
+			]
+		);
+	}
+
+	#[test]
+	fn never_overwrites_front_matter_with_back_matter()
+	{
+		// An epigraph that happens to begin like an afterword.
+		assert_eq!(
+			detect(&["Afterword, the silence.\n— Anon"]),
+			vec![Matter::Front]
+		);
+	}
+
+	#[test]
+	fn leaves_manuscripts_without_markers_whole()
+	{
+		assert_eq!(split_boilerplate("A\n\nB"), ("", "A\n\nB", ""));
+		assert_eq!(with_boilerplate("A\n\nB", "B\n\nA".to_string()), "B\n\nA");
+	}
+
+	#[test]
+	fn splits_boilerplate_at_both_markers()
+	{
+		let manuscript =
+			"Title\n\n===BEGIN===\nA\n\nB\n===END===\n\nLicense";
+		assert_eq!(
+			split_boilerplate(manuscript),
+			("Title", "A\n\nB\n", "License")
+		);
+		assert_eq!(
+			with_boilerplate(manuscript, "B\n\nA".to_string()),
+			"Title\n\nB\n\nA\n\nLicense"
+		);
+	}
+
+	#[test]
+	fn recognizes_markers_without_line_breaks()
+	{
+		assert_eq!(
+			split_boilerplate("Title\n  ===BEGIN===  "),
+			("Title", "", "")
+		);
+		assert_eq!(split_boilerplate("A\n===END==="), ("", "A\n", ""));
+		assert_eq!(split_boilerplate("===END==="), ("", "", ""));
+	}
+
+	#[test]
+	fn ignores_an_end_marker_before_the_begin_marker()
+	{
+		// The end marker only counts after the begin marker, so the stray one
+		// stays in the leading boilerplate.
+		assert_eq!(
+			split_boilerplate("A\n===END===\nB\n===BEGIN===\nC"),
+			("A\n===END===\nB", "C", "")
+		);
+	}
+}
@@ -0,0 +1,378 @@
+/*
+ * odt.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::Write;
+use crate::archive::{Archive, extract, is_archive};
+use crate::export::{escape_xml, paragraphs, unescape_xml};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Export.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Render the specified manuscript as an OpenDocument text document, e.g.,
+/// for LibreOffice, in standard manuscript format: double-spaced 12-point
+/// Times New Roman, with indented paragraphs. Blank lines separate
+/// paragraphs, and line breaks within a paragraph are kept. A paragraph for
+/// which `centered` answers `true`, e.g., a dinkus between sections, is
+/// centered instead of indented.
+pub(crate) fn manuscript_to_odt(
+	title: &str,
+	manuscript: &str,
+	centered: impl Fn(&str) -> bool
+) -> Vec<u8>
+{
+	let mut body = String::new();
+	for paragraph in paragraphs(manuscript)
+	{
+		let style = if centered(paragraph) { "Centered" } else { "Standard" };
+		let _ = write!(body, r#"<text:p text:style-name="{}">"#, style);
+		for (index, line) in paragraph.lines().enumerate()
+		{
+			if index > 0
+			{
+				body.push_str("<text:line-break/>");
+			}
+			write_line(&mut body, line);
+		}
+		body.push_str("</text:p>");
+	}
+	let content = format!(
+		r#"{}<office:document-content {} office:version="1.2"><office:body><office:text>{}</office:text></office:body></office:document-content>"#,
+		XML_DECLARATION,
+		NAMESPACES,
+		body
+	);
+	let meta = format!(
+		r#"{}<office:document-meta {} office:version="1.2"><office:meta><dc:title>{}</dc:title></office:meta></office:document-meta>"#,
+		XML_DECLARATION,
+		NAMESPACES,
+		escape_xml(title)
+	);
+	let styles = format!(
+		r#"{}<office:document-styles {} office:version="1.2"><office:font-face-decls><style:font-face style:name="Times New Roman" svg:font-family="'Times New Roman'" style:font-family-generic="roman"/></office:font-face-decls><office:styles><style:default-style style:family="paragraph"><style:paragraph-properties fo:line-height="200%"/><style:text-properties style:font-name="Times New Roman" fo:font-size="12pt"/></style:default-style><style:style style:name="Standard" style:family="paragraph" style:class="text"><style:paragraph-properties fo:text-indent="0.5in" fo:margin-top="0in" fo:margin-bottom="0in"/></style:style><style:style style:name="Centered" style:family="paragraph" style:parent-style-name="Standard" style:class="text"><style:paragraph-properties fo:text-indent="0in" fo:text-align="center"/></style:style></office:styles><office:automatic-styles><style:page-layout style:name="Manuscript"><style:page-layout-properties fo:page-width="8.5in" fo:page-height="11in" fo:margin-top="1in" fo:margin-bottom="1in" fo:margin-left="1in" fo:margin-right="1in"/></style:page-layout></office:automatic-styles><office:master-styles><style:master-page style:name="Standard" style:page-layout-name="Manuscript"/></office:master-styles></office:document-styles>"#,
+		XML_DECLARATION,
+		NAMESPACES
+	);
+	Archive::new()
+		// The media type must come first, uncompressed, so that it can be
+		// sniffed.
+		.store("mimetype", MEDIA_TYPE.as_bytes())
+		.deflate("META-INF/manifest.xml", MANIFEST.as_bytes())
+		.deflate("content.xml", content.as_bytes())
+		.deflate("meta.xml", meta.as_bytes())
+		.deflate("styles.xml", styles.as_bytes())
+		.finish()
+}
+
+/// Append the specified line of text to the specified OpenDocument body. Tabs
+/// and runs of spaces are encoded explicitly, because OpenDocument otherwise
+/// collapses whitespace.
+fn write_line(body: &mut String, line: &str)
+{
+	let mut spaces = 0;
+	let mut at_start = true;
+	let flush = |body: &mut String, spaces: &mut usize, at_start: bool| {
+		match (*spaces, at_start)
+		{
+			(0, _) => {},
+			(1, false) => body.push(' '),
+			// OpenDocument keeps one space by itself, but never a leading
+			// one.
+			(n, true) =>
+			{
+				let _ = write!(body, r#"<text:s text:c="{}"/>"#, n);
+			},
+			(n, false) =>
+			{
+				let _ = write!(body, r#" <text:s text:c="{}"/>"#, n - 1);
+			}
+		}
+		*spaces = 0;
+	};
+	for c in line.chars()
+	{
+		match c
+		{
+			' ' => spaces += 1,
+			'\t' =>
+			{
+				flush(body, &mut spaces, at_start);
+				body.push_str("<text:tab/>");
+				at_start = false;
+			},
+			c =>
+			{
+				flush(body, &mut spaces, at_start);
+				body.push_str(&escape_xml(c.encode_utf8(&mut [0; 4])));
+				at_start = false;
+			}
+		}
+	}
+	flush(body, &mut spaces, at_start);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Import.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the specified bytes look like an OpenDocument text
+/// document.
+pub(crate) fn is_odt(bytes: &[u8]) -> bool
+{
+	is_archive(bytes) && extract(bytes, "mimetype").map_or(false, |mime|
+		mime.as_deref() == Some(MEDIA_TYPE.as_bytes())
+	)
+}
+
+/// Read the paragraphs and headings of the specified OpenDocument text
+/// document as plain text, discarding all formatting, as well as notes,
+/// annotations, and tracked changes. Blank lines separate paragraphs, and
+/// empty paragraphs are dropped. Answer an explanation on failure.
+pub(crate) fn odt_to_text(bytes: &[u8]) -> Result<String, String>
+{
+	let content = extract(bytes, "content.xml")?
+		.ok_or_else(|| "the document has no content".to_string())?;
+	let content = String::from_utf8(content)
+		.map_err(|_| "the document isn't UTF-8 text".to_string())?;
+	let mut paragraphs = vec![];
+	let mut paragraph = String::new();
+	// The depth of nested paragraphs and headings.
+	let mut depth = 0usize;
+	// The depth of nested elements whose content isn't body text.
+	let mut skipped = 0usize;
+	let mut rest = content.as_str();
+	while let Some(open) = rest.find('<')
+	{
+		if depth > 0 && skipped == 0
+		{
+			paragraph.push_str(&unescape_xml(&rest[.. open]));
+		}
+		let close = rest[open ..].find('>')
+			.map(|close| open + close)
+			.ok_or_else(|| "the document is damaged".to_string())?;
+		let tag = &rest[open + 1 .. close];
+		rest = &rest[close + 1 ..];
+		let (closing, tag) = match tag.strip_prefix('/')
+		{
+			Some(tag) => (true, tag),
+			None => (false, tag)
+		};
+		let empty = tag.ends_with('/');
+		let tag = tag.trim_end_matches('/');
+		let name = tag.split_whitespace().next().unwrap_or_default();
+		if SKIPPED_ELEMENTS.contains(&name)
+		{
+			match (closing, empty)
+			{
+				(false, false) => skipped += 1,
+				(true, _) => skipped = skipped.saturating_sub(1),
+				_ => {}
+			}
+			continue
+		}
+		if skipped > 0
+		{
+			continue
+		}
+		match name
+		{
+			"text:p" | "text:h" if !empty =>
+			{
+				if closing
+				{
+					depth = depth.saturating_sub(1);
+					if depth == 0
+					{
+						paragraphs.push(std::mem::take(&mut paragraph));
+					}
+				}
+				else
+				{
+					depth += 1;
+				}
+			},
+			"text:s" if depth > 0 =>
+			{
+				let count = attribute(tag, "text:c")
+					.and_then(|count| count.parse::<usize>().ok())
+					.unwrap_or(1);
+				paragraph.push_str(&" ".repeat(count));
+			},
+			"text:tab" if depth > 0 => paragraph.push('\t'),
+			"text:line-break" if depth > 0 => paragraph.push('\n'),
+			_ => {}
+		}
+	}
+	let paragraphs = paragraphs.iter()
+		.map(|paragraph| paragraph.trim())
+		.filter(|paragraph| !paragraph.is_empty())
+		.collect::<Vec<_>>();
+	Ok(paragraphs.join("\n\n"))
+}
+
+/// Answer the value of the attribute with the specified name, if any, within
+/// the specified start tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str>
+{
+	let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+	let quote = tag[start ..].chars().next()?;
+	let value = &tag[start + 1 ..];
+	value.find(quote).map(|end| &value[.. end])
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The media type of an OpenDocument text document, which is also the content
+/// of its `mimetype` entry.
+const MEDIA_TYPE: &str = "application/vnd.oasis.opendocument.text";
+
+/// The XML declaration that begins every part.
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// The namespace declarations of every part.
+const NAMESPACES: &str = concat!(
+	r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" "#,
+	r#"xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" "#,
+	r#"xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" "#,
+	r#"xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" "#,
+	r#"xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0" "#,
+	r#"xmlns:dc="http://purl.org/dc/elements/1.1/""#
+);
+
+/// The manifest, which lists the parts of the package.
+const MANIFEST: &str = concat!(
+	r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+	r#"<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">"#,
+	r#"<manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.text"/>"#,
+	r#"<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>"#,
+	r#"<manifest:file-entry manifest:full-path="meta.xml" manifest:media-type="text/xml"/>"#,
+	r#"<manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>"#,
+	"</manifest:manifest>"
+);
+
+/// The elements whose content isn't body text.
+const SKIPPED_ELEMENTS: &[&str] = &[
+	"office:annotation",
+	"text:note",
+	"text:tracked-changes"
+];
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Answer the `content.xml` of the specified OpenDocument text document.
+	fn content(odt: &[u8]) -> String
+	{
+		String::from_utf8(extract(odt, "content.xml").unwrap().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn escapes_xml()
+	{
+		let odt = manuscript_to_odt(
+			"Tom & Jerry",
+			"<b>Fish</b> & \"chips\"",
+			|_| false
+		);
+		let content = content(&odt);
+		assert!(content.contains(
+			"&lt;b&gt;Fish&lt;/b&gt; &amp; &quot;chips&quot;"
+		));
+		assert!(!content.contains("<b>"));
+		let meta = extract(&odt, "meta.xml").unwrap().unwrap();
+		assert!(String::from_utf8(meta).unwrap().contains("Tom &amp; Jerry"));
+	}
+
+	#[test]
+	fn encodes_whitespace_and_centering()
+	{
+		let odt = manuscript_to_odt(
+			"",
+			"Indented\tand   spaced.\n  Broken.\n\n* * *",
+			|paragraph| paragraph == "* * *"
+		);
+		let content = content(&odt);
+		assert!(content.contains(
+			r#"<text:p text:style-name="Standard">Indented<text:tab/>and <text:s text:c="2"/>spaced.<text:line-break/><text:s text:c="2"/>Broken.</text:p>"#
+		));
+		assert!(content.contains(
+			r#"<text:p text:style-name="Centered">* * *</text:p>"#
+		));
+	}
+
+	#[test]
+	fn extracts_paragraphs()
+	{
+		let content = format!(
+			r#"{}<office:document-content {}><office:body><office:text><text:h text:outline-level="1">One</text:h><text:p>A <text:span>span</text:span><text:note><text:note-body><text:p>Footnote.</text:p></text:note-body></text:note>, a&#32;tab<text:tab/>and<text:s text:c="3"/>gap.</text:p><text:p/><text:p>  </text:p><text:p>Last<text:line-break/>line &amp; done.</text:p></office:text></office:body></office:document-content>"#,
+			XML_DECLARATION,
+			NAMESPACES
+		);
+		let odt = Archive::new()
+			.store("mimetype", MEDIA_TYPE.as_bytes())
+			.deflate("content.xml", content.as_bytes())
+			.finish();
+		assert!(is_odt(&odt));
+		assert_eq!(
+			odt_to_text(&odt).unwrap(),
+			"One\n\nA span, a tab\tand   gap.\n\nLast\nline & done."
+		);
+	}
+
+	#[test]
+	fn round_trips_manuscripts()
+	{
+		let manuscript = "“Tom & Jerry” <3\n\nIndented\t  thrice.\nBroken \
+			line.\n\n* * *\n\nThe end.";
+		let odt = manuscript_to_odt("Title", manuscript, |_| false);
+		assert!(is_odt(&odt));
+		assert_eq!(odt_to_text(&odt).unwrap(), manuscript);
+	}
+
+	#[test]
+	fn rejects_other_archives()
+	{
+		let zip = Archive::new()
+			.store("mimetype", b"application/epub+zip")
+			.finish();
+		assert!(!is_odt(&zip));
+		assert!(!is_odt(b"Not an archive."));
+		assert!(odt_to_text(&zip).is_err());
+	}
+}
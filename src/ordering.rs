@@ -0,0 +1,705 @@
+/*
+ * ordering.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::cmp::Ordering;
+use petgraph::{
+	Direction::Incoming,
+	graph::{DiGraph, NodeIndex}
+};
+use rand::{Rng, seq::SliceRandom};
+use crate::app::{Parity, PlacePreference};
+use crate::chapter::ChapterPolicy;
+
+////////////////////////////////////////////////////////////////////////////////
+//                            Topological peeling.                            //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Order the vertices of the specified constraint graph by peeling off its
+/// roots, i.e., those vertices that have no remaining predecessors, one at a
+/// time until nothing remains. `choose` chooses the next root from the graph
+/// at hand, given its roots, in vertex order, and the number of sections
+/// already placed; it may answer `None` to abandon the ordering, e.g.,
+/// because its choices led to an impasse. Answer the ordering as
+/// **zero-based** section indices, or `None` if abandoned. The graph must not
+/// contain a cycle.
+fn peel(
+	mut graph: DiGraph<usize, (), usize>,
+	mut choose: impl FnMut(
+		&DiGraph<usize, (), usize>,
+		&[NodeIndex<usize>],
+		usize
+	) -> Option<NodeIndex<usize>>
+) -> Option<Vec<usize>>
+{
+	let mut indices = Vec::with_capacity(graph.node_count());
+	while graph.node_count() != 0
+	{
+		// Find the roots of the graph. These are the sections that are not
+		// constrained to appear after some other section(s).
+		let roots = graph.node_indices()
+			.filter(|index|
+				graph.neighbors_directed(*index, Incoming).next().is_none()
+			)
+			.collect::<Vec<_>>();
+		let root = choose(&graph, &roots, indices.len())?;
+		indices.push(*graph.node_weight(root).unwrap() - 1);
+		// Remove the root from the graph. New sections may become roots as
+		// a consequence.
+		graph.remove_node(root);
+	}
+	Some(indices)
+}
+
+/// Answer the **zero-based** section index of the specified vertex of the
+/// specified constraint graph.
+fn section_of(
+	graph: &DiGraph<usize, (), usize>,
+	node: NodeIndex<usize>
+) -> usize
+{
+	*graph.node_weight(node).unwrap() - 1
+}
+
+/// Produce a random ordering of the vertices of the specified constraint
+/// graph, such that every vertex follows its predecessors. `preferences`
+/// supplies the [place&#32;preference](PlacePreference) of each section, if
+/// any, to bias the choices. Answer the ordering as **zero-based** section
+/// indices. The graph must not contain a cycle.
+pub(crate) fn random_order(
+	graph: DiGraph<usize, (), usize>,
+	preferences: &[(PlacePreference, f32)],
+	rng: &mut impl Rng
+) -> Vec<usize>
+{
+	peel(graph, |graph, roots, placed|
+		if preferences.is_empty()
+		{
+			// Shuffle the roots and pluck the first one.
+			let mut shuffled_roots = roots.to_vec();
+			shuffled_roots.shuffle(rng);
+			shuffled_roots.first().copied()
+		}
+		else
+		{
+			Some(choose_preferred_root(graph, roots, placed, preferences, rng))
+		}
+	).expect("Constraint graph must be acyclic")
+}
+
+/// Choose one of the specified roots of the specified constraint graph at
+/// random, favoring those whose [place&#32;preferences](PlacePreference)
+/// suit the next place. `placed` is the number of sections already removed
+/// from the graph, and `preferences` supplies the place preference of each
+/// section, which must not be empty. Progress is measured through the graph
+/// at hand, so the sections of a [chapter](crate::chapter::Chapter) that
+/// shuffles within favor their places within the chapter.
+fn choose_preferred_root(
+	graph: &DiGraph<usize, (), usize>,
+	roots: &[NodeIndex<usize>],
+	placed: usize,
+	preferences: &[(PlacePreference, f32)],
+	rng: &mut impl Rng
+) -> NodeIndex<usize>
+{
+	let total = placed + graph.node_count();
+	let progress = if total > 1
+	{
+		placed as f32 / (total - 1) as f32
+	}
+	else { 0.5 };
+	*roots.choose_weighted(rng, |root| {
+		preferences.get(section_of(graph, *root)).map_or(1.0, |(place, weight)|
+			(1.0 + weight).powf(place.affinity(progress))
+		)
+	}).unwrap()
+}
+
+/// Produce a random ordering of the vertices of the specified constraint
+/// graph, such that every vertex follows its predecessors and every pinned
+/// section occupies its position. `pins` supplies the **one-based** pinned
+/// position of each section, if any, `parities` the [parity](Parity) of each
+/// section, if any, `omitted` whether each section is omitted from the
+/// output, and `preferences` the [place&#32;preference](PlacePreference) of
+/// each section, if any. Positions count only the sections that aren't
+/// omitted, which are placed as early as possible, since they occupy no
+/// position. Answer the ordering as **zero-based** section indices, or `None`
+/// if the random choices led to an impasse, in which case another attempt may
+/// yet succeed. The graph must not contain a cycle.
+pub(crate) fn random_pinned_order(
+	graph: DiGraph<usize, (), usize>,
+	pins: &[Option<usize>],
+	parities: &[Option<Parity>],
+	omitted: &[bool],
+	preferences: &[(PlacePreference, f32)],
+	rng: &mut impl Rng
+) -> Option<Vec<usize>>
+{
+	let mut shown = 0;
+	peel(graph, |graph, roots, placed| {
+		if let Some(root) = roots.iter()
+			.find(|root| omitted.get(section_of(graph, **root)) == Some(&true))
+		{
+			return Some(*root)
+		}
+		shown += 1;
+		let position = shown;
+		match pins.iter().position(|pin| *pin == Some(position))
+		{
+			// The section pinned here must be free to come next.
+			Some(pinned) => roots.iter()
+				.find(|root| section_of(graph, **root) == pinned)
+				.copied(),
+			// Otherwise, any free section that isn't pinned may come next, if
+			// it suits the parity of the position.
+			None =>
+			{
+				let free = roots.iter()
+					.filter(|root| {
+						let index = section_of(graph, **root);
+						pins[index].is_none()
+							&& parities.get(index).copied().flatten()
+								.map_or(true, |parity| parity.admits(position))
+					})
+					.copied()
+					.collect::<Vec<_>>();
+				if free.is_empty()
+				{
+					None
+				}
+				else if preferences.is_empty()
+				{
+					free.choose(rng).copied()
+				}
+				else
+				{
+					Some(choose_preferred_root(
+						graph,
+						&free,
+						placed,
+						preferences,
+						rng
+					))
+				}
+			}
+		}
+	})
+}
+
+/// Produce a random ordering of the vertices of the specified constraint
+/// graph, such that every vertex follows its predecessors and the sections of
+/// the rotation `groups` appear in strict rotation. `groups` supplies the
+/// **zero-based** rotation group of each section, if any; sections outside of
+/// every group may appear anywhere. Once a group runs out of sections, the
+/// rotation continues without it. Answer the ordering as **zero-based**
+/// section indices, or `None` if the random choices led to an impasse, in
+/// which case another attempt may yet succeed. The graph must not contain a
+/// cycle.
+pub(crate) fn random_rotation(
+	graph: DiGraph<usize, (), usize>,
+	groups: &[Option<usize>],
+	group_count: usize,
+	rng: &mut impl Rng
+) -> Option<Vec<usize>>
+{
+	let mut remaining = vec![0; group_count];
+	for group in groups.iter().flatten()
+	{
+		remaining[*group] += 1;
+	}
+	// The group whose turn it is.
+	let mut turn = 0;
+	peel(graph, |graph, roots, _| {
+		// Skip any groups whose sections have run out.
+		if remaining.iter().any(|r| *r != 0)
+		{
+			while remaining[turn] == 0
+			{
+				turn = (turn + 1) % group_count;
+			}
+		}
+		let roots = roots.iter()
+			.filter(|root|
+				groups[section_of(graph, **root)]
+					.map_or(true, |group| group == turn)
+			)
+			.copied()
+			.collect::<Vec<_>>();
+		let root = *roots.choose(rng)?;
+		if let Some(group) = groups[section_of(graph, root)]
+		{
+			remaining[group] -= 1;
+			turn = (group + 1) % group_count;
+		}
+		Some(root)
+	})
+}
+
+/// Produce a random ordering of the vertices of the specified constraint
+/// graph, such that every vertex follows its predecessors and no two sections
+/// of the same rotation group are adjacent. `groups` supplies the
+/// **zero-based** rotation group of each section, if any; sections outside of
+/// every group may appear anywhere. Answer the ordering as **zero-based**
+/// section indices, or `None` if the random choices led to an impasse, in
+/// which case another attempt may yet succeed. The graph must not contain a
+/// cycle.
+pub(crate) fn random_alternation(
+	graph: DiGraph<usize, (), usize>,
+	groups: &[Option<usize>],
+	group_count: usize,
+	rng: &mut impl Rng
+) -> Option<Vec<usize>>
+{
+	let mut remaining = vec![0; group_count];
+	for group in groups.iter().flatten()
+	{
+		remaining[*group] += 1;
+	}
+	// The group of the previous section, if any.
+	let mut previous = None;
+	peel(graph, |graph, roots, _| {
+		let roots = roots.iter()
+			.filter(|root|
+				groups[section_of(graph, **root)]
+					.map_or(true, |group| Some(group) != previous)
+			)
+			.copied()
+			.collect::<Vec<_>>();
+		// A group with more than half of the remaining sections must take
+		// every other place from here on, so give it the next place if it
+		// can.
+		let left = graph.node_count();
+		let urgent = (0 .. group_count)
+			.find(|group| 2 * remaining[*group] > left)
+			.filter(|group| Some(*group) != previous);
+		let urgent_roots = roots.iter()
+			.filter(|root|
+				urgent.is_some() && groups[section_of(graph, **root)] == urgent
+			)
+			.copied()
+			.collect::<Vec<_>>();
+		let root = if urgent_roots.is_empty()
+		{
+			*roots.choose(rng)?
+		}
+		else
+		{
+			*urgent_roots.choose(rng)?
+		};
+		let group = groups[section_of(graph, root)];
+		if let Some(group) = group
+		{
+			remaining[group] -= 1;
+		}
+		previous = group;
+		Some(root)
+	})
+}
+
+/// Produce the ordering of the vertices of the specified constraint graph that
+/// best agrees with the specified comparison of **zero-based** section
+/// indices, such that every vertex follows its predecessors. Whenever several
+/// sections are free to come next, the least comes next, with ties broken by
+/// original position; if the comparison never contradicts the constraints,
+/// then the answer is simply sorted. Answer the ordering as **zero-based**
+/// section indices. The graph must not contain a cycle.
+pub(crate) fn sorted_order(
+	graph: DiGraph<usize, (), usize>,
+	compare: impl Fn(usize, usize) -> Ordering
+) -> Vec<usize>
+{
+	peel(graph, |graph, roots, _|
+		roots.iter()
+			.copied()
+			.min_by(|a, b| {
+				let (a, b) = (section_of(graph, *a), section_of(graph, *b));
+				compare(a, b).then(a.cmp(&b))
+			})
+	).expect("Constraint graph must be acyclic")
+}
+
+/// Produce a random ordering of the sections that honors the specified
+/// acyclic constraint graph and keeps the specified blocks, e.g., the stories
+/// of an anthology or the chapters of a novel, together. `blocks` supplies
+/// the **zero-based** block of each section, `block_graph` the constraint
+/// graph of the blocks, and `policies` the [policy](ChapterPolicy) of each
+/// block: blocks that don't [move](ChapterPolicy::moves) keep their places
+/// among the blocks, and the sections of blocks that don't
+/// [shuffle&#32;within](ChapterPolicy::shuffles_sections) keep their order.
+/// `preferences` supplies the [place&#32;preference](PlacePreference) of each
+/// section, if any, which biases the shuffles within blocks. Answer the
+/// ordering as **zero-based** section indices, or `None` if the random
+/// choices led to an impasse, in which case another attempt may yet succeed.
+pub(crate) fn random_block_order(
+	graph: &DiGraph<usize, (), usize>,
+	block_graph: &DiGraph<usize, (), usize>,
+	blocks: &[usize],
+	policies: &[ChapterPolicy],
+	preferences: &[(PlacePreference, f32)],
+	rng: &mut impl Rng
+) -> Option<Vec<usize>>
+{
+	let pins = policies.iter()
+		.enumerate()
+		.map(|(block, policy)| (!policy.moves()).then_some(block + 1))
+		.collect::<Vec<_>>();
+	let order = if pins.iter().any(Option::is_some)
+	{
+		random_pinned_order(block_graph.clone(), &pins, &[], &[], &[], rng)?
+	}
+	else
+	{
+		random_order(block_graph.clone(), &[], rng)
+	};
+	let mut indices = Vec::with_capacity(blocks.len());
+	for block in order
+	{
+		if policies[block].shuffles_sections()
+		{
+			// Shuffle the members among themselves. Filtering preserves the
+			// vertex weights, i.e., the section numbers.
+			let members = graph.filter_map(
+				|index, weight|
+					(blocks[index.index()] == block).then_some(*weight),
+				|_, _| Some(())
+			);
+			indices.extend(random_order(members, preferences, rng));
+		}
+		else
+		{
+			indices.extend(blocks.iter()
+				.enumerate()
+				.filter(|(_, b)| **b == block)
+				.map(|(index, _)| index)
+			);
+		}
+	}
+	Some(indices)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Stepping.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer the valid ordering of the specified acyclic constraint graph that
+/// immediately follows the specified valid ordering, or that immediately
+/// precedes it if not `forward`, in the lexicographic order of their
+/// **zero-based** section indices. Answer `None` if the specified ordering is
+/// the last, or the first, of them.
+pub(crate) fn neighboring_order(
+	graph: &DiGraph<usize, (), usize>,
+	order: &[usize],
+	forward: bool
+) -> Option<Vec<usize>>
+{
+	// Keep the longest possible prefix, and advance the section right after
+	// it to the nearest section that may legally take its place.
+	let mut placed = vec![true; order.len()];
+	for position in (0 .. order.len()).rev()
+	{
+		placed[order[position]] = false;
+		let candidates = (0 .. order.len())
+			.filter(|section| may_place(graph, &placed, *section));
+		let replacement = if forward
+		{
+			candidates.filter(|section| *section > order[position]).min()
+		}
+		else
+		{
+			candidates.filter(|section| *section < order[position]).max()
+		};
+		if let Some(replacement) = replacement
+		{
+			let mut prefix = order[.. position].to_vec();
+			prefix.push(replacement);
+			return Some(extreme_completion(graph, prefix, forward))
+		}
+	}
+	None
+}
+
+/// Complete the specified valid prefix of an ordering of the specified
+/// acyclic constraint graph, given as **zero-based** section indices, with
+/// the remaining sections in the lexicographically least valid order, or in
+/// the greatest if not `least`.
+pub(crate) fn extreme_completion(
+	graph: &DiGraph<usize, (), usize>,
+	mut prefix: Vec<usize>,
+	least: bool
+) -> Vec<usize>
+{
+	let count = graph.node_count();
+	let mut placed = vec![false; count];
+	for section in &prefix
+	{
+		placed[*section] = true;
+	}
+	while prefix.len() < count
+	{
+		let mut candidates = (0 .. count)
+			.filter(|section| may_place(graph, &placed, *section));
+		let section = if least { candidates.next() }
+			else { candidates.next_back() };
+		let section = section.expect("Constraint graph must be acyclic");
+		placed[section] = true;
+		prefix.push(section);
+	}
+	prefix
+}
+
+/// Answer the ordering of the specified acyclic constraint graph that keeps
+/// the sections of the specified valid ordering, given as **zero-based**
+/// section indices, that aren't `omitted` in the same order, but places each
+/// omitted section as early as possible. Every ordering with the same visible
+/// sections thus has the same such canonical form.
+pub(crate) fn with_omissions_early(
+	graph: &DiGraph<usize, (), usize>,
+	order: &[usize],
+	omitted: &[bool]
+) -> Vec<usize>
+{
+	let mut placed = vec![false; order.len()];
+	let mut visible = order.iter().filter(|index| !omitted[**index]);
+	let mut canonical = Vec::with_capacity(order.len());
+	while canonical.len() < order.len()
+	{
+		// Every omitted predecessor of the next visible section is placed
+		// before it, so the next visible section is always placeable here.
+		let Some(section) = (0 .. order.len())
+			.find(|section|
+				omitted[*section] && may_place(graph, &placed, *section)
+			)
+			.or_else(|| visible.next().copied())
+		else { break };
+		placed[section] = true;
+		canonical.push(section);
+	}
+	canonical
+}
+
+/// Answer `true` if the specified section, given by its **zero-based** index,
+/// isn't yet `placed`, but every section that must precede it in the
+/// specified constraint graph is.
+fn may_place(
+	graph: &DiGraph<usize, (), usize>,
+	placed: &[bool],
+	section: usize
+) -> bool
+{
+	!placed[section]
+		&& graph.neighbors_directed(NodeIndex::new(section), Incoming)
+			.all(|p| placed[p.index()])
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+	use rand::{rngs::StdRng, SeedableRng};
+	use super::*;
+
+	/// Build a constraint graph of the specified number of sections, with
+	/// the specified edges between **zero-based** section indices. The tests
+	/// of [analysis](crate::analysis) share it.
+	pub(crate) fn graph(
+		count: usize,
+		edges: &[(usize, usize)]
+	) -> DiGraph<usize, (), usize>
+	{
+		let mut graph = DiGraph::default();
+		for index in 0 .. count
+		{
+			graph.add_node(index + 1);
+		}
+		for (from, to) in edges
+		{
+			graph.add_edge(NodeIndex::new(*from), NodeIndex::new(*to), ());
+		}
+		graph
+	}
+
+	#[test]
+	fn steps_through_every_valid_ordering()
+	{
+		let graph = graph(4, &[(0, 1), (3, 2)]);
+		let mut orders = vec![extreme_completion(&graph, vec![], true)];
+		while let Some(next) =
+			neighboring_order(&graph, orders.last().unwrap(), true)
+		{
+			orders.push(next);
+		}
+		// Each of the two constraints halves the 24 orderings.
+		assert_eq!(orders.len(), 6);
+		assert!(orders.windows(2).all(|pair| pair[0] < pair[1]));
+		assert_eq!(
+			orders.last(),
+			Some(&extreme_completion(&graph, vec![], false))
+		);
+		for order in &orders
+		{
+			let position = |section: usize|
+				order.iter().position(|s| *s == section);
+			assert!(position(0) < position(1), "{:?}", order);
+			assert!(position(3) < position(2), "{:?}", order);
+		}
+		// Stepping backward retraces the same orderings.
+		let mut backward = vec![orders.last().unwrap().clone()];
+		while let Some(previous) =
+			neighboring_order(&graph, backward.last().unwrap(), false)
+		{
+			backward.push(previous);
+		}
+		backward.reverse();
+		assert_eq!(backward, orders);
+	}
+
+	#[test]
+	fn places_omitted_sections_early()
+	{
+		let omitted = [false, true, false];
+		assert_eq!(
+			with_omissions_early(&graph(3, &[]), &[0, 2, 1], &omitted),
+			vec![1, 0, 2]
+		);
+		// An omitted section still follows its predecessors.
+		assert_eq!(
+			with_omissions_early(&graph(3, &[(0, 1)]), &[0, 2, 1], &omitted),
+			vec![0, 1, 2]
+		);
+	}
+
+	#[test]
+	fn honors_the_constraint_graph()
+	{
+		let graph = graph(5, &[(0, 1), (1, 2), (4, 3)]);
+		let mut rng = StdRng::seed_from_u64(1);
+		for _ in 0 .. 50
+		{
+			let order = random_order(graph.clone(), &[], &mut rng);
+			let position = |section: usize|
+				order.iter().position(|s| *s == section).unwrap();
+			assert_eq!(order.len(), 5);
+			assert!(position(0) < position(1) && position(1) < position(2));
+			assert!(position(4) < position(3));
+		}
+	}
+
+	#[test]
+	fn counts_positions_without_omitted_sections()
+	{
+		// Omitting §1 makes §2 the first section shown, at an odd position,
+		// and §4 the second.
+		let pins = [None, None, None, Some(2)];
+		let parities = [None, Some(Parity::Odd), None, None];
+		let omitted = [true, false, false, false];
+		let mut rng = StdRng::seed_from_u64(5);
+		for _ in 0 .. 50
+		{
+			let order = random_pinned_order(
+				graph(4, &[(0, 1), (1, 2)]),
+				&pins,
+				&parities,
+				&omitted,
+				&[],
+				&mut rng
+			).unwrap();
+			let shown = order.into_iter()
+				.filter(|index| !omitted[*index])
+				.collect::<Vec<_>>();
+			assert_eq!(shown, vec![1, 3, 2]);
+		}
+	}
+
+	#[test]
+	fn keeps_pinned_sections_in_place()
+	{
+		let pins = [None, None, Some(1), None];
+		let mut rng = StdRng::seed_from_u64(2);
+		for _ in 0 .. 50
+		{
+			let order = random_pinned_order(
+				graph(4, &[(0, 1)]),
+				&pins,
+				&[],
+				&[],
+				&[],
+				&mut rng
+			).unwrap();
+			assert_eq!(order[0], 2);
+		}
+	}
+
+	#[test]
+	fn rotates_groups_strictly()
+	{
+		let groups = [Some(0), Some(0), Some(1), Some(1), None];
+		let mut rng = StdRng::seed_from_u64(3);
+		for _ in 0 .. 50
+		{
+			let order = random_rotation(graph(5, &[]), &groups, 2, &mut rng)
+				.unwrap();
+			let rotation = order.iter()
+				.filter_map(|index| groups[*index])
+				.collect::<Vec<_>>();
+			assert_eq!(rotation, vec![0, 1, 0, 1], "{:?}", order);
+		}
+	}
+
+	#[test]
+	fn keeps_groups_apart()
+	{
+		let groups = [Some(0), Some(0), Some(0), Some(1), Some(1)];
+		let mut rng = StdRng::seed_from_u64(4);
+		for _ in 0 .. 50
+		{
+			let order = random_alternation(graph(5, &[]), &groups, 2, &mut rng)
+				.unwrap();
+			assert!(
+				order.windows(2).all(|pair| groups[pair[0]] != groups[pair[1]]),
+				"{:?}",
+				order
+			);
+		}
+	}
+
+	#[test]
+	fn sorts_as_far_as_the_constraints_allow()
+	{
+		let descending = |a: usize, b: usize| b.cmp(&a);
+		assert_eq!(sorted_order(graph(4, &[]), descending), vec![3, 2, 1, 0]);
+		assert_eq!(
+			sorted_order(graph(4, &[(0, 1)]), descending),
+			vec![3, 2, 0, 1]
+		);
+	}
+}
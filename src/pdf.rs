@@ -0,0 +1,705 @@
+/*
+ * pdf.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::io::Write;
+use serde::{Deserialize, Serialize};
+use crate::export::{Block, paragraphs};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Layout.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The size of the pages of a PDF document.
+#[derive(
+	Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize
+)]
+pub(crate) enum PageSize
+{
+	/// US Letter, i.e., 8½ × 11 inches.
+	#[default]
+	Letter,
+
+	/// US Legal, i.e., 8½ × 14 inches.
+	Legal,
+
+	/// ISO A4, i.e., 210 × 297 millimeters.
+	A4,
+
+	/// ISO A5, i.e., 148 × 210 millimeters, which suits booklets.
+	A5
+}
+
+impl PageSize
+{
+	/// Every page size, in presentation order.
+	pub(crate) const ALL: [PageSize; 4] =
+		[PageSize::Letter, PageSize::Legal, PageSize::A4, PageSize::A5];
+
+	/// Answer the human-readable name of the page size.
+	pub(crate) fn name(self) -> &'static str
+	{
+		match self
+		{
+			PageSize::Letter => "US Letter",
+			PageSize::Legal => "US Legal",
+			PageSize::A4 => "A4",
+			PageSize::A5 => "A5"
+		}
+	}
+
+	/// Answer the width and height of the page size, in points.
+	fn dimensions(self) -> (f32, f32)
+	{
+		match self
+		{
+			PageSize::Letter => (612.0, 792.0),
+			PageSize::Legal => (612.0, 1008.0),
+			PageSize::A4 => (595.0, 842.0),
+			PageSize::A5 => (420.0, 595.0)
+		}
+	}
+}
+
+/// One of the two standard fonts used by [`blocks_to_pdf`]. The standard
+/// fonts are built into every PDF reader, so nothing needs to be embedded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PdfFont
+{
+	/// Helvetica, for body text.
+	Regular,
+
+	/// Helvetica Bold, for headings.
+	Bold
+}
+
+impl PdfFont
+{
+	/// Answer the resource name of the font, as used by content streams.
+	fn resource(self) -> &'static str
+	{
+		match self
+		{
+			Self::Regular => "F1",
+			Self::Bold => "F2"
+		}
+	}
+
+	/// Answer the width of the specified WinAnsi byte, in thousandths of the
+	/// font size.
+	fn width(self, byte: u8) -> u32
+	{
+		let table = match self
+		{
+			Self::Regular => &HELVETICA_WIDTHS,
+			Self::Bold => &HELVETICA_BOLD_WIDTHS
+		};
+		match byte
+		{
+			0x20 ..= 0x7e => table[(byte - 0x20) as usize] as u32,
+			0x91 | 0x92 => 222,
+			0x93 | 0x94 => 333,
+			0x95 => 350,
+			0x96 => 556,
+			0x85 | 0x97 => 1000,
+			_ => 556
+		}
+	}
+}
+
+/// A line of text, positioned on a page.
+struct PlacedLine
+{
+	/// The font of the line.
+	font: PdfFont,
+
+	/// The font size, in points.
+	size: f32,
+
+	/// The horizontal position of the start of the baseline, in points from
+	/// the left edge of the page.
+	x: f32,
+
+	/// The vertical position of the baseline, in points from the bottom edge
+	/// of the page.
+	y: f32,
+
+	/// The text, already encoded as WinAnsi.
+	text: Vec<u8>
+}
+
+/// Pages of [lines](PlacedLine) under construction, filled from top to
+/// bottom.
+struct Pages
+{
+	/// The width of each page, in points.
+	width: f32,
+
+	/// The height of each page, in points.
+	height: f32,
+
+	/// The pages so far. The last page is the one being filled.
+	pages: Vec<Vec<PlacedLine>>,
+
+	/// The vertical position of the baseline of the previous line on the
+	/// current page, in points from the bottom edge of the page.
+	y: f32
+}
+
+impl Pages
+{
+	/// Begin a single empty page of the specified size.
+	fn new(page_size: PageSize) -> Self
+	{
+		let (width, height) = page_size.dimensions();
+		Self { width, height, pages: vec![vec![]], y: height - PAGE_MARGIN }
+	}
+
+	/// Answer the width available for text, in points.
+	fn text_width(&self) -> f32
+	{
+		self.width - 2.0 * PAGE_MARGIN
+	}
+
+	/// Begin a new page, unless the current page is still empty.
+	fn break_page(&mut self)
+	{
+		if !self.pages.last().map_or(true, Vec::is_empty)
+		{
+			self.pages.push(vec![]);
+		}
+		self.y = self.height - PAGE_MARGIN;
+	}
+
+	/// Place the specified WinAnsi line below the previous line, at the
+	/// specified indentation from the left margin, or centered if `indent` is
+	/// `None`. Begin a new page first unless `needed` lines fit on the current
+	/// page.
+	fn place(
+		&mut self,
+		font: PdfFont,
+		size: f32,
+		indent: Option<f32>,
+		text: Vec<u8>,
+		needed: f32
+	)
+	{
+		let leading = size * LEADING;
+		if self.y - leading * needed < PAGE_MARGIN
+		{
+			self.pages.push(vec![]);
+			self.y = self.height - PAGE_MARGIN;
+		}
+		self.y -= leading;
+		let x = match indent
+		{
+			Some(indent) => PAGE_MARGIN + indent,
+			None => (self.width - measure(font, size, &text)) / 2.0
+		};
+		self.pages.last_mut().unwrap().push(PlacedLine {
+			font,
+			size,
+			x,
+			y: self.y,
+			text
+		});
+	}
+
+	/// Leave the specified vertical space below the previous line.
+	fn skip(&mut self, space: f32)
+	{
+		self.y -= space;
+	}
+
+	/// Serialize the pages as a complete PDF file with the specified document
+	/// title.
+	fn finish(self, title: &str) -> Vec<u8>
+	{
+		serialize(title, self.width, self.height, &self.pages)
+	}
+}
+
+/// Render the specified [blocks](Block) as a PDF document on pages of the
+/// specified size, with the specified document title. Text is reflowed to the
+/// width of the page. Characters beyond the WinAnsi repertoire are replaced
+/// by question marks, because the standard fonts can't draw them.
+pub(crate) fn blocks_to_pdf(
+	title: &str,
+	page_size: PageSize,
+	blocks: &[Block]
+) -> Vec<u8>
+{
+	let mut pages = Pages::new(page_size);
+	for block in blocks
+	{
+		let (font, size, indent, prefix, text) = match block
+		{
+			Block::Heading(level, text) => (
+				PdfFont::Bold,
+				HEADING_SIZES[(*level as usize).clamp(1, 3) - 1],
+				0.0,
+				"",
+				text.as_str()
+			),
+			Block::Paragraph(text) =>
+				(PdfFont::Regular, BODY_SIZE, 0.0, "", text.as_str()),
+			Block::Bullet(text) =>
+				(PdfFont::Regular, BODY_SIZE, BULLET_INDENT, "• ", text.as_str())
+		};
+		let width = pages.text_width() - indent;
+		let text = encode(&format!("{}{}", prefix, text));
+		// Keep a heading together with at least one following line.
+		let needed =
+			if matches!(block, Block::Heading(..)) { 2.0 } else { 1.0 };
+		for line in wrap(font, size, &text, width)
+		{
+			pages.place(font, size, Some(indent), line, needed);
+		}
+		pages.skip(BLOCK_SPACING);
+	}
+	pages.finish(title)
+}
+
+/// Render the specified manuscript as a PDF document on pages of the
+/// specified size, e.g., for workshop handouts. A title page presents the
+/// title and the subtitle, and the manuscript begins on the next page. Blank
+/// lines separate paragraphs, and line breaks within a paragraph are kept. A
+/// paragraph for which `centered` answers `true`, e.g., a dinkus between
+/// sections, is centered. Characters beyond the WinAnsi repertoire are
+/// replaced by question marks, because the standard fonts can't draw them.
+pub(crate) fn manuscript_to_pdf(
+	title: &str,
+	subtitle: &str,
+	manuscript: &str,
+	centered: impl Fn(&str) -> bool,
+	page_size: PageSize
+) -> Vec<u8>
+{
+	let mut pages = Pages::new(page_size);
+	let width = pages.text_width();
+	// Set the title a third of the way down the title page.
+	pages.skip((pages.height - 2.0 * PAGE_MARGIN) / 3.0);
+	for line in wrap(PdfFont::Bold, TITLE_SIZE, &encode(title), width)
+	{
+		pages.place(PdfFont::Bold, TITLE_SIZE, None, line, 1.0);
+	}
+	pages.skip(TITLE_SIZE);
+	for line in wrap(PdfFont::Regular, BODY_SIZE, &encode(subtitle), width)
+	{
+		pages.place(PdfFont::Regular, BODY_SIZE, None, line, 1.0);
+	}
+	pages.break_page();
+	for paragraph in paragraphs(manuscript)
+	{
+		let indent = (!centered(paragraph)).then_some(0.0);
+		for line in paragraph.lines()
+		{
+			let line = encode(line.trim_end());
+			for line in wrap(PdfFont::Regular, BODY_SIZE, &line, width)
+			{
+				pages.place(PdfFont::Regular, BODY_SIZE, indent, line, 1.0);
+			}
+		}
+		pages.skip(BLOCK_SPACING);
+	}
+	pages.finish(title)
+}
+
+/// Encode the specified text as WinAnsi, which is the encoding of the
+/// standard fonts.
+fn encode(text: &str) -> Vec<u8>
+{
+	text.chars()
+		.filter(|c| *c != '\r')
+		.map(|c| match c
+		{
+			'\n' | '\t' => b' ',
+			' ' ..= '~' => c as u8,
+			'\u{a0}' ..= '\u{ff}' => c as u32 as u8,
+			'…' => 0x85,
+			'‘' => 0x91,
+			'’' => 0x92,
+			'“' => 0x93,
+			'”' => 0x94,
+			'•' => 0x95,
+			'–' => 0x96,
+			'—' => 0x97,
+			_ => b'?'
+		})
+		.collect()
+}
+
+/// Break the specified WinAnsi text into lines no wider than `width` points,
+/// at spaces where possible.
+fn wrap(font: PdfFont, size: f32, text: &[u8], width: f32) -> Vec<Vec<u8>>
+{
+	let measure = |word: &[u8]| measure(font, size, word);
+	let space = measure(b" ");
+	let mut lines = vec![];
+	let mut line = Vec::<u8>::new();
+	let mut line_width = 0.0;
+	for word in text.split(|b| *b == b' ').filter(|w| !w.is_empty())
+	{
+		let word_width = measure(word);
+		if !line.is_empty() && line_width + space + word_width > width
+		{
+			lines.push(std::mem::take(&mut line));
+			line_width = 0.0;
+		}
+		if !line.is_empty()
+		{
+			line.push(b' ');
+			line_width += space;
+		}
+		line.extend_from_slice(word);
+		line_width += word_width;
+	}
+	if !line.is_empty() || lines.is_empty()
+	{
+		lines.push(line);
+	}
+	lines
+}
+
+/// Measure the width of the specified WinAnsi text, in points.
+fn measure(font: PdfFont, size: f32, text: &[u8]) -> f32
+{
+	text.iter().map(|b| font.width(*b)).sum::<u32>() as f32 * size / 1000.0
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                               Serialization.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Serialize the specified pages of [lines](PlacedLine), each of the specified
+/// width and height, in points, as a complete PDF file.
+fn serialize(
+	title: &str,
+	width: f32,
+	height: f32,
+	pages: &[Vec<PlacedLine>]
+) -> Vec<u8>
+{
+	// Objects 1–5 are the catalog, the page tree, the two fonts, and the
+	// information dictionary. Then come the pages, each immediately followed
+	// by its content stream.
+	let mut objects: Vec<Vec<u8>> = vec![];
+	objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+	let kids = (0 .. pages.len())
+		.map(|page| format!("{} 0 R", FIRST_PAGE_OBJECT + 2 * page))
+		.collect::<Vec<_>>()
+		.join(" ");
+	objects.push(format!(
+		"<< /Type /Pages /Kids [{}] /Count {} >>",
+		kids,
+		pages.len()
+	).into_bytes());
+	for font in ["Helvetica", "Helvetica-Bold"]
+	{
+		objects.push(format!(
+			"<< /Type /Font /Subtype /Type1 /BaseFont /{} \
+			/Encoding /WinAnsiEncoding >>",
+			font
+		).into_bytes());
+	}
+	let mut info = b"<< /Title ".to_vec();
+	info.extend(literal(&encode(title)));
+	info.extend(b" /Producer (Story Shuffler) >>");
+	objects.push(info);
+	for (page, lines) in pages.iter().enumerate()
+	{
+		let contents = FIRST_PAGE_OBJECT + 2 * page + 1;
+		objects.push(format!(
+			"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+			/Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> \
+			/Contents {} 0 R >>",
+			width,
+			height,
+			contents
+		).into_bytes());
+		let mut stream = Vec::new();
+		for line in lines
+		{
+			let _ = write!(
+				stream,
+				"BT /{} {} Tf {:.2} {:.2} Td ",
+				line.font.resource(),
+				line.size,
+				line.x,
+				line.y
+			);
+			stream.extend(literal(&line.text));
+			stream.extend(b" Tj ET\n");
+		}
+		let mut object =
+			format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+		object.extend(stream);
+		object.extend(b"\nendstream");
+		objects.push(object);
+	}
+	let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+	let mut offsets = vec![];
+	for (index, object) in objects.iter().enumerate()
+	{
+		offsets.push(pdf.len());
+		let _ = writeln!(pdf, "{} 0 obj", index + 1);
+		pdf.extend(object);
+		pdf.extend(b"\nendobj\n");
+	}
+	let xref = pdf.len();
+	let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+	for offset in offsets
+	{
+		let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+	}
+	let _ = write!(
+		pdf,
+		"trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+		objects.len() + 1,
+		INFO_OBJECT,
+		xref
+	);
+	pdf
+}
+
+/// Answer the specified WinAnsi text as a PDF literal string, escaping
+/// delimiters and writing non-ASCII bytes in octal.
+fn literal(text: &[u8]) -> Vec<u8>
+{
+	let mut literal = vec![b'('];
+	for byte in text
+	{
+		match byte
+		{
+			b'(' | b')' | b'\\' =>
+			{
+				literal.push(b'\\');
+				literal.push(*byte);
+			},
+			0x20 ..= 0x7e => literal.push(*byte),
+			_ => literal.extend(format!("\\{:03o}", byte).into_bytes())
+		}
+	}
+	literal.push(b')');
+	literal
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The margin on every side of the page, in points.
+const PAGE_MARGIN: f32 = 72.0;
+
+/// The font size of body text, in points.
+const BODY_SIZE: f32 = 11.0;
+
+/// The font size of the title on a title page, in points.
+const TITLE_SIZE: f32 = 24.0;
+
+/// The font sizes of headings, by level, in points.
+const HEADING_SIZES: [f32; 3] = [18.0, 14.0, 12.0];
+
+/// The distance between baselines, as a multiple of the font size.
+const LEADING: f32 = 1.35;
+
+/// The additional space after each block, in points.
+const BLOCK_SPACING: f32 = 6.0;
+
+/// The indentation of bulleted items, in points.
+const BULLET_INDENT: f32 = 12.0;
+
+/// The object number of the information dictionary.
+const INFO_OBJECT: usize = 5;
+
+/// The object number of the first page. The content stream of each page
+/// immediately follows the page.
+const FIRST_PAGE_OBJECT: usize = 6;
+
+/// The widths of the printable ASCII characters in Helvetica, in thousandths
+/// of the font size, from the standard font metrics.
+const HELVETICA_WIDTHS: [u16; 95] = [
+	278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+	278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584,
+	584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556,
+	833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278,
+	278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222,
+	500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500,
+	500, 334, 260, 334, 584
+];
+
+/// The widths of the printable ASCII characters in Helvetica Bold, in
+/// thousandths of the font size, from the standard font metrics.
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+	278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278,
+	278, 556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584,
+	584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611,
+	833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333,
+	278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278,
+	556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556,
+	500, 389, 280, 389, 584
+];
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Answer the number of pages of the specified PDF file.
+	fn page_count(pdf: &[u8]) -> usize
+	{
+		let pdf = String::from_utf8_lossy(pdf);
+		let count = &pdf[pdf.find("/Count ").unwrap() + 7 ..];
+		count[.. count.find(' ').unwrap()].parse().unwrap()
+	}
+
+	#[test]
+	fn wraps_lines_to_the_width()
+	{
+		let text = encode(&["All work and no play."; 20].join(" "));
+		let width = 200.0;
+		let lines = wrap(PdfFont::Regular, BODY_SIZE, &text, width);
+		assert!(lines.len() > 1);
+		for line in &lines
+		{
+			assert!(measure(PdfFont::Regular, BODY_SIZE, line) <= width);
+			assert!(!line.starts_with(b" ") && !line.ends_with(b" "));
+		}
+		assert_eq!(lines.join(&b' '), text);
+		// A word wider than the line gets a line to itself.
+		let long = encode(&format!("a {} b", "m".repeat(100)));
+		assert_eq!(wrap(PdfFont::Bold, BODY_SIZE, &long, width).len(), 3);
+		// Empty text is still a line.
+		assert_eq!(wrap(PdfFont::Regular, BODY_SIZE, b"", width).len(), 1);
+	}
+
+	#[test]
+	fn breaks_pages()
+	{
+		let short = blocks_to_pdf(
+			"Short",
+			PageSize::Letter,
+			&[Block::Paragraph("One line.".to_string())]
+		);
+		assert_eq!(page_count(&short), 1);
+		let blocks = (0 .. 100)
+			.map(|n| Block::Paragraph(format!("Paragraph {}.", n)))
+			.collect::<Vec<_>>();
+		let letter = blocks_to_pdf("Long", PageSize::Letter, &blocks);
+		let a5 = blocks_to_pdf("Long", PageSize::A5, &blocks);
+		assert!(page_count(&letter) > 1);
+		assert!(page_count(&a5) > page_count(&letter));
+		// The manuscript begins after the title page.
+		let handout = manuscript_to_pdf(
+			"Title",
+			"Subtitle",
+			"Text.",
+			|_| false,
+			PageSize::A4
+		);
+		assert_eq!(page_count(&handout), 2);
+	}
+
+	#[test]
+	fn keeps_lines_within_the_margins()
+	{
+		let mut pages = Pages::new(PageSize::A5);
+		for _ in 0 .. 200
+		{
+			let line = b"x".to_vec();
+			pages.place(PdfFont::Regular, BODY_SIZE, Some(0.0), line, 1.0);
+		}
+		assert!(pages.pages.len() > 1);
+		for line in pages.pages.iter().flatten()
+		{
+			assert!(line.y >= PAGE_MARGIN);
+			assert!(line.y <= pages.height - PAGE_MARGIN);
+		}
+	}
+
+	#[test]
+	fn escapes_literal_strings()
+	{
+		assert_eq!(literal(b"a (b) \\c"), b"(a \\(b\\) \\\\c)");
+		assert_eq!(literal(&encode("café “x”")), b"(caf\\351 \\223x\\224)");
+		assert_eq!(encode("→\tä"), b"? \xe4");
+		let pdf = manuscript_to_pdf(
+			"(Title)",
+			"",
+			"Call f(x) \\ g(y).",
+			|_| false,
+			PageSize::Letter
+		);
+		let pdf = String::from_utf8_lossy(&pdf);
+		assert!(pdf.contains("/Title (\\(Title\\))"));
+		assert!(pdf.contains("(Call f\\(x\\) \\\\ g\\(y\\).) Tj"));
+	}
+
+	#[test]
+	fn indexes_every_object()
+	{
+		let pdf = blocks_to_pdf(
+			"Title",
+			PageSize::Letter,
+			&[
+				Block::Heading(1, "Heading".to_string()),
+				Block::Bullet("Bullet".to_string())
+			]
+		);
+		let text = String::from_utf8_lossy(&pdf);
+		let xref = text[text.rfind("startxref\n").unwrap() + 10 ..]
+			.lines()
+			.next()
+			.unwrap()
+			.parse::<usize>()
+			.unwrap();
+		// The cross-reference table and the trailer are ASCII, but the header
+		// isn't, so work with bytes.
+		let table = std::str::from_utf8(&pdf[xref ..]).unwrap();
+		assert!(table.starts_with("xref\n"));
+		let offsets = table.lines()
+			.skip(3)
+			.take_while(|line| line.ends_with(" n "))
+			.map(|line| line[.. 10].parse::<usize>().unwrap())
+			.collect::<Vec<_>>();
+		// The catalog, the page tree, two fonts, the information dictionary,
+		// and one page with its contents.
+		assert_eq!(offsets.len(), 7);
+		for (index, offset) in offsets.into_iter().enumerate()
+		{
+			let object = format!("{} 0 obj", index + 1);
+			assert!(pdf[offset ..].starts_with(object.as_bytes()));
+		}
+	}
+}
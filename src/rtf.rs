@@ -0,0 +1,465 @@
+/*
+ * rtf.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Import.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the specified bytes look like a Rich Text Format document.
+pub(crate) fn is_rtf(bytes: &[u8]) -> bool
+{
+	bytes.starts_with(RTF_SIGNATURE)
+}
+
+/// Convert the specified Rich Text Format document to plain text, discarding
+/// all formatting, as well as fonts, styles, document information, headers,
+/// footers, footnotes, pictures, and other embedded objects. Paragraphs are
+/// separated by blank lines. Unicode escapes are honored; otherwise, 8-bit
+/// text is assumed to be Windows-1252, which word processors overwhelmingly
+/// use.
+pub(crate) fn rtf_to_text(rtf: &[u8]) -> String
+{
+	let mut reader = Reader {
+		rtf,
+		index: 0,
+		text: String::new(),
+		groups: vec![],
+		group: Group { skip: false, fallback_length: 1 },
+		fallback: 0,
+		high_surrogate: None
+	};
+	while let Some(byte) = reader.next_byte()
+	{
+		match byte
+		{
+			b'{' =>
+			{
+				reader.groups.push(reader.group);
+				reader.fallback = 0;
+			},
+			b'}' =>
+			{
+				match reader.groups.pop()
+				{
+					Some(outer) => reader.group = outer,
+					// The document is over.
+					None => break
+				}
+				reader.fallback = 0;
+			},
+			b'\\' => reader.read_control(),
+			// Line breaks in the source are insignificant.
+			b'\r' | b'\n' => {},
+			_ => reader.emit(windows_1252(byte))
+		}
+	}
+	normalize_breaks(&reader.text)
+}
+
+/// The state of a conversion from [Rich&#32;Text&#32;Format](rtf_to_text).
+struct Reader<'a>
+{
+	/// The document.
+	rtf: &'a [u8],
+
+	/// The index of the next byte of the [document](Self::rtf).
+	index: usize,
+
+	/// The plain text so far.
+	text: String,
+
+	/// The state of each enclosing group, saved when the group began.
+	groups: Vec<Group>,
+
+	/// The state of the current group.
+	group: Group,
+
+	/// The number of fallback characters that remain to be skipped after a
+	/// Unicode escape.
+	fallback: usize,
+
+	/// The high half of a surrogate pair, awaiting its low half.
+	high_surrogate: Option<u16>
+}
+
+impl Reader<'_>
+{
+	/// Consume and answer the next byte of the document, if any.
+	fn next_byte(&mut self) -> Option<u8>
+	{
+		let byte = self.rtf.get(self.index).copied();
+		self.index += 1;
+		byte
+	}
+
+	/// Consume bytes of the document while they satisfy `predicate`, and
+	/// answer them.
+	fn take_while(&mut self, predicate: impl Fn(&u8) -> bool) -> &[u8]
+	{
+		let start = self.index.min(self.rtf.len());
+		while self.rtf.get(self.index).map_or(false, &predicate)
+		{
+			self.index += 1;
+		}
+		&self.rtf[start .. self.index.min(self.rtf.len())]
+	}
+
+	/// Append the specified character of body text, unless the current group
+	/// is skipped or the character is the fallback for a Unicode escape.
+	fn emit(&mut self, c: char)
+	{
+		if self.group.skip
+		{
+			return
+		}
+		if self.fallback > 0
+		{
+			self.fallback -= 1;
+			return
+		}
+		self.text.push(c);
+	}
+
+	/// Read the control word or control symbol that follows a backslash.
+	fn read_control(&mut self)
+	{
+		let Some(next) = self.next_byte() else { return };
+		match next
+		{
+			b'a' ..= b'z' | b'A' ..= b'Z' =>
+			{
+				self.index -= 1;
+				self.read_control_word();
+			},
+			b'\'' =>
+			{
+				let code = self.rtf.get(self.index .. self.index + 2)
+					.and_then(|hex| std::str::from_utf8(hex).ok())
+					.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+				self.index += 2;
+				if let Some(code) = code
+				{
+					self.emit(windows_1252(code));
+				}
+			},
+			b'*' => self.group.skip = true,
+			b'\r' | b'\n' if !self.group.skip =>
+				self.text.push_str(PARAGRAPH_BREAK),
+			b'~' => self.emit('\u{a0}'),
+			b'_' => self.emit('\u{2011}'),
+			b'\\' | b'{' | b'}' => self.emit(next as char),
+			// Optional hyphens and unknown control symbols are ignored.
+			_ => {}
+		}
+	}
+
+	/// Read a control word and its optional numeric parameter, and act upon
+	/// it.
+	fn read_control_word(&mut self)
+	{
+		let word = self.take_while(u8::is_ascii_alphabetic).to_vec();
+		let sign = self.take_while(|byte| *byte == b'-').len();
+		let digits = self.take_while(u8::is_ascii_digit);
+		let parameter = std::str::from_utf8(digits)
+			.ok()
+			.and_then(|digits| digits.parse::<i32>().ok())
+			.map(|magnitude| if sign > 0 { -magnitude } else { magnitude });
+		// A single space delimits the control word.
+		if self.rtf.get(self.index) == Some(&b' ')
+		{
+			self.index += 1;
+		}
+		let word = word.as_slice();
+		if word == b"bin"
+		{
+			// Skip the binary data outright.
+			self.index += parameter.unwrap_or(0).max(0) as usize;
+			return
+		}
+		if self.group.skip
+		{
+			return
+		}
+		if SKIPPED_DESTINATIONS.contains(&word)
+		{
+			self.group.skip = true;
+			return
+		}
+		match word
+		{
+			b"uc" =>
+			{
+				self.group.fallback_length =
+					parameter.unwrap_or(1).max(0) as usize;
+			},
+			b"u" => self.read_unicode(parameter.unwrap_or(0)),
+			_ =>
+			{
+				if let Some(replacement) = replacement(word)
+				{
+					self.text.push_str(replacement);
+				}
+			}
+		}
+	}
+
+	/// Append the character of the specified Unicode escape, whose parameter
+	/// is a signed 16-bit code unit, and arrange to skip its fallback.
+	fn read_unicode(&mut self, parameter: i32)
+	{
+		// Negative parameters denote the upper half of the 16-bit range.
+		let unit = parameter.rem_euclid(0x1_0000) as u16;
+		let decoded = match self.high_surrogate.take()
+		{
+			Some(high) => char::decode_utf16([high, unit])
+				.next()
+				.and_then(Result::ok),
+			None if (0xd800 .. 0xdc00).contains(&unit) =>
+			{
+				self.high_surrogate = Some(unit);
+				None
+			},
+			None => char::from_u32(unit as u32)
+		};
+		if let Some(c) = decoded
+		{
+			self.text.push(c);
+		}
+		self.fallback = self.group.fallback_length;
+	}
+}
+
+/// The state of a group of a Rich Text Format document, which its nested
+/// groups inherit.
+#[derive(Clone, Copy, Debug)]
+struct Group
+{
+	/// Whether the content of the group is discarded, i.e., because it belongs
+	/// to a destination other than the body text.
+	skip: bool,
+
+	/// The number of fallback characters that follow each Unicode escape.
+	fallback_length: usize
+}
+
+/// Answer the text that replaces the specified control word, if any.
+fn replacement(word: &[u8]) -> Option<&'static str>
+{
+	let text = match word
+	{
+		b"par" | b"sect" | b"page" => PARAGRAPH_BREAK,
+		b"line" => "\n",
+		b"tab" => "\t",
+		b"emdash" => "\u{2014}",
+		b"endash" => "\u{2013}",
+		b"lquote" => "\u{2018}",
+		b"rquote" => "\u{2019}",
+		b"ldblquote" => "\u{201c}",
+		b"rdblquote" => "\u{201d}",
+		b"bullet" => "\u{2022}",
+		b"emspace" | b"enspace" | b"qmspace" => " ",
+		_ => return None
+	};
+	Some(text)
+}
+
+/// Decode the specified Windows-1252 byte.
+fn windows_1252(byte: u8) -> char
+{
+	match byte
+	{
+		0x80 ..= 0x9f => WINDOWS_1252_C1[(byte - 0x80) as usize],
+		// The rest of Windows-1252 coincides with Latin-1.
+		_ => byte as char
+	}
+}
+
+/// Tidy the line breaks of the specified text: trim trailing whitespace from
+/// every line, collapse runs of blank lines, and trim the whole text.
+fn normalize_breaks(text: &str) -> String
+{
+	let mut normalized = String::with_capacity(text.len());
+	let mut blank_lines = 0;
+	for line in text.trim().lines().map(str::trim_end)
+	{
+		if line.is_empty()
+		{
+			blank_lines += 1;
+			continue
+		}
+		if !normalized.is_empty()
+		{
+			normalized.push_str(if blank_lines > 0 { "\n\n" } else { "\n" });
+		}
+		normalized.push_str(line);
+		blank_lines = 0;
+	}
+	normalized
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The signature that begins every Rich Text Format document.
+const RTF_SIGNATURE: &[u8] = b"{\\rtf";
+
+/// The text that ends a paragraph.
+const PARAGRAPH_BREAK: &str = "\n\n";
+
+/// The destinations whose content isn't body text. Destinations introduced by
+/// `\*` are skipped, too.
+const SKIPPED_DESTINATIONS: &[&[u8]] = &[
+	b"author",
+	b"colortbl",
+	b"comment",
+	b"fldinst",
+	b"fonttbl",
+	b"footer",
+	b"footerf",
+	b"footerl",
+	b"footerr",
+	b"footnote",
+	b"header",
+	b"headerf",
+	b"headerl",
+	b"headerr",
+	b"info",
+	b"listoverridetable",
+	b"listtable",
+	b"object",
+	b"pict",
+	b"revtbl",
+	b"rsidtbl",
+	b"stylesheet",
+	b"title"
+];
+
+/// The characters of Windows-1252 from `0x80` to `0x9f`, where it departs
+/// from Latin-1. Unassigned codes map to the replacement character.
+const WINDOWS_1252_C1: [char; 32] = [
+	'€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡',
+	'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž', '\u{fffd}',
+	'\u{fffd}', '‘', '’', '“', '”', '•', '–', '—',
+	'˜', '™', 'š', '›', 'œ', '\u{fffd}', 'ž', 'Ÿ'
+];
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// Encode the specified plain text as a minimal Rich Text Format document,
+	/// one paragraph per blank-line-separated block, escaping the special
+	/// characters and writing everything beyond ASCII as Unicode escapes.
+	fn text_to_rtf(text: &str) -> Vec<u8>
+	{
+		let mut rtf = String::from("{\\rtf1\\ansi\\uc1 ");
+		for (index, paragraph) in text.split("\n\n").enumerate()
+		{
+			if index > 0
+			{
+				rtf.push_str("\\par\n");
+			}
+			for unit in paragraph.encode_utf16()
+			{
+				match unit
+				{
+					0x5c => rtf.push_str("\\\\"),
+					0x7b => rtf.push_str("\\{"),
+					0x7d => rtf.push_str("\\}"),
+					0x0a => rtf.push_str("\\line "),
+					0x20 ..= 0x7e => rtf.push(unit as u8 as char),
+					// Negative parameters denote the upper half.
+					_ => rtf.push_str(&format!("\\u{}?", unit as i16))
+				}
+			}
+		}
+		rtf.push('}');
+		rtf.into_bytes()
+	}
+
+	#[test]
+	fn recognizes_rtf()
+	{
+		assert!(is_rtf(b"{\\rtf1\\ansi Hello}"));
+		assert!(!is_rtf(b"Hello"));
+		assert!(!is_rtf(b"{\\rt"));
+	}
+
+	#[test]
+	fn honors_escapes()
+	{
+		let rtf = b"{\\rtf1 A \\{brace\\}, a \\\\ backslash, \\'93quotes\\'94, \
+			\\emdash , and\\~space.}";
+		assert_eq!(
+			rtf_to_text(rtf),
+			"A {brace}, a \\ backslash, “quotes”, —, and\u{a0}space."
+		);
+	}
+
+	#[test]
+	fn skips_destinations()
+	{
+		let rtf = b"{\\rtf1{\\fonttbl{\\f0 Times;}}{\\info{\\title Secret}}\
+			{\\*\\generator Writer;}Body{\\b  bold}{\\footnote Note.} text.\\par \
+			Next.}";
+		assert_eq!(rtf_to_text(rtf), "Body bold text.\n\nNext.");
+	}
+
+	#[test]
+	fn decodes_unicode_escapes()
+	{
+		// The fallback of each escape is skipped, per \uc.
+		assert_eq!(rtf_to_text(b"{\\rtf1 caf\\u233?}"), "café");
+		assert_eq!(rtf_to_text(b"{\\rtf1\\uc2 caf\\u233\\'e9e}"), "café");
+		// The fallback length belongs to the group.
+		assert_eq!(
+			rtf_to_text(b"{\\rtf1{\\uc0 \\u233}\\u233?}"),
+			"éé"
+		);
+		// Negative parameters and surrogate pairs.
+		assert_eq!(rtf_to_text(b"{\\rtf1 \\u-3913?}"), "\u{f0b7}");
+		assert_eq!(rtf_to_text(b"{\\rtf1 \\u-10179?\\u-8704?}"), "😀");
+	}
+
+	#[test]
+	fn round_trips_plain_text()
+	{
+		let text = "Chapter {1}: C:\\Stories\n\nShe said, “Café?” — 😀\nThen \
+			left.";
+		assert_eq!(rtf_to_text(&text_to_rtf(text)), text);
+	}
+}
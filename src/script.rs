@@ -0,0 +1,490 @@
+/*
+ * script.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::fmt::{self, Display, Formatter};
+use crate::app::{Parity, PlacePreference};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Statements.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A single constraint of a constraint script, which power users can type
+/// instead of filling in the widgets of each section. Sections and positions
+/// are **one-based**, as the writer sees them.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Statement
+{
+	/// The first section must come before the second, e.g., `3 < 7`.
+	Before(usize, usize),
+
+	/// The first section must come after the second, e.g., `7 > 3`.
+	After(usize, usize),
+
+	/// The section is fixed in place, e.g., `fix 1`.
+	Fixed(usize),
+
+	/// The section must precede every other section, e.g., `first 3`.
+	First(usize),
+
+	/// The section must follow every other section, e.g., `last 9`.
+	Last(usize),
+
+	/// The section is pinned to the position, e.g., `pin 7 = 3`.
+	Pin(usize, usize),
+
+	/// The section may only occupy positions of the parity, e.g., `odd 2`.
+	Parity(usize, Parity),
+
+	/// The section appears the number of times, separated by at least the
+	/// number of other sections, e.g., `repeat 4 = 3 apart 2`.
+	Repeat(usize, usize, usize),
+
+	/// The named group comprises the sections, e.g., `group A = 2, 4, 5`.
+	Group(String, Vec<usize>),
+
+	/// The two sections must land within the distance of each other, e.g.,
+	/// `near(2, 5) <= 3`.
+	Near(usize, usize, usize),
+
+	/// The two sections must be separated by at least the number of other
+	/// sections, e.g., `apart(6, 9) >= 4`.
+	Apart(usize, usize, usize),
+
+	/// The two sections must never be adjacent, e.g., `apart(6, 9)`.
+	NotAdjacent(usize, usize),
+
+	/// The second section must land exactly the number of positions after
+	/// the first, e.g., `offset(3, 7) = 2`.
+	Offset(usize, usize, usize),
+
+	/// The first section should preferably come before the second, with the
+	/// weight, if any, e.g., `prefer 3 < 7 weight 2`.
+	Prefer(usize, usize, Option<f32>),
+
+	/// The section should preferably land at the place, with the weight, if
+	/// any, e.g., `prefer 4 early weight 2`.
+	Place(usize, PlacePreference, Option<f32>)
+}
+
+impl Statement
+{
+	/// Answer the **one-based** numbers of the sections that the statement
+	/// mentions.
+	pub(crate) fn sections(&self) -> Vec<usize>
+	{
+		match self
+		{
+			Self::Fixed(a)
+				| Self::First(a)
+				| Self::Last(a)
+				| Self::Pin(a, _)
+				| Self::Parity(a, _)
+				| Self::Repeat(a, ..)
+				| Self::Place(a, ..) => vec![*a],
+			Self::Before(a, b)
+				| Self::After(a, b)
+				| Self::Near(a, b, _)
+				| Self::Apart(a, b, _)
+				| Self::NotAdjacent(a, b)
+				| Self::Offset(a, b, _)
+				| Self::Prefer(a, b, _) => vec![*a, *b],
+			Self::Group(_, members) => members.clone()
+		}
+	}
+}
+
+impl Display for Statement
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Before(a, b) => write!(f, "{} < {}", a, b),
+			Self::After(a, b) => write!(f, "{} > {}", a, b),
+			Self::Fixed(a) => write!(f, "fix {}", a),
+			Self::First(a) => write!(f, "first {}", a),
+			Self::Last(a) => write!(f, "last {}", a),
+			Self::Pin(a, position) => write!(f, "pin {} = {}", a, position),
+			Self::Parity(a, parity) => write!(f, "{} {}", parity.name(), a),
+			Self::Repeat(a, times, gap) =>
+				write!(f, "repeat {} = {} apart {}", a, times, gap),
+			Self::Group(name, members) => write!(
+				f,
+				"group {} = {}",
+				name,
+				members.iter()
+					.map(ToString::to_string)
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			Self::Near(a, b, distance) =>
+				write!(f, "near({}, {}) <= {}", a, b, distance),
+			Self::Apart(a, b, gap) => write!(f, "apart({}, {}) >= {}", a, b, gap),
+			Self::NotAdjacent(a, b) => write!(f, "apart({}, {})", a, b),
+			Self::Offset(a, b, offset) =>
+				write!(f, "offset({}, {}) = {}", a, b, offset),
+			Self::Prefer(a, b, None) => write!(f, "prefer {} < {}", a, b),
+			Self::Prefer(a, b, Some(weight)) =>
+				write!(f, "prefer {} < {} weight {}", a, b, weight),
+			Self::Place(a, place, None) =>
+				write!(f, "prefer {} {}", a, place.keyword()),
+			Self::Place(a, place, Some(weight)) =>
+				write!(f, "prefer {} {} weight {}", a, place.keyword(), weight)
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Parsing.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Parse the specified constraint script, one [statement](Statement) per
+/// line. Chains like `1 < 2 < 3` abbreviate several statements. Blank lines
+/// are ignored, as is anything after `//`. Answer each statement along with
+/// its **one-based** line number, or an explanation of every syntax error.
+pub(crate) fn parse_script(
+	text: &str
+) -> Result<Vec<(usize, Statement)>, Vec<String>>
+{
+	let mut statements = vec![];
+	let mut errors = vec![];
+	for (index, line) in text.lines().enumerate()
+	{
+		let line = line.split("//").next().unwrap_or_default().trim();
+		if line.is_empty()
+		{
+			continue
+		}
+		match parse_line(line)
+		{
+			Ok(parsed) => statements.extend(
+				parsed.into_iter().map(|statement| (index + 1, statement))
+			),
+			Err(e) => errors.push(format!("Line {}: {}", index + 1, e))
+		}
+	}
+	if errors.is_empty() { Ok(statements) } else { Err(errors) }
+}
+
+/// Parse the specified nonblank line of a constraint script, without its
+/// comment, into one or more [statements](Statement).
+fn parse_line(line: &str) -> Result<Vec<Statement>, String>
+{
+	if let Some(rest) = keyword(line, "fix")
+	{
+		return Ok(vec![Statement::Fixed(section(rest)?)])
+	}
+	if let Some(rest) = keyword(line, "first")
+	{
+		return Ok(vec![Statement::First(section(rest)?)])
+	}
+	if let Some(rest) = keyword(line, "last")
+	{
+		return Ok(vec![Statement::Last(section(rest)?)])
+	}
+	for parity in Parity::ALL
+	{
+		if let Some(rest) = keyword(line, parity.name())
+		{
+			return Ok(vec![Statement::Parity(section(rest)?, parity)])
+		}
+	}
+	if let Some(rest) = keyword(line, "pin")
+	{
+		let (a, position) = rest.split_once('=')
+			.ok_or_else(|| "Expected a pin, like `pin 7 = 3`.".to_string())?;
+		return Ok(vec![Statement::Pin(section(a)?, number(position)?)])
+	}
+	if let Some(rest) = keyword(line, "repeat")
+	{
+		let expected = ||
+			"Expected a repetition, like `repeat 4 = 3 apart 2`.".to_string();
+		let (a, rest) = rest.split_once('=').ok_or_else(expected)?;
+		let (times, gap) = rest.split_once("apart").ok_or_else(expected)?;
+		let times = number(times)?;
+		if times < 2
+		{
+			return Err(format!(
+				"Expected a section to appear at least twice, but found {}.",
+				times
+			))
+		}
+		return Ok(vec![Statement::Repeat(section(a)?, times, number(gap)?)])
+	}
+	if let Some(rest) = keyword(line, "group")
+	{
+		let (name, members) = rest.split_once('=')
+			.ok_or_else(||
+				"Expected a group, like `group A = 2, 4, 5`.".to_string()
+			)?;
+		let name = name.trim();
+		if name.is_empty()
+		{
+			return Err("Expected the name of the group.".to_string())
+		}
+		let members = members.split(',')
+			.map(section)
+			.collect::<Result<Vec<_>, _>>()?;
+		return Ok(vec![Statement::Group(name.to_string(), members)])
+	}
+	if let Some(rest) = keyword(line, "prefer")
+	{
+		let (chain, weight) = match rest.rsplit_once("weight")
+		{
+			Some((chain, weight)) => (chain, Some(weight.trim())),
+			None => (rest, None)
+		};
+		let weight = match weight
+		{
+			Some(weight) => match weight.parse::<f32>()
+			{
+				Ok(weight) if weight > 0.0 => Some(weight),
+				_ => return Err(format!(
+					"Expected a positive weight, but found `{}`.",
+					weight
+				))
+			},
+			None => None
+		};
+		if let Some((a, place)) = chain.trim().rsplit_once(char::is_whitespace)
+		{
+			if let Some(place) = PlacePreference::from_keyword(place)
+			{
+				return Ok(vec![Statement::Place(section(a)?, place, weight)])
+			}
+		}
+		return Ok(ordering(chain)?.into_iter()
+			.map(|statement| match statement
+			{
+				Statement::Before(a, b) => Statement::Prefer(a, b, weight),
+				Statement::After(a, b) => Statement::Prefer(b, a, weight),
+				other => other
+			})
+			.collect())
+	}
+	if let Some((a, b, rest)) = function(line, "near")?
+	{
+		let distance = rest.strip_prefix("<=")
+			.ok_or_else(||
+				"Expected a distance, like `near(2, 5) <= 3`.".to_string()
+			)?;
+		return Ok(vec![Statement::Near(a, b, number(distance)?)])
+	}
+	if let Some((a, b, rest)) = function(line, "apart")?
+	{
+		if rest.is_empty()
+		{
+			return Ok(vec![Statement::NotAdjacent(a, b)])
+		}
+		let gap = rest.strip_prefix(">=")
+			.ok_or_else(||
+				"Expected a gap, like `apart(6, 9) >= 4`.".to_string()
+			)?;
+		return Ok(vec![Statement::Apart(a, b, number(gap)?)])
+	}
+	if let Some((a, b, rest)) = function(line, "offset")?
+	{
+		let offset = rest.strip_prefix('=')
+			.ok_or_else(||
+				"Expected an offset, like `offset(3, 7) = 2`.".to_string()
+			)?;
+		return Ok(vec![Statement::Offset(a, b, number(offset)?)])
+	}
+	ordering(line)
+}
+
+/// Answer the remainder of the specified line if it begins with the specified
+/// keyword, followed by whitespace.
+fn keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str>
+{
+	let rest = line.strip_prefix(keyword)?;
+	rest.starts_with(char::is_whitespace).then_some(rest.trim())
+}
+
+/// Parse the specified line if it begins with a call of the specified
+/// function of two sections, like `near(2, 5)`. Answer the sections and the
+/// remainder of the line, or `None` if the line doesn't begin with the
+/// function.
+fn function(
+	line: &str,
+	name: &str
+) -> Result<Option<(usize, usize, String)>, String>
+{
+	let Some(rest) = line.strip_prefix(name)
+		.map(str::trim_start)
+		.and_then(|rest| rest.strip_prefix('('))
+	else
+	{
+		return Ok(None)
+	};
+	let (arguments, rest) = rest.split_once(')')
+		.ok_or_else(|| format!("Expected `)` to close `{}(`.", name))?;
+	match arguments.split(',').collect::<Vec<_>>()[..]
+	{
+		[a, b] => Ok(Some((
+			section(a)?,
+			section(b)?,
+			rest.split_whitespace().collect()
+		))),
+		_ => Err(format!("Expected two sections, like `{}(2, 5)`.", name))
+	}
+}
+
+/// Parse the specified chain of ordering constraints, like `1 < 2 > 3`.
+fn ordering(chain: &str) -> Result<Vec<Statement>, String>
+{
+	let mut operands = vec![];
+	let mut operators = vec![];
+	let mut operand = String::new();
+	for c in chain.chars()
+	{
+		if c == '<' || c == '>'
+		{
+			operands.push(section(&operand)?);
+			operators.push(c);
+			operand.clear();
+		}
+		else
+		{
+			operand.push(c);
+		}
+	}
+	if operators.is_empty()
+	{
+		return Err(format!(
+			"Expected a constraint, like `3 < 7` or `fix 1`, but found `{}`.",
+			chain.trim()
+		))
+	}
+	operands.push(section(&operand)?);
+	Ok(operators.iter()
+		.zip(operands.windows(2))
+		.map(|(operator, pair)| match operator
+		{
+			'<' => Statement::Before(pair[0], pair[1]),
+			_ => Statement::After(pair[0], pair[1])
+		})
+		.collect())
+}
+
+/// Parse the specified **one-based** section number, which may be written
+/// with a leading `§`.
+fn section(text: &str) -> Result<usize, String>
+{
+	let text = text.trim();
+	number(text.strip_prefix('§').unwrap_or(text))
+		.map_err(|_|
+			if text.is_empty() { "Expected a section number.".to_string() }
+			else { format!("Expected a section number, but found `{}`.", text) }
+		)
+}
+
+/// Parse the specified positive number.
+fn number(text: &str) -> Result<usize, String>
+{
+	let text = text.trim();
+	match text.parse::<usize>()
+	{
+		Ok(n) if n > 0 => Ok(n),
+		_ => Err(format!("Expected a positive number, but found `{}`.", text))
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn round_trips_every_statement()
+	{
+		let statements = vec![
+			Statement::Before(3, 7),
+			Statement::After(7, 3),
+			Statement::Fixed(1),
+			Statement::First(3),
+			Statement::Last(9),
+			Statement::Pin(7, 3),
+			Statement::Parity(2, Parity::Odd),
+			Statement::Parity(4, Parity::Even),
+			Statement::Repeat(4, 3, 2),
+			Statement::Group("A".to_string(), vec![2, 4, 5]),
+			Statement::Near(2, 5, 3),
+			Statement::Apart(6, 9, 4),
+			Statement::NotAdjacent(6, 9),
+			Statement::Offset(3, 7, 2),
+			Statement::Prefer(3, 7, None),
+			Statement::Prefer(3, 7, Some(2.5)),
+			Statement::Place(4, PlacePreference::Early, None),
+			Statement::Place(4, PlacePreference::Late, Some(2.0))
+		];
+		let script = statements.iter()
+			.map(|statement| format!("{}\n", statement))
+			.collect::<String>();
+		assert_eq!(
+			parse_script(&script),
+			Ok(statements.into_iter()
+				.enumerate()
+				.map(|(index, statement)| (index + 1, statement))
+				.collect())
+		);
+	}
+
+	#[test]
+	fn expands_chains()
+	{
+		assert_eq!(
+			parse_script("§1 < 2 > 3 // A chain."),
+			Ok(vec![
+				(1, Statement::Before(1, 2)),
+				(1, Statement::After(2, 3))
+			])
+		);
+	}
+
+	#[test]
+	fn reports_errors_by_line()
+	{
+		let script =
+			"1 < 2\n\n§0 < 3\nshuffle 4\npin 7\n// Just a comment.\n3 <";
+		assert_eq!(parse_script(script), Err(vec![
+			"Line 3: Expected a section number, but found `§0`.".to_string(),
+			"Line 4: Expected a constraint, like `3 < 7` or `fix 1`, but \
+				found `shuffle 4`.".to_string(),
+			"Line 5: Expected a pin, like `pin 7 = 3`.".to_string(),
+			"Line 7: Expected a section number.".to_string()
+		]));
+	}
+}
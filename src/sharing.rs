@@ -0,0 +1,310 @@
+/*
+ * sharing.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Blobs.                                    //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Encode the specified value as a compact, self-identifying text blob that
+/// survives the clipboard, email, and chat: [RON](ron), compressed with
+/// DEFLATE, encoded as URL-safe base64, and prefixed with [`BLOB_PREFIX`].
+pub(crate) fn encode_blob<T: Serialize>(value: &T) -> Result<String, String>
+{
+	let text = ron::to_string(value).map_err(|e| e.to_string())?;
+	let compressed = miniz_oxide::deflate::compress_to_vec(
+		text.as_bytes(),
+		COMPRESSION_LEVEL
+	);
+	Ok(format!(
+		"{}{}",
+		BLOB_PREFIX,
+		base64::encode_config(compressed, base64::URL_SAFE_NO_PAD)
+	))
+}
+
+/// Decode a value from a text blob produced by [`encode_blob`]. Surrounding
+/// whitespace is ignored, as are line breaks introduced by mail clients.
+pub(crate) fn decode_blob<T: DeserializeOwned>(blob: &str) -> Result<T, String>
+{
+	let blob = blob.split_whitespace().collect::<String>();
+	let encoded = blob.strip_prefix(BLOB_PREFIX)
+		.ok_or_else(|| "This isn't a Story Shuffler project.".to_string())?;
+	let compressed = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD)
+		.map_err(|e| format!("The project is damaged: {}.", e))?;
+	let text = miniz_oxide::inflate::decompress_to_vec_with_limit(
+		&compressed,
+		MAXIMUM_BLOB_SIZE
+	).map_err(|e| format!("The project is damaged: {}.", e))?;
+	let text = String::from_utf8(text)
+		.map_err(|e| format!("The project is damaged: {}.", e))?;
+	ron::from_str(&text).map_err(|e| format!("The project is damaged: {}.", e))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                               Project files.                               //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Encode the specified value as the text of a project file: pretty-printed
+/// [RON](ron), beneath a comment that identifies the application, so that the
+/// file stays legible, e.g., under version control.
+pub(crate) fn encode_project_file<T: Serialize>(
+	value: &T
+) -> Result<String, String>
+{
+	let text = ron::ser::to_string_pretty(value, PrettyConfig::default())
+		.map_err(|e| e.to_string())?;
+	Ok(format!("{}\n{}\n", PROJECT_FILE_HEADER, text))
+}
+
+/// Decode a value from the text of a project file produced by
+/// [`encode_project_file`]. A [blob](encode_blob) saved to a file is accepted,
+/// too.
+pub(crate) fn decode_project_file<T: DeserializeOwned>(
+	text: &str
+) -> Result<T, String>
+{
+	if text.trim_start().starts_with(BLOB_PREFIX)
+	{
+		return decode_blob(text)
+	}
+	ron::from_str(text).map_err(|e| format!("The project is damaged: {}.", e))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                               Web sharing.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer `true` if the browser supports the
+/// [Web Share API](https://developer.mozilla.org/en-US/docs/Web/API/Navigator/share),
+/// which is common on phones and tablets but rare on desktops.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn can_share() -> bool
+{
+	web_sys::window()
+		.and_then(|window|
+			js_sys::Reflect::get(&window.navigator(), &"share".into()).ok()
+		)
+		.map_or(false, |share| share.is_function())
+}
+
+/// Offer the specified text to the platform's share sheet, e.g., so that the
+/// writer can send it to a notes or messaging application. The share sheet
+/// completes asynchronously; cancellation is logged, but not reported. Answer
+/// an explanation if the share sheet couldn't be opened.
+///
+/// The Web Share API is still considered unstable by [`web_sys`], so it is
+/// invoked reflectively.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn share_text(title: &str, text: &str) -> Result<(), String>
+{
+	use js_sys::{Function, Object, Promise, Reflect};
+	use wasm_bindgen::{JsCast, JsValue};
+	let describe = |e: JsValue| format!("{:?}", e);
+	let navigator = web_sys::window()
+		.ok_or_else(|| "The window is unavailable.".to_string())?
+		.navigator();
+	let share = Reflect::get(&navigator, &"share".into())
+		.map_err(describe)?
+		.dyn_into::<Function>()
+		.map_err(|_| "This browser can't share.".to_string())?;
+	let data = Object::new();
+	Reflect::set(&data, &"title".into(), &title.into()).map_err(describe)?;
+	Reflect::set(&data, &"text".into(), &text.into()).map_err(describe)?;
+	let promise = share.call1(&navigator, &data)
+		.map_err(describe)?
+		.dyn_into::<Promise>()
+		.map_err(|_| "The share sheet misbehaved.".to_string())?;
+	wasm_bindgen_futures::spawn_local(async move {
+		match wasm_bindgen_futures::JsFuture::from(promise).await
+		{
+			Ok(_) => tracing::info!("Shared the shuffled manuscript."),
+			Err(e) => tracing::info!("Sharing didn't complete: {:?}", e)
+		}
+	});
+	Ok(())
+}
+
+/// Answer a link to the page at the specified URL that carries the specified
+/// [blob](encode_blob) in its fragment, replacing any fragment already
+/// present. Browsers never send the fragment to the server, so the blob stays
+/// between the writer and the recipient.
+#[cfg(any(target_arch = "wasm32", test))]
+pub(crate) fn blob_link(page_url: &str, blob: &str) -> String
+{
+	let base = page_url.split('#').next().unwrap_or_default();
+	format!("{}#{}", base, blob)
+}
+
+/// Answer the [blob](encode_blob) carried by the specified URL fragment, which
+/// may include its leading `#`, if any. Some mail and chat applications
+/// percent-encode the colons of a blob, so these are restored.
+#[cfg(any(target_arch = "wasm32", test))]
+pub(crate) fn blob_from_fragment(fragment: &str) -> Option<String>
+{
+	let fragment = fragment.strip_prefix('#').unwrap_or(fragment)
+		.replace("%3A", ":")
+		.replace("%3a", ":");
+	fragment.starts_with(BLOB_PREFIX).then_some(fragment)
+}
+
+/// Remove the fragment from the address of the current page, without
+/// reloading it, so that reloading the page doesn't reapply a
+/// [link](blob_link) over the writer's subsequent work.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn forget_fragment(page_url: &str) -> Result<(), String>
+{
+	let base = page_url.split('#').next().unwrap_or_default();
+	web_sys::window()
+		.ok_or_else(|| "The window is unavailable.".to_string())?
+		.history()
+		.and_then(|history| history.replace_state_with_url(
+			&wasm_bindgen::JsValue::NULL,
+			"",
+			Some(base)
+		))
+		.map_err(|e| format!("{:?}", e))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The prefix of every blob, which identifies both the application and the
+/// version of the encoding.
+const BLOB_PREFIX: &str = "story-shuffler:1:";
+
+/// The comment that begins every project file.
+const PROJECT_FILE_HEADER: &str = "// A Story Shuffler project.";
+
+/// The DEFLATE compression level, from `0` (none) to `10` (best).
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// The maximum size of a decompressed blob, in bytes, which guards against
+/// maliciously crafted input.
+const MAXIMUM_BLOB_SIZE: usize = 64 * 1024 * 1024;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use std::collections::BTreeMap;
+	use super::*;
+
+	/// Answer a value worth sharing.
+	fn value() -> BTreeMap<String, Vec<usize>>
+	{
+		[
+			("before".to_string(), vec![3, 1, 2]),
+			("“quoted” & <escaped>".to_string(), vec![])
+		].into_iter().collect()
+	}
+
+	#[test]
+	fn round_trips_blobs()
+	{
+		let blob = encode_blob(&value()).unwrap();
+		assert!(blob.starts_with(BLOB_PREFIX));
+		assert_eq!(decode_blob::<BTreeMap<_, _>>(&blob).unwrap(), value());
+		// Mail clients break long lines.
+		let (head, tail) = blob.split_at(blob.len() / 2);
+		let wrapped = format!("  {}\n{}\n", head, tail);
+		assert_eq!(decode_blob::<BTreeMap<_, _>>(&wrapped).unwrap(), value());
+		let file = encode_project_file(&value()).unwrap();
+		// Project files accept blobs, too.
+		for text in [file, blob]
+		{
+			assert_eq!(
+				decode_project_file::<BTreeMap<_, _>>(&text).unwrap(),
+				value()
+			);
+		}
+	}
+
+	#[test]
+	fn round_trips_links()
+	{
+		let blob = encode_blob(&value()).unwrap();
+		let link = blob_link("https://example.com/shuffler/#stale", &blob);
+		let (page, fragment) = link.split_once('#').unwrap();
+		assert_eq!(page, "https://example.com/shuffler/");
+		assert_eq!(
+			blob_from_fragment(&format!("#{}", fragment)),
+			Some(blob.clone())
+		);
+		assert_eq!(blob_from_fragment(fragment), Some(blob.clone()));
+		// Some applications percent-encode the colons.
+		let encoded = fragment.replacen(':', "%3A", 1).replacen(':', "%3a", 1);
+		assert_eq!(blob_from_fragment(&encoded), Some(blob));
+	}
+
+	#[test]
+	fn rejects_garbage()
+	{
+		type Value = BTreeMap<String, Vec<usize>>;
+		assert_eq!(blob_from_fragment(""), None);
+		assert_eq!(blob_from_fragment("#section-3"), None);
+		assert_eq!(blob_from_fragment("#story-shuffler"), None);
+		let not_a_project = decode_blob::<Value>("Hello, world!").unwrap_err();
+		assert!(not_a_project.contains("isn't a Story Shuffler project"));
+		for garbage in [
+			"story-shuffler:1:",
+			"story-shuffler:1:!!!not base64!!!",
+			"story-shuffler:1:AAAAAAAA",
+			// Valid DEFLATE, but not RON.
+			&format!(
+				"{}{}",
+				BLOB_PREFIX,
+				base64::encode_config(
+					miniz_oxide::deflate::compress_to_vec(b"{{{", 1),
+					base64::URL_SAFE_NO_PAD
+				)
+			)
+		]
+		{
+			let problem = decode_blob::<Value>(garbage).unwrap_err();
+			assert!(
+				problem.starts_with("The project is damaged"),
+				"{}",
+				garbage
+			);
+		}
+		// A truncated blob is damaged.
+		let blob = encode_blob(&value()).unwrap();
+		assert!(decode_blob::<Value>(&blob[.. blob.len() - 4]).is_err());
+		assert!(decode_project_file::<Value>("(garbage").is_err());
+	}
+}
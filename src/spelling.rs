@@ -0,0 +1,860 @@
+/*
+ * spelling.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::collections::HashMap;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use egui::{Color32, FontId, Stroke, text::{LayoutJob, TextFormat}};
+
+////////////////////////////////////////////////////////////////////////////////
+//                               Dictionaries.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// A set of correctly spelled words. Supports both plain word lists, i.e., one
+/// word per line, and Hunspell dictionaries, i.e., a `.dic` file and its
+/// [affix&#32;file](Affixes). The affix rules of a Hunspell dictionary derive
+/// the inflections of its words, e.g., "tried" from "try". A plain word list
+/// has no affix rules, so [lookup](Self::contains) compensates by stripping
+/// the most common English inflections.
+#[derive(Default)]
+pub(crate) struct Dictionary
+{
+	/// The words, exactly as they appeared in the source, each with its
+	/// [affix&#32;flags](Flag), if any.
+	words: HashMap<String, Vec<Flag>>,
+
+	/// The [affix&#32;rules](Affixes), if the dictionary has any.
+	affixes: Option<Affixes>
+}
+
+impl Dictionary
+{
+	/// Parse a dictionary from the specified text, which is either a plain
+	/// word list or the contents of a Hunspell `.dic` file without its affix
+	/// file. Hunspell files begin with an approximate word count, which is
+	/// skipped, and every word may be followed by a slash and some affix
+	/// flags, which are discarded.
+	pub(crate) fn parse(text: &str) -> Self
+	{
+		let mut dictionary = Self::default();
+		dictionary.extend(text);
+		dictionary
+	}
+
+	/// Parse a Hunspell dictionary from the specified contents of its `.dic`
+	/// file and its `.aff` file, so that [lookup](Self::contains) applies its
+	/// [affix&#32;rules](Affixes).
+	pub(crate) fn with_affixes(words: &str, affixes: &str) -> Self
+	{
+		let mut dictionary = Self {
+			affixes: Some(Affixes::parse(affixes)),
+			..Default::default()
+		};
+		dictionary.extend(words);
+		dictionary
+	}
+
+	/// Add the words of the specified text to the receiver. The text has the
+	/// same format as for [`parse`](Self::parse), but the affix flags are kept
+	/// if the receiver has [affix&#32;rules](Self::affixes). Anything after
+	/// the word and its flags, e.g., morphological fields, is ignored.
+	pub(crate) fn extend(&mut self, text: &str)
+	{
+		for line in text.lines()
+		{
+			let entry = line.split_whitespace().next().unwrap_or_default();
+			let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+			if !word.is_empty() && !word.chars().all(|c| c.is_ascii_digit())
+			{
+				let flags = self.affixes.as_ref()
+					.map(|affixes| affixes.parse_flags(flags))
+					.unwrap_or_default();
+				self.words.entry(word.to_string()).or_default().extend(flags);
+			}
+		}
+	}
+
+	/// Answer the number of words in the dictionary, not counting the words
+	/// that its [affix&#32;rules](Self::affixes) derive.
+	pub(crate) fn len(&self) -> usize
+	{
+		self.words.len()
+	}
+
+	/// Answer `true` if the specified word is spelled correctly, as far as the
+	/// dictionary is concerned. Capitalized words are also tried in lowercase,
+	/// because they may just begin a sentence. Inflected words are checked
+	/// against the [affix&#32;rules](Self::affixes), if any, or else common
+	/// inflections are stripped in search of a known stem.
+	pub(crate) fn contains(&self, word: &str) -> bool
+	{
+		let word = word.replace('’', "'");
+		let lowercase = word.to_lowercase();
+		if self.words.contains_key(&word) || self.words.contains_key(&lowercase)
+		{
+			return true
+		}
+		if let Some(affixes) = self.affixes.as_ref()
+		{
+			return affixes.derives(&word, &self.words)
+				|| affixes.derives(&lowercase, &self.words)
+		}
+		INFLECTIONS.iter().any(|(suffix, replacement)| {
+			match lowercase.strip_suffix(suffix)
+			{
+				Some(stem) if stem.chars().count() > 1 =>
+				{
+					self.is_stem(&format!("{}{}", stem, replacement))
+						// Handle doubled final consonants, as in "stopped".
+						|| (replacement.is_empty() && undouble(stem)
+							.map(|stem| self.is_stem(stem))
+							.unwrap_or(false))
+				},
+				_ => false
+			}
+		})
+	}
+
+	/// Answer `true` if the specified lowercase stem is in the dictionary,
+	/// either verbatim or capitalized.
+	fn is_stem(&self, stem: &str) -> bool
+	{
+		self.words.contains_key(stem) || self.has_capitalized(stem)
+	}
+
+	/// Answer `true` if the dictionary contains the capitalized form of the
+	/// specified lowercase word, e.g., as for a proper noun.
+	fn has_capitalized(&self, word: &str) -> bool
+	{
+		let mut chars = word.chars();
+		match chars.next()
+		{
+			Some(first) =>
+			{
+				let capitalized = first.to_uppercase().chain(chars).collect::<String>();
+				self.words.contains_key(&capitalized)
+			},
+			None => false
+		}
+	}
+}
+
+/// Answer the specified word without its final letter, but only if its final
+/// letter is doubled.
+fn undouble(word: &str) -> Option<&str>
+{
+	let mut chars = word.chars().rev();
+	match (chars.next(), chars.next())
+	{
+		(Some(last), Some(penultimate)) if last == penultimate =>
+			Some(&word[.. word.len() - last.len_utf8()]),
+		_ => None
+	}
+}
+
+/// A Hunspell affix flag, which associates the words of a
+/// [dictionary](Dictionary) with the [affixes](Affix) that they accept.
+type Flag = u64;
+
+/// How the affix flags of a Hunspell dictionary are written, as declared by
+/// the `FLAG` directive of its affix file.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+enum FlagType
+{
+	/// Each character is a flag. This is the default.
+	#[default]
+	Single,
+
+	/// Each pair of characters is a flag.
+	Long,
+
+	/// The flags are decimal numbers, separated by commas.
+	Numeric
+}
+
+/// The affix rules of a Hunspell dictionary, as parsed from its `.aff` file.
+/// Only the directives that derive words are supported, i.e., `FLAG`, `AF`,
+/// `PFX`, and `SFX`. A suffix may permit one further suffix through its
+/// continuation flags.
+#[derive(Default)]
+struct Affixes
+{
+	/// How the flags are written.
+	flag_type: FlagType,
+
+	/// The flag aliases declared by `AF` directives, if any, which the
+	/// dictionary then cites by their **one-based** indices.
+	aliases: Vec<Vec<Flag>>,
+
+	/// The prefixes, keyed by the text that they add.
+	prefixes: HashMap<String, Vec<Affix>>,
+
+	/// The suffixes, keyed by the text that they add.
+	suffixes: HashMap<String, Vec<Affix>>
+}
+
+/// A single prefix or suffix rule of an [affix&#32;file](Affixes).
+struct Affix
+{
+	/// The flag that a word must have in order to accept the affix.
+	flag: Flag,
+
+	/// Whether the affix combines with affixes of the other kind, i.e.,
+	/// whether a prefix combines with suffixes, or vice versa.
+	cross_product: bool,
+
+	/// The text that the affix removes from the word before adding itself.
+	strip: String,
+
+	/// The condition that the word must satisfy, at its start for a prefix or
+	/// at its end for a suffix, one [pattern](Pattern) per character.
+	condition: Vec<Pattern>,
+
+	/// The flags of the further affixes that the affixed word accepts.
+	continuation: Vec<Flag>
+}
+
+/// A pattern that matches a single character of the condition of an
+/// [affix](Affix).
+enum Pattern
+{
+	/// Match any character, i.e., `.`.
+	Any,
+
+	/// Match any of the characters, e.g., `[aeiou]` or just `a`.
+	OneOf(Vec<char>),
+
+	/// Match any character but these, e.g., `[^aeiou]`.
+	NoneOf(Vec<char>)
+}
+
+impl Pattern
+{
+	/// Answer `true` if the receiver matches the specified character.
+	fn matches(&self, c: char) -> bool
+	{
+		match self
+		{
+			Self::Any => true,
+			Self::OneOf(chars) => chars.contains(&c),
+			Self::NoneOf(chars) => !chars.contains(&c)
+		}
+	}
+}
+
+/// Parse the specified condition of an [affix](Affix), e.g., `[^aeiou]y`.
+fn parse_condition(condition: &str) -> Vec<Pattern>
+{
+	let mut patterns = vec![];
+	let mut chars = condition.chars();
+	while let Some(c) = chars.next()
+	{
+		let pattern = match c
+		{
+			'.' => Pattern::Any,
+			'[' =>
+			{
+				let class = chars.by_ref()
+					.take_while(|c| *c != ']')
+					.collect::<Vec<_>>();
+				match class.split_first()
+				{
+					Some(('^', rest)) => Pattern::NoneOf(rest.to_vec()),
+					_ => Pattern::OneOf(class)
+				}
+			},
+			c => Pattern::OneOf(vec![c])
+		};
+		patterns.push(pattern);
+	}
+	patterns
+}
+
+impl Affix
+{
+	/// Answer `true` if the specified word satisfies the
+	/// [condition](Self::condition) at its start.
+	fn admits_prefix(&self, word: &str) -> bool
+	{
+		let mut chars = word.chars();
+		self.condition.iter()
+			.all(|pattern| chars.next().map_or(false, |c| pattern.matches(c)))
+	}
+
+	/// Answer `true` if the specified word satisfies the
+	/// [condition](Self::condition) at its end.
+	fn admits_suffix(&self, word: &str) -> bool
+	{
+		let mut chars = word.chars().rev();
+		self.condition.iter()
+			.rev()
+			.all(|pattern| chars.next().map_or(false, |c| pattern.matches(c)))
+	}
+}
+
+impl Affixes
+{
+	/// Parse the specified contents of a Hunspell `.aff` file. Unsupported
+	/// directives and malformed rules are ignored.
+	fn parse(text: &str) -> Self
+	{
+		let mut affixes = Self::default();
+		let mut cross_products = HashMap::<(bool, Flag), bool>::new();
+		let mut counted_aliases = false;
+		for line in text.lines()
+		{
+			let fields = line.split_whitespace().collect::<Vec<_>>();
+			match fields.as_slice()
+			{
+				["FLAG", kind, ..] =>
+				{
+					affixes.flag_type = match *kind
+					{
+						"long" => FlagType::Long,
+						"num" => FlagType::Numeric,
+						_ => FlagType::Single
+					};
+				},
+				// The first `AF` directive just counts the aliases.
+				["AF", _, ..] if !counted_aliases => counted_aliases = true,
+				["AF", flags, ..] =>
+				{
+					let flags = affixes.parse_plain_flags(flags);
+					affixes.aliases.push(flags);
+				},
+				[kind @ ("PFX" | "SFX"), flag, cross, count]
+					if matches!(*cross, "Y" | "N")
+						&& count.chars().all(|c| c.is_ascii_digit()) =>
+				{
+					let prefix = *kind == "PFX";
+					let flag = affixes.parse_plain_flags(flag);
+					if let Some(flag) = flag.first()
+					{
+						cross_products.insert((prefix, *flag), *cross == "Y");
+					}
+				},
+				[kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] =>
+				{
+					let prefix = *kind == "PFX";
+					let Some(&flag) = affixes.parse_plain_flags(flag).first()
+						else { continue };
+					let (add, continuation) = add.split_once('/')
+						.unwrap_or((add, ""));
+					let affix = Affix {
+						flag,
+						cross_product: cross_products.get(&(prefix, flag))
+							.copied()
+							.unwrap_or(false),
+						strip: if *strip == "0" { "" } else { strip }.to_string(),
+						condition: parse_condition(rest.first().unwrap_or(&".")),
+						continuation: affixes.parse_flags(continuation)
+					};
+					let add = if add == "0" { "" } else { add }.to_string();
+					let table = if prefix { &mut affixes.prefixes }
+						else { &mut affixes.suffixes };
+					table.entry(add).or_default().push(affix);
+				},
+				_ => {}
+			}
+		}
+		affixes
+	}
+
+	/// Parse the specified flags, which may cite an
+	/// [alias](Self::aliases).
+	fn parse_flags(&self, flags: &str) -> Vec<Flag>
+	{
+		if !self.aliases.is_empty()
+		{
+			if let Ok(index) = flags.parse::<usize>()
+			{
+				return index.checked_sub(1)
+					.and_then(|index| self.aliases.get(index))
+					.cloned()
+					.unwrap_or_default()
+			}
+		}
+		self.parse_plain_flags(flags)
+	}
+
+	/// Parse the specified flags, according to the
+	/// [flag&#32;type](Self::flag_type), ignoring [aliases](Self::aliases).
+	fn parse_plain_flags(&self, flags: &str) -> Vec<Flag>
+	{
+		match self.flag_type
+		{
+			FlagType::Single => flags.chars().map(Flag::from).collect(),
+			FlagType::Long =>
+			{
+				let chars = flags.chars().collect::<Vec<_>>();
+				chars.chunks(2)
+					.map(|pair| pair.iter()
+						.fold(0, |flag, c| flag << 32 | Flag::from(*c))
+					)
+					.collect()
+			},
+			FlagType::Numeric => flags.split(',')
+				.filter_map(|flag| flag.trim().parse().ok())
+				.collect()
+		}
+	}
+
+	/// Answer `true` if the affixes derive the specified word from one of the
+	/// specified words, by a prefix, a suffix, or both.
+	fn derives(&self, word: &str, words: &HashMap<String, Vec<Flag>>) -> bool
+	{
+		let has = |stem: &str, flags: &[Flag]| words.get(stem)
+			.map_or(false, |own| flags.iter().all(|flag| own.contains(flag)));
+		if self.derives_by_suffix(word, None, &has)
+		{
+			return true
+		}
+		self.affixed_by_prefix(word).any(|(prefix, stem)| {
+			has(&stem, &[prefix.flag])
+				|| (prefix.cross_product && self.affixed_by_suffix(&stem, None)
+					.any(|(suffix, root)| suffix.cross_product
+						&& has(&root, &[prefix.flag, suffix.flag])
+					))
+		})
+	}
+
+	/// Answer `true` if the suffixes derive the specified word from a word
+	/// that `has` the necessary flags. If `outer` is given, then the
+	/// derivation must permit the suffix with that flag to follow it. Without
+	/// an `outer` suffix, the derivation may comprise two suffixes, the second
+	/// of which continues the first.
+	fn derives_by_suffix(
+		&self,
+		word: &str,
+		outer: Option<Flag>,
+		has: &impl Fn(&str, &[Flag]) -> bool
+	) -> bool
+	{
+		self.affixed_by_suffix(word, outer).any(|(suffix, stem)|
+			has(&stem, &[suffix.flag])
+				|| (outer.is_none()
+					&& self.derives_by_suffix(&stem, Some(suffix.flag), has))
+		)
+	}
+
+	/// Answer the prefixes that the specified word may bear, along with the
+	/// stems that they would have prefixed.
+	fn affixed_by_prefix<'a>(&'a self, word: &'a str)
+		-> impl Iterator<Item=(&'a Affix, String)> + 'a
+	{
+		word.char_indices()
+			.map(|(index, _)| index)
+			.filter_map(move |index| Some((
+				self.prefixes.get(&word[.. index])?,
+				&word[index ..]
+			)))
+			.flat_map(|(prefixes, rest)| prefixes.iter()
+				.map(move |prefix| (prefix, format!("{}{}", prefix.strip, rest)))
+			)
+			.filter(|(prefix, stem)| prefix.admits_prefix(stem))
+	}
+
+	/// Answer the suffixes that the specified word may bear, along with the
+	/// stems that they would have suffixed. If `outer` is given, then answer
+	/// only the suffixes whose continuation permits it.
+	fn affixed_by_suffix<'a>(&'a self, word: &'a str, outer: Option<Flag>)
+		-> impl Iterator<Item=(&'a Affix, String)> + 'a
+	{
+		word.char_indices()
+			.map(|(index, _)| index)
+			.chain(std::iter::once(word.len()))
+			// Some of the word must remain.
+			.filter(|index| *index > 0)
+			.filter_map(move |index| Some((
+				self.suffixes.get(&word[index ..])?,
+				&word[.. index]
+			)))
+			.flat_map(|(suffixes, rest)| suffixes.iter()
+				.map(move |suffix| (suffix, format!("{}{}", rest, suffix.strip)))
+			)
+			.filter(move |(suffix, stem)| suffix.admits_suffix(stem)
+				&& outer.map_or(true, |flag| suffix.continuation.contains(&flag))
+			)
+	}
+}
+
+/// The writer's complete spelling configuration: the selected system
+/// [dictionary](Dictionary), if any, and the personal dictionary.
+#[derive(Default)]
+pub(crate) struct Speller
+{
+	/// The name of the loaded system dictionary, if any.
+	pub(crate) system_name: Option<String>,
+
+	/// The loaded system dictionary, if any.
+	pub(crate) system: Option<Dictionary>,
+
+	/// The writer's personal dictionary.
+	pub(crate) personal: Dictionary
+}
+
+impl Speller
+{
+	/// Answer `true` if the speller has any words at all. A speller without
+	/// words would flag everything, which helps nobody.
+	pub(crate) fn is_usable(&self) -> bool
+	{
+		self.system.as_ref().map(|d| d.len()).unwrap_or(0)
+			+ self.personal.len() > 0
+	}
+
+	/// Answer `true` if the specified word is spelled correctly according to
+	/// either dictionary.
+	pub(crate) fn is_known(&self, word: &str) -> bool
+	{
+		self.personal.contains(word)
+			|| self.system.as_ref().map(|d| d.contains(word)).unwrap_or(false)
+	}
+}
+
+/// Find the Hunspell dictionaries and word lists installed in the customary
+/// places, answering their names and paths. The answer is sorted by name.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn system_dictionaries() -> Vec<(String, PathBuf)>
+{
+	let mut directories = SYSTEM_DICTIONARY_DIRECTORIES.iter()
+		.map(PathBuf::from)
+		.collect::<Vec<_>>();
+	if let Some(home) = std::env::var_os("HOME")
+	{
+		directories.push(Path::new(&home).join("Library/Spelling"));
+	}
+	let mut dictionaries = directories.iter()
+		.filter_map(|directory| std::fs::read_dir(directory).ok())
+		.flatten()
+		.filter_map(|entry| entry.ok())
+		.map(|entry| entry.path())
+		.filter(|path| path.extension().map(|e| e == "dic").unwrap_or(false))
+		.filter_map(|path| {
+			let name = path.file_stem()?.to_string_lossy().to_string();
+			Some((name, path))
+		})
+		.collect::<Vec<_>>();
+	let words = PathBuf::from(SYSTEM_WORD_LIST);
+	if words.is_file()
+	{
+		dictionaries.push(("words".to_string(), words));
+	}
+	dictionaries.sort();
+	dictionaries.dedup_by(|a, b| a.0 == b.0);
+	dictionaries
+}
+
+/// Choose the most appropriate of the specified
+/// [system&#32;dictionaries](system_dictionaries), based on the user's locale.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn preferred_dictionary(dictionaries: &[(String, PathBuf)])
+	-> Option<String>
+{
+	let locale = std::env::var("LANG").unwrap_or_default();
+	let locale = locale.split('.').next().unwrap_or_default();
+	dictionaries.iter()
+		.find(|(name, _)| !locale.is_empty() && name == locale)
+		.or_else(|| dictionaries.iter().find(|(name, _)| name.starts_with("en")))
+		.or_else(|| dictionaries.first())
+		.map(|(name, _)| name.clone())
+}
+
+/// Load the specified dictionary, along with its Hunspell affix file, i.e.,
+/// the `.aff` file beside it, if any. Dictionaries are not necessarily encoded
+/// as UTF-8, so decode them as their affix files declare, or else leniently.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn load_dictionary(path: &Path) -> std::io::Result<Dictionary>
+{
+	let bytes = std::fs::read(path)?;
+	match std::fs::read(path.with_extension("aff"))
+	{
+		Ok(affixes) =>
+		{
+			let latin1 = String::from_utf8_lossy(&affixes).lines()
+				.filter_map(|line| line.strip_prefix("SET"))
+				.any(|encoding| matches!(
+					encoding.trim().to_ascii_uppercase().as_str(),
+					"ISO8859-1" | "ISO-8859-1"
+				));
+			Ok(Dictionary::with_affixes(
+				&decode(&bytes, latin1),
+				&decode(&affixes, latin1)
+			))
+		},
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound =>
+			Ok(Dictionary::parse(&String::from_utf8_lossy(&bytes))),
+		Err(e) => Err(e)
+	}
+}
+
+/// Decode the specified bytes as ISO 8859-1, if `latin1` is `true`, or else
+/// leniently as UTF-8.
+#[cfg(not(target_arch = "wasm32"))]
+fn decode(bytes: &[u8], latin1: bool) -> String
+{
+	if latin1 { bytes.iter().map(|b| char::from(*b)).collect() }
+	else { String::from_utf8_lossy(bytes).into_owned() }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Checking.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Answer the byte ranges of the words in the specified text. A word is a run
+/// of letters, possibly with embedded apostrophes (as in contractions).
+pub(crate) fn words(text: &str) -> Vec<Range<usize>>
+{
+	let mut ranges = vec![];
+	let mut start = None;
+	let mut end = 0;
+	for (index, c) in text.char_indices()
+	{
+		if c.is_alphabetic()
+		{
+			start.get_or_insert(index);
+			end = index + c.len_utf8();
+		}
+		else if (c == '\'' || c == '’') && start.is_some()
+		{
+			// Apostrophes can continue a word, but only if a letter follows,
+			// which will extend the end.
+		}
+		else if let Some(begin) = start.take()
+		{
+			ranges.push(begin .. end);
+		}
+	}
+	if let Some(begin) = start
+	{
+		ranges.push(begin .. end);
+	}
+	ranges
+}
+
+/// Answer the byte ranges of the misspelled words in the specified text.
+/// Ignore single letters and words in all capitals, which are usually
+/// acronyms or shouting.
+pub(crate) fn misspellings(text: &str, speller: &Speller) -> Vec<Range<usize>>
+{
+	words(text).into_iter()
+		.filter(|range| {
+			let word = &text[range.clone()];
+			word.chars().count() > 1
+				&& !word.chars().all(|c| !c.is_lowercase())
+				&& !speller.is_known(word)
+		})
+		.collect()
+}
+
+/// Lay out the specified text, underlining the
+/// [misspelled&#32;words](misspellings) with the specified color.
+pub(crate) fn highlight_misspellings(
+	text: &str,
+	speller: &Speller,
+	font_id: FontId,
+	color: Color32,
+	underline: Color32
+) -> LayoutJob
+{
+	let plain = TextFormat::simple(font_id.clone(), color);
+	let flagged = TextFormat {
+		underline: Stroke::new(1.5, underline),
+		..TextFormat::simple(font_id, color)
+	};
+	let mut job = LayoutJob::default();
+	let mut next = 0;
+	for range in misspellings(text, speller)
+	{
+		job.append(&text[next .. range.start], 0.0, plain.clone());
+		job.append(&text[range.clone()], 0.0, flagged.clone());
+		next = range.end;
+	}
+	job.append(&text[next ..], 0.0, plain);
+	job
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The common English inflections, as pairs of suffixes and their
+/// replacements, for deriving the stem of an inflected word. Ordered so that
+/// longer suffixes are tried first.
+const INFLECTIONS: &[(&str, &str)] = &[
+	("iest", "y"), ("ies", "y"), ("ied", "y"), ("ier", "y"), ("ily", "y"),
+	("ness", ""), ("ment", ""), ("ings", ""), ("ing", ""), ("ing", "e"),
+	("est", ""), ("est", "e"), ("ers", ""), ("ers", "e"), ("er", ""),
+	("er", "e"), ("ed", ""), ("ed", "e"), ("es", ""), ("ly", ""), ("'s", ""),
+	("s'", ""), ("s", ""), ("'d", ""), ("'ll", ""), ("'ve", ""), ("'re", ""),
+	("n't", "")
+];
+
+/// The directories that customarily contain Hunspell dictionaries.
+#[cfg(not(target_arch = "wasm32"))]
+const SYSTEM_DICTIONARY_DIRECTORIES: &[&str] = &[
+	"/usr/share/hunspell",
+	"/usr/share/myspell",
+	"/usr/share/myspell/dicts",
+	"/usr/local/share/hunspell",
+	"/Library/Spelling"
+];
+
+/// The customary location of the system word list on Unix-like systems.
+#[cfg(not(target_arch = "wasm32"))]
+const SYSTEM_WORD_LIST: &str = "/usr/share/dict/words";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	/// An excerpt of the English affix file that ships with Hunspell.
+	const AFFIXES: &str = "\
+SET UTF-8
+TRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'
+
+PFX A Y 1
+PFX A   0     re         .
+
+SFX D Y 4
+SFX D   0     d          e
+SFX D   y     ied        [^aeiou]y
+SFX D   0     ed         [^ey]
+SFX D   0     ed         [aeiou]y
+
+SFX G Y 2
+SFX G   e     ing        e
+SFX G   0     ing        [^e]
+
+SFX M Y 1
+SFX M   0     's         .
+";
+
+	#[test]
+	fn applies_affix_rules()
+	{
+		let dictionary = Dictionary::with_affixes(
+			"4\ncreate/ADG\ntry/DG\nplay/D\nTodd/M\n",
+			AFFIXES
+		);
+		assert_eq!(dictionary.len(), 4);
+		for word in [
+			"create", "created", "creating", "recreate", "recreated",
+			"recreating", "tried", "trying", "played", "Todd's", "Todd’s",
+			"Recreated"
+		]
+		{
+			assert!(dictionary.contains(word), "{}", word);
+		}
+		for word in [
+			"creat", "createing", "creatd", "tryed", "plaied", "retry",
+			"playing", "todd's", "reed"
+		]
+		{
+			assert!(!dictionary.contains(word), "{}", word);
+		}
+	}
+
+	#[test]
+	fn parses_every_kind_of_flag()
+	{
+		let long = Dictionary::with_affixes(
+			"cat/AaBb\ndog/Bb\n",
+			"FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n"
+		);
+		assert!(long.contains("cats"));
+		assert!(!long.contains("dogs"));
+		let numeric = Dictionary::with_affixes(
+			"cat/12,7\ndog/7\n",
+			"FLAG num\nSFX 12 Y 1\nSFX 12 0 s .\n"
+		);
+		assert!(numeric.contains("cats"));
+		assert!(!numeric.contains("dogs"));
+		let aliased = Dictionary::with_affixes(
+			"cat/2\ndog/1\n",
+			"AF 2\nAF B\nAF AB\nSFX A Y 1\nSFX A 0 s .\n"
+		);
+		assert!(aliased.contains("cats"));
+		assert!(!aliased.contains("dogs"));
+	}
+
+	#[test]
+	fn continues_suffixes()
+	{
+		let dictionary = Dictionary::with_affixes(
+			"drink/X\n",
+			"SFX X Y 1\nSFX X 0 able/Y .\nSFX Y Y 1\nSFX Y 0 s .\n"
+		);
+		assert!(dictionary.contains("drinkable"));
+		assert!(dictionary.contains("drinkables"));
+		assert!(!dictionary.contains("drinks"));
+	}
+
+	#[test]
+	fn strips_inflections_without_affix_rules()
+	{
+		let dictionary = Dictionary::parse("walk\nstop\nhappy\n");
+		assert!(dictionary.contains("walked"));
+		assert!(dictionary.contains("stopped"));
+		assert!(dictionary.contains("happiest"));
+		assert!(!dictionary.contains("walkz"));
+	}
+
+	#[cfg(not(target_arch = "wasm32"))]
+	#[test]
+	fn loads_affix_files()
+	{
+		let directory = std::env::temp_dir()
+			.join(format!("story-shuffler-spelling-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("fr.dic");
+		std::fs::write(&path, b"1\ncaf\xE9/S\n").unwrap();
+		std::fs::write(
+			directory.join("fr.aff"),
+			"SET ISO8859-1\nSFX S Y 1\nSFX S 0 s .\n"
+		).unwrap();
+		let dictionary = load_dictionary(&path).unwrap();
+		let plain = directory.join("words.dic");
+		std::fs::write(&plain, "walk\n").unwrap();
+		let words = load_dictionary(&plain).unwrap();
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert!(dictionary.contains("café"));
+		assert!(dictionary.contains("cafés"));
+		assert!(!dictionary.contains("cafe"));
+		assert!(words.contains("walked"));
+	}
+}
@@ -0,0 +1,141 @@
+/*
+ * storage.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Storage.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Persistent application state kept in a directory of the writer's choosing,
+/// e.g., a synced folder, rather than in the platform's default location.
+/// Like the framework's own storage, it is a [RON](ron) map from keys to
+/// values, kept in the file [`STORAGE_FILE_NAME`]. Only available on native
+/// builds.
+#[derive(Debug)]
+pub(crate) struct DirectoryStorage
+{
+	/// The path of the storage file.
+	path: PathBuf,
+
+	/// The stored values, by key.
+	entries: HashMap<String, String>,
+
+	/// Whether the [entries](Self::entries) have changed since they were
+	/// last read or written.
+	dirty: bool
+}
+
+impl DirectoryStorage
+{
+	/// Open the storage within the specified directory, creating the directory
+	/// if necessary. Answer an explanation on failure.
+	pub(crate) fn open(directory: &Path) -> Result<Self, String>
+	{
+		fs::create_dir_all(directory).map_err(|e|
+			format!("Couldn't create {}: {}", directory.display(), e)
+		)?;
+		let path = directory.join(STORAGE_FILE_NAME);
+		let entries = match fs::read_to_string(&path)
+		{
+			Ok(text) => ron::from_str(&text).map_err(|e|
+				format!("Couldn't parse {}: {}", path.display(), e)
+			)?,
+			Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(e) =>
+				return Err(format!("Couldn't read {}: {}", path.display(), e))
+		};
+		Ok(Self { path, entries, dirty: false })
+	}
+
+	/// Answer the path of the storage file.
+	pub(crate) fn path(&self) -> &Path
+	{
+		&self.path
+	}
+
+	/// Write the [entries](Self::entries) to the storage file. Write a
+	/// temporary file first and then rename it, so that an interrupted write,
+	/// e.g., by a sync client, can't damage the previous state.
+	fn write(&self) -> Result<(), String>
+	{
+		let text = ron::ser::to_string_pretty(&self.entries, Default::default())
+			.map_err(|e| e.to_string())?;
+		let temporary = self.path.with_extension(TEMPORARY_EXTENSION);
+		fs::write(&temporary, text)
+			.and_then(|_| fs::rename(&temporary, &self.path))
+			.map_err(|e| format!("Couldn't write {}: {}", self.path.display(), e))
+	}
+}
+
+impl eframe::Storage for DirectoryStorage
+{
+	fn get_string(&self, key: &str) -> Option<String>
+	{
+		self.entries.get(key).cloned()
+	}
+
+	fn set_string(&mut self, key: &str, value: String)
+	{
+		if self.entries.get(key) != Some(&value)
+		{
+			self.entries.insert(key.to_string(), value);
+			self.dirty = true;
+		}
+	}
+
+	fn flush(&mut self)
+	{
+		if self.dirty
+		{
+			match self.write()
+			{
+				Ok(()) => self.dirty = false,
+				Err(e) => tracing::warn!("{}", e)
+			}
+		}
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The name of the storage file, within the storage directory.
+const STORAGE_FILE_NAME: &str = "app.ron";
+
+/// The extension of the temporary file written by
+/// [`write`](DirectoryStorage::write).
+const TEMPORARY_EXTENSION: &str = "ron.tmp";
@@ -0,0 +1,154 @@
+/*
+ * theme.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use egui::{Color32, Context, Id, Ui};
+use serde::{Deserialize, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Palette.                                  //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The status colors of the application, i.e., the theme tokens. Every status
+/// message and indicator takes its color from the active palette, never from
+/// a literal.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Palette
+{
+	/// The color of errors, e.g., malformed regular expressions, invalid
+	/// section lists, and paradoxes.
+	pub(crate) error: Color32,
+
+	/// The color of warnings, e.g., weak transitions.
+	pub(crate) warning: Color32,
+
+	/// The color of validated input.
+	pub(crate) success: Color32,
+
+	/// The color of headings.
+	pub(crate) heading: Color32,
+
+	/// The color of the track of a position range bar, i.e., the positions
+	/// that a section can't occupy.
+	pub(crate) range_track: Color32,
+
+	/// The color of the feasible positions on a position range bar.
+	pub(crate) range_fill: Color32
+}
+
+impl Palette
+{
+	/// The standard palette.
+	pub(crate) const STANDARD: Self = Self
+	{
+		error: Color32::from_rgb(0xaa, 0x00, 0x00),
+		warning: Color32::from_rgb(0xcc, 0x88, 0x00),
+		success: Color32::from_rgb(0x3c, 0x9a, 0x3c),
+		heading: Color32::from_rgb(0xaa, 0xaa, 0xaa),
+		range_track: Color32::from_rgb(0x44, 0x44, 0x44),
+		range_fill: Color32::from_rgb(0xaa, 0xaa, 0xaa)
+	};
+
+	/// A palette that remains distinguishable under the common forms of color
+	/// blindness, drawn from the Okabe–Ito palette. Notably, it avoids
+	/// contrasting red with green.
+	pub(crate) const COLORBLIND_SAFE: Self = Self
+	{
+		error: Color32::from_rgb(0xd5, 0x5e, 0x00),
+		warning: Color32::from_rgb(0xe6, 0x9f, 0x00),
+		success: Color32::from_rgb(0x56, 0xb4, 0xe9),
+		heading: Color32::from_rgb(0xaa, 0xaa, 0xaa),
+		range_track: Color32::from_rgb(0x44, 0x44, 0x44),
+		range_fill: Color32::from_rgb(0x56, 0xb4, 0xe9)
+	};
+
+	/// Make the receiver the active palette for the specified
+	/// [context](Context), so that [`palette`] can find it during this frame.
+	pub(crate) fn install(self, ctx: &Context)
+	{
+		ctx.data_mut(|data| data.insert_temp(Id::new(PALETTE_KEY), self));
+	}
+}
+
+impl Default for Palette
+{
+	fn default() -> Self
+	{
+		Self::STANDARD
+	}
+}
+
+/// Answer the active [palette](Palette), i.e., the one most recently
+/// [installed](Palette::install).
+pub(crate) fn palette(ui: &Ui) -> Palette
+{
+	ui.data_mut(|data| data.get_temp(Id::new(PALETTE_KEY))).unwrap_or_default()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The key under which the active [palette](Palette) is stored in the
+/// [context](Context).
+const PALETTE_KEY: &str = "palette";
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn round_trips_the_palette()
+	{
+		for palette in [Palette::STANDARD, Palette::COLORBLIND_SAFE]
+		{
+			let text = ron::to_string(&palette).unwrap();
+			assert_eq!(ron::from_str::<Palette>(&text).unwrap(), palette);
+		}
+	}
+
+	#[test]
+	fn fills_missing_colors_from_the_standard_palette()
+	{
+		let palette = ron::from_str::<Palette>(
+			"(error: ((1, 2, 3, 255)), heading: ((4, 5, 6, 255)))"
+		).unwrap();
+		assert_eq!(palette.error, Color32::from_rgb(1, 2, 3));
+		assert_eq!(palette.heading, Color32::from_rgb(4, 5, 6));
+		assert_eq!(palette.warning, Palette::STANDARD.warning);
+		assert_eq!(palette.range_fill, Palette::STANDARD.range_fill);
+	}
+}
@@ -0,0 +1,495 @@
+/*
+ * transitions.rs
+ * Copyright © 2023, Todd L Smith.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * 3. Neither the name of the copyright holder nor the names of its contributors
+ *    may be used to endorse or promote products derived from this software
+ *    without specific prior written permission.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS “AS IS”
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+ * ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE
+ * LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+ * CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+ * SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+ * CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+ * ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+ * POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use std::collections::{HashMap, HashSet};
+use crate::spelling::words;
+
+////////////////////////////////////////////////////////////////////////////////
+//                                Transitions.                                //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The analysis of a single transition between two adjacent sections of a
+/// shuffled manuscript, i.e., the boundary between the end of one section and
+/// the beginning of the next.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Transition
+{
+	/// The distinctive words and phrases that occur both near the end of the
+	/// preceding section and near the beginning of the following section. Such
+	/// echoes tend to read badly.
+	pub(crate) echoes: Vec<String>,
+
+	/// The other problems detected by the transition heuristics, as
+	/// descriptions suitable for a writer.
+	pub(crate) problems: Vec<String>,
+
+	/// The quality of the transition, from `0.0` (awful) to `1.0` (flawless),
+	/// as judged by cheap heuristics.
+	pub(crate) score: f32
+}
+
+impl Transition
+{
+	/// Answer `true` if the transition is of questionable quality and merits
+	/// the writer's attention.
+	pub(crate) fn is_poor(&self) -> bool
+	{
+		self.score < POOR_TRANSITION_SCORE
+	}
+}
+
+/// Analyze every transition of the specified sections, which are given in
+/// their output order. The answer contains one fewer element than the
+/// sections.
+pub(crate) fn analyze_transitions(sections: &[String]) -> Vec<Transition>
+{
+	let document_frequencies = document_frequencies(sections);
+	// A word is only distinctive if it's rare in the manuscript as a whole;
+	// otherwise, names of characters and places would echo everywhere.
+	let threshold = (sections.len() / 4).max(2);
+	sections.windows(2)
+		.map(|pair| {
+			let echoes = find_echoes(
+				&pair[0],
+				&pair[1],
+				|word| document_frequencies.get(word).copied().unwrap_or(0)
+					<= threshold
+			);
+			let problems = find_problems(&pair[0], &pair[1]);
+			let score = 1.0
+				- (ECHO_PENALTY * echoes.len() as f32).min(MAXIMUM_ECHO_PENALTY)
+				- PROBLEM_PENALTY * problems.len() as f32;
+			Transition { echoes, problems, score: score.max(0.0) }
+		})
+		.collect()
+}
+
+/// Answer the total score of the specified transitions, for comparing
+/// alternative orderings of the same sections. Higher is better.
+pub(crate) fn total_score(transitions: &[Transition]) -> f32
+{
+	transitions.iter().map(|t| t.score).sum()
+}
+
+/// Answer the first nonblank line of the specified text, trimmed.
+pub(crate) fn first_line(text: &str) -> &str
+{
+	text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("")
+}
+
+/// Answer the last nonblank line of the specified text, trimmed.
+pub(crate) fn last_line(text: &str) -> &str
+{
+	text.lines().rev().map(str::trim).find(|line| !line.is_empty()).unwrap_or("")
+}
+
+/// Answer the normalized words of the specified text, i.e., in lowercase and
+/// with typographic apostrophes replaced by typewriter apostrophes.
+fn normalized_words(text: &str) -> Vec<String>
+{
+	words(text).into_iter()
+		.map(|range| text[range].to_lowercase().replace('’', "'"))
+		.collect()
+}
+
+/// Answer the number of sections in which each normalized word occurs.
+fn document_frequencies(sections: &[String]) -> HashMap<String, usize>
+{
+	let mut frequencies = HashMap::new();
+	for section in sections
+	{
+		for word in normalized_words(section).into_iter().collect::<HashSet<_>>()
+		{
+			*frequencies.entry(word).or_insert(0) += 1;
+		}
+	}
+	frequencies
+}
+
+/// Answer `true` if the specified normalized word carries meaning, i.e., it is
+/// long enough and not a [stop&#32;word](STOP_WORDS).
+fn is_content_word(word: &str) -> bool
+{
+	word.chars().count() >= ECHO_MINIMUM_LENGTH && !STOP_WORDS.contains(&word)
+}
+
+/// Find the echoes between the end of `preceding` and the beginning of
+/// `following`. Three-word phrases are reported if they contain at least one
+/// [content&#32;word](is_content_word), whereas single words are only reported
+/// if they are content words approved by `is_rare`. Words that occur within a
+/// reported phrase are not reported separately.
+fn find_echoes(
+	preceding: &str,
+	following: &str,
+	is_rare: impl Fn(&str) -> bool
+) -> Vec<String>
+{
+	let tail = normalized_words(preceding);
+	let tail = &tail[tail.len().saturating_sub(ECHO_WINDOW) ..];
+	let head = normalized_words(following);
+	let head = &head[.. head.len().min(ECHO_WINDOW)];
+	let head_phrases = head.windows(3)
+		.map(|phrase| phrase.join(" "))
+		.collect::<HashSet<_>>();
+	let mut echoes = vec![];
+	for phrase in tail.windows(3)
+		.filter(|phrase| phrase.iter().any(|word| is_content_word(word)))
+		.map(|phrase| phrase.join(" "))
+	{
+		if head_phrases.contains(&phrase) && !echoes.contains(&phrase)
+		{
+			echoes.push(phrase);
+		}
+	}
+	let head_words = head.iter().collect::<HashSet<_>>();
+	let phrases = echoes.clone();
+	for word in tail
+	{
+		if is_content_word(word)
+			&& head_words.contains(word)
+			&& is_rare(word)
+			&& !echoes.contains(word)
+			&& !phrases.iter().any(|phrase| phrase.split(' ').any(|w| w == word))
+		{
+			echoes.push(word.clone());
+		}
+	}
+	echoes
+}
+
+/// Apply the cheap transition heuristics to the boundary between the end of
+/// `preceding` and the beginning of `following`, answering descriptions of any
+/// problems detected.
+fn find_problems(preceding: &str, following: &str) -> Vec<String>
+{
+	let tail = normalized_words(preceding);
+	let tail = &tail[tail.len().saturating_sub(HEURISTIC_WINDOW) ..];
+	let head = normalized_words(following);
+	let head = &head[.. head.len().min(HEURISTIC_WINDOW)];
+	let mut problems = vec![];
+	if let (Some(last), Some(first)) = (tail.last(), head.first())
+	{
+		if last == first
+		{
+			problems.push(format!(
+				"The next section opens with the same word that this one \
+				closes with: “{}”.",
+				first
+			));
+		}
+	}
+	if let (Some(before), Some(after)) = (tense(tail), tense(head))
+	{
+		if before != after
+		{
+			problems.push(format!(
+				"The tense changes from {} to {}.",
+				before.name(),
+				after.name()
+			));
+		}
+	}
+	if let (Some(before), Some(after)) = (person(tail), person(head))
+	{
+		if before != after
+		{
+			problems.push(format!(
+				"The point of view switches from {} to {}.",
+				before.name(),
+				after.name()
+			));
+		}
+	}
+	problems
+}
+
+/// A grammatical tense, as detected by [`tense`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tense
+{
+	Past,
+	Present
+}
+
+impl Tense
+{
+	/// Answer the name of the tense, for a writer.
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			Self::Past => "past tense",
+			Self::Present => "present tense"
+		}
+	}
+}
+
+/// Guess the predominant tense of the specified normalized words, by counting
+/// the telltale verbs of each tense. Answer `None` if neither tense clearly
+/// predominates.
+fn tense(words: &[String]) -> Option<Tense>
+{
+	let past = words.iter()
+		.filter(|w|
+			PAST_TENSE_VERBS.contains(&w.as_str())
+				|| (w.len() > 4 && w.ends_with("ed"))
+		)
+		.count();
+	let present = words.iter()
+		.filter(|w| PRESENT_TENSE_VERBS.contains(&w.as_str()))
+		.count();
+	predominant(past, present, Tense::Past, Tense::Present)
+}
+
+/// A grammatical person, i.e., a narrative point of view, as detected by
+/// [`person`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Person
+{
+	First,
+	Third
+}
+
+impl Person
+{
+	/// Answer the name of the point of view, for a writer.
+	fn name(self) -> &'static str
+	{
+		match self
+		{
+			Self::First => "first person",
+			Self::Third => "third person"
+		}
+	}
+}
+
+/// Guess the predominant narrative person of the specified normalized words,
+/// by counting pronouns. Answer `None` if neither person clearly predominates.
+fn person(words: &[String]) -> Option<Person>
+{
+	let first = words.iter()
+		.filter(|w| FIRST_PERSON_PRONOUNS.contains(&w.as_str()))
+		.count();
+	let third = words.iter()
+		.filter(|w| THIRD_PERSON_PRONOUNS.contains(&w.as_str()))
+		.count();
+	predominant(first, third, Person::First, Person::Third)
+}
+
+/// Answer `a` if `a_count` clearly predominates over `b_count`, `b` if the
+/// reverse is true, and `None` otherwise (including when the counts are too
+/// small to be meaningful).
+fn predominant<T>(a_count: usize, b_count: usize, a: T, b: T) -> Option<T>
+{
+	if a_count + b_count < HEURISTIC_MINIMUM_EVIDENCE
+	{
+		None
+	}
+	else if a_count > 2 * b_count
+	{
+		Some(a)
+	}
+	else if b_count > 2 * a_count
+	{
+		Some(b)
+	}
+	else
+	{
+		None
+	}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                  Pacing.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+/// Count the adjacencies of the specified ordering that pair the two longest
+/// sections or the two shortest sections, which make for lopsided pacing.
+/// `lengths` supplies the length of each section, in section order, and
+/// `order` supplies the **zero-based** indices of the sections, in output
+/// order. Ties go to the earlier section. Fewer than four sections can't be
+/// balanced, so the answer is then always `0`.
+pub(crate) fn lopsided_adjacencies(lengths: &[usize], order: &[usize]) -> usize
+{
+	if lengths.len() < 4
+	{
+		return 0
+	}
+	let mut by_length = (0 .. lengths.len()).collect::<Vec<_>>();
+	by_length.sort_by_key(|index| lengths[*index]);
+	let last = by_length.len() - 1;
+	let extremes = [
+		(by_length[0], by_length[1]),
+		(by_length[last - 1], by_length[last])
+	];
+	order.windows(2)
+		.filter(|pair| extremes.iter().any(|(a, b)|
+			(pair[0] == *a && pair[1] == *b) || (pair[0] == *b && pair[1] == *a)
+		))
+		.count()
+}
+
+/// Group consecutive sections into chapters of roughly the specified target
+/// length, in the same units as `lengths`, which supplies the length of each
+/// section in output order. A chapter ends before a section whenever ending
+/// it there leaves the chapter closer to the target than including the section
+/// would. Answer the **zero-based** positions at which the chapters after the
+/// first begin.
+pub(crate) fn chapter_breaks(lengths: &[usize], target: usize) -> Vec<usize>
+{
+	let mut breaks = vec![];
+	let mut length = 0;
+	for (position, section) in lengths.iter().enumerate()
+	{
+		if length > 0
+			&& (length + section).abs_diff(target) > length.abs_diff(target)
+		{
+			breaks.push(position);
+			length = 0;
+		}
+		length += section;
+	}
+	breaks
+}
+
+////////////////////////////////////////////////////////////////////////////////
+//                                 Constants.                                 //
+////////////////////////////////////////////////////////////////////////////////
+
+/// The number of words at the end and beginning of adjacent sections that are
+/// searched for echoes.
+const ECHO_WINDOW: usize = 50;
+
+/// The number of words at the end and beginning of adjacent sections that are
+/// examined by the tense and point of view heuristics.
+const HEURISTIC_WINDOW: usize = 80;
+
+/// The minimum number of telltale words required before the tense or point of
+/// view heuristics will venture an opinion.
+const HEURISTIC_MINIMUM_EVIDENCE: usize = 3;
+
+/// The score penalty for each echo.
+const ECHO_PENALTY: f32 = 0.15;
+
+/// The maximum score penalty for echoes, no matter how many there are.
+const MAXIMUM_ECHO_PENALTY: f32 = 0.45;
+
+/// The score penalty for each other problem.
+const PROBLEM_PENALTY: f32 = 0.3;
+
+/// The score penalty for each [lopsided](lopsided_adjacencies) adjacency, which
+/// outweighs even an awful transition.
+pub(crate) const LOPSIDED_PENALTY: f32 = 1.0;
+
+/// The score below which a transition is considered poor.
+const POOR_TRANSITION_SCORE: f32 = 0.6;
+
+/// The minimum length, in characters, of a distinctive word.
+const ECHO_MINIMUM_LENGTH: usize = 4;
+
+/// The common English words that are never distinctive, no matter how often
+/// they repeat. Only words of at least [`ECHO_MINIMUM_LENGTH`] characters are
+/// listed, because shorter words are never distinctive anyway.
+const STOP_WORDS: &[&str] = &[
+	"about", "above", "after", "again", "against", "almost", "along",
+	"also", "although", "always", "among", "another", "anything", "around",
+	"away", "back", "because", "been", "before", "behind", "being", "below",
+	"between", "both", "came", "can't", "come", "could", "couldn't", "didn't",
+	"does", "doesn't", "doing", "done", "don't", "down", "during", "each",
+	"either", "enough", "even", "ever", "every", "from", "gave", "give",
+	"going", "gone", "good", "great", "hadn't", "have", "having",
+	"he'd", "he's", "hers", "herself", "himself", "into", "isn't", "it's",
+	"just", "know", "knew", "like", "little", "long", "look", "made", "make",
+	"many", "might", "more", "most", "much", "must", "myself", "never",
+	"next", "nothing", "once", "only", "other", "ourselves", "over", "said",
+	"same", "says", "seemed", "she'd", "she's", "should", "since",
+	"some", "something", "still", "such", "take", "than", "that", "that's",
+	"their", "theirs", "them", "themselves", "then", "there", "there's",
+	"these", "they", "they're", "thing", "things", "think", "this", "those",
+	"though", "through", "time", "told", "took", "toward", "towards", "under",
+	"until", "upon", "very", "wasn't", "well", "went", "were", "weren't",
+	"what", "when", "where", "whether", "which", "while", "who's", "whom",
+	"whose", "will", "with", "within", "without", "won't", "would",
+	"wouldn't", "yeah", "your", "yours", "yourself"
+];
+
+/// The common irregular verbs of the past tense. Regular verbs are recognized
+/// by their `-ed` suffix.
+const PAST_TENSE_VERBS: &[&str] = &[
+	"was", "were", "had", "did", "said", "went", "came", "saw", "knew",
+	"thought", "took", "got", "made", "felt", "found", "gave", "told",
+	"became", "left", "kept", "began", "brought", "held", "stood", "heard",
+	"ran", "sat", "spoke", "wrote", "fell", "meant", "met", "lay", "led",
+	"rose", "drew", "grew", "threw", "woke", "wore", "could", "would"
+];
+
+/// The common verbs of the present tense.
+const PRESENT_TENSE_VERBS: &[&str] = &[
+	"is", "are", "am", "has", "have", "does", "do", "says", "say", "goes",
+	"comes", "sees", "knows", "thinks", "takes", "gets", "makes", "feels",
+	"finds", "gives", "tells", "becomes", "leaves", "keeps", "begins",
+	"brings", "holds", "stands", "hears", "runs", "sits", "speaks", "writes",
+	"falls", "means", "meets", "lies", "leads", "can", "will"
+];
+
+/// The pronouns of the first person.
+const FIRST_PERSON_PRONOUNS: &[&str] = &[
+	"i", "me", "my", "mine", "myself", "i'm", "i'd", "i've", "i'll"
+];
+
+/// The pronouns of the third person, excluding the plural, which often appears
+/// within first person narration.
+const THIRD_PERSON_PRONOUNS: &[&str] = &[
+	"he", "him", "his", "himself", "she", "her", "hers", "herself", "he'd",
+	"she'd", "he's", "she's"
+];
+
+////////////////////////////////////////////////////////////////////////////////
+//                                   Tests.                                   //
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests
+{
+	use std::collections::HashSet;
+	use super::*;
+
+	#[test]
+	fn lists_only_long_distinct_stop_words()
+	{
+		assert!(STOP_WORDS.iter()
+			.all(|word| word.chars().count() >= ECHO_MINIMUM_LENGTH));
+		assert_eq!(
+			STOP_WORDS.iter().collect::<HashSet<_>>().len(),
+			STOP_WORDS.len()
+		);
+	}
+}